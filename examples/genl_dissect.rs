@@ -0,0 +1,66 @@
+// SPDX-License-Identifier: MIT
+
+//! Dissect generic netlink messages given as hexadecimal bytes
+//!
+//! The bytes are read from the file given as argument, or from the standard
+//! input, e.g. copied from a `tcpdump -i nlmon0 -xx` output:
+//!
+//! ```sh
+//! echo "20 00 00 00 10 00 05 00 ..." | cargo run --example genl_dissect
+//! ```
+//!
+//! Pass `--devlink <id>` to decode the devlink messages sent with the given
+//! family ID.
+
+use netlink_packet_generic::{
+    devlink::GenlDevlink, dissect::Dissector, registry::GenlRegistry,
+};
+use std::{env, fs, io::Read, process};
+
+fn main() {
+    let mut registry = GenlRegistry::new();
+    let mut path = None;
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--devlink" => {
+                let id = args.next().and_then(|id| id.parse().ok());
+                match id {
+                    Some(id) => registry.register::<GenlDevlink>(id),
+                    None => usage(),
+                }
+            }
+            "-h" | "--help" => usage(),
+            _ => path = Some(arg),
+        }
+    }
+
+    let mut text = String::new();
+    match path {
+        Some(path) => text = fs::read_to_string(path).unwrap(),
+        None => {
+            std::io::stdin().read_to_string(&mut text).unwrap();
+        }
+    }
+    let bytes = text
+        .split_whitespace()
+        .map(|byte| u8::from_str_radix(byte, 16))
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap_or_else(|e| {
+            eprintln!("invalid hexadecimal input: {e}");
+            process::exit(1);
+        });
+
+    match Dissector::new(registry).dissect(&bytes) {
+        Ok(text) => print!("{text}"),
+        Err(e) => {
+            eprintln!("failed to dissect the messages: {e}");
+            process::exit(1);
+        }
+    }
+}
+
+fn usage() -> ! {
+    eprintln!("usage: genl_dissect [--devlink <family id>] [file]");
+    process::exit(2);
+}
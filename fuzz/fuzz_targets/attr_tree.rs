@@ -0,0 +1,21 @@
+// SPDX-License-Identifier: MIT
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use netlink_packet_generic::{
+    ctrl::nlas::{GenlCtrlAttrs, NlPolicyTypeAttrs, OppolicyAttr},
+    devlink::nldev::{GenlDevlinkAttrs, PortFunctionAttrs},
+    raw::RawNla,
+};
+use netlink_packet_generic_fuzz::{round_trip, AttrTree};
+
+fuzz_target!(|attrs: Vec<AttrTree>| {
+    let data = AttrTree::to_bytes(&attrs);
+    round_trip::<GenlCtrlAttrs>(&data);
+    round_trip::<NlPolicyTypeAttrs>(&data);
+    round_trip::<OppolicyAttr>(&data);
+    round_trip::<GenlDevlinkAttrs>(&data);
+    round_trip::<PortFunctionAttrs>(&data);
+    round_trip::<RawNla>(&data);
+});
@@ -0,0 +1,17 @@
+// SPDX-License-Identifier: MIT
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use netlink_packet_generic::{
+    ctrl::GenlCtrl, devlink::GenlDevlink, raw::GenlRaw, GenlBuffer, GenlMessage,
+};
+use netlink_packet_utils::ParseableParametrized;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(buf) = GenlBuffer::new_checked(data) {
+        let _ = GenlMessage::<GenlCtrl>::parse_with_param(&buf, 0x10);
+        let _ = GenlMessage::<GenlDevlink>::parse_with_param(&buf, 0x14);
+        let _ = GenlMessage::<GenlRaw>::parse_with_param(&buf, 0x20);
+    }
+});
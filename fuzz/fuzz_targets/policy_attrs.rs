@@ -0,0 +1,9 @@
+// SPDX-License-Identifier: MIT
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use netlink_packet_generic::ctrl::nlas::NlPolicyTypeAttrs;
+use netlink_packet_generic_fuzz::round_trip;
+
+fuzz_target!(|data: &[u8]| round_trip::<NlPolicyTypeAttrs>(data));
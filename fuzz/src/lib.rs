@@ -0,0 +1,105 @@
+// SPDX-License-Identifier: MIT
+
+//! Helpers shared by the fuzz targets
+
+use arbitrary::Arbitrary;
+use netlink_packet_utils::{
+    nla::{Nla, NlaBuffer, NlasIterator, NLA_F_NESTED},
+    Emitable, Parseable,
+};
+use std::fmt::Debug;
+
+/// Parse every attribute of `data` as `T`
+///
+/// The attributes which parse are emitted again, and must parse back to the
+/// same value.
+pub fn round_trip<T>(data: &[u8])
+where
+    T: Nla + Debug + PartialEq + for<'a> Parseable<NlaBuffer<&'a [u8]>>,
+{
+    // netlink-packet-utils cannot emit both flags on the same attribute:
+    // setting NLA_F_NESTED clears NLA_F_NET_BYTEORDER
+    if has_both_flags(data, 0) {
+        return;
+    }
+    for nla in NlasIterator::new(data) {
+        let nla = match nla {
+            Ok(nla) => nla,
+            Err(_) => return,
+        };
+        if let Ok(parsed) = T::parse(&nla) {
+            check_emit(&parsed);
+        }
+    }
+}
+
+// Look for an attribute with both flags, at any nesting level
+fn has_both_flags(data: &[u8], depth: usize) -> bool {
+    NlasIterator::new(data).flatten().any(|nla| {
+        (nla.nested_flag() && nla.network_byte_order_flag())
+            || (depth < 32 && has_both_flags(nla.value(), depth + 1))
+    })
+}
+
+/// Emit `nla`, and check it parses back to the same value
+pub fn check_emit<T>(nla: &T)
+where
+    T: Nla + Debug + PartialEq + for<'a> Parseable<NlaBuffer<&'a [u8]>>,
+{
+    let mut buf = vec![0; nla.buffer_len()];
+    nla.emit(&mut buf);
+    let reparsed = NlaBuffer::new_checked(&buf[..])
+        .and_then(|buf| T::parse(&buf))
+        .unwrap_or_else(|e| panic!("{:?} emitted as {:?}: {:?}", nla, buf, e));
+    assert_eq!(*nla, reparsed, "emitted as {:?}", buf);
+}
+
+/// Tree of netlink attributes
+///
+/// It produces well-formed attribute streams, which reach deeper in the
+/// parsers than random bytes.
+#[derive(Arbitrary, Debug)]
+pub enum AttrTree {
+    Value {
+        kind: u16,
+        value: Vec<u8>,
+    },
+    Nested {
+        kind: u16,
+        flag: bool,
+        children: Vec<AttrTree>,
+    },
+}
+
+impl AttrTree {
+    /// Serialize the attributes one after the other
+    pub fn to_bytes(attrs: &[AttrTree]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        for attr in attrs {
+            attr.write(&mut buf);
+        }
+        buf
+    }
+
+    fn write(&self, buf: &mut Vec<u8>) {
+        let (kind, value) = match self {
+            AttrTree::Value { kind, value } => (*kind, value.clone()),
+            AttrTree::Nested {
+                kind,
+                flag,
+                children,
+            } => {
+                let kind = if *flag { kind | NLA_F_NESTED } else { *kind };
+                (kind, Self::to_bytes(children))
+            }
+        };
+        let len = 4 + value.len();
+        if len > usize::from(u16::MAX) {
+            return;
+        }
+        buf.extend_from_slice(&(len as u16).to_ne_bytes());
+        buf.extend_from_slice(&kind.to_ne_bytes());
+        buf.extend_from_slice(&value);
+        buf.resize((buf.len() + 3) & !3, 0);
+    }
+}
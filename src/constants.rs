@@ -57,6 +57,8 @@ pub const NL_ATTR_TYPE_NUL_STRING: u32 = 12;
 pub const NL_ATTR_TYPE_NESTED: u32 = 13;
 pub const NL_ATTR_TYPE_NESTED_ARRAY: u32 = 14;
 pub const NL_ATTR_TYPE_BITFIELD32: u32 = 15;
+pub const NL_ATTR_TYPE_SINT: u32 = 16;
+pub const NL_ATTR_TYPE_UINT: u32 = 17;
 
 pub const NL_POLICY_TYPE_ATTR_UNSPEC: u16 = 0;
 pub const NL_POLICY_TYPE_ATTR_TYPE: u16 = 1;
@@ -218,8 +220,8 @@ pub const DEVLINK_ATTR_DPIPE_MATCH_TYPE: u16 = 42;		/* u32 */
 pub const DEVLINK_ATTR_DPIPE_ACTION: u16 = 43;		/* nested */
 pub const DEVLINK_ATTR_DPIPE_ACTION_VALUE: u16 = 44;		/* nested */
 pub const DEVLINK_ATTR_DPIPE_ACTION_TYPE: u16 = 45;		/* u32 */
-pub const DEVLINK_ATTR_DPIPE_VALUE: u16 = 46;
-pub const DEVLINK_ATTR_DPIPE_VALUE_MASK: u16 = 47;
+pub const DEVLINK_ATTR_DPIPE_VALUE: u16 = 46;		/* binary */
+pub const DEVLINK_ATTR_DPIPE_VALUE_MASK: u16 = 47;		/* binary */
 pub const DEVLINK_ATTR_DPIPE_VALUE_MAPPING: u16 = 48;	/* u32 */
 pub const DEVLINK_ATTR_DPIPE_HEADERS: u16 = 49;		/* nested */
 pub const DEVLINK_ATTR_DPIPE_HEADER: u16 = 50;		/* nested */
@@ -368,4 +370,173 @@ pub const DEVLINK_ATTR_RATE_TX_MAX: u16 = 167;		/* u64 */
 pub const DEVLINK_ATTR_RATE_NODE_NAME: u16 = 168;		/* string */
 pub const DEVLINK_ATTR_RATE_PARENT_NODE_NAME: u16 = 169;	/* string */
 
-pub const DEVLINK_ATTR_REGION_MAX_SNAPSHOTS: u16 =170; /* uint32 */
\ No newline at end of file
+pub const DEVLINK_ATTR_REGION_MAX_SNAPSHOTS: u16 =170; /* uint32 */
+pub const DEVLINK_ATTR_STATS_RX_PACKETS: u16 = 0; /* u64 */
+pub const DEVLINK_ATTR_STATS_RX_BYTES: u16 = 1; /* u64 */
+pub const DEVLINK_ATTR_STATS_RX_DROPPED: u16 = 2; /* u64 */
+pub const DEVLINK_ATTR_TRAP_METADATA_TYPE_IN_PORT: u16 = 0; /* flag */
+pub const DEVLINK_ATTR_TRAP_METADATA_TYPE_FA_COOKIE: u16 = 1; /* flag */
+pub const DEVLINK_PORT_FUNCTION_ATTR_HW_ADDR: u16 = 1; /* binary */
+pub const DEVLINK_PORT_FN_ATTR_STATE: u16 = 2; /* u8 */
+pub const DEVLINK_PORT_FN_ATTR_OPSTATE: u16 = 3; /* u8 */
+pub const DEVLINK_PORT_FN_ATTR_CAPS: u16 = 4; /* bitfield32 */
+
+/// Devlink port types
+pub const DEVLINK_PORT_TYPE_NOTSET: u16 = 0;
+pub const DEVLINK_PORT_TYPE_AUTO: u16 = 1;
+pub const DEVLINK_PORT_TYPE_ETH: u16 = 2;
+pub const DEVLINK_PORT_TYPE_IB: u16 = 3;
+
+/// Devlink port flavours
+pub const DEVLINK_PORT_FLAVOUR_PHYSICAL: u16 = 0;
+pub const DEVLINK_PORT_FLAVOUR_CPU: u16 = 1;
+pub const DEVLINK_PORT_FLAVOUR_DSA: u16 = 2;
+pub const DEVLINK_PORT_FLAVOUR_PCI_PF: u16 = 3;
+pub const DEVLINK_PORT_FLAVOUR_PCI_VF: u16 = 4;
+pub const DEVLINK_PORT_FLAVOUR_VIRTUAL: u16 = 5;
+pub const DEVLINK_PORT_FLAVOUR_UNUSED: u16 = 6;
+pub const DEVLINK_PORT_FLAVOUR_PCI_SF: u16 = 7;
+
+/// Devlink port function states
+pub const DEVLINK_PORT_FN_STATE_INACTIVE: u8 = 0;
+pub const DEVLINK_PORT_FN_STATE_ACTIVE: u8 = 1;
+
+/// Devlink port function operational states
+pub const DEVLINK_PORT_FN_OPSTATE_DETACHED: u8 = 0;
+pub const DEVLINK_PORT_FN_OPSTATE_ATTACHED: u8 = 1;
+
+/// Devlink port function capabilities
+pub const DEVLINK_PORT_FN_CAP_ROCE: u32 = 1 << 0;
+pub const DEVLINK_PORT_FN_CAP_MIGRATABLE: u32 = 1 << 1;
+
+/// Devlink parameter configuration modes
+pub const DEVLINK_PARAM_CMODE_RUNTIME: u8 = 0;
+pub const DEVLINK_PARAM_CMODE_DRIVERINIT: u8 = 1;
+pub const DEVLINK_PARAM_CMODE_PERMANENT: u8 = 2;
+
+/// Devlink parameter types
+pub const DEVLINK_PARAM_TYPE_U8: u8 = 1;
+pub const DEVLINK_PARAM_TYPE_U16: u8 = 2;
+pub const DEVLINK_PARAM_TYPE_U32: u8 = 3;
+pub const DEVLINK_PARAM_TYPE_STRING: u8 = 5;
+pub const DEVLINK_PARAM_TYPE_BOOL: u8 = 6;
+
+/// Devlink health reporter states
+pub const DEVLINK_HEALTH_REPORTER_STATE_HEALTHY: u8 = 0;
+pub const DEVLINK_HEALTH_REPORTER_STATE_ERROR: u8 = 1;
+
+/// Devlink trap actions
+pub const DEVLINK_TRAP_ACTION_DROP: u8 = 0;
+pub const DEVLINK_TRAP_ACTION_TRAP: u8 = 1;
+pub const DEVLINK_TRAP_ACTION_MIRROR: u8 = 2;
+
+/// Devlink trap types
+pub const DEVLINK_TRAP_TYPE_DROP: u8 = 0;
+pub const DEVLINK_TRAP_TYPE_EXCEPTION: u8 = 1;
+pub const DEVLINK_TRAP_TYPE_CONTROL: u8 = 2;
+
+/// Devlink rate object types
+pub const DEVLINK_RATE_TYPE_LEAF: u16 = 0;
+pub const DEVLINK_RATE_TYPE_NODE: u16 = 1;
+
+/// Devlink reload actions
+pub const DEVLINK_RELOAD_ACTION_UNSPEC: u8 = 0;
+pub const DEVLINK_RELOAD_ACTION_DRIVER_REINIT: u8 = 1;
+pub const DEVLINK_RELOAD_ACTION_FW_ACTIVATE: u8 = 2;
+
+/// Devlink reload limits
+pub const DEVLINK_RELOAD_LIMIT_UNSPEC: u8 = 0;
+pub const DEVLINK_RELOAD_LIMIT_NO_RESET: u8 = 1;
+
+/// Devlink shared buffer pool types
+pub const DEVLINK_SB_POOL_TYPE_INGRESS: u8 = 0;
+pub const DEVLINK_SB_POOL_TYPE_EGRESS: u8 = 1;
+
+/// Devlink shared buffer threshold types
+pub const DEVLINK_SB_THRESHOLD_TYPE_STATIC: u8 = 0;
+pub const DEVLINK_SB_THRESHOLD_TYPE_DYNAMIC: u8 = 1;
+
+/// Devlink dpipe match and action types
+pub const DEVLINK_DPIPE_MATCH_TYPE_FIELD_EXACT: u32 = 0;
+pub const DEVLINK_DPIPE_ACTION_TYPE_FIELD_MODIFY: u32 = 0;
+
+/// Devlink dpipe field mapping types
+pub const DEVLINK_DPIPE_FIELD_MAPPING_TYPE_NONE: u32 = 0;
+pub const DEVLINK_DPIPE_FIELD_MAPPING_TYPE_IFINDEX: u32 = 1;
+
+/// Devlink dpipe global headers
+pub const DEVLINK_DPIPE_HEADER_ETHERNET: u32 = 0;
+pub const DEVLINK_DPIPE_HEADER_IPV4: u32 = 1;
+pub const DEVLINK_DPIPE_HEADER_IPV6: u32 = 2;
+
+/// Devlink resource units
+pub const DEVLINK_RESOURCE_UNIT_ENTRY: u8 = 0;
+
+/// Generic names of the devlink info versions
+pub const DEVLINK_INFO_VERSION_GENERIC_BOARD_ID: &str = "board.id";
+pub const DEVLINK_INFO_VERSION_GENERIC_BOARD_REV: &str = "board.rev";
+pub const DEVLINK_INFO_VERSION_GENERIC_BOARD_MANUFACTURE: &str =
+    "board.manufacture";
+pub const DEVLINK_INFO_VERSION_GENERIC_BOARD_PART_NUMBER: &str =
+    "board.part_number";
+pub const DEVLINK_INFO_VERSION_GENERIC_ASIC_ID: &str = "asic.id";
+pub const DEVLINK_INFO_VERSION_GENERIC_ASIC_REV: &str = "asic.rev";
+pub const DEVLINK_INFO_VERSION_GENERIC_FW: &str = "fw";
+pub const DEVLINK_INFO_VERSION_GENERIC_FW_MGMT: &str = "fw.mgmt";
+pub const DEVLINK_INFO_VERSION_GENERIC_FW_MGMT_API: &str = "fw.mgmt.api";
+pub const DEVLINK_INFO_VERSION_GENERIC_FW_APP: &str = "fw.app";
+pub const DEVLINK_INFO_VERSION_GENERIC_FW_UNDI: &str = "fw.undi";
+pub const DEVLINK_INFO_VERSION_GENERIC_FW_NCSI: &str = "fw.ncsi";
+pub const DEVLINK_INFO_VERSION_GENERIC_FW_PSID: &str = "fw.psid";
+pub const DEVLINK_INFO_VERSION_GENERIC_FW_ROCE: &str = "fw.roce";
+pub const DEVLINK_INFO_VERSION_GENERIC_FW_BUNDLE_ID: &str = "fw.bundle_id";
+pub const DEVLINK_INFO_VERSION_GENERIC_FW_BOOTLOADER: &str = "fw.bootloader";
+
+/// Drop monitor (NET_DM) family, which reports the packets dropped by the
+/// kernel and trapped by devlink devices
+pub const NET_DM_CMD_UNSPEC: u8 = 0;
+pub const NET_DM_CMD_ALERT: u8 = 1;
+pub const NET_DM_CMD_CONFIG: u8 = 2;
+pub const NET_DM_CMD_START: u8 = 3;
+pub const NET_DM_CMD_STOP: u8 = 4;
+pub const NET_DM_CMD_PACKET_ALERT: u8 = 5;
+pub const NET_DM_CMD_CONFIG_GET: u8 = 6;
+pub const NET_DM_CMD_CONFIG_NEW: u8 = 7;
+pub const NET_DM_CMD_STATS_GET: u8 = 8;
+pub const NET_DM_CMD_STATS_NEW: u8 = 9;
+
+pub const NET_DM_ATTR_UNSPEC: u16 = 0;
+pub const NET_DM_ATTR_ALERT_MODE: u16 = 1; /* u8 */
+pub const NET_DM_ATTR_PC: u16 = 2; /* u64 */
+pub const NET_DM_ATTR_SYMBOL: u16 = 3; /* string */
+pub const NET_DM_ATTR_IN_PORT: u16 = 4; /* nested */
+pub const NET_DM_ATTR_TIMESTAMP: u16 = 5; /* u64 */
+pub const NET_DM_ATTR_PROTO: u16 = 6; /* u16 */
+pub const NET_DM_ATTR_PAYLOAD: u16 = 7; /* binary */
+pub const NET_DM_ATTR_PAD: u16 = 8;
+pub const NET_DM_ATTR_TRUNC_LEN: u16 = 9; /* u32 */
+pub const NET_DM_ATTR_ORIG_LEN: u16 = 10; /* u32 */
+pub const NET_DM_ATTR_QUEUE_LEN: u16 = 11; /* u32 */
+pub const NET_DM_ATTR_STATS: u16 = 12; /* nested */
+pub const NET_DM_ATTR_HW_STATS: u16 = 13; /* nested */
+pub const NET_DM_ATTR_ORIGIN: u16 = 14; /* u16 */
+pub const NET_DM_ATTR_HW_TRAP_GROUP_NAME: u16 = 15; /* string */
+pub const NET_DM_ATTR_HW_TRAP_NAME: u16 = 16; /* string */
+pub const NET_DM_ATTR_HW_ENTRIES: u16 = 17; /* nested */
+pub const NET_DM_ATTR_HW_ENTRY: u16 = 18; /* nested */
+pub const NET_DM_ATTR_HW_TRAP_COUNT: u16 = 19; /* u32 */
+pub const NET_DM_ATTR_SW_DROPS: u16 = 20; /* flag */
+pub const NET_DM_ATTR_HW_DROPS: u16 = 21; /* flag */
+pub const NET_DM_ATTR_FLOW_ACTION_COOKIE: u16 = 22; /* binary */
+pub const NET_DM_ATTR_REASON: u16 = 23; /* string */
+
+pub const NET_DM_ATTR_PORT_NETDEV_IFINDEX: u16 = 0; /* u32 */
+pub const NET_DM_ATTR_PORT_NETDEV_NAME: u16 = 1; /* string */
+
+/// Drop monitor alert modes
+pub const NET_DM_ALERT_MODE_SUMMARY: u8 = 0;
+pub const NET_DM_ALERT_MODE_PACKET: u8 = 1;
+
+/// Drop monitor origins of the dropped packets
+pub const NET_DM_ORIGIN_SW: u16 = 0;
+pub const NET_DM_ORIGIN_HW: u16 = 1;
@@ -0,0 +1,134 @@
+// SPDX-License-Identifier: MIT
+
+//! Multicast group lookup from the controller replies
+
+use super::{
+    nlas::{GenlCtrlAttrs, McastGrpAttrs},
+    GenlCtrl,
+};
+use crate::traits::GenlFamily;
+use std::collections::HashMap;
+
+/// Multicast groups registered by a generic netlink family
+///
+/// The resolver is built from the attributes of a `CTRL_CMD_NEWFAMILY` reply
+/// (the answer to [`GenlCtrlCmd::GetFamily`](super::GenlCtrlCmd::GetFamily)),
+/// which carries the family name, its ID and the name and ID of every
+/// multicast group of the family.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct McastGroupResolver {
+    family_name: String,
+    family_id: u16,
+    groups: HashMap<String, u32>,
+}
+
+impl McastGroupResolver {
+    /// Build the resolver from the attributes of a controller reply
+    pub fn from_nlas(nlas: &[GenlCtrlAttrs]) -> Self {
+        let mut resolver = Self::default();
+        for nla in nlas {
+            match nla {
+                GenlCtrlAttrs::FamilyName(name) => {
+                    resolver.family_name = name.clone()
+                }
+                GenlCtrlAttrs::FamilyId(id) => resolver.family_id = *id,
+                GenlCtrlAttrs::McastGroups(groups) => {
+                    resolver.groups.extend(groups.iter().filter_map(|group| {
+                        let name = group.iter().find_map(|nla| match nla {
+                            McastGrpAttrs::Name(name) => Some(name.clone()),
+                            _ => None,
+                        })?;
+                        let id = group.iter().find_map(|nla| match nla {
+                            McastGrpAttrs::Id(id) => Some(*id),
+                            _ => None,
+                        })?;
+                        Some((name, id))
+                    }))
+                }
+                _ => {}
+            }
+        }
+        resolver
+    }
+
+    /// Return the name of the family
+    pub fn family_name(&self) -> &str {
+        &self.family_name
+    }
+
+    /// Return the ID of the family
+    pub fn family_id(&self) -> u16 {
+        self.family_id
+    }
+
+    /// Return the ID of the multicast group with the given name
+    pub fn group_id(&self, name: &str) -> Option<u32> {
+        self.groups.get(name).copied()
+    }
+
+    /// Return the ID of a multicast group declared by the family `F`
+    ///
+    /// Returns `None` if the resolver was built for another family, if `F`
+    /// does not declare the group in [`GenlFamily::multicast_groups()`] or if
+    /// the kernel does not provide it.
+    pub fn subscribe<F>(&self, name: &str) -> Option<u32>
+    where
+        F: GenlFamily,
+    {
+        if self.family_name != F::family_name()
+            || !F::multicast_groups().contains(&name)
+        {
+            return None;
+        }
+        self.group_id(name)
+    }
+
+    /// Iterate over the `(name, id)` pairs of the multicast groups
+    pub fn groups(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.groups.iter().map(|(name, id)| (name.as_str(), *id))
+    }
+}
+
+impl From<&GenlCtrl> for McastGroupResolver {
+    fn from(ctrl: &GenlCtrl) -> Self {
+        Self::from_nlas(&ctrl.nlas)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ctrl::GenlCtrlCmd, devlink::GenlDevlink};
+
+    fn devlink_family() -> GenlCtrl {
+        GenlCtrl {
+            cmd: GenlCtrlCmd::NewFamily,
+            nlas: vec![
+                GenlCtrlAttrs::FamilyId(0x15),
+                GenlCtrlAttrs::FamilyName("devlink".to_owned()),
+                GenlCtrlAttrs::McastGroups(vec![vec![
+                    McastGrpAttrs::Id(4),
+                    McastGrpAttrs::Name("config".to_owned()),
+                ]]),
+            ],
+        }
+    }
+
+    #[test]
+    fn resolve_groups() {
+        let resolver = McastGroupResolver::from(&devlink_family());
+        assert_eq!("devlink", resolver.family_name());
+        assert_eq!(0x15, resolver.family_id());
+        assert_eq!(Some(4), resolver.group_id("config"));
+        assert_eq!(None, resolver.group_id("notify"));
+        assert_eq!(vec![("config", 4)], resolver.groups().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn subscribe_declared_groups() {
+        let resolver = McastGroupResolver::from(&devlink_family());
+        assert_eq!(Some(4), resolver.subscribe::<GenlDevlink>("config"));
+        assert_eq!(None, resolver.subscribe::<GenlDevlink>("notify"));
+        assert_eq!(None, resolver.subscribe::<GenlCtrl>("config"));
+    }
+}
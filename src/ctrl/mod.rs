@@ -6,16 +6,24 @@
 //! It also serves as an example for creating a generic family.
 
 use self::nlas::*;
-use crate::{constants::*, traits::*, GenlHeader};
-use anyhow::Context;
-use netlink_packet_utils::{nla::NlasIterator, traits::*, DecodeError};
+use crate::{
+    constants::*,
+    error::{parse_nlas, GenlDecodeError},
+    traits::*,
+    GenlHeader,
+};
+use netlink_packet_utils::{traits::*, DecodeError};
 use std::convert::{TryFrom, TryInto};
 
 /// Netlink attributes for this family
 pub mod nlas;
 
+mod mcast;
+pub use self::mcast::McastGroupResolver;
+
 /// Command code definition of Netlink controller (nlctrl) family
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GenlCtrlCmd {
     /// Notify from event
     NewFamily,
@@ -84,6 +92,7 @@ impl TryFrom<u8> for GenlCtrlCmd {
 
 /// Payload of generic netlink controller
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GenlCtrl {
     /// Command code of this message
     pub cmd: GenlCtrlCmd,
@@ -107,6 +116,10 @@ impl GenlFamily for GenlCtrl {
     fn version(&self) -> u8 {
         2
     }
+
+    fn multicast_groups() -> &'static [&'static str] {
+        &["notify"]
+    }
 }
 
 impl Emitable for GenlCtrl {
@@ -119,23 +132,43 @@ impl Emitable for GenlCtrl {
     }
 }
 
-impl ParseableParametrized<[u8], GenlHeader> for GenlCtrl {
-    fn parse_with_param(
+impl GenlCtrl {
+    /// Decode the payload of a controller message
+    ///
+    /// `buf` holds the attributes following the generic netlink header.
+    /// Unlike the [`ParseableParametrized`] implementation, the error tells
+    /// which attribute failed to parse.
+    pub fn decode(
         buf: &[u8],
         header: GenlHeader,
-    ) -> Result<Self, DecodeError> {
+    ) -> Result<Self, GenlDecodeError> {
         Ok(Self {
-            cmd: header.cmd.try_into()?,
-            nlas: parse_ctrlnlas(buf)?,
+            cmd: header.cmd.try_into().map_err(|e| {
+                GenlDecodeError::new(Self::family_name(), header.cmd, e)
+            })?,
+            nlas: parse_ctrlnlas(buf, header.cmd)?,
         })
     }
 }
 
-fn parse_ctrlnlas(buf: &[u8]) -> Result<Vec<GenlCtrlAttrs>, DecodeError> {
-    let nlas = NlasIterator::new(buf)
-        .map(|nla| nla.and_then(|nla| GenlCtrlAttrs::parse(&nla)))
-        .collect::<Result<Vec<_>, _>>()
-        .context("failed to parse control message attributes")?;
+impl ParseableParametrized<[u8], GenlHeader> for GenlCtrl {
+    fn parse_with_param(
+        buf: &[u8],
+        header: GenlHeader,
+    ) -> Result<Self, DecodeError> {
+        Ok(Self::decode(buf, header)?)
+    }
+}
 
-    Ok(nlas)
+fn parse_ctrlnlas(
+    buf: &[u8],
+    cmd: u8,
+) -> Result<Vec<GenlCtrlAttrs>, GenlDecodeError> {
+    parse_nlas(
+        GenlCtrl::family_name(),
+        cmd,
+        buf,
+        |nla| GenlCtrlAttrs::parse(nla),
+        |_| false,
+    )
 }
@@ -38,6 +38,7 @@ impl From<&Vec<Vec<McastGrpAttrs>>> for McastGroupList {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct McastGroup {
     pub index: u16,
     pub nlas: Vec<McastGrpAttrs>,
@@ -58,6 +59,8 @@ impl Nla for McastGroup {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum McastGrpAttrs {
     Name(String),
     Id(u32),
@@ -67,7 +70,7 @@ impl Nla for McastGrpAttrs {
     fn value_len(&self) -> usize {
         use McastGrpAttrs::*;
         match self {
-            Name(s) => s.as_bytes().len() + 1,
+            Name(s) => s.len() + 1,
             Id(v) => size_of_val(v),
         }
     }
@@ -22,6 +22,8 @@ pub use ops::*;
 pub use policy::*;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum GenlCtrlAttrs {
     FamilyId(u16),
     FamilyName(String),
@@ -87,11 +89,17 @@ impl Nla for GenlCtrlAttrs {
             McastGroups(nlas) => {
                 McastGroupList::from(nlas).as_slice().emit(buffer);
             }
-            Policy(nla) => nla.emit_value(buffer),
-            OpPolicy(nla) => nla.emit_value(buffer),
+            Policy(nla) => nla.emit(buffer),
+            OpPolicy(nla) => nla.emit(buffer),
             Op(v) => NativeEndian::write_u32(buffer, *v),
         }
     }
+
+    fn is_nested(&self) -> bool {
+        use GenlCtrlAttrs::*;
+        // The kernel only sets NLA_F_NESTED on the policy attributes
+        matches!(self, Policy(_) | OpPolicy(_))
+    }
 }
 
 impl<'a, T: AsRef<[u8]> + ?Sized> Parseable<NlaBuffer<&'a T>>
@@ -153,11 +161,11 @@ impl<'a, T: AsRef<[u8]> + ?Sized> Parseable<NlaBuffer<&'a T>>
                 Self::McastGroups(groups)
             }
             CTRL_ATTR_POLICY => Self::Policy(
-                PolicyAttr::parse(&NlaBuffer::new(payload))
+                PolicyAttr::parse(&NlaBuffer::new_checked(payload)?)
                     .context("failed to parse CTRL_ATTR_POLICY")?,
             ),
             CTRL_ATTR_OP_POLICY => Self::OpPolicy(
-                OppolicyAttr::parse(&NlaBuffer::new(payload))
+                OppolicyAttr::parse(&NlaBuffer::new_checked(payload)?)
                     .context("failed to parse CTRL_ATTR_OP_POLICY")?,
             ),
             CTRL_ATTR_OP => Self::Op(parse_u32(payload)?),
@@ -199,6 +207,21 @@ mod tests {
         assert_eq!(expected_attr, result_attr);
     }
 
+    #[test]
+    fn policy_parse_truncated() {
+        let policy_bytes: [u8; 12] = [
+            12, 0, // Netlink header length
+            8, 0, // Netlink header kind (Policy)
+            8, 0, // Policy nested NLA length
+            0, 0, // Policy index
+            2, 0, // Attribute policy length, shorter than its header
+            1, 0, // Attribute index
+        ];
+        let nla_buffer = NlaBuffer::new_checked(&policy_bytes[..])
+            .expect("Failed to create NlaBuffer");
+        assert!(GenlCtrlAttrs::parse(&nla_buffer).is_err());
+    }
+
     #[test]
     fn mcast_groups_emit() {
         let mcast_attr = GenlCtrlAttrs::McastGroups(vec![
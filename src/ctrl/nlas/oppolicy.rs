@@ -12,6 +12,8 @@ use netlink_packet_utils::{
 use std::mem::size_of_val;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct OppolicyAttr {
     pub cmd: u8,
     pub policy_idx: Vec<OppolicyIndexAttr>,
@@ -51,6 +53,8 @@ impl<'a, T: AsRef<[u8]> + ?Sized> Parseable<NlaBuffer<&'a T>> for OppolicyAttr {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum OppolicyIndexAttr {
     Do(u32),
     Dump(u32),
@@ -37,6 +37,7 @@ impl From<&Vec<Vec<OpAttrs>>> for OpList {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Op {
     pub index: u16,
     pub nlas: Vec<OpAttrs>,
@@ -57,6 +58,8 @@ impl Nla for Op {
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum OpAttrs {
     Id(u32),
     Flags(u32),
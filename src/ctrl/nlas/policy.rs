@@ -1,6 +1,8 @@
 // SPDX-License-Identifier: MIT
 
 use crate::constants::*;
+#[cfg(feature = "arbitrary")]
+use crate::raw::arbitrary_nla_type;
 use anyhow::Context;
 use byteorder::{ByteOrder, NativeEndian};
 use netlink_packet_utils::{
@@ -17,7 +19,10 @@ use std::{
 // PolicyAttr
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct PolicyAttr {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_nla_type))]
     pub index: u16,
     pub attr_policy: AttributePolicyAttr,
 }
@@ -43,11 +48,13 @@ impl Nla for PolicyAttr {
 impl<'a, T: AsRef<[u8]> + ?Sized> Parseable<NlaBuffer<&'a T>> for PolicyAttr {
     fn parse(buf: &NlaBuffer<&'a T>) -> Result<Self, DecodeError> {
         let payload = buf.value();
+        let attr_policy = NlaBuffer::new_checked(payload)
+            .and_then(|nla| AttributePolicyAttr::parse(&nla))
+            .context("failed to parse PolicyAttr")?;
 
         Ok(Self {
             index: buf.kind(),
-            attr_policy: AttributePolicyAttr::parse(&NlaBuffer::new(payload))
-                .context("failed to parse PolicyAttr")?,
+            attr_policy,
         })
     }
 }
@@ -55,7 +62,10 @@ impl<'a, T: AsRef<[u8]> + ?Sized> Parseable<NlaBuffer<&'a T>> for PolicyAttr {
 // AttributePolicyAttr
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct AttributePolicyAttr {
+    #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_nla_type))]
     pub index: u16,
     pub policies: Vec<NlPolicyTypeAttrs>,
 }
@@ -98,6 +108,8 @@ impl<'a, T: AsRef<[u8]> + ?Sized> Parseable<NlaBuffer<&'a T>>
 // PolicyTypeAttrs
 
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum NlPolicyTypeAttrs {
     Type(NlaType),
     MinValueSigned(i64),
@@ -109,6 +121,8 @@ pub enum NlPolicyTypeAttrs {
     PolicyIdx(u32),
     PolicyMaxType(u32),
     Bitfield32Mask(u32),
+    /// Alignment padding emitted by the kernel in front of 64-bit values
+    Pad(Vec<u8>),
     Mask(u64),
 }
 
@@ -116,7 +130,7 @@ impl Nla for NlPolicyTypeAttrs {
     fn value_len(&self) -> usize {
         use NlPolicyTypeAttrs::*;
         match self {
-            Type(v) => size_of_val(v),
+            Type(_) => size_of::<u32>(),
             MinValueSigned(v) => size_of_val(v),
             MaxValueSigned(v) => size_of_val(v),
             MaxValueUnsigned(v) => size_of_val(v),
@@ -126,6 +140,7 @@ impl Nla for NlPolicyTypeAttrs {
             PolicyIdx(v) => size_of_val(v),
             PolicyMaxType(v) => size_of_val(v),
             Bitfield32Mask(v) => size_of_val(v),
+            Pad(v) => v.len(),
             Mask(v) => size_of_val(v),
         }
     }
@@ -136,13 +151,14 @@ impl Nla for NlPolicyTypeAttrs {
             Type(_) => NL_POLICY_TYPE_ATTR_TYPE,
             MinValueSigned(_) => NL_POLICY_TYPE_ATTR_MIN_VALUE_S,
             MaxValueSigned(_) => NL_POLICY_TYPE_ATTR_MAX_VALUE_S,
-            MaxValueUnsigned(_) => NL_POLICY_TYPE_ATTR_MIN_VALUE_U,
-            MinValueUnsigned(_) => NL_POLICY_TYPE_ATTR_MAX_VALUE_U,
+            MaxValueUnsigned(_) => NL_POLICY_TYPE_ATTR_MAX_VALUE_U,
+            MinValueUnsigned(_) => NL_POLICY_TYPE_ATTR_MIN_VALUE_U,
             MinLength(_) => NL_POLICY_TYPE_ATTR_MIN_LENGTH,
             MaxLength(_) => NL_POLICY_TYPE_ATTR_MAX_LENGTH,
             PolicyIdx(_) => NL_POLICY_TYPE_ATTR_POLICY_IDX,
             PolicyMaxType(_) => NL_POLICY_TYPE_ATTR_POLICY_MAXTYPE,
             Bitfield32Mask(_) => NL_POLICY_TYPE_ATTR_BITFIELD32_MASK,
+            Pad(_) => NL_POLICY_TYPE_ATTR_PAD,
             Mask(_) => NL_POLICY_TYPE_ATTR_MASK,
         }
     }
@@ -160,6 +176,7 @@ impl Nla for NlPolicyTypeAttrs {
             PolicyIdx(v) => NativeEndian::write_u32(buffer, *v),
             PolicyMaxType(v) => NativeEndian::write_u32(buffer, *v),
             Bitfield32Mask(v) => NativeEndian::write_u32(buffer, *v),
+            Pad(v) => buffer.copy_from_slice(v),
             Mask(v) => NativeEndian::write_u64(buffer, *v),
         }
     }
@@ -214,6 +231,7 @@ impl<'a, T: AsRef<[u8]> + ?Sized> Parseable<NlaBuffer<&'a T>>
                     "invalid NL_POLICY_TYPE_ATTR_BITFIELD32_MASK value",
                 )?)
             }
+            NL_POLICY_TYPE_ATTR_PAD => Self::Pad(payload.to_vec()),
             NL_POLICY_TYPE_ATTR_MASK => Self::Mask(
                 parse_u64(payload)
                     .context("invalid NL_POLICY_TYPE_ATTR_MASK value")?,
@@ -228,6 +246,8 @@ impl<'a, T: AsRef<[u8]> + ?Sized> Parseable<NlaBuffer<&'a T>>
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum NlaType {
     Flag,
     U8,
@@ -244,6 +264,8 @@ pub enum NlaType {
     Nested,
     NestedArray,
     Bitfield32,
+    SInt,
+    UInt,
 }
 
 impl From<NlaType> for u32 {
@@ -264,6 +286,8 @@ impl From<NlaType> for u32 {
             NlaType::Nested => NL_ATTR_TYPE_NESTED,
             NlaType::NestedArray => NL_ATTR_TYPE_NESTED_ARRAY,
             NlaType::Bitfield32 => NL_ATTR_TYPE_BITFIELD32,
+            NlaType::SInt => NL_ATTR_TYPE_SINT,
+            NlaType::UInt => NL_ATTR_TYPE_UINT,
         }
     }
 }
@@ -288,6 +312,8 @@ impl TryFrom<u32> for NlaType {
             NL_ATTR_TYPE_NESTED => NlaType::Nested,
             NL_ATTR_TYPE_NESTED_ARRAY => NlaType::NestedArray,
             NL_ATTR_TYPE_BITFIELD32 => NlaType::Bitfield32,
+            NL_ATTR_TYPE_SINT => NlaType::SInt,
+            NL_ATTR_TYPE_UINT => NlaType::UInt,
             _ => {
                 return Err(DecodeError::from(format!(
                     "invalid NLA type: {value}"
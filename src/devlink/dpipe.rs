@@ -0,0 +1,839 @@
+// SPDX-License-Identifier: MIT
+
+//! Dpipe tables of devlink devices
+//!
+//! Dpipe exposes the tables of the hardware pipeline of a device: each
+//! [`DpipeTable`] matches fields of packet headers and applies actions on
+//! them, and its [`DpipeEntry`] values can be dumped. The fields are only
+//! referred to by ID, their names and sizes are listed by the headers
+//! catalog, [`DpipeHeaders`], which also renders the values as
+//! `devlink dpipe table dump` does.
+
+use super::{
+    nldev::GenlDevlinkAttrs, DevlinkHandle, GenlDevlink, GenlDevlinkCmd,
+};
+use crate::constants::*;
+use netlink_packet_utils::DecodeError;
+use std::{
+    fmt::Write,
+    net::{Ipv4Addr, Ipv6Addr},
+};
+
+/// How a table matches a field
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DpipeMatchType {
+    /// The field equals the value
+    FieldExact,
+    Other(u32),
+}
+
+impl From<u32> for DpipeMatchType {
+    fn from(value: u32) -> Self {
+        match value {
+            DEVLINK_DPIPE_MATCH_TYPE_FIELD_EXACT => Self::FieldExact,
+            v => Self::Other(v),
+        }
+    }
+}
+
+impl From<DpipeMatchType> for u32 {
+    fn from(match_type: DpipeMatchType) -> Self {
+        match match_type {
+            DpipeMatchType::FieldExact => DEVLINK_DPIPE_MATCH_TYPE_FIELD_EXACT,
+            DpipeMatchType::Other(v) => v,
+        }
+    }
+}
+
+/// How a table acts on a field
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DpipeActionType {
+    /// The field is set to the value
+    FieldModify,
+    Other(u32),
+}
+
+impl From<u32> for DpipeActionType {
+    fn from(value: u32) -> Self {
+        match value {
+            DEVLINK_DPIPE_ACTION_TYPE_FIELD_MODIFY => Self::FieldModify,
+            v => Self::Other(v),
+        }
+    }
+}
+
+impl From<DpipeActionType> for u32 {
+    fn from(action_type: DpipeActionType) -> Self {
+        match action_type {
+            DpipeActionType::FieldModify => {
+                DEVLINK_DPIPE_ACTION_TYPE_FIELD_MODIFY
+            }
+            DpipeActionType::Other(v) => v,
+        }
+    }
+}
+
+/// What the values of a field are mapped to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum DpipeFieldMapping {
+    None,
+    /// The values are mapped to the index of a network interface
+    Ifindex,
+    Other(u32),
+}
+
+impl From<u32> for DpipeFieldMapping {
+    fn from(value: u32) -> Self {
+        match value {
+            DEVLINK_DPIPE_FIELD_MAPPING_TYPE_NONE => Self::None,
+            DEVLINK_DPIPE_FIELD_MAPPING_TYPE_IFINDEX => Self::Ifindex,
+            v => Self::Other(v),
+        }
+    }
+}
+
+impl From<DpipeFieldMapping> for u32 {
+    fn from(mapping: DpipeFieldMapping) -> Self {
+        match mapping {
+            DpipeFieldMapping::None => DEVLINK_DPIPE_FIELD_MAPPING_TYPE_NONE,
+            DpipeFieldMapping::Ifindex => {
+                DEVLINK_DPIPE_FIELD_MAPPING_TYPE_IFINDEX
+            }
+            DpipeFieldMapping::Other(v) => v,
+        }
+    }
+}
+
+/// Field of an instance of a header, as referred to by the tables
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DpipeFieldRef {
+    pub header_id: u32,
+    /// Whether the header is one of the `DEVLINK_DPIPE_HEADER_*` headers
+    /// shared by the drivers, rather than a header of the driver
+    pub header_global: bool,
+    /// Instance of the header, e.g. for encapsulated packets
+    pub header_index: u32,
+    pub field_id: u32,
+}
+
+impl DpipeFieldRef {
+    fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Result<Self, DecodeError> {
+        Ok(Self {
+            header_id: *required!(nlas, DpipeHeaderId)?,
+            header_global: *required!(nlas, DpipeHeaderGlobal)? != 0,
+            header_index: *required!(nlas, DpipeHeaderIndex)?,
+            field_id: *required!(nlas, DpipeFieldId)?,
+        })
+    }
+}
+
+/// Field matched by a table
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DpipeMatch {
+    pub match_type: DpipeMatchType,
+    pub field: DpipeFieldRef,
+}
+
+impl DpipeMatch {
+    fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Result<Self, DecodeError> {
+        Ok(Self {
+            match_type: (*required!(nlas, DpipeMatchType)?).into(),
+            field: DpipeFieldRef::from_nlas(nlas)?,
+        })
+    }
+}
+
+/// Field modified by a table
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct DpipeAction {
+    pub action_type: DpipeActionType,
+    pub field: DpipeFieldRef,
+}
+
+impl DpipeAction {
+    fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Result<Self, DecodeError> {
+        Ok(Self {
+            action_type: (*required!(nlas, DpipeActionType)?).into(),
+            field: DpipeFieldRef::from_nlas(nlas)?,
+        })
+    }
+}
+
+/// Table of the pipeline, from a `DEVLINK_CMD_DPIPE_TABLE_GET` reply
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DpipeTable {
+    pub name: String,
+    /// Number of entries of the table
+    pub size: u64,
+    pub matches: Vec<DpipeMatch>,
+    pub actions: Vec<DpipeAction>,
+    pub counters_enabled: bool,
+    /// Resource the entries are allocated from, if any
+    pub resource_id: Option<u64>,
+    /// Number of resource units used by each entry
+    pub resource_units: Option<u64>,
+}
+
+impl DpipeTable {
+    /// Read a table from the attributes nested in `DEVLINK_ATTR_DPIPE_TABLE`
+    pub fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Result<Self, DecodeError> {
+        Ok(Self {
+            name: required!(nlas, DpipeTableName)?.clone(),
+            size: *required!(nlas, DpipeTableSize)?,
+            matches: nested(attr!(nlas, DpipeTableMatches), |nla| match nla {
+                GenlDevlinkAttrs::DpipeMatch(nlas) => Some(nlas),
+                _ => None,
+            })
+            .map(|nlas| DpipeMatch::from_nlas(nlas))
+            .collect::<Result<_, _>>()?,
+            actions: nested(attr!(nlas, DpipeTableActions), |nla| match nla {
+                GenlDevlinkAttrs::DpipeAction(nlas) => Some(nlas),
+                _ => None,
+            })
+            .map(|nlas| DpipeAction::from_nlas(nlas))
+            .collect::<Result<_, _>>()?,
+            counters_enabled: *required!(nlas, DpipeTableCountersEnabled)? != 0,
+            resource_id: attr!(nlas, DpipeTableResourceId).copied(),
+            resource_units: attr!(nlas, DpipeTableResourceUnit).copied(),
+        })
+    }
+
+    /// Read the tables listed in the replies of a table dump
+    pub fn from_replies<'a, I>(replies: I) -> Result<Vec<Self>, DecodeError>
+    where
+        I: IntoIterator<Item = &'a GenlDevlink>,
+    {
+        let mut tables = Vec::new();
+        for reply in replies {
+            for nlas in nested(attr!(reply.nldev, DpipeTables), |nla| match nla
+            {
+                GenlDevlinkAttrs::DpipeTable(nlas) => Some(nlas),
+                _ => None,
+            }) {
+                tables.push(Self::from_nlas(nlas)?);
+            }
+        }
+        Ok(tables)
+    }
+
+    /// Build the request dumping the entries of the table `name`
+    pub fn entries_request(handle: &DevlinkHandle, name: &str) -> GenlDevlink {
+        let mut request = handle.request(GenlDevlinkCmd::GetDpipeEntries);
+        request
+            .nldev
+            .push(GenlDevlinkAttrs::DpipeTableName(name.to_owned()));
+        request
+    }
+
+    /// Build the request enabling or disabling the counters of the table
+    /// `name`
+    pub fn counters_set(
+        handle: &DevlinkHandle,
+        name: &str,
+        enabled: bool,
+    ) -> GenlDevlink {
+        let mut request = handle.request(GenlDevlinkCmd::SetDpipeTableCounters);
+        request.nldev.extend([
+            GenlDevlinkAttrs::DpipeTableName(name.to_owned()),
+            GenlDevlinkAttrs::DpipeTableCountersEnabled(enabled.into()),
+        ]);
+        request
+    }
+}
+
+/// Value of a field in an entry
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DpipeValue {
+    /// Bytes of the value, in the layout of the driver
+    pub value: Vec<u8>,
+    pub mask: Option<Vec<u8>>,
+    /// Value the field is mapped to, e.g. an interface index
+    pub mapping: Option<u32>,
+}
+
+impl DpipeValue {
+    fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Result<Self, DecodeError> {
+        Ok(Self {
+            value: required!(nlas, DpipeValue)?.clone(),
+            mask: attr!(nlas, DpipeValueMask).cloned(),
+            mapping: attr!(nlas, DpipeValueMapping).copied(),
+        })
+    }
+}
+
+/// Value matched by an entry
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DpipeMatchValue {
+    pub field_match: DpipeMatch,
+    pub value: DpipeValue,
+}
+
+/// Value set by an entry
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DpipeActionValue {
+    pub action: DpipeAction,
+    pub value: DpipeValue,
+}
+
+/// Entry of a table, from a `DEVLINK_CMD_DPIPE_ENTRIES_GET` reply
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DpipeEntry {
+    pub index: u64,
+    pub match_values: Vec<DpipeMatchValue>,
+    pub action_values: Vec<DpipeActionValue>,
+    /// Number of packets which hit the entry, if the counters are enabled
+    pub counter: Option<u64>,
+}
+
+impl DpipeEntry {
+    /// Read an entry from the attributes nested in `DEVLINK_ATTR_DPIPE_ENTRY`
+    pub fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Result<Self, DecodeError> {
+        let match_values =
+            nested(attr!(nlas, DpipeEntryMatchValues), |nla| match nla {
+                GenlDevlinkAttrs::DpipeMatchValue(nlas) => Some(nlas),
+                _ => None,
+            })
+            .map(|nlas| {
+                Ok(DpipeMatchValue {
+                    field_match: DpipeMatch::from_nlas(required!(
+                        nlas, DpipeMatch
+                    )?)?,
+                    value: DpipeValue::from_nlas(nlas)?,
+                })
+            })
+            .collect::<Result<_, DecodeError>>()?;
+        let action_values =
+            nested(attr!(nlas, DpipeEntryActionValues), |nla| match nla {
+                GenlDevlinkAttrs::DpipeActionValue(nlas) => Some(nlas),
+                _ => None,
+            })
+            .map(|nlas| {
+                Ok(DpipeActionValue {
+                    action: DpipeAction::from_nlas(required!(
+                        nlas,
+                        DpipeAction
+                    )?)?,
+                    value: DpipeValue::from_nlas(nlas)?,
+                })
+            })
+            .collect::<Result<_, DecodeError>>()?;
+        Ok(Self {
+            index: *required!(nlas, DpipeEntryIndex)?,
+            match_values,
+            action_values,
+            counter: attr!(nlas, DpipeEntryCounter).copied(),
+        })
+    }
+
+    /// Read the entries listed in the replies of an entries dump
+    pub fn from_replies<'a, I>(replies: I) -> Result<Vec<Self>, DecodeError>
+    where
+        I: IntoIterator<Item = &'a GenlDevlink>,
+    {
+        let mut entries = Vec::new();
+        for reply in replies {
+            for nlas in
+                nested(attr!(reply.nldev, DpipeEntries), |nla| match nla {
+                    GenlDevlinkAttrs::DpipeEntry(nlas) => Some(nlas),
+                    _ => None,
+                })
+            {
+                entries.push(Self::from_nlas(nlas)?);
+            }
+        }
+        Ok(entries)
+    }
+}
+
+/// Field of a header
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DpipeField {
+    pub name: String,
+    pub id: u32,
+    /// Size of the field, in bits
+    pub bitwidth: u32,
+    pub mapping: DpipeFieldMapping,
+}
+
+impl DpipeField {
+    fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Result<Self, DecodeError> {
+        Ok(Self {
+            name: required!(nlas, DpipeFieldName)?.clone(),
+            id: *required!(nlas, DpipeFieldId)?,
+            bitwidth: *required!(nlas, DpipeFieldBitwidth)?,
+            mapping: (*required!(nlas, DpipeFieldMappingType)?).into(),
+        })
+    }
+}
+
+/// Header of the packets, whose fields are matched and modified
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct DpipeHeader {
+    pub name: String,
+    pub id: u32,
+    pub global: bool,
+    pub fields: Vec<DpipeField>,
+}
+
+impl DpipeHeader {
+    /// Read a header from the attributes nested in
+    /// `DEVLINK_ATTR_DPIPE_HEADER`
+    pub fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Result<Self, DecodeError> {
+        Ok(Self {
+            name: required!(nlas, DpipeHeaderName)?.clone(),
+            id: *required!(nlas, DpipeHeaderId)?,
+            global: *required!(nlas, DpipeHeaderGlobal)? != 0,
+            fields: nested(attr!(nlas, DpipeHeaderFields), |nla| match nla {
+                GenlDevlinkAttrs::DpipeField(nlas) => Some(nlas),
+                _ => None,
+            })
+            .map(|nlas| DpipeField::from_nlas(nlas))
+            .collect::<Result<_, _>>()?,
+        })
+    }
+
+    /// Return the field `id` of the header
+    pub fn field(&self, id: u32) -> Option<&DpipeField> {
+        self.fields.iter().find(|field| field.id == id)
+    }
+}
+
+/// Catalog of the headers of a device, from `DEVLINK_CMD_DPIPE_HEADERS_GET`
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct DpipeHeaders {
+    headers: Vec<DpipeHeader>,
+}
+
+impl DpipeHeaders {
+    /// Build the catalog from the replies of the headers request
+    pub fn from_replies<'a, I>(replies: I) -> Result<Self, DecodeError>
+    where
+        I: IntoIterator<Item = &'a GenlDevlink>,
+    {
+        let mut headers = Vec::new();
+        for reply in replies {
+            for nlas in
+                nested(attr!(reply.nldev, DpipeHeaders), |nla| match nla {
+                    GenlDevlinkAttrs::DpipeHader(nlas) => Some(nlas),
+                    _ => None,
+                })
+            {
+                headers.push(DpipeHeader::from_nlas(nlas)?);
+            }
+        }
+        Ok(Self { headers })
+    }
+
+    /// Iterate over the headers
+    pub fn headers(&self) -> impl Iterator<Item = &DpipeHeader> {
+        self.headers.iter()
+    }
+
+    /// Return the header `id`, global or of the driver
+    pub fn header(&self, id: u32, global: bool) -> Option<&DpipeHeader> {
+        self.headers
+            .iter()
+            .find(|header| header.id == id && header.global == global)
+    }
+
+    /// Return the header and the field a table refers to
+    pub fn field(
+        &self,
+        field: &DpipeFieldRef,
+    ) -> Option<(&DpipeHeader, &DpipeField)> {
+        let header = self.header(field.header_id, field.header_global)?;
+        Some((header, header.field(field.field_id)?))
+    }
+
+    /// Name the field as `<header>.<field>`
+    ///
+    /// The IDs are used for the headers and fields missing in the catalog.
+    pub fn field_name(&self, field: &DpipeFieldRef) -> String {
+        let header = self.header(field.header_id, field.header_global);
+        let header_name = match header {
+            Some(header) => header.name.clone(),
+            None => format!("header{}", field.header_id),
+        };
+        match header.and_then(|header| header.field(field.field_id)) {
+            Some(f) => format!("{header_name}.{}", f.name),
+            None => format!("{header_name}.field{}", field.field_id),
+        }
+    }
+
+    /// Render the value of a field
+    ///
+    /// The 48-bit fields are rendered as MAC addresses, the 128-bit fields
+    /// as IPv6 addresses and the fields of the global IPv4 header as IPv4
+    /// addresses. The other values of up to 8 bytes are rendered as
+    /// integers and the longer ones in hexadecimal. The mask follows the
+    /// value, after a `/`.
+    pub fn format_value(
+        &self,
+        field: &DpipeFieldRef,
+        value: &DpipeValue,
+    ) -> String {
+        let bitwidth = self.field(field).map(|(_, f)| f.bitwidth);
+        let ipv4 =
+            field.header_global && field.header_id == DEVLINK_DPIPE_HEADER_IPV4;
+        let mut out = format_bytes(&value.value, bitwidth, ipv4);
+        if let Some(mask) = &value.mask {
+            out.push('/');
+            out.push_str(&format_bytes(mask, bitwidth, ipv4));
+        }
+        out
+    }
+
+    /// Render an entry, with one line per value as
+    /// `devlink dpipe table dump` does
+    pub fn format_entry(&self, entry: &DpipeEntry) -> String {
+        let mut out = format!("index {}", entry.index);
+        if let Some(counter) = entry.counter {
+            let _ = write!(out, " counter {counter}");
+        }
+        out.push('\n');
+        for match_value in &entry.match_values {
+            let field_match = &match_value.field_match;
+            let match_type = match field_match.match_type {
+                DpipeMatchType::FieldExact => "field_exact".to_owned(),
+                DpipeMatchType::Other(v) => v.to_string(),
+            };
+            self.write_value(
+                &mut out,
+                "match",
+                &match_type,
+                &field_match.field,
+                &match_value.value,
+            );
+        }
+        for action_value in &entry.action_values {
+            let action = &action_value.action;
+            let action_type = match action.action_type {
+                DpipeActionType::FieldModify => "field_modify".to_owned(),
+                DpipeActionType::Other(v) => v.to_string(),
+            };
+            self.write_value(
+                &mut out,
+                "action",
+                &action_type,
+                &action.field,
+                &action_value.value,
+            );
+        }
+        out
+    }
+
+    fn write_value(
+        &self,
+        out: &mut String,
+        kind: &str,
+        value_type: &str,
+        field: &DpipeFieldRef,
+        value: &DpipeValue,
+    ) {
+        let _ = write!(
+            out,
+            "  {kind} {value_type} {} {}",
+            self.field_name(field),
+            self.format_value(field, value)
+        );
+        if let Some(mapping) = value.mapping {
+            match self.field(field).map(|(_, f)| f.mapping) {
+                Some(DpipeFieldMapping::Ifindex) => {
+                    let _ = write!(out, " ifindex {mapping}");
+                }
+                _ => {
+                    let _ = write!(out, " mapping {mapping}");
+                }
+            }
+        }
+        out.push('\n');
+    }
+}
+
+// Iterate over the attributes nested in `nlas` selected by `select`
+fn nested<'a, F>(
+    nlas: Option<&'a Vec<GenlDevlinkAttrs>>,
+    select: F,
+) -> impl Iterator<Item = &'a Vec<GenlDevlinkAttrs>>
+where
+    F: Fn(&'a GenlDevlinkAttrs) -> Option<&'a Vec<GenlDevlinkAttrs>>,
+{
+    nlas.into_iter().flatten().filter_map(select)
+}
+
+fn format_bytes(bytes: &[u8], bitwidth: Option<u32>, ipv4: bool) -> String {
+    match (bytes.len(), bitwidth) {
+        (6, Some(48)) => bytes
+            .iter()
+            .map(|b| format!("{b:02x}"))
+            .collect::<Vec<_>>()
+            .join(":"),
+        (16, Some(128)) => {
+            let mut octets = [0; 16];
+            octets.copy_from_slice(bytes);
+            Ipv6Addr::from(octets).to_string()
+        }
+        // The drivers store the IPv4 addresses in host order
+        (4, _) if ipv4 => {
+            let mut octets = [0; 4];
+            octets.copy_from_slice(bytes);
+            Ipv4Addr::from(u32::from_ne_bytes(octets)).to_string()
+        }
+        (1, _) => bytes[0].to_string(),
+        (2, _) => u16::from_ne_bytes([bytes[0], bytes[1]]).to_string(),
+        (4, _) => {
+            let mut octets = [0; 4];
+            octets.copy_from_slice(bytes);
+            u32::from_ne_bytes(octets).to_string()
+        }
+        (8, _) => {
+            let mut octets = [0; 8];
+            octets.copy_from_slice(bytes);
+            u64::from_ne_bytes(octets).to_string()
+        }
+        _ => {
+            let mut out = "0x".to_owned();
+            for b in bytes {
+                let _ = write!(out, "{b:02x}");
+            }
+            out
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devlink::handle::{handle, reply};
+
+    fn field(
+        name: &str,
+        id: u32,
+        bitwidth: u32,
+        mapping: u32,
+    ) -> GenlDevlinkAttrs {
+        GenlDevlinkAttrs::DpipeField(vec![
+            GenlDevlinkAttrs::DpipeFieldName(name.to_owned()),
+            GenlDevlinkAttrs::DpipeFieldId(id),
+            GenlDevlinkAttrs::DpipeFieldBitwidth(bitwidth),
+            GenlDevlinkAttrs::DpipeFieldMappingType(mapping),
+        ])
+    }
+
+    fn header(
+        name: &str,
+        id: u32,
+        global: bool,
+        fields: Vec<GenlDevlinkAttrs>,
+    ) -> GenlDevlinkAttrs {
+        GenlDevlinkAttrs::DpipeHader(vec![
+            GenlDevlinkAttrs::DpipeHeaderName(name.to_owned()),
+            GenlDevlinkAttrs::DpipeHeaderId(id),
+            GenlDevlinkAttrs::DpipeHeaderGlobal(global.into()),
+            GenlDevlinkAttrs::DpipeHeaderFields(fields),
+        ])
+    }
+
+    // The headers of mlxsw: its metadata and the global ones
+    fn headers() -> DpipeHeaders {
+        let reply = reply(
+            GenlDevlinkCmd::GetDpipeHeaders,
+            vec![GenlDevlinkAttrs::DpipeHeaders(vec![
+                header(
+                    "mlxsw_meta",
+                    0,
+                    false,
+                    vec![
+                        field(
+                            "erif_port",
+                            0,
+                            32,
+                            DEVLINK_DPIPE_FIELD_MAPPING_TYPE_IFINDEX,
+                        ),
+                        field("l3_forward", 1, 1, 0),
+                    ],
+                ),
+                header(
+                    "ethernet",
+                    DEVLINK_DPIPE_HEADER_ETHERNET,
+                    true,
+                    vec![field("destination mac", 0, 48, 0)],
+                ),
+                header(
+                    "ipv4",
+                    DEVLINK_DPIPE_HEADER_IPV4,
+                    true,
+                    vec![field("destination ip", 0, 32, 0)],
+                ),
+                header(
+                    "ipv6",
+                    DEVLINK_DPIPE_HEADER_IPV6,
+                    true,
+                    vec![field("destination ip", 0, 128, 0)],
+                ),
+            ])],
+        );
+        DpipeHeaders::from_replies(&[reply]).unwrap()
+    }
+
+    fn field_ref(
+        header_id: u32,
+        global: bool,
+        field_id: u32,
+    ) -> Vec<GenlDevlinkAttrs> {
+        vec![
+            GenlDevlinkAttrs::DpipeHeaderId(header_id),
+            GenlDevlinkAttrs::DpipeHeaderGlobal(global.into()),
+            GenlDevlinkAttrs::DpipeHeaderIndex(0),
+            GenlDevlinkAttrs::DpipeFieldId(field_id),
+        ]
+    }
+
+    fn dpipe_match(
+        header_id: u32,
+        global: bool,
+        field_id: u32,
+    ) -> GenlDevlinkAttrs {
+        let mut nlas = vec![GenlDevlinkAttrs::DpipeMatchType(
+            DEVLINK_DPIPE_MATCH_TYPE_FIELD_EXACT,
+        )];
+        nlas.extend(field_ref(header_id, global, field_id));
+        GenlDevlinkAttrs::DpipeMatch(nlas)
+    }
+
+    fn dpipe_action(
+        header_id: u32,
+        global: bool,
+        field_id: u32,
+    ) -> GenlDevlinkAttrs {
+        let mut nlas = vec![GenlDevlinkAttrs::DpipeActionType(
+            DEVLINK_DPIPE_ACTION_TYPE_FIELD_MODIFY,
+        )];
+        nlas.extend(field_ref(header_id, global, field_id));
+        GenlDevlinkAttrs::DpipeAction(nlas)
+    }
+
+    #[test]
+    fn parse_tables() {
+        let reply = reply(
+            GenlDevlinkCmd::GetDpipeTable,
+            vec![GenlDevlinkAttrs::DpipeTables(vec![
+                GenlDevlinkAttrs::DpipeTable(vec![
+                    GenlDevlinkAttrs::DpipeTableName("mlxsw_host4".to_owned()),
+                    GenlDevlinkAttrs::DpipeTableSize(1024),
+                    GenlDevlinkAttrs::DpipeTableMatches(vec![
+                        dpipe_match(0, false, 0),
+                        dpipe_match(DEVLINK_DPIPE_HEADER_IPV4, true, 0),
+                    ]),
+                    GenlDevlinkAttrs::DpipeTableActions(vec![dpipe_action(
+                        DEVLINK_DPIPE_HEADER_ETHERNET,
+                        true,
+                        0,
+                    )]),
+                    GenlDevlinkAttrs::DpipeTableCountersEnabled(1),
+                    GenlDevlinkAttrs::DpipeTableResourceId(2),
+                    GenlDevlinkAttrs::DpipeTableResourceUnit(1),
+                ]),
+            ])],
+        );
+        let tables = DpipeTable::from_replies(&[reply]).unwrap();
+        assert_eq!(1, tables.len());
+        let table = &tables[0];
+        assert_eq!("mlxsw_host4", table.name);
+        assert!(table.counters_enabled);
+        assert_eq!(Some(2), table.resource_id);
+        assert_eq!(DpipeMatchType::FieldExact, table.matches[1].match_type);
+        assert_eq!(
+            "ipv4.destination ip",
+            headers().field_name(&table.matches[1].field)
+        );
+        assert_eq!(
+            "mlxsw_meta.erif_port",
+            headers().field_name(&table.matches[0].field)
+        );
+        assert_eq!(DpipeActionType::FieldModify, table.actions[0].action_type);
+
+        let request = DpipeTable::counters_set(&handle(), "mlxsw_host4", false);
+        assert_eq!(GenlDevlinkCmd::SetDpipeTableCounters, request.cmd);
+        assert_eq!(Some(&0), attr!(request.nldev, DpipeTableCountersEnabled));
+    }
+
+    #[test]
+    fn parse_and_format_entries() {
+        let erif = GenlDevlinkAttrs::DpipeMatchValue(vec![
+            dpipe_match(0, false, 0),
+            GenlDevlinkAttrs::DpipeValue(5u32.to_ne_bytes().to_vec()),
+            GenlDevlinkAttrs::DpipeValueMapping(12),
+        ]);
+        let dip = GenlDevlinkAttrs::DpipeMatchValue(vec![
+            dpipe_match(DEVLINK_DPIPE_HEADER_IPV4, true, 0),
+            GenlDevlinkAttrs::DpipeValue(0xc0a8_0001u32.to_ne_bytes().to_vec()),
+        ]);
+        let dmac = GenlDevlinkAttrs::DpipeActionValue(vec![
+            dpipe_action(DEVLINK_DPIPE_HEADER_ETHERNET, true, 0),
+            GenlDevlinkAttrs::DpipeValue(vec![0, 0x11, 0x22, 0x33, 0x44, 0x55]),
+        ]);
+        let reply = reply(
+            GenlDevlinkCmd::GetDpipeEntries,
+            vec![GenlDevlinkAttrs::DpipeEntries(vec![
+                GenlDevlinkAttrs::DpipeEntry(vec![
+                    GenlDevlinkAttrs::DpipeEntryIndex(3),
+                    GenlDevlinkAttrs::DpipeEntryMatchValues(vec![erif, dip]),
+                    GenlDevlinkAttrs::DpipeEntryActionValues(vec![dmac]),
+                    GenlDevlinkAttrs::DpipeEntryCounter(42),
+                ]),
+            ])],
+        );
+        let entries = DpipeEntry::from_replies(&[reply]).unwrap();
+        assert_eq!(1, entries.len());
+        assert_eq!(Some(42), entries[0].counter);
+        assert_eq!(Some(12), entries[0].match_values[0].value.mapping);
+        assert_eq!(
+            "index 3 counter 42\n\
+             \x20 match field_exact mlxsw_meta.erif_port 5 ifindex 12\n\
+             \x20 match field_exact ipv4.destination ip 192.168.0.1\n\
+             \x20 action field_modify ethernet.destination mac \
+             00:11:22:33:44:55\n",
+            headers().format_entry(&entries[0])
+        );
+    }
+
+    #[test]
+    fn format_values() {
+        let headers = headers();
+        let ipv6 = DpipeFieldRef {
+            header_id: DEVLINK_DPIPE_HEADER_IPV6,
+            header_global: true,
+            header_index: 0,
+            field_id: 0,
+        };
+        let mut value = DpipeValue {
+            value: vec![
+                0x20, 0x01, 0x0d, 0xb8, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1,
+            ],
+            mask: None,
+            mapping: None,
+        };
+        assert_eq!("2001:db8::1", headers.format_value(&ipv6, &value));
+        value.mask = Some(vec![0xff; 16]);
+        assert_eq!(
+            "2001:db8::1/ffff:ffff:ffff:ffff:ffff:ffff:ffff:ffff",
+            headers.format_value(&ipv6, &value)
+        );
+
+        // Unknown fields are rendered by their size
+        let unknown = DpipeFieldRef {
+            header_id: 7,
+            header_global: false,
+            header_index: 0,
+            field_id: 1,
+        };
+        assert_eq!("header7.field1", headers.field_name(&unknown));
+        let value = DpipeValue {
+            value: vec![1, 2, 3],
+            mask: None,
+            mapping: None,
+        };
+        assert_eq!("0x010203", headers.format_value(&unknown, &value));
+    }
+}
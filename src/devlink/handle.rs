@@ -0,0 +1,73 @@
+// SPDX-License-Identifier: MIT
+
+use super::{nldev::GenlDevlinkAttrs, GenlDevlink, GenlDevlinkCmd};
+use netlink_packet_utils::DecodeError;
+use std::fmt;
+
+/// Bus name and location identifying a devlink device
+///
+/// It is displayed as `devlink dev show` does, e.g. `pci/0000:01:00.0`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct DevlinkHandle {
+    /// Name of the bus of the device, e.g. `pci`
+    pub bus_name: String,
+    /// Location of the device on its bus, e.g. `0000:01:00.0`
+    pub location: String,
+}
+
+impl DevlinkHandle {
+    /// Create the handle of the device at `location` on the bus `bus_name`
+    pub fn new(bus_name: &str, location: &str) -> Self {
+        Self {
+            bus_name: bus_name.to_owned(),
+            location: location.to_owned(),
+        }
+    }
+
+    /// Read the handle from the attributes of a devlink message
+    pub fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Result<Self, DecodeError> {
+        Ok(Self {
+            bus_name: required!(nlas, BusName)?.clone(),
+            location: required!(nlas, Location)?.clone(),
+        })
+    }
+
+    /// Return the attributes addressing the device
+    pub fn nlas(&self) -> Vec<GenlDevlinkAttrs> {
+        vec![
+            GenlDevlinkAttrs::BusName(self.bus_name.clone()),
+            GenlDevlinkAttrs::Location(self.location.clone()),
+        ]
+    }
+
+    /// Build a request of the command `cmd` addressed to the device
+    pub fn request(&self, cmd: GenlDevlinkCmd) -> GenlDevlink {
+        GenlDevlink {
+            cmd,
+            nldev: self.nlas(),
+        }
+    }
+}
+
+impl fmt::Display for DevlinkHandle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.bus_name, self.location)
+    }
+}
+
+/// Handle of the device the unit tests talk to
+#[cfg(test)]
+pub(crate) fn handle() -> DevlinkHandle {
+    DevlinkHandle::new("pci", "0000:03:00.0")
+}
+
+/// Build a reply of the command `cmd` from the test device with `nlas`
+#[cfg(test)]
+pub(crate) fn reply(
+    cmd: GenlDevlinkCmd,
+    nlas: Vec<GenlDevlinkAttrs>,
+) -> GenlDevlink {
+    let mut reply = handle().request(cmd);
+    reply.nldev.extend(nlas);
+    reply
+}
@@ -0,0 +1,193 @@
+// SPDX-License-Identifier: MIT
+
+//! Identity and versions of devlink devices
+//!
+//! The reply of a `DEVLINK_CMD_INFO_GET` request names the driver, the
+//! serial numbers of the device and of its board, and lists the versions
+//! of its components. Each version is nested in one of three attributes:
+//!
+//! - `DEVLINK_ATTR_INFO_VERSION_FIXED`: the hardware, e.g. `board.id`;
+//! - `DEVLINK_ATTR_INFO_VERSION_RUNNING`: the firmware in use, e.g.
+//!   `fw.mgmt`;
+//! - `DEVLINK_ATTR_INFO_VERSION_STORED`: the firmware flashed, which is
+//!   used once the device is reset or reloaded.
+//!
+//! [`DeviceInfo`] gathers them by component name, see the
+//! `DEVLINK_INFO_VERSION_GENERIC_*` constants for the names defined by the
+//! kernel, and reports the components whose flashed firmware is not yet
+//! running.
+
+use super::{
+    nldev::GenlDevlinkAttrs, DevlinkHandle, GenlDevlink, GenlDevlinkCmd,
+};
+use netlink_packet_utils::DecodeError;
+use std::{collections::BTreeMap, convert::TryFrom};
+
+/// Identity and versions of a device, from a `DEVLINK_CMD_INFO_GET` reply
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DeviceInfo {
+    pub handle: DevlinkHandle,
+    pub driver_name: String,
+    /// Serial number of the device, if the driver reports it
+    pub serial_number: Option<String>,
+    /// Serial number of the board, if the driver reports it
+    pub board_serial_number: Option<String>,
+    /// Versions of the hardware, by component name
+    pub fixed: BTreeMap<String, String>,
+    /// Versions of the firmware in use, by component name
+    pub running: BTreeMap<String, String>,
+    /// Versions of the firmware flashed, by component name
+    pub stored: BTreeMap<String, String>,
+}
+
+impl DeviceInfo {
+    /// Read the information from the attributes of a reply
+    pub fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Result<Self, DecodeError> {
+        let mut info = Self {
+            handle: DevlinkHandle::from_nlas(nlas)?,
+            driver_name: required!(nlas, InfoDriverName)?.clone(),
+            serial_number: attr!(nlas, InfoSerialNo).cloned(),
+            board_serial_number: attr!(nlas, InfoBoardSerialNumber).cloned(),
+            fixed: BTreeMap::new(),
+            running: BTreeMap::new(),
+            stored: BTreeMap::new(),
+        };
+        for nla in nlas {
+            let (versions, nlas) = match nla {
+                GenlDevlinkAttrs::InfoVersionFixed(nlas) => {
+                    (&mut info.fixed, nlas)
+                }
+                GenlDevlinkAttrs::InfoVersionRunning(nlas) => {
+                    (&mut info.running, nlas)
+                }
+                GenlDevlinkAttrs::InfoVersionStored(nlas) => {
+                    (&mut info.stored, nlas)
+                }
+                _ => continue,
+            };
+            versions.insert(
+                required!(nlas, InfoVersionName)?.clone(),
+                required!(nlas, InfoVersionValue)?.clone(),
+            );
+        }
+        Ok(info)
+    }
+
+    /// Build the request reading the information of `handle`
+    pub fn request(handle: &DevlinkHandle) -> GenlDevlink {
+        handle.request(GenlDevlinkCmd::GetDeviceInfo)
+    }
+
+    /// Return the version of the component `name`: the running one for the
+    /// firmware, the fixed one for the hardware
+    pub fn version(&self, name: &str) -> Option<&str> {
+        self.running
+            .get(name)
+            .or_else(|| self.fixed.get(name))
+            .map(String::as_str)
+    }
+
+    /// Return the components whose stored version differs from the running
+    /// one, by name
+    ///
+    /// The flashed firmware of these components is activated by a reset of
+    /// the device, or by a reload with the `fw_activate` action if the
+    /// driver supports it.
+    pub fn pending_updates(&self) -> Vec<PendingUpdate> {
+        self.stored
+            .iter()
+            .filter(|&(name, stored)| self.running.get(name) != Some(stored))
+            .map(|(name, stored)| PendingUpdate {
+                component: name.clone(),
+                running: self.running.get(name).cloned(),
+                stored: stored.clone(),
+            })
+            .collect()
+    }
+}
+
+impl TryFrom<&GenlDevlink> for DeviceInfo {
+    type Error = DecodeError;
+
+    fn try_from(msg: &GenlDevlink) -> Result<Self, Self::Error> {
+        Self::from_nlas(&msg.nldev)
+    }
+}
+
+/// Firmware of a component flashed but not running yet
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PendingUpdate {
+    pub component: String,
+    /// Version in use, if the driver reports it
+    pub running: Option<String>,
+    /// Version flashed
+    pub stored: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        constants::*,
+        devlink::handle::{handle, reply},
+    };
+
+    fn version(name: &str, value: &str) -> Vec<GenlDevlinkAttrs> {
+        vec![
+            GenlDevlinkAttrs::InfoVersionName(name.to_owned()),
+            GenlDevlinkAttrs::InfoVersionValue(value.to_owned()),
+        ]
+    }
+
+    #[test]
+    fn parse_info() {
+        use GenlDevlinkAttrs::*;
+        let mut reply = reply(
+            GenlDevlinkCmd::GetDeviceInfo,
+            vec![
+                InfoDriverName("mlx5_core".to_owned()),
+                InfoSerialNo("MT2015X12345".to_owned()),
+                InfoBoardSerialNumber("MT2015X12345".to_owned()),
+                InfoVersionFixed(version("fw.psid", "MT_0000000080")),
+                InfoVersionRunning(version("fw.version", "22.31.1014")),
+                InfoVersionRunning(version("fw", "22.31.1014")),
+                InfoVersionStored(version("fw.version", "22.32.1010")),
+                InfoVersionStored(version("fw", "22.31.1014")),
+                InfoVersionStored(version("fw.bootloader", "1.2")),
+            ],
+        );
+        let info = DeviceInfo::try_from(&reply).unwrap();
+        assert_eq!(handle(), info.handle);
+        assert_eq!("mlx5_core", info.driver_name);
+        assert_eq!(Some("MT2015X12345"), info.serial_number.as_deref());
+        assert_eq!(
+            Some("MT_0000000080"),
+            info.version(DEVLINK_INFO_VERSION_GENERIC_FW_PSID)
+        );
+        assert_eq!(
+            Some("22.31.1014"),
+            info.version(DEVLINK_INFO_VERSION_GENERIC_FW)
+        );
+        assert_eq!(None, info.version(DEVLINK_INFO_VERSION_GENERIC_BOARD_ID));
+        assert_eq!(
+            vec![
+                PendingUpdate {
+                    component: "fw.bootloader".to_owned(),
+                    running: None,
+                    stored: "1.2".to_owned(),
+                },
+                PendingUpdate {
+                    component: "fw.version".to_owned(),
+                    running: Some("22.31.1014".to_owned()),
+                    stored: "22.32.1010".to_owned(),
+                },
+            ],
+            info.pending_updates()
+        );
+
+        reply.nldev.push(InfoVersionRunning(vec![InfoVersionName(
+            "fw.undi".to_owned(),
+        )]));
+        assert!(DeviceInfo::try_from(&reply).is_err());
+    }
+}
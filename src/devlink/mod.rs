@@ -4,18 +4,74 @@
 //!
 //! This module provides the definition of the devlink packet.
 //! It also serves as an example for creating a generic family.
+//!
+//! On top of the raw attributes, the submodules provide typed views of the
+//! devlink objects and builders of the requests changing them:
+//!
+//! - [`dpipe`]: dpipe tables, their entries and the headers catalog.
+//! - [`info`]: the identity of the devices and the versions of their
+//!   components.
+//! - [`port`]: ports and their functions, subfunctions, and the planning of
+//!   the port splits.
+//! - [`rate`]: the hierarchy of the rate objects shaping the transmit rate.
+//! - [`resource`]: the resource tree and the planning of its sizes.
+//! - [`sb`]: shared buffers, their pools and occupancy.
+//! - [`trap`]: packet traps, trap groups and policers, with their counters.
 
 use self::nldev::*;
-use crate::{constants::*, traits::*, GenlHeader};
-use anyhow::Context;
-use netlink_packet_utils::{nla::NlasIterator, traits::*, DecodeError};
+use crate::{
+    constants::*,
+    error::{parse_nlas, GenlDecodeError},
+    traits::*,
+    GenlHeader,
+};
+use netlink_packet_utils::{traits::*, DecodeError};
 use std::convert::{TryFrom, TryInto};
 
+// Find the value of the first attribute of the given variant
+macro_rules! attr {
+    ($nlas:expr, $variant:ident) => {
+        $nlas.iter().find_map(|nla| match nla {
+            $crate::devlink::nldev::GenlDevlinkAttrs::$variant(v) => Some(v),
+            _ => None,
+        })
+    };
+}
+
+// Same as `attr!`, failing with a `DecodeError` if the attribute is missing
+macro_rules! required {
+    ($nlas:expr, $variant:ident) => {
+        attr!($nlas, $variant).ok_or_else(|| {
+            netlink_packet_utils::DecodeError::from(concat!(
+                "missing ",
+                stringify!($variant),
+                " attribute"
+            ))
+        })
+    };
+}
+
+pub mod dpipe;
+
+mod handle;
+pub use self::handle::DevlinkHandle;
+pub mod info;
+
 /// Netlink attributes for this family
 pub mod nldev;
+pub mod port;
+pub mod rate;
+pub mod resource;
+
+pub mod sb;
+pub mod trap;
+
+#[cfg(feature = "mock")]
+pub mod sim;
 
 /// Command code definition of Netlink Devlink family
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum GenlDevlinkCmd {
     GetDevices,
     SetDevice,
@@ -282,6 +338,7 @@ impl TryFrom<u8> for GenlDevlinkCmd {
 
 /// Payload of generic netlink controller
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GenlDevlink {
     /// Command code of this message
     pub cmd: GenlDevlinkCmd,
@@ -305,6 +362,10 @@ impl GenlFamily for GenlDevlink {
     fn version(&self) -> u8 {
         1
     }
+
+    fn multicast_groups() -> &'static [&'static str] {
+        &["config"]
+    }
 }
 
 impl Emitable for GenlDevlink {
@@ -317,25 +378,43 @@ impl Emitable for GenlDevlink {
     }
 }
 
-impl ParseableParametrized<[u8], GenlHeader> for GenlDevlink {
-    fn parse_with_param(
+impl GenlDevlink {
+    /// Decode the payload of a devlink message
+    ///
+    /// `buf` holds the attributes following the generic netlink header.
+    /// Unlike the [`ParseableParametrized`] implementation, the error tells
+    /// which attribute failed to parse, down to the nested attributes.
+    pub fn decode(
         buf: &[u8],
         header: GenlHeader,
-    ) -> Result<Self, DecodeError> {
+    ) -> Result<Self, GenlDecodeError> {
         Ok(Self {
-            cmd: header.cmd.try_into()?,
-            nldev: parse_ctrlnldev(buf)?,
+            cmd: header.cmd.try_into().map_err(|e| {
+                GenlDecodeError::new(Self::family_name(), header.cmd, e)
+            })?,
+            nldev: parse_ctrlnldev(buf, header.cmd)?,
         })
     }
 }
 
-fn parse_ctrlnldev(buf: &[u8]) -> Result<Vec<GenlDevlinkAttrs>, DecodeError> {
-    let nldev = NlasIterator::new(buf)
-        .map(|nlattr| {
-            nlattr.and_then(|nlattr| GenlDevlinkAttrs::parse(&nlattr))
-        })
-        .collect::<Result<Vec<_>, _>>()
-        .context("failed to parse control message attributes")?;
+impl ParseableParametrized<[u8], GenlHeader> for GenlDevlink {
+    fn parse_with_param(
+        buf: &[u8],
+        header: GenlHeader,
+    ) -> Result<Self, DecodeError> {
+        Ok(Self::decode(buf, header)?)
+    }
+}
 
-    Ok(nldev)
+fn parse_ctrlnldev(
+    buf: &[u8],
+    cmd: u8,
+) -> Result<Vec<GenlDevlinkAttrs>, GenlDecodeError> {
+    parse_nlas(
+        GenlDevlink::family_name(),
+        cmd,
+        buf,
+        |nla| GenlDevlinkAttrs::parse(nla),
+        nests_devlink_attrs,
+    )
 }
@@ -1,6 +1,8 @@
 // SPDX-License-Identifier: MIT
 
-use crate::constants::*;
+#[cfg(feature = "arbitrary")]
+use crate::raw::raw_nla_from_parts;
+use crate::{constants::*, raw::RawNla};
 use anyhow::Context;
 use byteorder::{ByteOrder, NativeEndian};
 use netlink_packet_utils::{
@@ -12,7 +14,12 @@ use netlink_packet_utils::{
 use std::mem::size_of_val;
 use std::fmt;
 
+mod port_function;
+pub use self::port_function::*;
+
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub enum GenlDevlinkAttrs {
     BusName(String),
     Location(String),
@@ -59,8 +66,8 @@ pub enum GenlDevlinkAttrs {
     DpipeAction(Vec<GenlDevlinkAttrs>),
     DpipeActionValue(Vec<GenlDevlinkAttrs>),
     DpipeActionType(u32),
-    DpipeValue(u32),
-    DpipeValueMask(u32),
+    DpipeValue(Vec<u8>),
+    DpipeValueMask(Vec<u8>),
     DpipeValueMapping(u32),
     DpipeHeaders(Vec<GenlDevlinkAttrs>),
     DpipeHader(Vec<GenlDevlinkAttrs>),
@@ -93,11 +100,14 @@ pub enum GenlDevlinkAttrs {
     PortNumber(u32),
     Param(Vec<GenlDevlinkAttrs>),
     ParamName(String),
-    ParamGeneric(bool),
+    ParamGeneric(
+        #[cfg_attr(feature = "arbitrary", arbitrary(value = true))] bool,
+    ),
     ParamType(u8),
     ParamValueList(Vec<GenlDevlinkAttrs>),
-    ParamValue(u64),
-    ParamValueData(Vec<GenlDevlinkAttrs>),
+    ParamValue(Vec<GenlDevlinkAttrs>),
+    /// Value of the type given by `ParamType`
+    ParamValueData(Vec<u8>),
     ParamValueCmode(u8),
     RegionName(String),
     RegionSize(u64),
@@ -118,13 +128,22 @@ pub enum GenlDevlinkAttrs {
     InfoVersionValue(String),
     SbPoolCellSize(u32),
     Fmsg(Vec<GenlDevlinkAttrs>),
-    FmsgObjNestStart(bool),
-    FmsgPairNestStart(bool),
-    FmsgArrNestStart(bool),
-    FmsgNestEnd(bool),
+    FmsgObjNestStart(
+        #[cfg_attr(feature = "arbitrary", arbitrary(value = true))] bool,
+    ),
+    FmsgPairNestStart(
+        #[cfg_attr(feature = "arbitrary", arbitrary(value = true))] bool,
+    ),
+    FmsgArrNestStart(
+        #[cfg_attr(feature = "arbitrary", arbitrary(value = true))] bool,
+    ),
+    FmsgNestEnd(
+        #[cfg_attr(feature = "arbitrary", arbitrary(value = true))] bool,
+    ),
     FmsgObjName(String),
     FmsgObjValueType(u8),
-    FmsgObjValueData(Vec<GenlDevlinkAttrs>),
+    /// Value of the type given by `FmsgObjValueType`
+    FmsgObjValueData(Vec<u8>),
     HealthReporter(Vec<GenlDevlinkAttrs>),
     HealthReporterName(String),
     HealthReporterState(u8),
@@ -140,12 +159,14 @@ pub enum GenlDevlinkAttrs {
     FlashUpdateStatusTotal(u64),
     PortPciPfNumber(u16),
     PortPciVfNumber(u16),
-    Stats(Vec<GenlDevlinkAttrs>),
+    Stats(Vec<RawNla>),
     TrapName(String),
     TrapAction(u8),
     TrapType(u8),
-    TrapGeneric(bool),
-    TrapMetadata(Vec<GenlDevlinkAttrs>),
+    TrapGeneric(
+        #[cfg_attr(feature = "arbitrary", arbitrary(value = true))] bool,
+    ),
+    TrapMetadata(Vec<RawNla>),
     TrapGroupName(String),
     ReloadStatus(u8),
     HealthReporterDumpTsNs(u64),
@@ -156,7 +177,7 @@ pub enum GenlDevlinkAttrs {
     TrapPolicerId(u32),
     TrapPolicerRate(u64),
     TrapPolicerBurst(u64),
-    PortFunction(Vec<GenlDevlinkAttrs>),
+    PortFunction(Vec<PortFunctionAttrs>),
     InfoBoardSerialNumber(String),
     PortLanes(u32),
     PortSplittable(u8),
@@ -182,6 +203,12 @@ pub enum GenlDevlinkAttrs {
     RateNodeName(String),
     RateParentNodeName(String),
     RegionMaxSnapshots(u32),
+    PortSplitSubportNumber(u32),
+    /// Attribute unknown to this crate, kept as is
+    Other(
+        #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_other))]
+        RawNla,
+    ),
 }
 
 impl GenlDevlinkAttrs {
@@ -190,6 +217,7 @@ impl GenlDevlinkAttrs {
         match self {
             Param(v) => Some(v.clone()),
             ParamValueList(v) => Some(v.clone()),
+            ParamValue(v) => Some(v.clone()),
             RegionSnapshots(v) => Some(v.clone()),
             RegionSnapshot(v) => Some(v.clone()),
             RegionChunks(v) => Some(v.clone()),
@@ -221,13 +249,8 @@ impl GenlDevlinkAttrs {
             DpipeField(v) => Some(v.clone()),
             ResourceList(v) => Some(v.clone()),
             Resource(v) => Some(v.clone()),
-            ParamValueData(v) => Some(v.clone()),
             Fmsg(v) => Some(v.clone()),
-            FmsgObjValueData(v) => Some(v.clone()),
             HealthReporter(v) => Some(v.clone()),
-            TrapMetadata(v) => Some(v.clone()),
-            PortFunction(v) => Some(v.clone()),
-            Stats(v) => Some(v  .clone()),
             _ => None,
         }
     }
@@ -403,6 +426,8 @@ impl GenlDevlinkAttrs {
             RateNodeName(_) => "RateNodeName".to_string(),
             RateParentNodeName(_) => "RateParentNodeName".to_string(),
             RegionMaxSnapshots(_) => "RegionMaxSnapshots".to_string(),
+            PortSplitSubportNumber(_) => "PortSplitSubportNumber".to_string(),
+            Other(_) => "Other".to_string(),
         }
     }
 }
@@ -444,8 +469,8 @@ impl fmt::Display for GenlDevlinkAttrs {
             DpipeEntryCounter(v) => write!(f, "DpipeEntryCounter: {v}"),
             DpipeMatchType(v) => write!(f, "DpipeMatchType: {v}"),
             DpipeActionType(v) => write!(f, "DpipeActionType: {v}"),
-            DpipeValue(v) => write!(f, "DpipeValue: {v}"),
-            DpipeValueMask(v) => write!(f, "DpipeValueMask: {v}"),
+            DpipeValue(v) => write!(f, "DpipeValue: {v:?}"),
+            DpipeValueMask(v) => write!(f, "DpipeValueMask: {v:?}"),
             DpipeValueMapping(v) => write!(f, "DpipeValueMapping: {v}"),
             DpipeHeaderName(s) => write!(f, "DpipeHeaderName: {s}"),
             DpipeHeaderId(v) => write!(f, "DpipeHeaderId: {v}"),
@@ -473,7 +498,6 @@ impl fmt::Display for GenlDevlinkAttrs {
             ParamName(s) => write!(f, "ParamName: {s}"),
             ParamGeneric(v) => write!(f, "ParamGeneric: {v}"),
             ParamType(v) => write!(f, "ParamType: {v}"),
-            ParamValue(v) => write!(f, "ParamValue: {v}"),
             ParamValueCmode(v) => write!(f, "ParamValueCmode: {v}"),
             RegionName(s) => write!(f, "RegionName: {s}"),
             RegionSize(v) => write!(f, "RegionSize: {v}"),
@@ -539,6 +563,17 @@ impl fmt::Display for GenlDevlinkAttrs {
             PortControllerNo(v) => write!(f, "PortControllerNo: {v}"),
             ReloadStatsLimit(v) => write!(f, "ReloadStatsLimit: {v}"),
             ReloadStatsValue(v) => write!(f, "ReloadStatsValue: {v}"),
+            PortSplitSubportNumber(v) => {
+                write!(f, "PortSplitSubportNumber: {v}")
+            }
+            ParamValueData(v) => write!(f, "ParamValueData: {v:?}"),
+            FmsgObjValueData(v) => write!(f, "FmsgObjValueData: {v:?}"),
+            Stats(v) => write!(f, "Stats: {v:?}"),
+            TrapMetadata(v) => write!(f, "TrapMetadata: {v:?}"),
+            PortFunction(v) => write!(f, "PortFunction: {v:?}"),
+            Other(nla) => {
+                write!(f, "Other({}): {:?}", nla.attr_type(), nla.value())
+            }
 
             RemoteReloadStats(v) |
             ReloadActionInfo(v) |
@@ -546,9 +581,6 @@ impl fmt::Display for GenlDevlinkAttrs {
             DevStats(v) |
             ReloadStats(v) |
             ReloadStatsEntry(v) |
-            PortFunction(v) |
-            TrapMetadata(v) |
-            Stats(v) |
             DpipeEntries(v) |
             DpipeEntry(v) |
             DpipeEntryMatchValues(v) |
@@ -565,7 +597,7 @@ impl fmt::Display for GenlDevlinkAttrs {
             Resource(v) |
             Param(v) |
             ParamValueList(v) |
-            ParamValueData(v) |
+            ParamValue(v) |
             RegionSnapshots(v) |
             RegionSnapshot(v) |
             RegionChunks(v) |
@@ -577,7 +609,6 @@ impl fmt::Display for GenlDevlinkAttrs {
             DpipeTable(v) |
             DpipeTableMatches(v) |
             DpipeTableActions(v) |
-            FmsgObjValueData(v) |
             HealthReporter(v) |
             DpipeTables(v) => {
                 let attibutes = v
@@ -606,11 +637,11 @@ impl Nla for GenlDevlinkAttrs {
             PortNumber(v) => size_of_val(v),
             Param(v) => v.iter().map(|nla| nla.buffer_len()).sum(),
             ParamName(s) => s.len() + 1,
-            ParamGeneric(v) => size_of_val(v),
+            ParamGeneric(_) => 0,
             ParamType(v) => size_of_val(v),
             ParamValueList(v) => v.iter().map(|nla| nla.buffer_len()).sum(),
-            ParamValue(v) => size_of_val(v),
-            ParamValueData(v) => v.iter().map(|nla| nla.buffer_len()).sum(),
+            ParamValue(v) => v.as_slice().buffer_len(),
+            ParamValueData(v) => v.len(),
             ParamValueCmode(v) => size_of_val(v),
             RegionName(s) => s.len() + 1,
             RegionSize(v) => size_of_val(v),
@@ -679,8 +710,8 @@ impl Nla for GenlDevlinkAttrs {
             DpipeAction(v) => v.iter().map(|nla| nla.buffer_len()).sum(),
             DpipeActionValue(v) => v.iter().map(|nla| nla.buffer_len()).sum(),
             DpipeActionType(v) => size_of_val(v),
-            DpipeValue(v) => size_of_val(v),
-            DpipeValueMask(v) => size_of_val(v),
+            DpipeValue(v) => v.len(),
+            DpipeValueMask(v) => v.len(),
             DpipeValueMapping(v) => size_of_val(v),
             DpipeHeaders(v) => v.iter().map(|nla| nla.buffer_len()).sum(),
             DpipeHader(v) => v.iter().map(|nla| nla.buffer_len()).sum(),
@@ -727,13 +758,13 @@ impl Nla for GenlDevlinkAttrs {
             RateParentNodeName(s) => s.len() + 1,
             SbPoolCellSize(v) => size_of_val(v),
             Fmsg(v) => v.iter().map(|nla| nla.buffer_len()).sum(),
-            FmsgObjNestStart(v) => size_of_val(v),
-            FmsgPairNestStart(v) => size_of_val(v),
-            FmsgArrNestStart(v) => size_of_val(v),
-            FmsgNestEnd(v) => size_of_val(v),
+            FmsgObjNestStart(_) => 0,
+            FmsgPairNestStart(_) => 0,
+            FmsgArrNestStart(_) => 0,
+            FmsgNestEnd(_) => 0,
             FmsgObjName(s) => s.len() + 1,
             FmsgObjValueType(v) => size_of_val(v),
-            FmsgObjValueData(v) => v.iter().map(|nla| nla.buffer_len()).sum(),
+            FmsgObjValueData(v) => v.len(),
             HealthReporter(v) => v.iter().map(|nla| nla.buffer_len()).sum(),
             HealthReporterName(s) => s.len() + 1,
             HealthReporterState(v) => size_of_val(v),
@@ -752,7 +783,7 @@ impl Nla for GenlDevlinkAttrs {
             TrapName(s) => s.len() + 1,
             TrapAction(v) => size_of_val(v),
             TrapType(v) => size_of_val(v),
-            TrapGeneric(v) => size_of_val(v),
+            TrapGeneric(_) => 0,
             TrapMetadata(v) => v.iter().map(|nla| nla.buffer_len()).sum(),
             TrapGroupName(s) => s.len() + 1,
             HealthReporterDumpTsNs(v) => size_of_val(v),
@@ -763,9 +794,12 @@ impl Nla for GenlDevlinkAttrs {
             TrapPolicerId(v) => size_of_val(v),
             TrapPolicerRate(v) => size_of_val(v),
             TrapPolicerBurst(v) => size_of_val(v),
+            PortSplitSubportNumber(v) => size_of_val(v),
+            Other(nla) => nla.value_len(),
         }
     }
 
+    // The kernel only sets NLA_F_NESTED on the nests added after Linux 5.2
     fn is_nested(&self) -> bool {
         use GenlDevlinkAttrs::*;
         match self {
@@ -778,29 +812,29 @@ impl Nla for GenlDevlinkAttrs {
             NetdevName(_) => false,
             PortFlavour(_) => false,
             PortNumber(_) => false,
-            Param(_) => true,
+            Param(_) => false,
             ParamName(_) => false,
             ParamGeneric(_) => false,
             ParamType(_) => false,
-            ParamValueList(_) => true,
-            ParamValue(_) => true,
+            ParamValueList(_) => false,
+            ParamValue(_) => false,
             ParamValueData(_) => false,
             ParamValueCmode(_) => false,
             RegionName(_) => false,
             RegionSize(_) => false,
-            RegionSnapshots(_) => true,
-            RegionSnapshot(_) => true,
+            RegionSnapshots(_) => false,
+            RegionSnapshot(_) => false,
             RegionSnapshotId(_) => false,
-            RegionChunks(_) => true,
-            RegionChunk(_) => true,
+            RegionChunks(_) => false,
+            RegionChunk(_) => false,
             RegionChunkData(_) => false,
             RegionChunkOffset(_) => false,
             RegionChunkSize(_) => false,
             InfoDriverName(_) => false,
             InfoSerialNo(_) => false,
-            InfoVersionFixed(_) => true,
-            InfoVersionRunning(_) => true,
-            InfoVersionStored(_) => true,
+            InfoVersionFixed(_) => false,
+            InfoVersionRunning(_) => false,
+            InfoVersionStored(_) => false,
             InfoVersionName(_) => false,
             InfoVersionValue(_) => false,
             FlashUpdateFileName(_) => false,
@@ -824,7 +858,7 @@ impl Nla for GenlDevlinkAttrs {
             TrapGeneric(_) => false,
             TrapMetadata(_) => true,
             TrapGroupName(_) => false,
-            HealthReporter(_) => true,
+            HealthReporter(_) => false,
             HealthReporterName(_) => false,
             HealthReporterState(_) => false,
             HealthReporterErrCount(_) => false,
@@ -837,7 +871,7 @@ impl Nla for GenlDevlinkAttrs {
             FlashUpdateStatusDone(_) => false,
             FlashUpdateStatusTotal(_) => false,
             SbPoolCellSize(_) => false,
-            Fmsg(_) => true,
+            Fmsg(_) => false,
             FmsgObjNestStart(_) => false,
             FmsgPairNestStart(_) => false,
             FmsgArrNestStart(_) => false,
@@ -853,7 +887,7 @@ impl Nla for GenlDevlinkAttrs {
             TrapPolicerId(_) => false,
             TrapPolicerRate(_) => false,
             TrapPolicerBurst(_) => false,
-            PortFunction(_) => true,
+            PortFunction(_) => false,
             InfoBoardSerialNumber(_) => false,
             PortLanes(_) => false,
             PortSplittable(_) => false,
@@ -887,43 +921,43 @@ impl Nla for GenlDevlinkAttrs {
             SbOccMax(_) => false,
             EswitchMode(_) => false,
             EswitchInlineMode(_) => false,
-            DpipeTables(_) => true,
-            DpipeTable(_) => true,
+            DpipeTables(_) => false,
+            DpipeTable(_) => false,
             DpipeTableName(_) => false,
             DpipeTableSize(_) => false,
-            DpipeTableMatches(_) => true,
-            DpipeTableActions(_) => true,
+            DpipeTableMatches(_) => false,
+            DpipeTableActions(_) => false,
             DpipeTableCountersEnabled(_) => false,
-            DpipeEntries(_) => true,
-            DpipeEntry(_) => true,
+            DpipeEntries(_) => false,
+            DpipeEntry(_) => false,
             DpipeEntryIndex(_) => false,
-            DpipeEntryMatchValues(_) => true,
-            DpipeEntryActionValues(_) => true,
+            DpipeEntryMatchValues(_) => false,
+            DpipeEntryActionValues(_) => false,
             DpipeEntryCounter(_) => false,
-            DpipeMatch(_) => true,
-            DpipeMatchValue(_) => true,
+            DpipeMatch(_) => false,
+            DpipeMatchValue(_) => false,
             DpipeMatchType(_) => false,
-            DpipeAction(_) => true,
-            DpipeActionValue(_) => true,
+            DpipeAction(_) => false,
+            DpipeActionValue(_) => false,
             DpipeActionType(_) => false,
             DpipeValue(_) => false,
             DpipeValueMask(_) => false,
             DpipeValueMapping(_) => false,
-            DpipeHeaders(_) => true,
-            DpipeHader(_) => true,
+            DpipeHeaders(_) => false,
+            DpipeHader(_) => false,
             DpipeHeaderName(_) => false,
             DpipeHeaderId(_) => false,
-            DpipeHeaderFields(_) => true,
+            DpipeHeaderFields(_) => false,
             DpipeHeaderGlobal(_) => false,
             DpipeHeaderIndex(_) => false,
-            DpipeField(_) => true,
+            DpipeField(_) => false,
             DpipeFieldName(_) => false,
             DpipeFieldId(_) => false,
             DpipeFieldBitwidth(_) => false,
             DpipeFieldMappingType(_) => false,
             EswitchEncapMode(_) => false,
-            ResourceList(_) => true,
-            Resource(_) => true,
+            ResourceList(_) => false,
+            Resource(_) => false,
             ResoureceName(_) => false,
             ResourceId(_) => false,
             ResourceSize(_) => false,
@@ -937,8 +971,16 @@ impl Nla for GenlDevlinkAttrs {
             DpipeTableResourceId(_) => false,
             DpipeTableResourceUnit(_) => false,
             PortIbdevName(_) => false,
+            PortSplitSubportNumber(_) => false,
+            Other(nla) => nla.is_nested(),
         }
+    }
 
+    fn is_network_byteorder(&self) -> bool {
+        match self {
+            Self::Other(nla) => nla.is_network_byteorder(),
+            _ => false,
+        }
     }
 
     fn kind(&self) -> u16 {
@@ -970,7 +1012,7 @@ impl Nla for GenlDevlinkAttrs {
             RegionChunk(_) => DEVLINK_ATTR_REGION_CHUNK,
             RegionChunkData(_) => DEVLINK_ATTR_REGION_CHUNK_DATA,
             RegionChunkOffset(_) => DEVLINK_ATTR_REGION_CHUNK_ADDR,
-            RegionChunkSize(_) => DEVLINK_ATTR_REGION_SIZE,
+            RegionChunkSize(_) => DEVLINK_ATTR_REGION_CHUNK_LEN,
             InfoDriverName(_) => DEVLINK_ATTR_INFO_DRIVER_NAME,
             InfoSerialNo(_) => DEVLINK_ATTR_INFO_SERIAL_NUMBER,
             InfoVersionFixed(_) => DEVLINK_ATTR_INFO_VERSION_FIXED,
@@ -1045,6 +1087,8 @@ impl Nla for GenlDevlinkAttrs {
             RateNodeName(_) => DEVLINK_ATTR_RATE_NODE_NAME,
             RateParentNodeName(_) => DEVLINK_ATTR_RATE_PARENT_NODE_NAME,
             PortSplitCount(_) => DEVLINK_ATTR_PORT_SPLIT_COUNT,
+            PortSplitSubportNumber(_) => DEVLINK_ATTR_PORT_SPLIT_SUBPORT_NUMBER,
+            Other(nla) => nla.kind(),
             PortSplitGroup(_) => DEVLINK_ATTR_PORT_SPLIT_GROUP,
             SbIndex(_) => DEVLINK_ATTR_SB_INDEX,
             SbSize(_) => DEVLINK_ATTR_SB_SIZE,
@@ -1137,21 +1181,20 @@ impl Nla for GenlDevlinkAttrs {
             PortFlavour(v) => NativeEndian::write_u16(buffer, *v),
             PortNumber(v) => NativeEndian::write_u32(buffer, *v),
             Param(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             ParamName(s) => {
                 buffer[..s.len()].copy_from_slice(s.as_bytes());
                 buffer[s.len()] = 0;
             }
-            ParamGeneric(v) => buffer[0] = *v as u8,
             ParamType(v) => buffer[0] = *v,
             ParamValueList(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
-            ParamValue(v) => NativeEndian::write_u64(buffer, *v),
-            ParamValueData(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+            ParamValue(nla) => {
+                nla.as_slice().emit(buffer);
             }
+            ParamValueData(v) => buffer.copy_from_slice(v),
             ParamValueCmode(v) => buffer[0] = *v,
             RegionName(s) => {
                 buffer[..s.len()].copy_from_slice(s.as_bytes());
@@ -1159,17 +1202,17 @@ impl Nla for GenlDevlinkAttrs {
             }
             RegionSize(v) => NativeEndian::write_u64(buffer, *v),
             RegionSnapshots(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             RegionSnapshot(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             RegionSnapshotId(v) => NativeEndian::write_u32(buffer, *v),
             RegionChunks(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             RegionChunk(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             RegionChunkData(s) => {
                 buffer[..s.len()].copy_from_slice(s);
@@ -1185,13 +1228,13 @@ impl Nla for GenlDevlinkAttrs {
                 buffer[s.len()] = 0;
             }
             InfoVersionFixed(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             InfoVersionRunning(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             InfoVersionStored(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             InfoVersionName(s) => {
                 buffer[..s.len()].copy_from_slice(s.as_bytes());
@@ -1208,30 +1251,30 @@ impl Nla for GenlDevlinkAttrs {
             ReloadStatus(v) => buffer[0] = *v,
             ReloadAction(v) => buffer[0] = *v,
             DevStats(v) => {
-                v.iter().for_each(|val| val.emit_value(buffer));
+                v.as_slice().emit(buffer);
             }
             ReloadStats(v) => {
-                v.iter().for_each(|val| val.emit_value(buffer));
+                v.as_slice().emit(buffer);
             }
             ReloadStatsEntry(v) => {
-                v.iter().for_each(|val| val.emit_value(buffer));
+                v.as_slice().emit(buffer);
             }
             ReloadStatsLimit(v) => buffer[0] = *v,
             ReloadStatsValue(v) => NativeEndian::write_u32(buffer, *v),
             RemoteReloadStats(v) => {
-                v.iter().for_each(|val| val.emit_value(buffer));
+                v.as_slice().emit(buffer);
             }
             ReloadActionInfo(v) => {
-                v.iter().for_each(|val| val.emit_value(buffer));
+                v.as_slice().emit(buffer);
             }
             ReloadActionStats(v) => {
-                v.iter().for_each(|val| val.emit_value(buffer));
+                v.as_slice().emit(buffer);
             }
             RegionMaxSnapshots(v) => NativeEndian::write_u32(buffer, *v),
             PortPciPfNumber(v) => NativeEndian::write_u16(buffer, *v),
             PortPciVfNumber(v) => NativeEndian::write_u16(buffer, *v),
             Stats(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             TrapName(s) => {
                 buffer[..s.len()].copy_from_slice(s.as_bytes());
@@ -1239,16 +1282,15 @@ impl Nla for GenlDevlinkAttrs {
             }
             TrapAction(v) => buffer[0] = *v,
             TrapType(v) => buffer[0] = *v,
-            TrapGeneric(v) => buffer[0] = *v as u8,
             TrapMetadata(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             TrapGroupName(s) => {
                 buffer[..s.len()].copy_from_slice(s.as_bytes());
                 buffer[s.len()] = 0;
             }
             HealthReporter(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             HealthReporterName(s) => {
                 buffer[..s.len()].copy_from_slice(s.as_bytes());
@@ -1272,20 +1314,21 @@ impl Nla for GenlDevlinkAttrs {
             FlashUpdateStatusTotal(v) => NativeEndian::write_u64(buffer, *v),
             SbPoolCellSize(v) => NativeEndian::write_u32(buffer, *v),
             Fmsg(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
-            }
-            FmsgObjNestStart(v) => buffer[0] = *v as u8,
-            FmsgPairNestStart(v) => buffer[0] = *v as u8,
-            FmsgArrNestStart(v) => buffer[0] = *v as u8,
-            FmsgNestEnd(v) => buffer[0] = *v as u8,
+                nla.as_slice().emit(buffer);
+            }
+            // Flags have no value, only their presence matters
+            ParamGeneric(_)
+            | TrapGeneric(_)
+            | FmsgObjNestStart(_)
+            | FmsgPairNestStart(_)
+            | FmsgArrNestStart(_)
+            | FmsgNestEnd(_) => {}
             FmsgObjName(s) => {
                 buffer[..s.len()].copy_from_slice(s.as_bytes());
                 buffer[s.len()] = 0;
             }
             FmsgObjValueType(v) => buffer[0] = *v,
-            FmsgObjValueData(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
-            }
+            FmsgObjValueData(v) => buffer.copy_from_slice(v),
             HealthReporterDumpTsNs(v) => NativeEndian::write_u64(buffer, *v),
             NetnsFd(v) => NativeEndian::write_u32(buffer, *v),
             NetnsPid(v) => NativeEndian::write_u32(buffer, *v),
@@ -1295,7 +1338,7 @@ impl Nla for GenlDevlinkAttrs {
             TrapPolicerRate(v) => NativeEndian::write_u64(buffer, *v),
             TrapPolicerBurst(v) => NativeEndian::write_u64(buffer, *v),
             PortFunction(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             InfoBoardSerialNumber(s) => {
                 buffer[..s.len()].copy_from_slice(s.as_bytes());
@@ -1326,6 +1369,8 @@ impl Nla for GenlDevlinkAttrs {
                 buffer[s.len()] = 0;
             }
             PortSplitCount(v) => NativeEndian::write_u32(buffer, *v),
+            PortSplitSubportNumber(v) => NativeEndian::write_u32(buffer, *v),
+            Other(nla) => nla.emit_value(buffer),
             PortSplitGroup(v) => NativeEndian::write_u32(buffer, *v),
             SbIndex(v) => NativeEndian::write_u32(buffer, *v),
             SbSize(v) => NativeEndian::write_u32(buffer, *v),
@@ -1344,10 +1389,10 @@ impl Nla for GenlDevlinkAttrs {
             EswitchMode(v) => NativeEndian::write_u16(buffer, *v),
             EswitchInlineMode(v) => buffer[0] = *v,
             DpipeTables(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             DpipeTable(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             DpipeTableName(s) => {
                 buffer[..s.len()].copy_from_slice(s.as_bytes());
@@ -1355,48 +1400,48 @@ impl Nla for GenlDevlinkAttrs {
             }
             DpipeTableSize(v) => NativeEndian::write_u64(buffer, *v),
             DpipeTableMatches(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             DpipeTableActions(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             DpipeTableCountersEnabled(v) => buffer[0] = *v,
             DpipeEntries(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             DpipeEntry(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             DpipeEntryIndex(v) => NativeEndian::write_u64(buffer, *v),
             DpipeEntryMatchValues(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             DpipeEntryActionValues(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             DpipeEntryCounter(v) => NativeEndian::write_u64(buffer, *v),
             DpipeMatch(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             DpipeMatchValue(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             DpipeMatchType(v) => NativeEndian::write_u32(buffer, *v),
             DpipeAction(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             DpipeActionValue(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             DpipeActionType(v) => NativeEndian::write_u32(buffer, *v),
-            DpipeValue(v) => NativeEndian::write_u32(buffer, *v),
-            DpipeValueMask(v) => NativeEndian::write_u32(buffer, *v),
+            DpipeValue(v) => buffer.copy_from_slice(v),
+            DpipeValueMask(v) => buffer.copy_from_slice(v),
             DpipeValueMapping(v) => NativeEndian::write_u32(buffer, *v),
             DpipeHeaders(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             DpipeHader(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             DpipeHeaderName(s) => {
                 buffer[..s.len()].copy_from_slice(s.as_bytes());
@@ -1404,12 +1449,12 @@ impl Nla for GenlDevlinkAttrs {
             }
             DpipeHeaderId(v) => NativeEndian::write_u32(buffer, *v),
             DpipeHeaderFields(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             DpipeHeaderGlobal(v) => buffer[0] = *v,
             DpipeHeaderIndex(v) => NativeEndian::write_u32(buffer, *v),
             DpipeField(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             DpipeFieldName(s) => {
                 buffer[..s.len()].copy_from_slice(s.as_bytes());
@@ -1420,10 +1465,10 @@ impl Nla for GenlDevlinkAttrs {
             DpipeFieldMappingType(v) => NativeEndian::write_u32(buffer, *v),
             EswitchEncapMode(v) => buffer[0] = *v,
             ResourceList(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             Resource(nla) => {
-                nla.iter().for_each(|val| val.emit_value(buffer));
+                nla.as_slice().emit(buffer);
             }
             ResoureceName(s) => {
                 buffer[..s.len()].copy_from_slice(s.as_bytes());
@@ -1444,6 +1489,75 @@ impl Nla for GenlDevlinkAttrs {
     }
 }
 
+// Generate an attribute of a type unknown to this crate
+#[cfg(feature = "arbitrary")]
+fn arbitrary_other(
+    u: &mut arbitrary::Unstructured,
+) -> arbitrary::Result<RawNla> {
+    let nla: RawNla = u.arbitrary()?;
+    let mut buf = vec![0; nla.buffer_len()];
+    nla.emit(&mut buf);
+    match GenlDevlinkAttrs::parse(&NlaBuffer::new(&buf)) {
+        Ok(GenlDevlinkAttrs::Other(nla)) => Ok(nla),
+        // DEVLINK_ATTR_UNSPEC is never parsed as a known attribute
+        _ => {
+            let flags = nla.kind() & !netlink_packet_utils::nla::NLA_TYPE_MASK;
+            raw_nla_from_parts(flags, &nla.value())
+        }
+    }
+}
+
+/// Tell whether the attributes nested in `kind` are devlink attributes
+pub(crate) fn nests_devlink_attrs(kind: u16) -> bool {
+    matches!(
+        kind,
+        DEVLINK_ATTR_PARAM
+        | DEVLINK_ATTR_PARAM_VALUES_LIST
+        | DEVLINK_ATTR_PARAM_VALUE
+        | DEVLINK_ATTR_REGION_SNAPSHOTS
+        | DEVLINK_ATTR_REGION_SNAPSHOT
+        | DEVLINK_ATTR_REGION_CHUNKS
+        | DEVLINK_ATTR_REGION_CHUNK
+        | DEVLINK_ATTR_INFO_VERSION_FIXED
+        | DEVLINK_ATTR_INFO_VERSION_RUNNING
+        | DEVLINK_ATTR_INFO_VERSION_STORED
+        | DEVLINK_ATTR_DEV_STATS
+        | DEVLINK_ATTR_RELOAD_STATS
+        | DEVLINK_ATTR_RELOAD_STATS_ENTRY
+        | DEVLINK_ATTR_REMOTE_RELOAD_SATS
+        | DEVLINK_ATTR_RELOAD_ACTION_INFO
+        | DEVLINK_ATTR_RELAOD_ACTION_STATS
+        | DEVLINK_ATTR_HEALTH_REPORTER
+        | DEVLINK_ATTR_FMSG
+        | DEVLINK_ATTR_DPIPE_TABLES
+        | DEVLINK_ATTR_DPIPE_TABLE
+        | DEVLINK_ATTR_DPIPE_TABLE_MATCHES
+        | DEVLINK_ATTR_DPIPE_TABLE_ACTIONS
+        | DEVLINK_ATTR_DPIPE_ENTRIES
+        | DEVLINK_ATTR_DPIPE_ENTRY
+        | DEVLINK_ATTR_DPIPE_ENTRY_MATCH_VALUES
+        | DEVLINK_ATTR_DPIPE_ENTRY_ACTION_VALUES
+        | DEVLINK_ATTR_DPIPE_MATCH_VALUE
+        | DEVLINK_ATTR_DPIPE_ACTION
+        | DEVLINK_ATTR_DPIPE_ACTION_VALUE
+        | DEVLINK_ATTR_DPIPE_MATCH
+        | DEVLINK_ATTR_DPIPE_HEADERS
+        | DEVLINK_ATTR_DPIPE_HEADER
+        | DEVLINK_ATTR_DPIPE_HEADER_FIELDS
+        | DEVLINK_ATTR_DPIPE_FIELD
+        | DEVLINK_ATTR_RESOURCE_LIST
+        | DEVLINK_ATTR_RESOURCE
+    )
+}
+
+// Parse the attributes nested in an attribute of another attribute space,
+// e.g. `DEVLINK_ATTR_STATS_*`, which are kept as raw attributes
+fn parse_raw_nlas(payload: &[u8]) -> Result<Vec<RawNla>, DecodeError> {
+    NlasIterator::new(payload)
+        .map(|nla| nla.and_then(|nla| RawNla::parse(&nla)))
+        .collect()
+}
+
 impl<'a, T: AsRef<[u8]> + ?Sized> Parseable<NlaBuffer<&'a T>>
     for GenlDevlinkAttrs
 {
@@ -1498,11 +1612,7 @@ impl<'a, T: AsRef<[u8]> + ?Sized> Parseable<NlaBuffer<&'a T>>
                 parse_string(payload)
                     .context("invalid DEVLINK_ATTR_PARAM_NAME value")?,
             ),
-            DEVLINK_ATTR_PARAM_GENERIC => Self::ParamGeneric({
-                let val = parse_u8(payload)
-                    .context("invalid DEVLINK_ATTR_PARAM_GENERIC value")?;
-                val != 0
-            }),
+            DEVLINK_ATTR_PARAM_GENERIC => Self::ParamGeneric(true),
             DEVLINK_ATTR_PARAM_TYPE => Self::ParamType(
                 parse_u8(payload)
                     .context("invalid DEVLINK_ATTR_PARAM_TYPE value")?,
@@ -1515,18 +1625,15 @@ impl<'a, T: AsRef<[u8]> + ?Sized> Parseable<NlaBuffer<&'a T>>
                     .collect::<Result<Vec<_>, _>>()
                     .context("failed to parse DEVLINK_ATTR_PARAM_VALUE_LIST")?
             }),
-            DEVLINK_ATTR_PARAM_VALUE => Self::ParamValue(
-                parse_u64(payload)
-                    .context("invalid DEVLINK_ATTR_PARAM_VALUE value")?,
-            ),
-            DEVLINK_ATTR_PARAM_VALUE_DATA => Self::ParamValueData({
+            DEVLINK_ATTR_PARAM_VALUE => Self::ParamValue({
                 NlasIterator::new(payload)
                     .map(|nla| {
                         nla.and_then(|nla| GenlDevlinkAttrs::parse(&nla))
                     })
                     .collect::<Result<Vec<_>, _>>()
-                    .context("failed to parse DEVLINK_ATTR_REGION_CHUNK")?
+                    .context("failed to parse DEVLINK_ATTR_PARAM_VALUE")?
             }),
+            DEVLINK_ATTR_PARAM_VALUE_DATA => Self::ParamValueData(payload.to_vec()),
             DEVLINK_ATTR_PARAM_VALUE_CMODE => Self::ParamValueCmode(
                 parse_u8(payload)
                     .context("invalid DEVLINK_ATTR_PARAM_VALUE_CMODE value")?,
@@ -1575,11 +1682,17 @@ impl<'a, T: AsRef<[u8]> + ?Sized> Parseable<NlaBuffer<&'a T>>
                     .collect::<Result<Vec<_>, _>>()
                     .context("failed to parse DEVLINK_ATTR_REGION_CHUNK")?
             }),
-            // DEVLINK_ATTR_REGION_CHUNK_DATA => Self::RegionChunkData(_),
+            DEVLINK_ATTR_REGION_CHUNK_DATA => {
+                Self::RegionChunkData(payload.to_vec())
+            }
             DEVLINK_ATTR_REGION_CHUNK_ADDR => Self::RegionChunkOffset(
                 parse_u64(payload)
                     .context("invalid DEVLINK_ATTR_REGION_CHUNK_ADDR value")?,
             ),
+            DEVLINK_ATTR_REGION_CHUNK_LEN => Self::RegionChunkSize(
+                parse_u64(payload)
+                    .context("invalid DEVLINK_ATTR_REGION_CHUNK_LEN value")?,
+            ),
             DEVLINK_ATTR_INFO_DRIVER_NAME => Self::InfoDriverName(
                 parse_string(payload)
                     .context("invalid DEVLINK_ATTR_INFO_DRIVER_NAME value")?,
@@ -1719,14 +1832,10 @@ impl<'a, T: AsRef<[u8]> + ?Sized> Parseable<NlaBuffer<&'a T>>
                 parse_u16(payload)
                     .context("invalid DEVLINK_ATTR_PORT_PCI_VF_NUMBER value")?,
             ),
-            DEVLINK_ATTR_STATS => Self::Stats({
-                NlasIterator::new(payload)
-                    .map(|nla| {
-                        nla.and_then(|nla| GenlDevlinkAttrs::parse(&nla))
-                    })
-                    .collect::<Result<Vec<_>, _>>()
-                    .context("failed to parse DEVLINK_ATTR_STATS")?
-            }),
+            DEVLINK_ATTR_STATS => Self::Stats(
+                parse_raw_nlas(payload)
+                    .context("failed to parse DEVLINK_ATTR_STATS")?,
+            ),
             DEVLINK_ATTR_TRAP_NAME => Self::TrapName(
                 parse_string(payload)
                     .context("invalid DEVLINK_ATTR_TRAP_NAME value")?,
@@ -1739,19 +1848,11 @@ impl<'a, T: AsRef<[u8]> + ?Sized> Parseable<NlaBuffer<&'a T>>
                 parse_u8(payload)
                     .context("invalid DEVLINK_ATTR_TRAP_TYPE value")?,
             ),
-            DEVLINK_ATTR_TRAP_GENERIC => Self::TrapGeneric({
-                let val = parse_u8(payload)
-                    .context("invalid DEVLINK_ATTR_TRAP_GENERIC value")?;
-                val != 0
-            }),
-            DEVLINK_ATTR_TRAP_METADATA => Self::TrapMetadata({
-                NlasIterator::new(payload)
-                    .map(|nla| {
-                        nla.and_then(|nla| GenlDevlinkAttrs::parse(&nla))
-                    })
-                    .collect::<Result<Vec<_>, _>>()
-                    .context("failed to parse DEVLINK_ATTR_TRAP_METADATA")?
-            }),
+            DEVLINK_ATTR_TRAP_GENERIC => Self::TrapGeneric(true),
+            DEVLINK_ATTR_TRAP_METADATA => Self::TrapMetadata(
+                parse_raw_nlas(payload)
+                    .context("failed to parse DEVLINK_ATTR_TRAP_METADATA")?,
+            ),
             DEVLINK_ATTR_TRAP_GROUP_NAME => Self::TrapGroupName(
                 parse_string(payload)
                     .context("invalid DEVLINK_ATTR_TRAP_GROUP_NAME value")?,
@@ -1820,22 +1921,10 @@ impl<'a, T: AsRef<[u8]> + ?Sized> Parseable<NlaBuffer<&'a T>>
                     .collect::<Result<Vec<_>, _>>()
                     .context("failed to parse DEVLINK_ATTR_FMSG")?
             }),
-            DEVLINK_ATTR_FMSG_OBJ_NEST_START => Self::FmsgObjNestStart(
-                parse_u8(payload)
-                    .context("invalid DEVLINK_ATTR_FMSG_OBJ_NEST_START value")? != 0,
-            ),
-            DEVLINK_ATTR_FMSG_PAIR_NEST_START => Self::FmsgPairNestStart(
-                parse_u8(payload)
-                    .context("invalid DEVLINK_ATTR_FMSG_PAIR_NEST_START value")? != 0,
-            ),
-            DEVLINK_ATTR_FMSG_ARR_NEST_START => Self::FmsgArrNestStart(
-                parse_u8(payload)
-                    .context("invalid DEVLINK_ATTR_FMSG_ARR_NEST_START value")? != 0,
-            ),
-            DEVLINK_ATTR_FMSG_NEST_END => Self::FmsgNestEnd(
-                parse_u8(payload)
-                    .context("invalid DEVLINK_ATTR_FMSG_NEST_END value")? != 0,
-            ),
+            DEVLINK_ATTR_FMSG_OBJ_NEST_START => Self::FmsgObjNestStart(true),
+            DEVLINK_ATTR_FMSG_PAIR_NEST_START => Self::FmsgPairNestStart(true),
+            DEVLINK_ATTR_FMSG_ARR_NEST_START => Self::FmsgArrNestStart(true),
+            DEVLINK_ATTR_FMSG_NEST_END => Self::FmsgNestEnd(true),
             DEVLINK_ATTR_FMSG_OBJ_NAME => Self::FmsgObjName(
                 parse_string(payload)
                     .context("invalid DEVLINK_ATTR_FMSG_OBJ_NAME value")?,
@@ -1844,14 +1933,7 @@ impl<'a, T: AsRef<[u8]> + ?Sized> Parseable<NlaBuffer<&'a T>>
                 parse_u8(payload)
                     .context("invalid DEVLINK_ATTR_FMSG_OBJ_VALUE_TYPE value")?,
             ),
-            DEVLINK_ATTR_FMSG_OBJ_VALUE_DATA => Self::FmsgObjValueData({
-                NlasIterator::new(payload)
-                    .map(|nla| {
-                        nla.and_then(|nla| GenlDevlinkAttrs::parse(&nla))
-                    })
-                    .collect::<Result<Vec<_>, _>>()
-                    .context("failed to parse DEVLINK_ATTR_FMSG_OBJ_VALUE_DATA")?
-            }),
+            DEVLINK_ATTR_FMSG_OBJ_VALUE_DATA => Self::FmsgObjValueData(payload.to_vec()),
             DEVLINK_ATTR_HEALTH_REPORTER_DUMP_TS_NS => Self::HealthReporterDumpTsNs(
                 parse_u64(payload)
                     .context("invalid DEVLINK_ATTR_HEALTH_REPORTER_DUMP_TS_NS value")?,
@@ -1887,7 +1969,7 @@ impl<'a, T: AsRef<[u8]> + ?Sized> Parseable<NlaBuffer<&'a T>>
             DEVLINK_ATTR_PORT_FUNCTION => Self::PortFunction({
                 NlasIterator::new(payload)
                     .map(|nla| {
-                        nla.and_then(|nla| GenlDevlinkAttrs::parse(&nla))
+                        nla.and_then(|nla| PortFunctionAttrs::parse(&nla))
                     })
                     .collect::<Result<Vec<_>, _>>()
                     .context("failed to parse DEVLINK_ATTR_PORT_FUNCTION")?
@@ -2154,14 +2236,10 @@ impl<'a, T: AsRef<[u8]> + ?Sized> Parseable<NlaBuffer<&'a T>>
                     .collect::<Result<Vec<_>, _>>()
                     .context("failed to parse DEVLINK_ATTR_DPIPE_MATCH")?
             }),
-            DEVLINK_ATTR_DPIPE_VALUE => Self::DpipeValue(
-                parse_u32(payload)
-                    .context("invalid DEVLINK_ATTR_DPIPE_VALUE value")?,
-            ),
-            DEVLINK_ATTR_DPIPE_VALUE_MASK => Self::DpipeValueMask(
-                parse_u32(payload)
-                    .context("invalid DEVLINK_ATTR_DPIPE_VALUE_MASK value")?,
-            ),
+            DEVLINK_ATTR_DPIPE_VALUE => Self::DpipeValue(payload.to_vec()),
+            DEVLINK_ATTR_DPIPE_VALUE_MASK => {
+                Self::DpipeValueMask(payload.to_vec())
+            }
             DEVLINK_ATTR_DPIPE_VALUE_MAPPING => Self::DpipeValueMapping(
                 parse_u32(payload)
                     .context("invalid DEVLINK_ATTR_DPIPE_VALUE_MAPPING value")?,
@@ -2290,16 +2368,13 @@ impl<'a, T: AsRef<[u8]> + ?Sized> Parseable<NlaBuffer<&'a T>>
                 parse_u64(payload)
                     .context("invalid DEVLINK_ATTR_DPIPE_TABLE_RESOURCE_UNITS value")?,
             ),
-            DEVLINK_ATTR_PORT_SPLIT_SUBPORT_NUMBER => Self::PortSplitCount(
+            DEVLINK_ATTR_PORT_SPLIT_SUBPORT_NUMBER => Self::PortSplitSubportNumber(
                 parse_u32(payload)
                     .context("invalid DEVLINK_ATTR_PORT_SPLIT_SUBPORT_NUMBER value")?,
             ),
-            _ => {
-                return Err(DecodeError::from(format!(
-                    "Unknown NLA type: {}",
-                    buf.kind()
-                )))
-            }
+            _ => Self::Other(
+                RawNla::parse(buf).context("invalid devlink attribute")?,
+            ),
         })
     }
 }
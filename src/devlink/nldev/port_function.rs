@@ -0,0 +1,368 @@
+// SPDX-License-Identifier: MIT
+
+#[cfg(feature = "arbitrary")]
+use crate::raw::raw_nla_from_parts;
+use crate::{constants::*, raw::RawNla};
+use anyhow::Context;
+use byteorder::{ByteOrder, NativeEndian};
+use netlink_packet_utils::{
+    nla::{Nla, NlaBuffer},
+    parsers::*,
+    traits::*,
+    DecodeError,
+};
+use std::{convert::TryInto, fmt, mem::size_of_val};
+
+/// Attributes nested in `DEVLINK_ATTR_PORT_FUNCTION`
+///
+/// They have their own attribute space, `DEVLINK_PORT_FUNCTION_ATTR_*`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum PortFunctionAttrs {
+    HwAddr(HwAddr),
+    State(PortFunctionState),
+    OpState(PortFunctionOpState),
+    Caps(PortFunctionCaps),
+    /// Attribute unknown to this crate, kept as is
+    Other(
+        #[cfg_attr(feature = "arbitrary", arbitrary(with = arbitrary_other))]
+        RawNla,
+    ),
+}
+
+/// Hardware address of a port function, usually a MAC address
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct HwAddr(pub Vec<u8>);
+
+impl HwAddr {
+    /// Return the address as a MAC address, if it is 6 bytes long
+    pub fn mac(&self) -> Option<[u8; 6]> {
+        self.0.as_slice().try_into().ok()
+    }
+}
+
+impl From<[u8; 6]> for HwAddr {
+    fn from(mac: [u8; 6]) -> Self {
+        Self(mac.to_vec())
+    }
+}
+
+impl fmt::Display for HwAddr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (i, byte) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, ":")?;
+            }
+            write!(f, "{byte:02x}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Administrative state of a port function
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PortFunctionState {
+    Inactive,
+    Active,
+    Other(u8),
+}
+
+impl From<u8> for PortFunctionState {
+    fn from(v: u8) -> Self {
+        match v {
+            DEVLINK_PORT_FN_STATE_INACTIVE => Self::Inactive,
+            DEVLINK_PORT_FN_STATE_ACTIVE => Self::Active,
+            v => Self::Other(v),
+        }
+    }
+}
+
+impl From<PortFunctionState> for u8 {
+    fn from(state: PortFunctionState) -> Self {
+        match state {
+            PortFunctionState::Inactive => DEVLINK_PORT_FN_STATE_INACTIVE,
+            PortFunctionState::Active => DEVLINK_PORT_FN_STATE_ACTIVE,
+            PortFunctionState::Other(v) => v,
+        }
+    }
+}
+
+/// Operational state of a port function
+///
+/// A function is attached once a driver of the host uses it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PortFunctionOpState {
+    Detached,
+    Attached,
+    Other(u8),
+}
+
+impl From<u8> for PortFunctionOpState {
+    fn from(v: u8) -> Self {
+        match v {
+            DEVLINK_PORT_FN_OPSTATE_DETACHED => Self::Detached,
+            DEVLINK_PORT_FN_OPSTATE_ATTACHED => Self::Attached,
+            v => Self::Other(v),
+        }
+    }
+}
+
+impl From<PortFunctionOpState> for u8 {
+    fn from(state: PortFunctionOpState) -> Self {
+        match state {
+            PortFunctionOpState::Detached => DEVLINK_PORT_FN_OPSTATE_DETACHED,
+            PortFunctionOpState::Attached => DEVLINK_PORT_FN_OPSTATE_ATTACHED,
+            PortFunctionOpState::Other(v) => v,
+        }
+    }
+}
+
+// Generate the states through `From<u8>`, so that they are emitted and
+// parsed back to the same variant
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for PortFunctionState {
+    fn arbitrary(
+        u: &mut arbitrary::Unstructured<'a>,
+    ) -> arbitrary::Result<Self> {
+        Ok(u8::arbitrary(u)?.into())
+    }
+}
+
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for PortFunctionOpState {
+    fn arbitrary(
+        u: &mut arbitrary::Unstructured<'a>,
+    ) -> arbitrary::Result<Self> {
+        Ok(u8::arbitrary(u)?.into())
+    }
+}
+
+/// Capabilities of a port function, a bitfield32 of `DEVLINK_PORT_FN_CAP_*`
+///
+/// Only the capabilities of `selector` are reported or changed, each one
+/// enabled if its bit of `value` is set.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub struct PortFunctionCaps {
+    pub value: u32,
+    pub selector: u32,
+}
+
+impl PortFunctionCaps {
+    /// Return whether the capability `cap` is enabled, if it is selected
+    pub fn get(&self, cap: u32) -> Option<bool> {
+        if self.selector & cap == cap {
+            Some(self.value & cap == cap)
+        } else {
+            None
+        }
+    }
+
+    /// Select the capability `cap`, enabling or disabling it
+    pub fn set(&mut self, cap: u32, enabled: bool) {
+        self.selector |= cap;
+        if enabled {
+            self.value |= cap;
+        } else {
+            self.value &= !cap;
+        }
+    }
+}
+
+impl Nla for PortFunctionAttrs {
+    fn value_len(&self) -> usize {
+        use PortFunctionAttrs::*;
+        match self {
+            HwAddr(v) => v.0.len(),
+            State(_) => 1,
+            OpState(_) => 1,
+            Caps(v) => size_of_val(&v.value) + size_of_val(&v.selector),
+            Other(nla) => nla.value_len(),
+        }
+    }
+
+    fn kind(&self) -> u16 {
+        use PortFunctionAttrs::*;
+        match self {
+            HwAddr(_) => DEVLINK_PORT_FUNCTION_ATTR_HW_ADDR,
+            State(_) => DEVLINK_PORT_FN_ATTR_STATE,
+            OpState(_) => DEVLINK_PORT_FN_ATTR_OPSTATE,
+            Caps(_) => DEVLINK_PORT_FN_ATTR_CAPS,
+            Other(nla) => nla.kind(),
+        }
+    }
+
+    fn is_nested(&self) -> bool {
+        match self {
+            Self::Other(nla) => nla.is_nested(),
+            _ => false,
+        }
+    }
+
+    fn emit_value(&self, buffer: &mut [u8]) {
+        use PortFunctionAttrs::*;
+        match self {
+            HwAddr(v) => buffer.copy_from_slice(&v.0),
+            State(v) => buffer[0] = (*v).into(),
+            OpState(v) => buffer[0] = (*v).into(),
+            Caps(v) => {
+                NativeEndian::write_u32(&mut buffer[..4], v.value);
+                NativeEndian::write_u32(&mut buffer[4..], v.selector);
+            }
+            Other(nla) => nla.emit_value(buffer),
+        }
+    }
+}
+
+// Generate an attribute of a type unknown to this crate
+#[cfg(feature = "arbitrary")]
+fn arbitrary_other(
+    u: &mut arbitrary::Unstructured,
+) -> arbitrary::Result<RawNla> {
+    let nla: RawNla = u.arbitrary()?;
+    let mut buf = vec![0; nla.buffer_len()];
+    nla.emit(&mut buf);
+    match PortFunctionAttrs::parse(&NlaBuffer::new(&buf)) {
+        Ok(PortFunctionAttrs::Other(nla)) => Ok(nla),
+        // DEVLINK_PORT_FUNCTION_ATTR_UNSPEC is never parsed as a known
+        // attribute
+        _ => {
+            let flags = nla.kind() & !netlink_packet_utils::nla::NLA_TYPE_MASK;
+            raw_nla_from_parts(flags, &nla.value())
+        }
+    }
+}
+
+impl<'a, T: AsRef<[u8]> + ?Sized> Parseable<NlaBuffer<&'a T>>
+    for PortFunctionAttrs
+{
+    fn parse(buf: &NlaBuffer<&'a T>) -> Result<Self, DecodeError> {
+        let payload = buf.value();
+        Ok(match buf.kind() {
+            DEVLINK_PORT_FUNCTION_ATTR_HW_ADDR => {
+                Self::HwAddr(HwAddr(payload.to_vec()))
+            }
+            DEVLINK_PORT_FN_ATTR_STATE => Self::State(
+                parse_u8(payload)
+                    .context("invalid DEVLINK_PORT_FN_ATTR_STATE value")?
+                    .into(),
+            ),
+            DEVLINK_PORT_FN_ATTR_OPSTATE => Self::OpState(
+                parse_u8(payload)
+                    .context("invalid DEVLINK_PORT_FN_ATTR_OPSTATE value")?
+                    .into(),
+            ),
+            DEVLINK_PORT_FN_ATTR_CAPS => {
+                if payload.len() != 8 {
+                    return Err(DecodeError::from(
+                        "invalid DEVLINK_PORT_FN_ATTR_CAPS value",
+                    ));
+                }
+                Self::Caps(PortFunctionCaps {
+                    value: NativeEndian::read_u32(&payload[..4]),
+                    selector: NativeEndian::read_u32(&payload[4..]),
+                })
+            }
+            _ => Self::Other(
+                RawNla::parse(buf)
+                    .context("invalid devlink port function attribute")?,
+            ),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use netlink_packet_utils::nla::NlasIterator;
+
+    fn round_trip(nlas: &[PortFunctionAttrs]) -> Vec<PortFunctionAttrs> {
+        let mut buf = vec![0; nlas.buffer_len()];
+        nlas.emit(&mut buf);
+        NlasIterator::new(&buf)
+            .map(|nla| nla.and_then(|nla| PortFunctionAttrs::parse(&nla)))
+            .collect::<Result<_, _>>()
+            .unwrap()
+    }
+
+    #[test]
+    fn parse_kernel_layout() {
+        // hw_addr 00:11:22:33:44:55, state active, opstate attached and
+        // the roce capability enabled
+        let buf = [
+            0x0a, 0x00, 0x01, 0x00, 0x00, 0x11, 0x22, 0x33, 0x44, 0x55, 0x00,
+            0x00, 0x05, 0x00, 0x02, 0x00, 0x01, 0x00, 0x00, 0x00, 0x05, 0x00,
+            0x03, 0x00, 0x01, 0x00, 0x00, 0x00, 0x0c, 0x00, 0x04, 0x00, 0x01,
+            0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00,
+        ];
+        let nlas: Vec<PortFunctionAttrs> = NlasIterator::new(&buf[..])
+            .map(|nla| nla.and_then(|nla| PortFunctionAttrs::parse(&nla)))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        let caps = PortFunctionCaps {
+            value: DEVLINK_PORT_FN_CAP_ROCE,
+            selector: DEVLINK_PORT_FN_CAP_ROCE | DEVLINK_PORT_FN_CAP_MIGRATABLE,
+        };
+        assert_eq!(
+            nlas,
+            vec![
+                PortFunctionAttrs::HwAddr(HwAddr::from([
+                    0x00, 0x11, 0x22, 0x33, 0x44, 0x55
+                ])),
+                PortFunctionAttrs::State(PortFunctionState::Active),
+                PortFunctionAttrs::OpState(PortFunctionOpState::Attached),
+                PortFunctionAttrs::Caps(caps),
+            ]
+        );
+        assert_eq!(Some(true), caps.get(DEVLINK_PORT_FN_CAP_ROCE));
+        assert_eq!(Some(false), caps.get(DEVLINK_PORT_FN_CAP_MIGRATABLE));
+        assert_eq!(None, caps.get(1 << 2));
+
+        let mut emitted = vec![0; nlas.as_slice().buffer_len()];
+        nlas.as_slice().emit(&mut emitted);
+        assert_eq!(&buf[..], &emitted[..]);
+    }
+
+    #[test]
+    fn unknown_attributes_and_values() {
+        let nlas = vec![
+            PortFunctionAttrs::State(PortFunctionState::Other(7)),
+            PortFunctionAttrs::HwAddr(HwAddr(vec![0xfe; 20])),
+            PortFunctionAttrs::Other(RawNla::Value(
+                netlink_packet_utils::nla::DefaultNla::new(5, vec![1, 0, 0, 0]),
+            )),
+        ];
+        assert_eq!(nlas, round_trip(&nlas));
+    }
+
+    #[test]
+    fn hw_addr() {
+        let addr = HwAddr::from([0x02, 0x00, 0x00, 0xab, 0xcd, 0xef]);
+        assert_eq!("02:00:00:ab:cd:ef", addr.to_string());
+        assert_eq!(Some([0x02, 0x00, 0x00, 0xab, 0xcd, 0xef]), addr.mac());
+        assert_eq!(None, HwAddr(vec![0; 20]).mac());
+    }
+
+    #[test]
+    fn set_caps() {
+        let mut caps = PortFunctionCaps::default();
+        caps.set(DEVLINK_PORT_FN_CAP_ROCE, true);
+        caps.set(DEVLINK_PORT_FN_CAP_MIGRATABLE, false);
+        assert_eq!(
+            PortFunctionCaps {
+                value: DEVLINK_PORT_FN_CAP_ROCE,
+                selector: DEVLINK_PORT_FN_CAP_ROCE
+                    | DEVLINK_PORT_FN_CAP_MIGRATABLE,
+            },
+            caps
+        );
+        caps.set(DEVLINK_PORT_FN_CAP_ROCE, false);
+        assert_eq!(Some(false), caps.get(DEVLINK_PORT_FN_CAP_ROCE));
+    }
+}
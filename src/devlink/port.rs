@@ -0,0 +1,914 @@
+// SPDX-License-Identifier: MIT
+
+//! Ports of devlink devices
+//!
+//! A [`Port`] is described by its flavour, which tells what it is connected
+//! to, and by the attributes of that flavour.
+//!
+//! The ports of PCI PF, VF and SF flavours represent a function of the
+//! device, handed to a host or to a virtual machine. [`PortFunction`]
+//! decodes its hardware address, its state and its capabilities, nested in
+//! `DEVLINK_ATTR_PORT_FUNCTION`, and [`PortFunctionSet`] changes them.
+//!
+//! The subfunctions are created by the user: [`SfPortSpec`] builds the
+//! request adding the port of a new subfunction, which is then configured
+//! and activated through its function.
+//!
+//! The physical ports of several lanes can be split into ports of fewer
+//! lanes. [`SplitPlanner`] reports the splits the ports of a device
+//! support, builds the requests splitting and unsplitting them, and finds
+//! the ports a port was split into.
+
+use super::{
+    nldev::{
+        GenlDevlinkAttrs, HwAddr, PortFunctionAttrs, PortFunctionCaps,
+        PortFunctionOpState, PortFunctionState,
+    },
+    DevlinkHandle, GenlDevlink, GenlDevlinkCmd,
+};
+use crate::{constants::*, raw::RawNla};
+use netlink_packet_utils::{
+    nla::{DefaultNla, Nla, NLA_F_NESTED},
+    DecodeError,
+};
+use std::{collections::BTreeMap, convert::TryFrom, fmt};
+
+/// Kind of the network device of a port
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PortType {
+    NotSet,
+    Auto,
+    Eth,
+    Ib,
+    Other(u16),
+}
+
+impl From<u16> for PortType {
+    fn from(v: u16) -> Self {
+        match v {
+            DEVLINK_PORT_TYPE_NOTSET => Self::NotSet,
+            DEVLINK_PORT_TYPE_AUTO => Self::Auto,
+            DEVLINK_PORT_TYPE_ETH => Self::Eth,
+            DEVLINK_PORT_TYPE_IB => Self::Ib,
+            v => Self::Other(v),
+        }
+    }
+}
+
+impl From<PortType> for u16 {
+    fn from(port_type: PortType) -> Self {
+        match port_type {
+            PortType::NotSet => DEVLINK_PORT_TYPE_NOTSET,
+            PortType::Auto => DEVLINK_PORT_TYPE_AUTO,
+            PortType::Eth => DEVLINK_PORT_TYPE_ETH,
+            PortType::Ib => DEVLINK_PORT_TYPE_IB,
+            PortType::Other(v) => v,
+        }
+    }
+}
+
+/// What a port is connected to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PortFlavour {
+    /// A front panel port
+    Physical,
+    /// The port facing the CPU of a switch
+    Cpu,
+    /// A port linking two switches of a cluster
+    Dsa,
+    /// The port of a PCI physical function
+    PciPf,
+    /// The port of a PCI virtual function
+    PciVf,
+    /// A port of a virtual device
+    Virtual,
+    /// A port which is not used, e.g. a disabled port of a switch
+    Unused,
+    /// The port of a PCI subfunction
+    PciSf,
+    Other(u16),
+}
+
+impl From<u16> for PortFlavour {
+    fn from(v: u16) -> Self {
+        match v {
+            DEVLINK_PORT_FLAVOUR_PHYSICAL => Self::Physical,
+            DEVLINK_PORT_FLAVOUR_CPU => Self::Cpu,
+            DEVLINK_PORT_FLAVOUR_DSA => Self::Dsa,
+            DEVLINK_PORT_FLAVOUR_PCI_PF => Self::PciPf,
+            DEVLINK_PORT_FLAVOUR_PCI_VF => Self::PciVf,
+            DEVLINK_PORT_FLAVOUR_VIRTUAL => Self::Virtual,
+            DEVLINK_PORT_FLAVOUR_UNUSED => Self::Unused,
+            DEVLINK_PORT_FLAVOUR_PCI_SF => Self::PciSf,
+            v => Self::Other(v),
+        }
+    }
+}
+
+impl From<PortFlavour> for u16 {
+    fn from(flavour: PortFlavour) -> Self {
+        match flavour {
+            PortFlavour::Physical => DEVLINK_PORT_FLAVOUR_PHYSICAL,
+            PortFlavour::Cpu => DEVLINK_PORT_FLAVOUR_CPU,
+            PortFlavour::Dsa => DEVLINK_PORT_FLAVOUR_DSA,
+            PortFlavour::PciPf => DEVLINK_PORT_FLAVOUR_PCI_PF,
+            PortFlavour::PciVf => DEVLINK_PORT_FLAVOUR_PCI_VF,
+            PortFlavour::Virtual => DEVLINK_PORT_FLAVOUR_VIRTUAL,
+            PortFlavour::Unused => DEVLINK_PORT_FLAVOUR_UNUSED,
+            PortFlavour::PciSf => DEVLINK_PORT_FLAVOUR_PCI_SF,
+            PortFlavour::Other(v) => v,
+        }
+    }
+}
+
+/// Port of a device, from a `DEVLINK_CMD_PORT_GET` reply or a
+/// `DEVLINK_CMD_PORT_NEW` reply or notification
+///
+/// The attributes of the PCI ports are only reported for their flavours:
+/// the PF number for all of them, the VF number for the VFs and the SF
+/// number for the SFs.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Port {
+    pub handle: DevlinkHandle,
+    pub index: u32,
+    pub port_type: PortType,
+    pub flavour: PortFlavour,
+    /// Name of the network device of an Ethernet port
+    pub netdev_name: Option<String>,
+    /// Name of the InfiniBand device of an InfiniBand port
+    pub ibdev_name: Option<String>,
+    /// Number of the physical port, shared by the ports it was split into
+    pub number: Option<u32>,
+    /// Number of the port which was split into this one
+    pub split_group: Option<u32>,
+    /// Number of this port among the ports of its split group
+    pub split_subport: Option<u32>,
+    /// Number of the lanes of a physical port
+    pub lanes: Option<u32>,
+    /// Whether the port can be split
+    pub splittable: bool,
+    pub pci_pf_number: Option<u16>,
+    pub pci_vf_number: Option<u16>,
+    pub pci_sf_number: Option<u32>,
+    /// Controller of the PCI function, 0 for the local one
+    pub controller: Option<u32>,
+    /// Whether the PCI function belongs to an external controller, e.g.
+    /// the host of a SmartNIC
+    pub external: bool,
+    /// Function of a PCI port, if the driver reports it
+    pub function: Option<PortFunction>,
+}
+
+impl Port {
+    /// Read the port from the attributes of a reply
+    pub fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Result<Self, DecodeError> {
+        Ok(Self {
+            handle: DevlinkHandle::from_nlas(nlas)?,
+            index: *required!(nlas, PortIndex)?,
+            port_type: (*required!(nlas, PortType)?).into(),
+            flavour: (*required!(nlas, PortFlavour)?).into(),
+            netdev_name: attr!(nlas, NetdevName).cloned(),
+            ibdev_name: attr!(nlas, PortIbdevName).cloned(),
+            number: attr!(nlas, PortNumber).copied(),
+            split_group: attr!(nlas, PortSplitGroup).copied(),
+            split_subport: attr!(nlas, PortSplitSubportNumber).copied(),
+            lanes: attr!(nlas, PortLanes).copied(),
+            splittable: attr!(nlas, PortSplittable).is_some_and(|v| *v != 0),
+            pci_pf_number: attr!(nlas, PortPciPfNumber).copied(),
+            pci_vf_number: attr!(nlas, PortPciVfNumber).copied(),
+            pci_sf_number: attr!(nlas, PortPciSfNo).copied(),
+            controller: attr!(nlas, PortControllerNo).copied(),
+            external: attr!(nlas, PortExternal).is_some_and(|v| *v != 0),
+            function: match attr!(nlas, PortFunction) {
+                Some(_) => Some(PortFunction::from_nlas(nlas)?),
+                None => None,
+            },
+        })
+    }
+
+    /// Build the request reading the port `index` of `handle`
+    pub fn request(handle: &DevlinkHandle, index: u32) -> GenlDevlink {
+        let mut request = handle.request(GenlDevlinkCmd::GetPort);
+        request.nldev.push(GenlDevlinkAttrs::PortIndex(index));
+        request
+    }
+
+    /// Return whether the function of the port is active
+    pub fn is_active(&self) -> bool {
+        self.function
+            .as_ref()
+            .is_some_and(|f| f.state == Some(PortFunctionState::Active))
+    }
+
+    /// Build the request activating the function of the port
+    ///
+    /// A subfunction is created inactive, and only probed by the host once
+    /// it is activated. The driver rejects the ports of other flavours.
+    pub fn activate(&self) -> GenlDevlink {
+        PortFunctionSet::new(&self.handle, self.index)
+            .state(PortFunctionState::Active)
+            .build()
+    }
+
+    /// Build the request deactivating the function of the port
+    pub fn deactivate(&self) -> GenlDevlink {
+        PortFunctionSet::new(&self.handle, self.index)
+            .state(PortFunctionState::Inactive)
+            .build()
+    }
+
+    /// Build the request deleting the port, which only the ports created
+    /// by a `DEVLINK_CMD_PORT_NEW` request support
+    pub fn delete(&self) -> GenlDevlink {
+        let mut request = self.handle.request(GenlDevlinkCmd::DeletePort);
+        request.nldev.push(GenlDevlinkAttrs::PortIndex(self.index));
+        request
+    }
+}
+
+impl TryFrom<&GenlDevlink> for Port {
+    type Error = DecodeError;
+
+    fn try_from(msg: &GenlDevlink) -> Result<Self, Self::Error> {
+        Self::from_nlas(&msg.nldev)
+    }
+}
+
+/// Function of a port, from a `DEVLINK_CMD_PORT_GET` reply
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PortFunction {
+    pub handle: DevlinkHandle,
+    /// Index of the port of the function
+    pub port_index: u32,
+    /// Hardware address the host uses, if the driver reports it
+    pub hw_addr: Option<HwAddr>,
+    /// Administrative state, only reported for the subfunctions
+    pub state: Option<PortFunctionState>,
+    /// Operational state, only reported for the subfunctions
+    pub opstate: Option<PortFunctionOpState>,
+    /// Capabilities, none are selected if the driver reports none
+    pub caps: PortFunctionCaps,
+}
+
+impl PortFunction {
+    /// Read the function from the attributes of a reply
+    pub fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Result<Self, DecodeError> {
+        let function = required!(nlas, PortFunction)?;
+        let mut port_function = Self {
+            handle: DevlinkHandle::from_nlas(nlas)?,
+            port_index: *required!(nlas, PortIndex)?,
+            hw_addr: None,
+            state: None,
+            opstate: None,
+            caps: PortFunctionCaps::default(),
+        };
+        for nla in function {
+            match nla {
+                PortFunctionAttrs::HwAddr(v) => {
+                    port_function.hw_addr = Some(v.clone())
+                }
+                PortFunctionAttrs::State(v) => port_function.state = Some(*v),
+                PortFunctionAttrs::OpState(v) => {
+                    port_function.opstate = Some(*v)
+                }
+                PortFunctionAttrs::Caps(v) => port_function.caps = *v,
+                PortFunctionAttrs::Other(_) => (),
+            }
+        }
+        Ok(port_function)
+    }
+
+    /// Build the request reading the port `index` of `handle`, with its
+    /// function
+    pub fn request(handle: &DevlinkHandle, index: u32) -> GenlDevlink {
+        Port::request(handle, index)
+    }
+
+    /// Return whether RoCE is enabled, if the driver reports it
+    pub fn roce(&self) -> Option<bool> {
+        self.caps.get(DEVLINK_PORT_FN_CAP_ROCE)
+    }
+
+    /// Return whether the function can be migrated, if the driver reports
+    /// it
+    pub fn migratable(&self) -> Option<bool> {
+        self.caps.get(DEVLINK_PORT_FN_CAP_MIGRATABLE)
+    }
+}
+
+impl TryFrom<&GenlDevlink> for PortFunction {
+    type Error = DecodeError;
+
+    fn try_from(msg: &GenlDevlink) -> Result<Self, Self::Error> {
+        Self::from_nlas(&msg.nldev)
+    }
+}
+
+/// Builder of a `DEVLINK_CMD_PORT_SET` request changing a port function
+///
+/// Only the attributes which are set are changed. The state can only be
+/// changed for subfunctions, and most drivers only change the
+/// capabilities while the function is not in use.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PortFunctionSet {
+    handle: DevlinkHandle,
+    port_index: u32,
+    hw_addr: Option<HwAddr>,
+    state: Option<PortFunctionState>,
+    caps: PortFunctionCaps,
+}
+
+impl PortFunctionSet {
+    /// Change the function of the port `port_index` of `handle`
+    pub fn new(handle: &DevlinkHandle, port_index: u32) -> Self {
+        Self {
+            handle: handle.clone(),
+            port_index,
+            hw_addr: None,
+            state: None,
+            caps: PortFunctionCaps::default(),
+        }
+    }
+
+    /// Set the hardware address, e.g. the MAC address of a VF
+    pub fn hw_addr<A: Into<HwAddr>>(mut self, hw_addr: A) -> Self {
+        self.hw_addr = Some(hw_addr.into());
+        self
+    }
+
+    /// Activate or deactivate the function
+    pub fn state(mut self, state: PortFunctionState) -> Self {
+        self.state = Some(state);
+        self
+    }
+
+    /// Enable or disable RoCE
+    pub fn roce(mut self, enabled: bool) -> Self {
+        self.caps.set(DEVLINK_PORT_FN_CAP_ROCE, enabled);
+        self
+    }
+
+    /// Allow or forbid the migration of the function
+    pub fn migratable(mut self, enabled: bool) -> Self {
+        self.caps.set(DEVLINK_PORT_FN_CAP_MIGRATABLE, enabled);
+        self
+    }
+
+    /// Build the request
+    pub fn build(&self) -> GenlDevlink {
+        let mut function = vec![];
+        if let Some(hw_addr) = &self.hw_addr {
+            function.push(PortFunctionAttrs::HwAddr(hw_addr.clone()));
+        }
+        if let Some(state) = self.state {
+            function.push(PortFunctionAttrs::State(state));
+        }
+        if self.caps.selector != 0 {
+            function.push(PortFunctionAttrs::Caps(self.caps));
+        }
+        let mut request = self.handle.request(GenlDevlinkCmd::SetPort);
+        request
+            .nldev
+            .push(GenlDevlinkAttrs::PortIndex(self.port_index));
+        if !function.is_empty() {
+            request.nldev.push(nested_port_function(&function));
+        }
+        request
+    }
+}
+
+/// Builder of a `DEVLINK_CMD_PORT_NEW` request creating a subfunction
+///
+/// The kernel replies with the new port, which [`Port`] decodes. The
+/// subfunction is created inactive: its hardware address can be set before
+/// it is activated with [`Port::activate`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SfPortSpec {
+    handle: DevlinkHandle,
+    pf_number: u16,
+    sf_number: Option<u32>,
+    controller: Option<u32>,
+    index: Option<u32>,
+}
+
+impl SfPortSpec {
+    /// Create a subfunction of the PF `pf_number` of `handle`
+    pub fn new(handle: &DevlinkHandle, pf_number: u16) -> Self {
+        Self {
+            handle: handle.clone(),
+            pf_number,
+            sf_number: None,
+            controller: None,
+            index: None,
+        }
+    }
+
+    /// Set the number of the subfunction, which most drivers require
+    pub fn sf_number(mut self, sf_number: u32) -> Self {
+        self.sf_number = Some(sf_number);
+        self
+    }
+
+    /// Create the subfunction on the controller `controller`
+    ///
+    /// The controllers other than 0 are external, e.g. the host of a
+    /// SmartNIC. The subfunction is created on the local controller by
+    /// default.
+    ///
+    /// `DEVLINK_CMD_PORT_NEW` does not take `DEVLINK_ATTR_PORT_EXTERNAL`:
+    /// the driver tells from the controller whether the new port is
+    /// external, and reports it in [`Port::external`].
+    pub fn controller(mut self, controller: u32) -> Self {
+        self.controller = Some(controller);
+        self
+    }
+
+    /// Request the index of the new port, which the driver picks otherwise
+    pub fn index(mut self, index: u32) -> Self {
+        self.index = Some(index);
+        self
+    }
+
+    /// Build the request
+    pub fn build(&self) -> GenlDevlink {
+        use GenlDevlinkAttrs::*;
+        let mut request = self.handle.request(GenlDevlinkCmd::NewPort);
+        if let Some(index) = self.index {
+            request.nldev.push(PortIndex(index));
+        }
+        request.nldev.extend([
+            PortFlavour(DEVLINK_PORT_FLAVOUR_PCI_SF),
+            PortPciPfNumber(self.pf_number),
+        ]);
+        if let Some(sf_number) = self.sf_number {
+            request.nldev.push(PortPciSfNo(sf_number));
+        }
+        if let Some(controller) = self.controller {
+            request.nldev.push(PortControllerNo(controller));
+        }
+        request
+    }
+}
+
+/// Planner of the splits of the ports of a device, from a port dump
+///
+/// The kernel only splits a port reported as splittable, into a power of 2
+/// of ports, at most its number of lanes. The ports it is split into share
+/// its number, reported as their split group.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SplitPlanner {
+    handle: DevlinkHandle,
+    ports: BTreeMap<u32, Port>,
+}
+
+impl SplitPlanner {
+    /// Build the request dumping the ports of `handle`
+    pub fn request(handle: &DevlinkHandle) -> GenlDevlink {
+        handle.request(GenlDevlinkCmd::GetPort)
+    }
+
+    /// Gather the ports of the device `handle` listed in the replies of a
+    /// port dump
+    ///
+    /// The ports of the other devices are ignored.
+    pub fn from_replies<'a, I>(
+        handle: &DevlinkHandle,
+        replies: I,
+    ) -> Result<Self, DecodeError>
+    where
+        I: IntoIterator<Item = &'a GenlDevlink>,
+    {
+        let mut ports = BTreeMap::new();
+        for reply in replies {
+            let port = Port::try_from(reply)?;
+            if port.handle == *handle {
+                ports.insert(port.index, port);
+            }
+        }
+        Ok(Self {
+            handle: handle.clone(),
+            ports,
+        })
+    }
+
+    /// Return the handle of the device
+    pub fn handle(&self) -> &DevlinkHandle {
+        &self.handle
+    }
+
+    /// Return the port `index`
+    pub fn get(&self, index: u32) -> Option<&Port> {
+        self.ports.get(&index)
+    }
+
+    /// Iterate over the ports, by index
+    pub fn iter(&self) -> impl Iterator<Item = &Port> {
+        self.ports.values()
+    }
+
+    /// Return the counts the port `index` can be split into
+    pub fn split_counts(&self, index: u32) -> Result<Vec<u32>, SplitError> {
+        let port = self.port(index)?;
+        if !port.splittable {
+            return Err(SplitError::NotSplittable(index));
+        }
+        let lanes = port.lanes.unwrap_or(0);
+        Ok((1..u32::BITS)
+            .map(|shift| 1 << shift)
+            .take_while(|&count| count <= lanes)
+            .collect())
+    }
+
+    /// Iterate over the splittable ports, with the counts each one can be
+    /// split into
+    pub fn splittable(&self) -> impl Iterator<Item = (&Port, Vec<u32>)> {
+        self.ports.values().filter_map(move |port| {
+            self.split_counts(port.index)
+                .ok()
+                .filter(|counts| !counts.is_empty())
+                .map(|counts| (port, counts))
+        })
+    }
+
+    /// Plan the split of the port `index` into `count` ports
+    pub fn split(
+        &self,
+        index: u32,
+        count: u32,
+    ) -> Result<PortSplit, SplitError> {
+        let port = self.port(index)?;
+        let group = port.number.ok_or(SplitError::NotSplittable(index))?;
+        if !self.split_counts(index)?.contains(&count) {
+            return Err(SplitError::InvalidCount {
+                index,
+                count,
+                lanes: port.lanes,
+            });
+        }
+        Ok(PortSplit {
+            handle: self.handle.clone(),
+            index,
+            group,
+            count,
+        })
+    }
+
+    /// Return the ports of the split group `group`, by subport number
+    pub fn subports(&self, group: u32) -> Vec<&Port> {
+        let mut subports: Vec<&Port> = self
+            .ports
+            .values()
+            .filter(|port| port.split_group == Some(group))
+            .collect();
+        subports.sort_by_key(|port| port.split_subport);
+        subports
+    }
+
+    /// Build the request merging back the split group of the port `index`
+    pub fn unsplit(&self, index: u32) -> Result<GenlDevlink, SplitError> {
+        if self.port(index)?.split_group.is_none() {
+            return Err(SplitError::NotSplit(index));
+        }
+        let mut request = self.handle.request(GenlDevlinkCmd::UnsplitPort);
+        request.nldev.push(GenlDevlinkAttrs::PortIndex(index));
+        Ok(request)
+    }
+
+    fn port(&self, index: u32) -> Result<&Port, SplitError> {
+        self.ports
+            .get(&index)
+            .ok_or(SplitError::PortNotFound(index))
+    }
+}
+
+/// Split of a port, planned by a [`SplitPlanner`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PortSplit {
+    pub handle: DevlinkHandle,
+    /// Index of the port to split
+    pub index: u32,
+    /// Number of the port, which becomes the split group of the new ports
+    pub group: u32,
+    /// Number of the new ports
+    pub count: u32,
+}
+
+impl PortSplit {
+    /// Build the `DEVLINK_CMD_PORT_SPLIT` request
+    pub fn build(&self) -> GenlDevlink {
+        let mut request = self.handle.request(GenlDevlinkCmd::SplitPort);
+        request.nldev.extend([
+            GenlDevlinkAttrs::PortIndex(self.index),
+            GenlDevlinkAttrs::PortSplitCount(self.count),
+        ]);
+        request
+    }
+
+    /// Return the ports the port was split into, by subport number, from
+    /// the ports dumped once the split is done
+    pub fn subports<'a>(
+        &self,
+        after: &'a SplitPlanner,
+    ) -> Result<Vec<&'a Port>, SplitError> {
+        let subports = after.subports(self.group);
+        if subports.len() != self.count as usize {
+            return Err(SplitError::SubportsMissing {
+                group: self.group,
+                expected: self.count,
+                found: subports.len(),
+            });
+        }
+        Ok(subports)
+    }
+}
+
+/// Error raised when planning the split of a port
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum SplitError {
+    PortNotFound(u32),
+    /// The port is not splittable, e.g. it is already split
+    NotSplittable(u32),
+    /// The port cannot be split into `count` ports
+    InvalidCount {
+        index: u32,
+        count: u32,
+        lanes: Option<u32>,
+    },
+    /// The port to unsplit is not part of a split group
+    NotSplit(u32),
+    /// The split group does not have the expected number of ports
+    SubportsMissing {
+        group: u32,
+        expected: u32,
+        found: usize,
+    },
+}
+
+impl fmt::Display for SplitError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::PortNotFound(index) => write!(f, "port {index} not found"),
+            Self::NotSplittable(index) => {
+                write!(f, "port {index} cannot be split")
+            }
+            Self::InvalidCount {
+                index,
+                count,
+                lanes,
+            } => {
+                write!(f, "port {index} cannot be split into {count} ports")?;
+                match lanes {
+                    Some(lanes) => write!(f, ", it has {lanes} lanes"),
+                    None => Ok(()),
+                }
+            }
+            Self::NotSplit(index) => write!(f, "port {index} was not split"),
+            Self::SubportsMissing {
+                group,
+                expected,
+                found,
+            } => write!(
+                f,
+                "split group {group} has {found} ports instead of {expected}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for SplitError {}
+
+// `GenlDevlinkAttrs::PortFunction` is emitted without NLA_F_NESTED, as the
+// kernel sends it, but the kernel validates strictly the request attributes
+// from DEVLINK_ATTR_TRAP_POLICER_ID on, and rejects this nest without the
+// flag
+fn nested_port_function(function: &[PortFunctionAttrs]) -> GenlDevlinkAttrs {
+    let nlas = function
+        .iter()
+        .map(|nla| {
+            let mut value = vec![0; nla.value_len()];
+            nla.emit_value(&mut value);
+            RawNla::Value(DefaultNla::new(nla.kind(), value))
+        })
+        .collect();
+    GenlDevlinkAttrs::Other(RawNla::Nested(
+        DEVLINK_ATTR_PORT_FUNCTION | NLA_F_NESTED,
+        nlas,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devlink::handle::{handle, reply};
+    use netlink_packet_utils::{nla::NlaBuffer, Emitable, Parseable};
+    use std::convert::TryInto;
+
+    #[test]
+    fn parse_port_function() {
+        let msg = reply(
+            GenlDevlinkCmd::NewPort,
+            vec![
+                GenlDevlinkAttrs::PortIndex(32768),
+                GenlDevlinkAttrs::PortFlavour(DEVLINK_PORT_FLAVOUR_PCI_SF),
+                GenlDevlinkAttrs::PortFunction(vec![
+                    PortFunctionAttrs::HwAddr(HwAddr::from([
+                        0x00, 0x00, 0x00, 0x00, 0x88, 0x88,
+                    ])),
+                    PortFunctionAttrs::State(PortFunctionState::Inactive),
+                    PortFunctionAttrs::OpState(PortFunctionOpState::Detached),
+                    PortFunctionAttrs::Caps(PortFunctionCaps {
+                        value: DEVLINK_PORT_FN_CAP_ROCE,
+                        selector: DEVLINK_PORT_FN_CAP_ROCE,
+                    }),
+                ]),
+            ],
+        );
+        let function: PortFunction = (&msg).try_into().unwrap();
+        assert_eq!(32768, function.port_index);
+        assert_eq!(
+            "00:00:00:00:88:88",
+            function.hw_addr.as_ref().unwrap().to_string()
+        );
+        assert_eq!(Some(PortFunctionState::Inactive), function.state);
+        assert_eq!(Some(PortFunctionOpState::Detached), function.opstate);
+        assert_eq!(Some(true), function.roce());
+        assert_eq!(None, function.migratable());
+
+        let mut msg = msg;
+        msg.nldev
+            .retain(|nla| !matches!(nla, GenlDevlinkAttrs::PortFunction(_)));
+        assert!(PortFunction::try_from(&msg).is_err());
+    }
+
+    fn port(
+        index: u32,
+        number: u32,
+        lanes: u32,
+        split: Option<(u32, u32)>,
+    ) -> GenlDevlink {
+        use GenlDevlinkAttrs::*;
+        let mut reply = reply(
+            GenlDevlinkCmd::NewPort,
+            vec![
+                PortIndex(index),
+                PortType(DEVLINK_PORT_TYPE_ETH),
+                PortFlavour(DEVLINK_PORT_FLAVOUR_PHYSICAL),
+                PortNumber(number),
+                PortLanes(lanes),
+                PortSplittable(u8::from(split.is_none() && lanes > 1)),
+            ],
+        );
+        if let Some((group, subport)) = split {
+            reply.nldev.extend([
+                PortSplitGroup(group),
+                PortSplitSubportNumber(subport),
+            ]);
+        }
+        reply
+    }
+
+    #[test]
+    fn plan_splits() {
+        // Port 1 of 8 lanes, port 2 of a single lane and port 3 split in
+        // two ports, and a port of another device
+        let mut other = port(0, 1, 4, None);
+        other.nldev[1] = GenlDevlinkAttrs::Location("0000:09:00.0".to_owned());
+        let replies = vec![
+            port(0, 1, 8, None),
+            port(1, 2, 1, None),
+            port(4, 3, 2, Some((3, 1))),
+            port(5, 3, 2, Some((3, 0))),
+            other,
+        ];
+        let planner = SplitPlanner::from_replies(&handle(), &replies).unwrap();
+        assert_eq!(4, planner.iter().count());
+        assert_eq!(
+            vec![(0, vec![2, 4, 8])],
+            planner
+                .splittable()
+                .map(|(port, counts)| (port.index, counts))
+                .collect::<Vec<_>>()
+        );
+
+        assert_eq!(
+            Err(SplitError::InvalidCount {
+                index: 0,
+                count: 6,
+                lanes: Some(8)
+            }),
+            planner.split(0, 6)
+        );
+        assert_eq!(Err(SplitError::NotSplittable(4)), planner.split(4, 2));
+        assert_eq!(Err(SplitError::PortNotFound(9)), planner.split(9, 2));
+        let split = planner.split(0, 4).unwrap();
+        assert_eq!(1, split.group);
+        assert_eq!(
+            &[
+                GenlDevlinkAttrs::PortIndex(0),
+                GenlDevlinkAttrs::PortSplitCount(4)
+            ],
+            &split.build().nldev[2..]
+        );
+        assert_eq!(
+            Err(SplitError::SubportsMissing {
+                group: 1,
+                expected: 4,
+                found: 0
+            }),
+            split.subports(&planner)
+        );
+
+        assert_eq!(
+            vec![5, 4],
+            planner
+                .subports(3)
+                .iter()
+                .map(|port| port.index)
+                .collect::<Vec<_>>()
+        );
+        assert!(planner.unsplit(4).is_ok());
+        assert_eq!(Err(SplitError::NotSplit(0)), planner.unsplit(0));
+    }
+
+    #[test]
+    fn parse_external_sf_port() {
+        let msg = reply(
+            GenlDevlinkCmd::NewPort,
+            vec![
+                GenlDevlinkAttrs::PortIndex(32768),
+                GenlDevlinkAttrs::PortType(DEVLINK_PORT_TYPE_ETH),
+                GenlDevlinkAttrs::PortFlavour(DEVLINK_PORT_FLAVOUR_PCI_SF),
+                GenlDevlinkAttrs::PortControllerNo(1),
+                GenlDevlinkAttrs::PortPciPfNumber(0),
+                GenlDevlinkAttrs::PortPciSfNo(88),
+                GenlDevlinkAttrs::PortExternal(1),
+            ],
+        );
+        let port = Port::try_from(&msg).unwrap();
+        assert_eq!(PortFlavour::PciSf, port.flavour);
+        assert_eq!(
+            (Some(0), Some(88), Some(1), true),
+            (
+                port.pci_pf_number,
+                port.pci_sf_number,
+                port.controller,
+                port.external
+            )
+        );
+    }
+
+    #[test]
+    fn build_sf_port_spec() {
+        let request = SfPortSpec::new(&handle(), 0)
+            .sf_number(88)
+            .controller(1)
+            .build();
+        assert_eq!(GenlDevlinkCmd::NewPort, request.cmd);
+        assert_eq!(
+            &[
+                GenlDevlinkAttrs::PortFlavour(DEVLINK_PORT_FLAVOUR_PCI_SF),
+                GenlDevlinkAttrs::PortPciPfNumber(0),
+                GenlDevlinkAttrs::PortPciSfNo(88),
+                GenlDevlinkAttrs::PortControllerNo(1),
+            ],
+            &request.nldev[2..]
+        );
+    }
+
+    #[test]
+    fn build_port_function_set() {
+        let request = PortFunctionSet::new(&handle(), 1)
+            .hw_addr([0x02, 0x00, 0x00, 0x00, 0x00, 0x01])
+            .state(PortFunctionState::Active)
+            .roce(false)
+            .migratable(true)
+            .build();
+        assert_eq!(GenlDevlinkCmd::SetPort, request.cmd);
+
+        // The nest is sent with NLA_F_NESTED, and parsed back as the
+        // function attributes
+        let nla = request.nldev.last().unwrap();
+        let mut buf = vec![0; nla.buffer_len()];
+        nla.emit(&mut buf);
+        let buffer = NlaBuffer::new(&buf);
+        assert!(buffer.nested_flag());
+        assert_eq!(
+            GenlDevlinkAttrs::PortFunction(vec![
+                PortFunctionAttrs::HwAddr(HwAddr::from([
+                    0x02, 0x00, 0x00, 0x00, 0x00, 0x01
+                ])),
+                PortFunctionAttrs::State(PortFunctionState::Active),
+                PortFunctionAttrs::Caps(PortFunctionCaps {
+                    value: DEVLINK_PORT_FN_CAP_MIGRATABLE,
+                    selector: DEVLINK_PORT_FN_CAP_ROCE
+                        | DEVLINK_PORT_FN_CAP_MIGRATABLE,
+                }),
+            ]),
+            GenlDevlinkAttrs::parse(&buffer).unwrap()
+        );
+
+        // Nothing but the port is sent if nothing is changed
+        let request = PortFunctionSet::new(&handle(), 1).build();
+        assert_eq!(Some(&GenlDevlinkAttrs::PortIndex(1)), request.nldev.last());
+    }
+}
@@ -0,0 +1,461 @@
+// SPDX-License-Identifier: MIT
+
+//! Rate objects of devlink devices
+//!
+//! The transmit rate of a device is shaped by a hierarchy of rate objects:
+//! a leaf for each port supporting it, and the nodes created by the user to
+//! group them. Each object has a guaranteed rate, `tx_share`, and a maximum
+//! rate, `tx_max`, both in bytes per second, and may be attached to a
+//! parent node.
+//!
+//! [`RateTree`] gathers the objects of a device from a rate dump, and checks
+//! the changes built with [`RateSet`] as the kernel does, so that a missing
+//! parent or a cycle is caught before the request is sent.
+
+use super::{
+    nldev::GenlDevlinkAttrs, DevlinkHandle, GenlDevlink, GenlDevlinkCmd,
+};
+use crate::constants::*;
+use netlink_packet_utils::DecodeError;
+use std::{collections::BTreeMap, convert::TryFrom, fmt};
+
+/// Identifier of a rate object in its device
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum RateId {
+    /// Leaf of the port of this index
+    Leaf(u32),
+    /// Node of this name
+    Node(String),
+}
+
+impl fmt::Display for RateId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Leaf(port_index) => write!(f, "{port_index}"),
+            Self::Node(name) => write!(f, "{name}"),
+        }
+    }
+}
+
+/// Rate object, from a `DEVLINK_CMD_RATE_GET` reply
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RateObject {
+    pub handle: DevlinkHandle,
+    pub id: RateId,
+    /// Guaranteed rate, in bytes per second
+    pub tx_share: u64,
+    /// Maximum rate, in bytes per second, 0 if unlimited
+    pub tx_max: u64,
+    /// Name of the parent node, if any
+    pub parent: Option<String>,
+}
+
+impl RateObject {
+    /// Read the rate object from the attributes of a reply
+    pub fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Result<Self, DecodeError> {
+        let id = match *required!(nlas, RateType)? {
+            DEVLINK_RATE_TYPE_LEAF => {
+                RateId::Leaf(*required!(nlas, PortIndex)?)
+            }
+            DEVLINK_RATE_TYPE_NODE => {
+                RateId::Node(required!(nlas, RateNodeName)?.clone())
+            }
+            v => return Err(format!("Unknown rate object type: {v}").into()),
+        };
+        Ok(Self {
+            handle: DevlinkHandle::from_nlas(nlas)?,
+            id,
+            tx_share: attr!(nlas, RateTxShare).copied().unwrap_or_default(),
+            tx_max: attr!(nlas, RateTxMax).copied().unwrap_or_default(),
+            parent: attr!(nlas, RateParentNodeName).cloned(),
+        })
+    }
+}
+
+impl TryFrom<&GenlDevlink> for RateObject {
+    type Error = DecodeError;
+
+    fn try_from(msg: &GenlDevlink) -> Result<Self, Self::Error> {
+        Self::from_nlas(&msg.nldev)
+    }
+}
+
+/// Rate objects of a device
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RateTree {
+    handle: DevlinkHandle,
+    objects: BTreeMap<RateId, RateObject>,
+}
+
+impl RateTree {
+    /// Build the request dumping the rate objects of the device `handle`
+    pub fn request(handle: &DevlinkHandle) -> GenlDevlink {
+        handle.request(GenlDevlinkCmd::GetRate)
+    }
+
+    /// Gather the rate objects of the device `handle` listed in the replies
+    /// of a rate dump
+    ///
+    /// The objects of the other devices are ignored.
+    pub fn from_replies<'a, I>(
+        handle: &DevlinkHandle,
+        replies: I,
+    ) -> Result<Self, DecodeError>
+    where
+        I: IntoIterator<Item = &'a GenlDevlink>,
+    {
+        let mut objects = BTreeMap::new();
+        for reply in replies {
+            let object = RateObject::try_from(reply)?;
+            if object.handle == *handle {
+                objects.insert(object.id.clone(), object);
+            }
+        }
+        Ok(Self {
+            handle: handle.clone(),
+            objects,
+        })
+    }
+
+    /// Return the handle of the device
+    pub fn handle(&self) -> &DevlinkHandle {
+        &self.handle
+    }
+
+    /// Return the rate object `id`
+    pub fn get(&self, id: &RateId) -> Option<&RateObject> {
+        self.objects.get(id)
+    }
+
+    /// Iterate over the rate objects, leaves first
+    pub fn iter(&self) -> impl Iterator<Item = &RateObject> {
+        self.objects.values()
+    }
+
+    /// Iterate over the objects attached to the node `name`
+    pub fn children<'a>(
+        &'a self,
+        name: &'a str,
+    ) -> impl Iterator<Item = &'a RateObject> + 'a {
+        self.objects
+            .values()
+            .filter(move |object| object.parent.as_deref() == Some(name))
+    }
+
+    /// Iterate over the names of the ancestors of the object `id`, starting
+    /// with its parent
+    pub fn ancestors<'a>(
+        &'a self,
+        id: &RateId,
+    ) -> impl Iterator<Item = &'a str> + 'a {
+        let parent = self.objects.get(id).and_then(|o| o.parent.as_deref());
+        // The hierarchy reported by the kernel has no cycle, but stop anyway
+        // once every node was visited
+        std::iter::successors(parent, move |name| {
+            self.objects
+                .get(&RateId::Node((*name).to_owned()))
+                .and_then(|node| node.parent.as_deref())
+        })
+        .take(self.objects.len())
+    }
+
+    /// Check `change` against the rate objects, as the kernel would
+    pub fn validate(&self, change: &RateSet) -> Result<(), RateError> {
+        let exists = self.objects.contains_key(&change.id);
+        match &change.id {
+            RateId::Node(name) if change.create && exists => {
+                return Err(RateError::NodeExists(name.clone()))
+            }
+            RateId::Node(name) if !change.create && !exists => {
+                return Err(RateError::NodeNotFound(name.clone()))
+            }
+            RateId::Leaf(index) if !exists => {
+                return Err(RateError::LeafNotFound(*index))
+            }
+            _ => (),
+        }
+        let parent = match &change.parent {
+            Some(Some(parent)) => parent,
+            _ => return Ok(()),
+        };
+        let parent_id = RateId::Node(parent.clone());
+        if !self.objects.contains_key(&parent_id) {
+            return Err(RateError::ParentNotFound(parent.clone()));
+        }
+        if let RateId::Node(name) = &change.id {
+            let mut ancestors = std::iter::once(parent.as_str())
+                .chain(self.ancestors(&parent_id));
+            if ancestors.any(|ancestor| ancestor == name) {
+                return Err(RateError::Cycle {
+                    node: name.clone(),
+                    parent: parent.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Check that the node `name` can be deleted, and build the request
+    /// deleting it
+    pub fn delete_node(&self, name: &str) -> Result<GenlDevlink, RateError> {
+        if !self.objects.contains_key(&RateId::Node(name.to_owned())) {
+            return Err(RateError::NodeNotFound(name.to_owned()));
+        }
+        if self.children(name).next().is_some() {
+            return Err(RateError::NodeHasChildren(name.to_owned()));
+        }
+        let mut request = self.handle.request(GenlDevlinkCmd::DeleteRate);
+        request
+            .nldev
+            .push(GenlDevlinkAttrs::RateNodeName(name.to_owned()));
+        Ok(request)
+    }
+}
+
+/// Builder of a `DEVLINK_CMD_RATE_SET` or `DEVLINK_CMD_RATE_NEW` request
+///
+/// Only the parameters which are set are changed. The request is not
+/// checked, see [`RateTree::validate()`] for that.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RateSet {
+    handle: DevlinkHandle,
+    id: RateId,
+    create: bool,
+    tx_share: Option<u64>,
+    tx_max: Option<u64>,
+    parent: Option<Option<String>>,
+}
+
+impl RateSet {
+    /// Change the leaf of the port `port_index` of `handle`
+    pub fn leaf(handle: &DevlinkHandle, port_index: u32) -> Self {
+        Self::with_id(handle, RateId::Leaf(port_index), false)
+    }
+
+    /// Change the node `name` of `handle`
+    pub fn node(handle: &DevlinkHandle, name: &str) -> Self {
+        Self::with_id(handle, RateId::Node(name.to_owned()), false)
+    }
+
+    /// Create the node `name` in `handle`
+    pub fn new_node(handle: &DevlinkHandle, name: &str) -> Self {
+        Self::with_id(handle, RateId::Node(name.to_owned()), true)
+    }
+
+    fn with_id(handle: &DevlinkHandle, id: RateId, create: bool) -> Self {
+        Self {
+            handle: handle.clone(),
+            id,
+            create,
+            tx_share: None,
+            tx_max: None,
+            parent: None,
+        }
+    }
+
+    /// Set the guaranteed rate, in bytes per second
+    pub fn tx_share(mut self, tx_share: u64) -> Self {
+        self.tx_share = Some(tx_share);
+        self
+    }
+
+    /// Set the maximum rate, in bytes per second, 0 for unlimited
+    pub fn tx_max(mut self, tx_max: u64) -> Self {
+        self.tx_max = Some(tx_max);
+        self
+    }
+
+    /// Attach the object to the node `parent`
+    pub fn parent(mut self, parent: &str) -> Self {
+        self.parent = Some(Some(parent.to_owned()));
+        self
+    }
+
+    /// Detach the object from its parent
+    pub fn no_parent(mut self) -> Self {
+        self.parent = Some(None);
+        self
+    }
+
+    /// Build the request
+    pub fn build(&self) -> GenlDevlink {
+        use GenlDevlinkAttrs::*;
+        let mut request = self.handle.request(match self.create {
+            true => GenlDevlinkCmd::NewRate,
+            false => GenlDevlinkCmd::SetRate,
+        });
+        request.nldev.push(match &self.id {
+            RateId::Leaf(port_index) => PortIndex(*port_index),
+            RateId::Node(name) => RateNodeName(name.clone()),
+        });
+        if let Some(tx_share) = self.tx_share {
+            request.nldev.push(RateTxShare(tx_share));
+        }
+        if let Some(tx_max) = self.tx_max {
+            request.nldev.push(RateTxMax(tx_max));
+        }
+        if let Some(parent) = &self.parent {
+            // The kernel detaches the object when the name is empty
+            request
+                .nldev
+                .push(RateParentNodeName(parent.clone().unwrap_or_default()));
+        }
+        request
+    }
+}
+
+/// Error raised when checking a change of the rate objects
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum RateError {
+    LeafNotFound(u32),
+    NodeNotFound(String),
+    /// The node to create already exists
+    NodeExists(String),
+    /// The parent node does not exist
+    ParentNotFound(String),
+    /// The node would become an ancestor of itself
+    Cycle {
+        node: String,
+        parent: String,
+    },
+    /// Objects are still attached to the node to delete
+    NodeHasChildren(String),
+}
+
+impl fmt::Display for RateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::LeafNotFound(port_index) => {
+                write!(f, "no rate leaf for port {port_index}")
+            }
+            Self::NodeNotFound(name) => write!(f, "rate node {name} not found"),
+            Self::NodeExists(name) => {
+                write!(f, "rate node {name} already exists")
+            }
+            Self::ParentNotFound(name) => {
+                write!(f, "parent rate node {name} not found")
+            }
+            Self::Cycle { node, parent } => write!(
+                f,
+                "rate node {node} cannot be attached to its descendant {parent}"
+            ),
+            Self::NodeHasChildren(name) => {
+                write!(f, "rate node {name} has children")
+            }
+        }
+    }
+}
+
+impl std::error::Error for RateError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devlink::handle::{handle, reply};
+
+    fn object(attrs: Vec<GenlDevlinkAttrs>) -> GenlDevlink {
+        reply(GenlDevlinkCmd::NewRate, attrs)
+    }
+
+    // root <- group <- leaf 1, and leaf 2 detached
+    fn tree() -> RateTree {
+        use GenlDevlinkAttrs::*;
+        let node = |name: &str| RateNodeName(name.to_owned());
+        let parent = |name: &str| RateParentNodeName(name.to_owned());
+        let mut other =
+            object(vec![RateType(DEVLINK_RATE_TYPE_NODE), node("other")]);
+        other.nldev[1] = Location("0000:04:00.0".to_owned());
+        let replies = vec![
+            object(vec![
+                PortIndex(1),
+                RateType(DEVLINK_RATE_TYPE_LEAF),
+                RateTxShare(100),
+                RateTxMax(0),
+                parent("group"),
+            ]),
+            object(vec![PortIndex(2), RateType(DEVLINK_RATE_TYPE_LEAF)]),
+            object(vec![RateType(DEVLINK_RATE_TYPE_NODE), node("root")]),
+            object(vec![
+                RateType(DEVLINK_RATE_TYPE_NODE),
+                node("group"),
+                RateTxMax(1000),
+                parent("root"),
+            ]),
+            other,
+        ];
+        RateTree::from_replies(&handle(), &replies).unwrap()
+    }
+
+    #[test]
+    fn hierarchy() {
+        let tree = tree();
+        assert_eq!(4, tree.iter().count());
+        let leaf = tree.get(&RateId::Leaf(1)).unwrap();
+        assert_eq!(100, leaf.tx_share);
+        assert_eq!(
+            vec!["group", "root"],
+            tree.ancestors(&RateId::Leaf(1)).collect::<Vec<_>>()
+        );
+        let children: Vec<_> =
+            tree.children("root").map(|object| &object.id).collect();
+        assert_eq!(vec![&RateId::Node("group".to_owned())], children);
+        assert!(tree.get(&RateId::Node("other".to_owned())).is_none());
+    }
+
+    #[test]
+    fn validate_changes() {
+        let tree = tree();
+        let h = handle();
+        assert_eq!(
+            Ok(()),
+            tree.validate(&RateSet::new_node(&h, "leaves").parent("root"))
+        );
+        assert_eq!(
+            Err(RateError::NodeExists("root".to_owned())),
+            tree.validate(&RateSet::new_node(&h, "root"))
+        );
+        assert_eq!(
+            Err(RateError::ParentNotFound("none".to_owned())),
+            tree.validate(&RateSet::leaf(&h, 2).parent("none"))
+        );
+        assert_eq!(
+            Err(RateError::LeafNotFound(3)),
+            tree.validate(&RateSet::leaf(&h, 3).tx_max(10))
+        );
+        assert_eq!(
+            Err(RateError::Cycle {
+                node: "root".to_owned(),
+                parent: "group".to_owned(),
+            }),
+            tree.validate(&RateSet::node(&h, "root").parent("group"))
+        );
+        assert_eq!(
+            Ok(()),
+            tree.validate(&RateSet::node(&h, "group").no_parent())
+        );
+        assert_eq!(
+            Err(RateError::NodeHasChildren("group".to_owned())),
+            tree.delete_node("group")
+        );
+    }
+
+    #[test]
+    fn build_request() {
+        use GenlDevlinkAttrs::*;
+        let request = RateSet::leaf(&handle(), 1)
+            .tx_share(125_000)
+            .no_parent()
+            .build();
+        assert_eq!(GenlDevlinkCmd::SetRate, request.cmd);
+        assert_eq!(
+            &[
+                PortIndex(1),
+                RateTxShare(125_000),
+                RateParentNodeName(String::new())
+            ][..],
+            &request.nldev[2..]
+        );
+        let request = RateSet::new_node(&handle(), "group").build();
+        assert_eq!(GenlDevlinkCmd::NewRate, request.cmd);
+    }
+}
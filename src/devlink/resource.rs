@@ -0,0 +1,537 @@
+// SPDX-License-Identifier: MIT
+
+//! Resources of devlink devices
+//!
+//! The resources of a device, e.g. the KVD memory of `mlxsw` and its
+//! partitions, form a tree reported by `DEVLINK_CMD_RESOURCE_DUMP`, see
+//! [`ResourceTree`]. Resources are addressed by their path, as in
+//! `devlink resource show`, e.g. `/kvd/linear`.
+//!
+//! A new size set by `DEVLINK_CMD_RESOURCE_SET` only takes effect when the
+//! device is reloaded, and the reload fails unless the sizes of the
+//! children of each resource fit in its size. [`ResourcePlan`] checks the
+//! proposed sizes against the limits of the resources and against each
+//! other before building the requests.
+
+use super::{
+    nldev::GenlDevlinkAttrs, DevlinkHandle, GenlDevlink, GenlDevlinkCmd,
+};
+use crate::constants::*;
+use netlink_packet_utils::DecodeError;
+use std::{collections::BTreeMap, convert::TryFrom, fmt};
+
+/// Unit a resource is counted in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum ResourceUnit {
+    /// The resource is a number of entries
+    Entry,
+    Other(u8),
+}
+
+impl From<u8> for ResourceUnit {
+    fn from(value: u8) -> Self {
+        match value {
+            DEVLINK_RESOURCE_UNIT_ENTRY => Self::Entry,
+            v => Self::Other(v),
+        }
+    }
+}
+
+impl From<ResourceUnit> for u8 {
+    fn from(unit: ResourceUnit) -> Self {
+        match unit {
+            ResourceUnit::Entry => DEVLINK_RESOURCE_UNIT_ENTRY,
+            ResourceUnit::Other(v) => v,
+        }
+    }
+}
+
+/// Resource of a device, with its children
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Resource {
+    pub name: String,
+    pub id: u64,
+    /// Size in use
+    pub size: u64,
+    /// Size taking effect at the next reload, if it differs from the size
+    /// in use
+    pub size_new: Option<u64>,
+    /// Whether the sizes of the children fit in the size of the resource
+    ///
+    /// It is only reported for the resources which have children.
+    pub size_valid: Option<bool>,
+    pub size_min: u64,
+    pub size_max: u64,
+    /// The size must be a multiple of the granularity
+    pub size_gran: u64,
+    pub unit: ResourceUnit,
+    /// Amount in use, if the driver reports it
+    pub occupancy: Option<u64>,
+    pub children: Vec<Resource>,
+}
+
+impl Resource {
+    /// Read a resource from the attributes nested in
+    /// `DEVLINK_ATTR_RESOURCE`
+    pub fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Result<Self, DecodeError> {
+        Ok(Self {
+            name: required!(nlas, ResoureceName)?.clone(),
+            id: *required!(nlas, ResourceId)?,
+            size: *required!(nlas, ResourceSize)?,
+            size_new: attr!(nlas, ResourceSizeNew).copied(),
+            size_valid: attr!(nlas, ResourceSizeValid).map(|v| *v != 0),
+            size_min: *required!(nlas, ResourceSizeMin)?,
+            size_max: *required!(nlas, ResourceSizeMax)?,
+            size_gran: *required!(nlas, ResourceSizeGran)?,
+            unit: (*required!(nlas, ResourceUnit)?).into(),
+            occupancy: attr!(nlas, ResourceOcc).copied(),
+            children: resources(attr!(nlas, ResourceList))?,
+        })
+    }
+
+    /// Return the size the resource will have after the next reload
+    pub fn pending_size(&self) -> u64 {
+        self.size_new.unwrap_or(self.size)
+    }
+
+    /// Return the child named `name`
+    pub fn child(&self, name: &str) -> Option<&Resource> {
+        self.children.iter().find(|child| child.name == name)
+    }
+
+    // Check `size` against the limits of the resource, as the kernel does
+    // on `DEVLINK_CMD_RESOURCE_SET`
+    fn check_size(&self, path: &str, size: u64) -> Result<(), ResourceError> {
+        if size > self.size_max {
+            return Err(ResourceError::AboveMax {
+                path: path.to_owned(),
+                size,
+                max: self.size_max,
+            });
+        }
+        if size < self.size_min {
+            return Err(ResourceError::BelowMin {
+                path: path.to_owned(),
+                size,
+                min: self.size_min,
+            });
+        }
+        if self.size_gran != 0 && size % self.size_gran != 0 {
+            return Err(ResourceError::Granularity {
+                path: path.to_owned(),
+                size,
+                granularity: self.size_gran,
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Resources of a device, from a `DEVLINK_CMD_RESOURCE_DUMP` reply
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResourceTree {
+    pub handle: DevlinkHandle,
+    /// Top-level resources
+    pub resources: Vec<Resource>,
+}
+
+impl ResourceTree {
+    /// Build the request reading the resources of the device `handle`
+    ///
+    /// Despite its name, `DEVLINK_CMD_RESOURCE_DUMP` is not a dump request:
+    /// the whole tree comes in a single reply.
+    pub fn request(handle: &DevlinkHandle) -> GenlDevlink {
+        handle.request(GenlDevlinkCmd::DumpResource)
+    }
+
+    /// Read the tree from the attributes of a reply
+    pub fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Result<Self, DecodeError> {
+        Ok(Self {
+            handle: DevlinkHandle::from_nlas(nlas)?,
+            resources: resources(attr!(nlas, ResourceList))?,
+        })
+    }
+
+    /// Return the resource at `path`, e.g. `/kvd/linear`
+    ///
+    /// The leading `/` is optional.
+    pub fn get(&self, path: &str) -> Option<&Resource> {
+        let mut names = path.trim_start_matches('/').split('/');
+        let first = names.next()?;
+        let mut resource = self.resources.iter().find(|r| r.name == first)?;
+        for name in names {
+            resource = resource.child(name)?;
+        }
+        Some(resource)
+    }
+
+    /// Iterate over the resources and their paths, parents first
+    pub fn iter(&self) -> impl Iterator<Item = (String, &Resource)> {
+        let mut resources = Vec::new();
+        let mut stack: Vec<_> = self
+            .resources
+            .iter()
+            .rev()
+            .map(|r| (format!("/{}", r.name), r))
+            .collect();
+        while let Some((path, resource)) = stack.pop() {
+            stack.extend(
+                resource
+                    .children
+                    .iter()
+                    .rev()
+                    .map(|child| (format!("{path}/{}", child.name), child)),
+            );
+            resources.push((path, resource));
+        }
+        resources.into_iter()
+    }
+
+    /// Whether some sizes only take effect at the next reload
+    pub fn reload_pending(&self) -> bool {
+        self.iter().any(|(_, r)| r.size_new.is_some())
+    }
+
+    /// Start planning new sizes for the resources
+    pub fn plan(&self) -> ResourcePlan<'_> {
+        ResourcePlan {
+            tree: self,
+            sizes: BTreeMap::new(),
+        }
+    }
+}
+
+impl TryFrom<&GenlDevlink> for ResourceTree {
+    type Error = DecodeError;
+
+    fn try_from(msg: &GenlDevlink) -> Result<Self, Self::Error> {
+        Self::from_nlas(&msg.nldev)
+    }
+}
+
+/// Builder of a `DEVLINK_CMD_RESOURCE_SET` request
+///
+/// The new size only takes effect when the device is reloaded. The request
+/// is not checked, see [`ResourcePlan`] for that.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResourceSet {
+    handle: DevlinkHandle,
+    id: u64,
+    size: u64,
+}
+
+impl ResourceSet {
+    /// Set the size of the resource `id` of `handle`
+    pub fn new(handle: &DevlinkHandle, id: u64, size: u64) -> Self {
+        Self {
+            handle: handle.clone(),
+            id,
+            size,
+        }
+    }
+
+    /// Build the request
+    pub fn build(&self) -> GenlDevlink {
+        let mut request = self.handle.request(GenlDevlinkCmd::SetResource);
+        request.nldev.extend([
+            GenlDevlinkAttrs::ResourceId(self.id),
+            GenlDevlinkAttrs::ResourceSize(self.size),
+        ]);
+        request
+    }
+}
+
+/// New sizes of the resources of a device, checked before being requested
+///
+/// Each size is checked against the limits of its resource when it is
+/// [`set()`](Self::set), and the sizes of the children of each resource
+/// are checked against its size by [`validate()`](Self::validate), since
+/// several sizes may have to change together. The resources whose size is
+/// not planned keep their pending size.
+#[derive(Clone, Debug)]
+pub struct ResourcePlan<'a> {
+    tree: &'a ResourceTree,
+    sizes: BTreeMap<u64, u64>,
+}
+
+impl ResourcePlan<'_> {
+    /// Plan the size of the resource at `path`
+    pub fn set(
+        &mut self,
+        path: &str,
+        size: u64,
+    ) -> Result<&mut Self, ResourceError> {
+        let resource = self
+            .tree
+            .get(path)
+            .ok_or_else(|| ResourceError::NotFound(path.to_owned()))?;
+        resource.check_size(path, size)?;
+        self.sizes.insert(resource.id, size);
+        Ok(self)
+    }
+
+    /// Return the size the resource at `path` will have once the plan is
+    /// applied
+    pub fn size(&self, path: &str) -> Option<u64> {
+        self.tree.get(path).map(|resource| self.size_of(resource))
+    }
+
+    /// Check that the children of each resource fit in its planned size
+    pub fn validate(&self) -> Result<(), ResourceError> {
+        for (path, resource) in self.tree.iter() {
+            if resource.children.is_empty() {
+                continue;
+            }
+            let total = resource
+                .children
+                .iter()
+                .map(|child| u128::from(self.size_of(child)))
+                .sum::<u128>();
+            let size = self.size_of(resource);
+            if total > u128::from(size) {
+                return Err(ResourceError::ChildrenExceedSize {
+                    path,
+                    size,
+                    total,
+                });
+            }
+        }
+        Ok(())
+    }
+
+    /// Validate the plan and build its requests
+    ///
+    /// A `DEVLINK_CMD_RESOURCE_SET` request is built for each planned size
+    /// differing from the pending one, followed by the
+    /// `DEVLINK_CMD_RELOAD` request applying them. The reload is also
+    /// requested if sizes set earlier are still pending, no request is built
+    /// otherwise.
+    pub fn requests(&self) -> Result<Vec<GenlDevlink>, ResourceError> {
+        self.validate()?;
+        let handle = &self.tree.handle;
+        let mut requests: Vec<_> = self
+            .tree
+            .iter()
+            .filter_map(|(_, resource)| {
+                let size = *self.sizes.get(&resource.id)?;
+                (size != resource.pending_size()).then(|| {
+                    ResourceSet::new(handle, resource.id, size).build()
+                })
+            })
+            .collect();
+        if !requests.is_empty() || self.tree.reload_pending() {
+            let mut reload = handle.request(GenlDevlinkCmd::Reload);
+            reload.nldev.push(GenlDevlinkAttrs::ReloadAction(
+                DEVLINK_RELOAD_ACTION_DRIVER_REINIT,
+            ));
+            requests.push(reload);
+        }
+        Ok(requests)
+    }
+
+    fn size_of(&self, resource: &Resource) -> u64 {
+        self.sizes
+            .get(&resource.id)
+            .copied()
+            .unwrap_or_else(|| resource.pending_size())
+    }
+}
+
+/// Error raised when planning the sizes of resources
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ResourceError {
+    /// No resource has the path
+    NotFound(String),
+    /// The size is below the minimum size of the resource
+    BelowMin { path: String, size: u64, min: u64 },
+    /// The size is above the maximum size of the resource
+    AboveMax { path: String, size: u64, max: u64 },
+    /// The size is not a multiple of the granularity of the resource
+    Granularity {
+        path: String,
+        size: u64,
+        granularity: u64,
+    },
+    /// The sizes of the children add up to more than the size of the
+    /// resource, which makes the reload fail
+    ChildrenExceedSize {
+        path: String,
+        size: u64,
+        total: u128,
+    },
+}
+
+impl fmt::Display for ResourceError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::NotFound(path) => write!(f, "resource {path} not found"),
+            Self::BelowMin { path, size, min } => write!(
+                f,
+                "size {size} of resource {path} is smaller than the minimum \
+                 {min}"
+            ),
+            Self::AboveMax { path, size, max } => write!(
+                f,
+                "size {size} of resource {path} is larger than the maximum \
+                 {max}"
+            ),
+            Self::Granularity {
+                path,
+                size,
+                granularity,
+            } => write!(
+                f,
+                "size {size} of resource {path} is not a multiple of the \
+                 granularity {granularity}"
+            ),
+            Self::ChildrenExceedSize { path, size, total } => write!(
+                f,
+                "children of resource {path} need {total}, more than its size \
+                 {size}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ResourceError {}
+
+fn resources(
+    list: Option<&Vec<GenlDevlinkAttrs>>,
+) -> Result<Vec<Resource>, DecodeError> {
+    list.into_iter()
+        .flatten()
+        .filter_map(|nla| match nla {
+            GenlDevlinkAttrs::Resource(nlas) => Some(Resource::from_nlas(nlas)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devlink::handle::{handle, reply};
+    use std::convert::TryInto;
+
+    fn resource(
+        name: &str,
+        id: u64,
+        size: u64,
+        gran: u64,
+        children: Vec<GenlDevlinkAttrs>,
+    ) -> GenlDevlinkAttrs {
+        use GenlDevlinkAttrs::*;
+        let mut nlas = vec![
+            ResoureceName(name.to_owned()),
+            ResourceSize(size),
+            ResourceId(id),
+            ResourceSizeGran(gran),
+            ResourceSizeMax(1 << 20),
+            ResourceSizeMin(0),
+            ResourceUnit(DEVLINK_RESOURCE_UNIT_ENTRY),
+        ];
+        if !children.is_empty() {
+            nlas.push(ResourceSizeValid(1));
+            nlas.push(ResourceList(children));
+        }
+        Resource(nlas)
+    }
+
+    // Modelled after the KVD of mlxsw
+    fn tree() -> ResourceTree {
+        let kvd = resource(
+            "kvd",
+            1,
+            1 << 19,
+            128,
+            vec![
+                resource("linear", 2, 1 << 17, 128, vec![]),
+                resource("hash_single", 3, 1 << 18, 128, vec![]),
+                resource("hash_double", 4, 1 << 17, 128, vec![]),
+            ],
+        );
+        let reply = reply(
+            GenlDevlinkCmd::DumpResource,
+            vec![GenlDevlinkAttrs::ResourceList(vec![kvd])],
+        );
+        (&reply).try_into().unwrap()
+    }
+
+    #[test]
+    fn lookup() {
+        let tree = tree();
+        let linear = tree.get("/kvd/linear").unwrap();
+        assert_eq!(2, linear.id);
+        assert_eq!(ResourceUnit::Entry, linear.unit);
+        assert_eq!(Some(true), tree.get("kvd").unwrap().size_valid);
+        assert_eq!(None, linear.size_valid);
+        assert!(tree.get("/kvd/linear/none").is_none());
+        assert!(tree.get("/").is_none());
+        assert!(!tree.reload_pending());
+
+        let paths: Vec<_> = tree.iter().map(|(path, _)| path).collect();
+        assert_eq!(
+            vec![
+                "/kvd",
+                "/kvd/linear",
+                "/kvd/hash_single",
+                "/kvd/hash_double"
+            ],
+            paths
+        );
+    }
+
+    #[test]
+    fn plan_checks_limits() {
+        let tree = tree();
+        let mut plan = tree.plan();
+        assert_eq!(
+            Err(ResourceError::NotFound("/kvd/lpm".to_owned())),
+            plan.set("/kvd/lpm", 0).map(|_| ())
+        );
+        assert_eq!(
+            Err(ResourceError::Granularity {
+                path: "/kvd/linear".to_owned(),
+                size: 1000,
+                granularity: 128,
+            }),
+            plan.set("/kvd/linear", 1000).map(|_| ())
+        );
+        assert!(matches!(
+            plan.set("/kvd/linear", 1 << 21),
+            Err(ResourceError::AboveMax { max, .. }) if max == 1 << 20
+        ));
+        assert_eq!(None, plan.size("/kvd/lpm"));
+        assert_eq!(Some(1 << 17), plan.size("/kvd/linear"));
+    }
+
+    #[test]
+    fn plan_checks_parent_totals() {
+        let tree = tree();
+        let mut plan = tree.plan();
+        plan.set("/kvd/linear", 1 << 18).unwrap();
+        assert_eq!(
+            Err(ResourceError::ChildrenExceedSize {
+                path: "/kvd".to_owned(),
+                size: 1 << 19,
+                total: (1 << 19) + (1 << 17),
+            }),
+            plan.requests()
+        );
+
+        plan.set("/kvd/hash_single", 1 << 17).unwrap();
+        plan.set("/kvd/hash_double", 1 << 17).unwrap();
+        let requests = plan.requests().unwrap();
+        assert_eq!(
+            vec![
+                ResourceSet::new(&handle(), 2, 1 << 18).build(),
+                ResourceSet::new(&handle(), 3, 1 << 17).build(),
+            ],
+            requests[..2]
+        );
+        assert_eq!(GenlDevlinkCmd::Reload, requests[2].cmd);
+        assert_eq!(3, requests.len());
+
+        assert!(tree.plan().requests().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,830 @@
+// SPDX-License-Identifier: MIT
+
+//! Shared buffers of devlink devices
+//!
+//! A shared buffer is split into ingress and egress pools. Each port has a
+//! threshold per pool ([`SbPortPool`]), and each traffic class of a port is
+//! bound to a pool with its own threshold ([`SbTcBind`]). A threshold is a
+//! number of bytes or an alpha factor, depending on the threshold type of
+//! the pool, see [`SbThreshold`].
+//!
+//! The occupancy of the buffers is read in two steps: the
+//! [`SharedBuffer::occ_snapshot()`] request takes a snapshot of the
+//! occupancy, which is then reported in the replies of the port pool and
+//! traffic class bind dumps. [`SbOccupancyReport`] joins those replies into
+//! the current and maximum usage of each port and traffic class.
+
+use super::{
+    nldev::GenlDevlinkAttrs, DevlinkHandle, GenlDevlink, GenlDevlinkCmd,
+};
+use crate::constants::*;
+use netlink_packet_utils::DecodeError;
+use std::{
+    collections::BTreeMap,
+    convert::{TryFrom, TryInto},
+};
+
+/// Direction of the traffic using a pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SbPoolType {
+    Ingress,
+    Egress,
+}
+
+impl From<SbPoolType> for u8 {
+    fn from(pool_type: SbPoolType) -> Self {
+        match pool_type {
+            SbPoolType::Ingress => DEVLINK_SB_POOL_TYPE_INGRESS,
+            SbPoolType::Egress => DEVLINK_SB_POOL_TYPE_EGRESS,
+        }
+    }
+}
+
+impl TryFrom<u8> for SbPoolType {
+    type Error = DecodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            DEVLINK_SB_POOL_TYPE_INGRESS => Ok(Self::Ingress),
+            DEVLINK_SB_POOL_TYPE_EGRESS => Ok(Self::Egress),
+            v => Err(format!("Unknown shared buffer pool type: {v}").into()),
+        }
+    }
+}
+
+/// Interpretation of the thresholds of a pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SbThresholdType {
+    /// The thresholds are a number of bytes
+    Static,
+    /// The thresholds are alpha factors of the free space of the pool
+    Dynamic,
+}
+
+impl From<SbThresholdType> for u8 {
+    fn from(threshold_type: SbThresholdType) -> Self {
+        match threshold_type {
+            SbThresholdType::Static => DEVLINK_SB_THRESHOLD_TYPE_STATIC,
+            SbThresholdType::Dynamic => DEVLINK_SB_THRESHOLD_TYPE_DYNAMIC,
+        }
+    }
+}
+
+impl TryFrom<u8> for SbThresholdType {
+    type Error = DecodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            DEVLINK_SB_THRESHOLD_TYPE_STATIC => Ok(Self::Static),
+            DEVLINK_SB_THRESHOLD_TYPE_DYNAMIC => Ok(Self::Dynamic),
+            v => {
+                Err(format!("Unknown shared buffer threshold type: {v}").into())
+            }
+        }
+    }
+}
+
+/// Threshold of a port or traffic class in a pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SbThreshold {
+    /// Maximum number of bytes, in a pool of static threshold type
+    Bytes(u32),
+    /// Alpha factor, in a pool of dynamic threshold type
+    ///
+    /// The driver maps the value to the fraction of the free space of the
+    /// pool which can be used, e.g. `mlxsw` uses `2^(value - 10)`.
+    Alpha(u32),
+}
+
+impl SbThreshold {
+    /// Interpret the threshold `value` for a pool of type `threshold_type`
+    pub fn new(threshold_type: SbThresholdType, value: u32) -> Self {
+        match threshold_type {
+            SbThresholdType::Static => Self::Bytes(value),
+            SbThresholdType::Dynamic => Self::Alpha(value),
+        }
+    }
+
+    /// Return the threshold type of the pool the threshold belongs to
+    pub fn threshold_type(&self) -> SbThresholdType {
+        match self {
+            Self::Bytes(_) => SbThresholdType::Static,
+            Self::Alpha(_) => SbThresholdType::Dynamic,
+        }
+    }
+
+    /// Return the value of `DEVLINK_ATTR_SB_THRESHOLD`
+    pub fn value(&self) -> u32 {
+        match self {
+            Self::Bytes(v) | Self::Alpha(v) => *v,
+        }
+    }
+}
+
+/// Occupancy of a pool, as of the last snapshot
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct SbOccupancy {
+    /// Number of bytes in use when the snapshot was taken
+    pub current: u32,
+    /// Maximum number of bytes in use since the maximum was last cleared
+    pub max: u32,
+}
+
+impl SbOccupancy {
+    // The occupancy is only reported by the drivers which support it
+    fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Option<Self> {
+        Some(Self {
+            current: *attr!(nlas, SbOccCur)?,
+            max: *attr!(nlas, SbOccMax)?,
+        })
+    }
+}
+
+/// Shared buffer of a device, from a `DEVLINK_CMD_SB_GET` reply
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SharedBuffer {
+    pub handle: DevlinkHandle,
+    pub index: u32,
+    /// Size of the buffer, in bytes
+    pub size: u32,
+    pub ingress_pool_count: u16,
+    pub egress_pool_count: u16,
+    pub ingress_tc_count: u16,
+    pub egress_tc_count: u16,
+}
+
+impl SharedBuffer {
+    /// Read the shared buffer from the attributes of a reply
+    pub fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Result<Self, DecodeError> {
+        Ok(Self {
+            handle: DevlinkHandle::from_nlas(nlas)?,
+            index: *required!(nlas, SbIndex)?,
+            size: *required!(nlas, SbSize)?,
+            ingress_pool_count: *required!(nlas, SbIngressPoolCount)?,
+            egress_pool_count: *required!(nlas, SbEgressPoolCount)?,
+            ingress_tc_count: *required!(nlas, SbIngressTcCount)?,
+            egress_tc_count: *required!(nlas, SbEgressTcCount)?,
+        })
+    }
+
+    /// Build the request taking a snapshot of the occupancy of the buffer
+    pub fn occ_snapshot(&self) -> GenlDevlink {
+        self.request(GenlDevlinkCmd::SbOccSnapshot)
+    }
+
+    /// Build the request clearing the maximum occupancy of the buffer
+    pub fn occ_max_clear(&self) -> GenlDevlink {
+        self.request(GenlDevlinkCmd::SbOccMaxClear)
+    }
+
+    fn request(&self, cmd: GenlDevlinkCmd) -> GenlDevlink {
+        let mut request = self.handle.request(cmd);
+        request.nldev.push(GenlDevlinkAttrs::SbIndex(self.index));
+        request
+    }
+}
+
+impl TryFrom<&GenlDevlink> for SharedBuffer {
+    type Error = DecodeError;
+
+    fn try_from(msg: &GenlDevlink) -> Result<Self, Self::Error> {
+        Self::from_nlas(&msg.nldev)
+    }
+}
+
+/// Pool of a shared buffer, from a `DEVLINK_CMD_SB_POOL_GET` reply
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SbPool {
+    pub handle: DevlinkHandle,
+    pub sb_index: u32,
+    pub index: u16,
+    pub pool_type: SbPoolType,
+    /// Size of the pool, in bytes
+    pub size: u32,
+    pub threshold_type: SbThresholdType,
+    /// Size of the cells the pool is allocated by, if reported
+    pub cell_size: Option<u32>,
+}
+
+impl SbPool {
+    /// Read the pool from the attributes of a reply
+    pub fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Result<Self, DecodeError> {
+        Ok(Self {
+            handle: DevlinkHandle::from_nlas(nlas)?,
+            sb_index: *required!(nlas, SbIndex)?,
+            index: *required!(nlas, SbPoolIndex)?,
+            pool_type: (*required!(nlas, SbPoolType)?).try_into()?,
+            size: *required!(nlas, SbPoolSize)?,
+            threshold_type: (*required!(nlas, SbPoolThresholdType)?)
+                .try_into()?,
+            cell_size: attr!(nlas, SbPoolCellSize).copied(),
+        })
+    }
+
+    // Whether the pool is the one `index` refers to in the shared buffer
+    // `sb_index` of the device `handle`
+    fn is(&self, handle: &DevlinkHandle, sb_index: u32, index: u16) -> bool {
+        self.index == index
+            && self.sb_index == sb_index
+            && self.handle == *handle
+    }
+}
+
+impl TryFrom<&GenlDevlink> for SbPool {
+    type Error = DecodeError;
+
+    fn try_from(msg: &GenlDevlink) -> Result<Self, Self::Error> {
+        Self::from_nlas(&msg.nldev)
+    }
+}
+
+/// Threshold of a port in a pool, from a `DEVLINK_CMD_SB_PORT_POOL_GET`
+/// reply
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SbPortPool {
+    pub handle: DevlinkHandle,
+    pub port_index: u32,
+    pub sb_index: u32,
+    pub pool_index: u16,
+    /// Value of the threshold, see [`threshold()`](Self::threshold)
+    pub raw_threshold: u32,
+    pub occupancy: Option<SbOccupancy>,
+}
+
+impl SbPortPool {
+    /// Read the port pool from the attributes of a reply
+    pub fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Result<Self, DecodeError> {
+        Ok(Self {
+            handle: DevlinkHandle::from_nlas(nlas)?,
+            port_index: *required!(nlas, PortIndex)?,
+            sb_index: *required!(nlas, SbIndex)?,
+            pool_index: *required!(nlas, SbPoolIndex)?,
+            raw_threshold: *required!(nlas, SbPoolThreshold)?,
+            occupancy: SbOccupancy::from_nlas(nlas),
+        })
+    }
+
+    /// Interpret the threshold with the threshold type of the pool
+    ///
+    /// Returns `None` if the pool is not in `pools`.
+    pub fn threshold(&self, pools: &[SbPool]) -> Option<SbThreshold> {
+        pools
+            .iter()
+            .find(|pool| pool.is(&self.handle, self.sb_index, self.pool_index))
+            .map(|pool| {
+                SbThreshold::new(pool.threshold_type, self.raw_threshold)
+            })
+    }
+}
+
+impl TryFrom<&GenlDevlink> for SbPortPool {
+    type Error = DecodeError;
+
+    fn try_from(msg: &GenlDevlink) -> Result<Self, Self::Error> {
+        Self::from_nlas(&msg.nldev)
+    }
+}
+
+/// Binding of a traffic class of a port to a pool, from a
+/// `DEVLINK_CMD_SB_TC_POOL_BIND_GET` reply
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SbTcBind {
+    pub handle: DevlinkHandle,
+    pub port_index: u32,
+    pub sb_index: u32,
+    pub tc_index: u16,
+    pub pool_type: SbPoolType,
+    pub pool_index: u16,
+    /// Value of the threshold, see [`threshold()`](Self::threshold)
+    pub raw_threshold: u32,
+    pub occupancy: Option<SbOccupancy>,
+}
+
+impl SbTcBind {
+    /// Read the binding from the attributes of a reply
+    pub fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Result<Self, DecodeError> {
+        Ok(Self {
+            handle: DevlinkHandle::from_nlas(nlas)?,
+            port_index: *required!(nlas, PortIndex)?,
+            sb_index: *required!(nlas, SbIndex)?,
+            tc_index: *required!(nlas, SbTcIndex)?,
+            pool_type: (*required!(nlas, SbPoolType)?).try_into()?,
+            pool_index: *required!(nlas, SbPoolIndex)?,
+            raw_threshold: *required!(nlas, SbPoolThreshold)?,
+            occupancy: SbOccupancy::from_nlas(nlas),
+        })
+    }
+
+    /// Interpret the threshold with the threshold type of the pool
+    ///
+    /// Returns `None` if the pool is not in `pools`.
+    pub fn threshold(&self, pools: &[SbPool]) -> Option<SbThreshold> {
+        pools
+            .iter()
+            .find(|pool| pool.is(&self.handle, self.sb_index, self.pool_index))
+            .map(|pool| {
+                SbThreshold::new(pool.threshold_type, self.raw_threshold)
+            })
+    }
+}
+
+impl TryFrom<&GenlDevlink> for SbTcBind {
+    type Error = DecodeError;
+
+    fn try_from(msg: &GenlDevlink) -> Result<Self, Self::Error> {
+        Self::from_nlas(&msg.nldev)
+    }
+}
+
+/// Builder of a `DEVLINK_CMD_SB_POOL_SET` request
+///
+/// The request applies to the shared buffer 0 unless
+/// [`sb_index()`](Self::sb_index) is set, as `devlink sb pool set` does.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SbPoolSet {
+    handle: DevlinkHandle,
+    sb_index: u32,
+    pool_index: u16,
+    size: u32,
+    threshold_type: SbThresholdType,
+}
+
+impl SbPoolSet {
+    /// Set the size and threshold type of the pool `pool_index` of `handle`
+    pub fn new(
+        handle: &DevlinkHandle,
+        pool_index: u16,
+        size: u32,
+        threshold_type: SbThresholdType,
+    ) -> Self {
+        Self {
+            handle: handle.clone(),
+            sb_index: 0,
+            pool_index,
+            size,
+            threshold_type,
+        }
+    }
+
+    /// Address the pool in the shared buffer `sb_index`
+    pub fn sb_index(mut self, sb_index: u32) -> Self {
+        self.sb_index = sb_index;
+        self
+    }
+
+    /// Build the request
+    pub fn build(&self) -> GenlDevlink {
+        let mut request = self.handle.request(GenlDevlinkCmd::SetSbPool);
+        request.nldev.extend([
+            GenlDevlinkAttrs::SbIndex(self.sb_index),
+            GenlDevlinkAttrs::SbPoolIndex(self.pool_index),
+            GenlDevlinkAttrs::SbPoolSize(self.size),
+            GenlDevlinkAttrs::SbPoolThresholdType(self.threshold_type.into()),
+        ]);
+        request
+    }
+}
+
+/// Builder of a `DEVLINK_CMD_SB_PORT_POOL_SET` request
+///
+/// The request applies to the shared buffer 0 unless
+/// [`sb_index()`](Self::sb_index) is set, as `devlink sb port pool set`
+/// does.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SbPortPoolSet {
+    handle: DevlinkHandle,
+    port_index: u32,
+    sb_index: u32,
+    pool_index: u16,
+    threshold: SbThreshold,
+}
+
+impl SbPortPoolSet {
+    /// Set the threshold of the port `port_index` in the pool `pool_index`
+    pub fn new(
+        handle: &DevlinkHandle,
+        port_index: u32,
+        pool_index: u16,
+        threshold: SbThreshold,
+    ) -> Self {
+        Self {
+            handle: handle.clone(),
+            port_index,
+            sb_index: 0,
+            pool_index,
+            threshold,
+        }
+    }
+
+    /// Address the pool in the shared buffer `sb_index`
+    pub fn sb_index(mut self, sb_index: u32) -> Self {
+        self.sb_index = sb_index;
+        self
+    }
+
+    /// Build the request
+    pub fn build(&self) -> GenlDevlink {
+        let mut request = self.handle.request(GenlDevlinkCmd::SetSbPortPool);
+        request.nldev.extend([
+            GenlDevlinkAttrs::PortIndex(self.port_index),
+            GenlDevlinkAttrs::SbIndex(self.sb_index),
+            GenlDevlinkAttrs::SbPoolIndex(self.pool_index),
+            GenlDevlinkAttrs::SbPoolThreshold(self.threshold.value()),
+        ]);
+        request
+    }
+}
+
+/// Builder of a `DEVLINK_CMD_SB_TC_POOL_BIND_SET` request
+///
+/// The request applies to the shared buffer 0 unless
+/// [`sb_index()`](Self::sb_index) is set, as `devlink sb tc bind set` does.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SbTcBindSet {
+    handle: DevlinkHandle,
+    port_index: u32,
+    sb_index: u32,
+    tc_index: u16,
+    pool_type: SbPoolType,
+    pool_index: u16,
+    threshold: SbThreshold,
+}
+
+impl SbTcBindSet {
+    /// Bind the traffic class `tc_index` of the port `port_index` in the
+    /// direction `pool_type` to the pool `pool_index`, with `threshold`
+    pub fn new(
+        handle: &DevlinkHandle,
+        port_index: u32,
+        tc_index: u16,
+        pool_type: SbPoolType,
+        pool_index: u16,
+        threshold: SbThreshold,
+    ) -> Self {
+        Self {
+            handle: handle.clone(),
+            port_index,
+            sb_index: 0,
+            tc_index,
+            pool_type,
+            pool_index,
+            threshold,
+        }
+    }
+
+    /// Address the traffic class in the shared buffer `sb_index`
+    pub fn sb_index(mut self, sb_index: u32) -> Self {
+        self.sb_index = sb_index;
+        self
+    }
+
+    /// Build the request
+    pub fn build(&self) -> GenlDevlink {
+        let mut request = self.handle.request(GenlDevlinkCmd::SetSbTcPoolBind);
+        request.nldev.extend([
+            GenlDevlinkAttrs::PortIndex(self.port_index),
+            GenlDevlinkAttrs::SbIndex(self.sb_index),
+            GenlDevlinkAttrs::SbTcIndex(self.tc_index),
+            GenlDevlinkAttrs::SbPoolType(self.pool_type.into()),
+            GenlDevlinkAttrs::SbPoolIndex(self.pool_index),
+            GenlDevlinkAttrs::SbPoolThreshold(self.threshold.value()),
+        ]);
+        request
+    }
+}
+
+/// Usage of a pool by a port
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SbPoolUsage {
+    pub pool_index: u16,
+    /// Threshold of the port, if the pool was listed
+    pub threshold: Option<SbThreshold>,
+    pub occupancy: SbOccupancy,
+}
+
+/// Usage of a pool by a traffic class of a port
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SbTcUsage {
+    pub tc_index: u16,
+    pub pool_type: SbPoolType,
+    pub pool_index: u16,
+    /// Threshold of the traffic class, if the pool was listed
+    pub threshold: Option<SbThreshold>,
+    pub occupancy: SbOccupancy,
+}
+
+/// Occupancy of a shared buffer by a port
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SbPortUsage {
+    /// Usage of the pools, sorted by pool index
+    pub pools: Vec<SbPoolUsage>,
+    /// Usage of the traffic classes, the ingress ones first, sorted by
+    /// traffic class index
+    pub tcs: Vec<SbTcUsage>,
+}
+
+/// Occupancy of a shared buffer, per port and traffic class
+///
+/// The report is built from the replies of the `DEVLINK_CMD_SB_POOL_GET`,
+/// `DEVLINK_CMD_SB_PORT_POOL_GET` and `DEVLINK_CMD_SB_TC_POOL_BIND_GET`
+/// dumps which follow a [`SharedBuffer::occ_snapshot()`] request. The
+/// entries of other devices or shared buffers, and the ones without
+/// occupancy, are left out.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SbOccupancyReport {
+    ports: BTreeMap<u32, SbPortUsage>,
+}
+
+impl SbOccupancyReport {
+    /// Join the pools, port pools and traffic class bindings of `sb`
+    pub fn new(
+        sb: &SharedBuffer,
+        pools: &[SbPool],
+        port_pools: &[SbPortPool],
+        tc_binds: &[SbTcBind],
+    ) -> Self {
+        let in_sb = |handle: &DevlinkHandle, sb_index: u32| {
+            *handle == sb.handle && sb_index == sb.index
+        };
+        let mut ports = BTreeMap::<u32, SbPortUsage>::new();
+        for port_pool in port_pools {
+            if !in_sb(&port_pool.handle, port_pool.sb_index) {
+                continue;
+            }
+            if let Some(occupancy) = port_pool.occupancy {
+                let port = ports.entry(port_pool.port_index).or_default();
+                port.pools.push(SbPoolUsage {
+                    pool_index: port_pool.pool_index,
+                    threshold: port_pool.threshold(pools),
+                    occupancy,
+                });
+            }
+        }
+        for tc_bind in tc_binds {
+            if !in_sb(&tc_bind.handle, tc_bind.sb_index) {
+                continue;
+            }
+            if let Some(occupancy) = tc_bind.occupancy {
+                let port = ports.entry(tc_bind.port_index).or_default();
+                port.tcs.push(SbTcUsage {
+                    tc_index: tc_bind.tc_index,
+                    pool_type: tc_bind.pool_type,
+                    pool_index: tc_bind.pool_index,
+                    threshold: tc_bind.threshold(pools),
+                    occupancy,
+                });
+            }
+        }
+        for port in ports.values_mut() {
+            port.pools.sort_by_key(|usage| usage.pool_index);
+            port.tcs
+                .sort_by_key(|usage| (usage.pool_type, usage.tc_index));
+        }
+        Self { ports }
+    }
+
+    /// Build the report of `sb` from the replies of the dumps
+    ///
+    /// The replies of other commands are ignored.
+    pub fn from_replies<'a, I>(
+        sb: &SharedBuffer,
+        replies: I,
+    ) -> Result<Self, DecodeError>
+    where
+        I: IntoIterator<Item = &'a GenlDevlink>,
+    {
+        let mut pools = Vec::new();
+        let mut port_pools = Vec::new();
+        let mut tc_binds = Vec::new();
+        for reply in replies {
+            match reply.cmd {
+                GenlDevlinkCmd::NewSbPool => pools.push(reply.try_into()?),
+                GenlDevlinkCmd::NewSbPortPool => {
+                    port_pools.push(reply.try_into()?)
+                }
+                GenlDevlinkCmd::NewSbTcPoolBind => {
+                    tc_binds.push(reply.try_into()?)
+                }
+                _ => {}
+            }
+        }
+        Ok(Self::new(sb, &pools, &port_pools, &tc_binds))
+    }
+
+    /// Return the usage of the port `port_index`
+    pub fn port(&self, port_index: u32) -> Option<&SbPortUsage> {
+        self.ports.get(&port_index)
+    }
+
+    /// Iterate over the ports and their usage, sorted by port index
+    pub fn ports(&self) -> impl Iterator<Item = (u32, &SbPortUsage)> {
+        self.ports.iter().map(|(index, usage)| (*index, usage))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devlink::handle::{handle, reply};
+
+    fn pool(index: u16, threshold_type: u8) -> GenlDevlink {
+        reply(
+            GenlDevlinkCmd::NewSbPool,
+            vec![
+                GenlDevlinkAttrs::SbIndex(0),
+                GenlDevlinkAttrs::SbPoolIndex(index),
+                GenlDevlinkAttrs::SbPoolType(DEVLINK_SB_POOL_TYPE_INGRESS),
+                GenlDevlinkAttrs::SbPoolSize(12_000_000),
+                GenlDevlinkAttrs::SbPoolThresholdType(threshold_type),
+                GenlDevlinkAttrs::SbPoolCellSize(96),
+            ],
+        )
+    }
+
+    fn port_pool(port: u32, pool: u16, cur: u32, max: u32) -> GenlDevlink {
+        reply(
+            GenlDevlinkCmd::NewSbPortPool,
+            vec![
+                GenlDevlinkAttrs::PortIndex(port),
+                GenlDevlinkAttrs::SbIndex(0),
+                GenlDevlinkAttrs::SbPoolIndex(pool),
+                GenlDevlinkAttrs::SbPoolThreshold(16),
+                GenlDevlinkAttrs::SbOccCur(cur),
+                GenlDevlinkAttrs::SbOccMax(max),
+            ],
+        )
+    }
+
+    fn tc_bind(port: u32, tc: u16, pool_type: u8, pool: u16) -> GenlDevlink {
+        reply(
+            GenlDevlinkCmd::NewSbTcPoolBind,
+            vec![
+                GenlDevlinkAttrs::PortIndex(port),
+                GenlDevlinkAttrs::SbIndex(0),
+                GenlDevlinkAttrs::SbTcIndex(tc),
+                GenlDevlinkAttrs::SbPoolType(pool_type),
+                GenlDevlinkAttrs::SbPoolIndex(pool),
+                GenlDevlinkAttrs::SbPoolThreshold(40_000),
+                GenlDevlinkAttrs::SbOccCur(1_000),
+                GenlDevlinkAttrs::SbOccMax(2_000),
+            ],
+        )
+    }
+
+    fn shared_buffer() -> SharedBuffer {
+        SharedBuffer::try_from(&reply(
+            GenlDevlinkCmd::NewSb,
+            vec![
+                GenlDevlinkAttrs::SbIndex(0),
+                GenlDevlinkAttrs::SbSize(16_000_000),
+                GenlDevlinkAttrs::SbIngressPoolCount(4),
+                GenlDevlinkAttrs::SbEgressPoolCount(4),
+                GenlDevlinkAttrs::SbIngressTcCount(8),
+                GenlDevlinkAttrs::SbEgressTcCount(16),
+            ],
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn parse_replies() {
+        let sb = shared_buffer();
+        assert_eq!(handle(), sb.handle);
+        assert_eq!(16_000_000, sb.size);
+        assert_eq!(16, sb.egress_tc_count);
+
+        let pools = vec![
+            SbPool::try_from(&pool(0, DEVLINK_SB_THRESHOLD_TYPE_DYNAMIC))
+                .unwrap(),
+            SbPool::try_from(&pool(1, DEVLINK_SB_THRESHOLD_TYPE_STATIC))
+                .unwrap(),
+        ];
+        assert_eq!(SbPoolType::Ingress, pools[0].pool_type);
+        assert_eq!(SbThresholdType::Dynamic, pools[0].threshold_type);
+        assert_eq!(Some(96), pools[0].cell_size);
+
+        let port_pool = SbPortPool::try_from(&port_pool(1, 0, 10, 20)).unwrap();
+        assert_eq!(
+            Some(SbOccupancy {
+                current: 10,
+                max: 20
+            }),
+            port_pool.occupancy
+        );
+        assert_eq!(Some(SbThreshold::Alpha(16)), port_pool.threshold(&pools));
+        assert_eq!(None, port_pool.threshold(&pools[1..]));
+
+        let tc_bind =
+            SbTcBind::try_from(&tc_bind(1, 3, DEVLINK_SB_POOL_TYPE_INGRESS, 1))
+                .unwrap();
+        assert_eq!(3, tc_bind.tc_index);
+        assert_eq!(Some(SbThreshold::Bytes(40_000)), tc_bind.threshold(&pools));
+
+        let mut invalid = pool(2, 7);
+        assert!(SbPool::try_from(&invalid).is_err());
+        invalid.nldev.retain(|nla| {
+            !matches!(nla, GenlDevlinkAttrs::SbPoolThresholdType(_))
+        });
+        assert!(SbPool::try_from(&invalid).is_err());
+    }
+
+    #[test]
+    fn build_set_requests() {
+        let pool =
+            SbPoolSet::new(&handle(), 1, 1_000_000, SbThresholdType::Static)
+                .sb_index(2)
+                .build();
+        assert_eq!(GenlDevlinkCmd::SetSbPool, pool.cmd);
+        assert_eq!(
+            &[
+                GenlDevlinkAttrs::SbIndex(2),
+                GenlDevlinkAttrs::SbPoolIndex(1),
+                GenlDevlinkAttrs::SbPoolSize(1_000_000),
+                GenlDevlinkAttrs::SbPoolThresholdType(
+                    DEVLINK_SB_THRESHOLD_TYPE_STATIC
+                ),
+            ],
+            &pool.nldev[2..]
+        );
+
+        let port_pool =
+            SbPortPoolSet::new(&handle(), 4, 1, SbThreshold::Alpha(12)).build();
+        assert_eq!(GenlDevlinkCmd::SetSbPortPool, port_pool.cmd);
+        assert_eq!(handle().nlas(), port_pool.nldev[..2]);
+        assert_eq!(
+            &[
+                GenlDevlinkAttrs::PortIndex(4),
+                GenlDevlinkAttrs::SbIndex(0),
+                GenlDevlinkAttrs::SbPoolIndex(1),
+                GenlDevlinkAttrs::SbPoolThreshold(12),
+            ],
+            &port_pool.nldev[2..]
+        );
+
+        let tc_bind = SbTcBindSet::new(
+            &handle(),
+            4,
+            7,
+            SbPoolType::Egress,
+            5,
+            SbThreshold::Bytes(4096),
+        )
+        .build();
+        assert_eq!(GenlDevlinkCmd::SetSbTcPoolBind, tc_bind.cmd);
+        assert!(tc_bind.nldev.contains(&GenlDevlinkAttrs::SbPoolType(
+            DEVLINK_SB_POOL_TYPE_EGRESS
+        )));
+        assert!(tc_bind
+            .nldev
+            .contains(&GenlDevlinkAttrs::SbPoolThreshold(4096)));
+
+        let snapshot = shared_buffer().occ_snapshot();
+        assert_eq!(GenlDevlinkCmd::SbOccSnapshot, snapshot.cmd);
+        assert_eq!(Some(&0), attr!(snapshot.nldev, SbIndex));
+    }
+
+    #[test]
+    fn occupancy_report() {
+        let mut other_sb = port_pool(2, 0, 5, 5);
+        other_sb.nldev[3] = GenlDevlinkAttrs::SbIndex(1);
+        let mut no_occupancy = port_pool(3, 0, 0, 0);
+        no_occupancy.nldev.truncate(6);
+        let replies = vec![
+            pool(0, DEVLINK_SB_THRESHOLD_TYPE_DYNAMIC),
+            port_pool(1, 1, 300, 400),
+            port_pool(1, 0, 100, 200),
+            other_sb,
+            no_occupancy,
+            tc_bind(1, 1, DEVLINK_SB_POOL_TYPE_EGRESS, 4),
+            tc_bind(1, 2, DEVLINK_SB_POOL_TYPE_INGRESS, 0),
+            tc_bind(2, 0, DEVLINK_SB_POOL_TYPE_INGRESS, 0),
+            reply(GenlDevlinkCmd::NewPort, vec![]),
+        ];
+        let report =
+            SbOccupancyReport::from_replies(&shared_buffer(), &replies)
+                .unwrap();
+
+        assert_eq!(
+            vec![1, 2],
+            report.ports().map(|(i, _)| i).collect::<Vec<_>>()
+        );
+        let port = report.port(1).unwrap();
+        assert_eq!(
+            vec![0, 1],
+            port.pools.iter().map(|u| u.pool_index).collect::<Vec<_>>()
+        );
+        assert_eq!(Some(SbThreshold::Alpha(16)), port.pools[0].threshold);
+        assert_eq!(200, port.pools[0].occupancy.max);
+        assert_eq!(None, port.pools[1].threshold);
+        assert_eq!(
+            vec![(SbPoolType::Ingress, 2), (SbPoolType::Egress, 1)],
+            port.tcs
+                .iter()
+                .map(|u| (u.pool_type, u.tc_index))
+                .collect::<Vec<_>>()
+        );
+        assert!(report.port(2).unwrap().pools.is_empty());
+        assert_eq!(1, report.port(2).unwrap().tcs.len());
+        assert!(report.port(3).is_none());
+    }
+}
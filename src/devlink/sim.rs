@@ -0,0 +1,2083 @@
+// SPDX-License-Identifier: MIT
+
+//! Emulated devlink devices
+//!
+//! [`DevlinkSim`] registers the devlink family in a [`MockKernel`] and
+//! emulates devices modelled after `netdevsim`, with their bus name
+//! `netdevsim` and their location `netdevsim<id>`. Each device has:
+//!
+//! - physical ports of 4 lanes, which can be split and unsplit;
+//! - the subfunctions of PF 0 added by the user, whose ports can be
+//!   deleted and whose function address and state can be changed;
+//! - the `max_macs` and `test1` driverinit parameters;
+//! - the `dummy` region, of which snapshots can be taken and read;
+//! - the `empty` and `dummy` health reporters, the latter supporting
+//!   recovery, diagnosis and dumps;
+//! - a set of traps, trap groups and policers;
+//! - a leaf rate object per port, and the rate nodes created by the user;
+//! - a `fw.mgmt` firmware component, flashed with the next patch version
+//!   of the running one, which a reload activates.
+//!
+//! The requests are validated as the kernel does and the changes are
+//! announced on the `config` multicast group. Reloading a device recreates
+//! its ports and removes its rate nodes, it is refused while the device has
+//! subfunctions.
+//!
+//! ```
+//! # #[cfg(feature = "sync")]
+//! # fn main() {
+//! use netlink_packet_generic::{
+//!     devlink::{sim::DevlinkSim, GenlDevlink, GenlDevlinkCmd},
+//!     mock::MockKernel,
+//!     sync::GenlSocket,
+//! };
+//!
+//! let kernel = MockKernel::new();
+//! let sim = DevlinkSim::register(&kernel).unwrap();
+//! sim.add_device(1, 2).unwrap();
+//!
+//! let mut socket = GenlSocket::from_mock(kernel.connect());
+//! let ports = socket
+//!     .dump(GenlDevlink {
+//!         cmd: GenlDevlinkCmd::GetPort,
+//!         nldev: vec![],
+//!     })
+//!     .unwrap();
+//! assert_eq!(2, ports.len());
+//! # }
+//! # #[cfg(not(feature = "sync"))]
+//! # fn main() {}
+//! ```
+//!
+//! The example requires the `sync` feature, this module is only available
+//! with the `mock` feature.
+
+use crate::{
+    constants::*,
+    devlink::{
+        nldev::{
+            GenlDevlinkAttrs, HwAddr, PortFunctionAttrs, PortFunctionCaps,
+            PortFunctionOpState, PortFunctionState,
+        },
+        GenlDevlink, GenlDevlinkCmd,
+    },
+    mock::{MockError, MockFamily, MockKernel, MockRequest},
+    raw::RawNla,
+};
+use netlink_packet_utils::{
+    nla::DefaultNla,
+    parsers::{parse_string, parse_u16, parse_u32, parse_u8},
+};
+use std::{
+    collections::BTreeMap,
+    io,
+    sync::{Arc, Mutex, MutexGuard},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+const ENOENT: i32 = 2;
+const EBUSY: i32 = 16;
+const EEXIST: i32 = 17;
+const ENODEV: i32 = 19;
+const EINVAL: i32 = 22;
+const ENOSPC: i32 = 28;
+const EOPNOTSUPP: i32 = 95;
+
+// Types of the fmsg values
+const NLA_U32: u8 = 3;
+const NLA_U64: u8 = 4;
+const NLA_NUL_STRING: u8 = 10;
+
+const BUS_NAME: &str = "netdevsim";
+const PORT_LANES: u32 = 4;
+const REGION_SIZE: u64 = 32768;
+const REGION_MAX_SNAPSHOTS: u32 = 16;
+const REGION_CHUNK_SIZE: usize = 256;
+const PARAM_MAX_STRING_LEN: usize = 32;
+const POLICER_RATE: (u64, u64) = (1, 8000);
+const POLICER_BURST: (u64, u64) = (8, 65536);
+const FLASH_SIZE: u64 = 500000;
+const FLASH_CHUNKS: u64 = 10;
+const FW_VERSION: &str = "1.0.0";
+
+/// Value of a devlink parameter
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParamValue {
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    String(String),
+    Bool(bool),
+}
+
+impl ParamValue {
+    fn param_type(&self) -> u8 {
+        match self {
+            Self::U8(_) => DEVLINK_PARAM_TYPE_U8,
+            Self::U16(_) => DEVLINK_PARAM_TYPE_U16,
+            Self::U32(_) => DEVLINK_PARAM_TYPE_U32,
+            Self::String(_) => DEVLINK_PARAM_TYPE_STRING,
+            Self::Bool(_) => DEVLINK_PARAM_TYPE_BOOL,
+        }
+    }
+
+    // A false boolean is a missing flag
+    fn data(&self) -> Option<Vec<u8>> {
+        match self {
+            Self::U8(v) => Some(vec![*v]),
+            Self::U16(v) => Some(v.to_ne_bytes().to_vec()),
+            Self::U32(v) => Some(v.to_ne_bytes().to_vec()),
+            Self::String(v) => {
+                let mut data = v.as_bytes().to_vec();
+                data.push(0);
+                Some(data)
+            }
+            Self::Bool(true) => Some(vec![]),
+            Self::Bool(false) => None,
+        }
+    }
+
+    fn parse(param_type: u8, data: Option<&[u8]>) -> Result<Self, MockError> {
+        let invalid = |_| MockError::new(EINVAL);
+        if param_type == DEVLINK_PARAM_TYPE_BOOL {
+            return Ok(Self::Bool(data.is_some()));
+        }
+        let data = data.ok_or_else(|| MockError::new(EINVAL))?;
+        Ok(match param_type {
+            DEVLINK_PARAM_TYPE_U8 => Self::U8(parse_u8(data).map_err(invalid)?),
+            DEVLINK_PARAM_TYPE_U16 => {
+                Self::U16(parse_u16(data).map_err(invalid)?)
+            }
+            DEVLINK_PARAM_TYPE_U32 => {
+                Self::U32(parse_u32(data).map_err(invalid)?)
+            }
+            DEVLINK_PARAM_TYPE_STRING => {
+                let value = parse_string(data).map_err(invalid)?;
+                if value.len() >= PARAM_MAX_STRING_LEN {
+                    return Err(MockError::new(EINVAL));
+                }
+                Self::String(value)
+            }
+            _ => return Err(MockError::new(EINVAL)),
+        })
+    }
+}
+
+/// Emulated devlink devices
+///
+/// The state of the devices is shared by the clones of the simulator and
+/// the handler of the devlink family.
+#[derive(Clone, Debug)]
+pub struct DevlinkSim {
+    kernel: MockKernel,
+    state: Arc<Mutex<State>>,
+}
+
+impl DevlinkSim {
+    /// Register the devlink family, with its static ID, in the kernel
+    pub fn register(kernel: &MockKernel) -> io::Result<Self> {
+        let state = Arc::new(Mutex::new(State::default()));
+        let mut family = MockFamily::of::<GenlDevlink>();
+        family.set_id(GENL_ID_DEVLINK);
+        let handler_state = state.clone();
+        family.set_handler(move |request: &mut MockRequest<GenlDevlink>| {
+            lock(&handler_state).handle(request)
+        });
+        kernel.register(family)?;
+        Ok(Self {
+            kernel: kernel.clone(),
+            state,
+        })
+    }
+
+    /// Create the device `netdevsim<id>` with `port_count` ports
+    pub fn add_device(&self, id: u32, port_count: u32) -> io::Result<()> {
+        let mut notifications = Vec::new();
+        {
+            let mut state = self.lock();
+            if state.devices.contains_key(&id) {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("device {BUS_NAME}{id} already exists"),
+                ));
+            }
+            let dev = Device::new(id, port_count);
+            notifications.push(dev.message(GenlDevlinkCmd::NewDevice));
+            notifications.extend(
+                dev.ports.values().map(|port| {
+                    dev.port_message(GenlDevlinkCmd::NewPort, port)
+                }),
+            );
+            state.devices.insert(id, dev);
+        }
+        self.notify(notifications)
+    }
+
+    /// Remove the device `netdevsim<id>`
+    pub fn remove_device(&self, id: u32) -> io::Result<()> {
+        let mut notifications = Vec::new();
+        {
+            let mut state = self.lock();
+            let dev = state.devices.remove(&id).ok_or_else(|| no_device(id))?;
+            notifications.extend(dev.ports.values().map(|port| {
+                dev.port_message(GenlDevlinkCmd::DeletePort, port)
+            }));
+            notifications.push(dev.message(GenlDevlinkCmd::DeleteDevice));
+        }
+        self.notify(notifications)
+    }
+
+    /// Report an error to the health reporter `reporter` of a device
+    ///
+    /// The error is dumped and recovered if the reporter is configured to.
+    pub fn report_health_error(
+        &self,
+        id: u32,
+        reporter: &str,
+        msg: &str,
+    ) -> io::Result<()> {
+        let mut notifications = Vec::new();
+        {
+            let mut state = self.lock();
+            let dev =
+                state.devices.get_mut(&id).ok_or_else(|| no_device(id))?;
+            dev.report_health_error(reporter, msg, &mut notifications)
+                .map_err(|e| io::Error::from_raw_os_error(e.errno))?;
+        }
+        self.notify(notifications)
+    }
+
+    /// Account a packet of `len` bytes received by the trap `trap` of a
+    /// device
+    ///
+    /// Packets of traps with the `drop` action are not accounted.
+    pub fn record_trap(&self, id: u32, trap: &str, len: u64) -> io::Result<()> {
+        let mut state = self.lock();
+        let dev = state.devices.get_mut(&id).ok_or_else(|| no_device(id))?;
+        let trap = dev
+            .traps
+            .iter_mut()
+            .find(|t| t.name == trap)
+            .ok_or_else(|| io::Error::from_raw_os_error(ENOENT))?;
+        if trap.action != DEVLINK_TRAP_ACTION_DROP {
+            trap.rx_packets += 1;
+            trap.rx_bytes += len;
+        }
+        Ok(())
+    }
+
+    /// Return the value of the parameter `name` of a device in the
+    /// configuration mode `cmode`
+    pub fn param(&self, id: u32, name: &str, cmode: u8) -> Option<ParamValue> {
+        let state = self.lock();
+        let param = state
+            .devices
+            .get(&id)?
+            .params
+            .iter()
+            .find(|p| p.name == name)?;
+        param.values.get(&cmode).cloned()
+    }
+
+    /// Return the number of reloads of a device
+    pub fn reload_count(&self, id: u32) -> Option<u32> {
+        self.lock().devices.get(&id).map(|dev| dev.reload_count)
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        lock(&self.state)
+    }
+
+    fn notify(&self, notifications: Vec<GenlDevlink>) -> io::Result<()> {
+        for notification in notifications {
+            self.kernel.notify("config", notification)?;
+        }
+        Ok(())
+    }
+}
+
+fn lock(state: &Mutex<State>) -> MutexGuard<'_, State> {
+    state
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+fn no_device(id: u32) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("device {BUS_NAME}{id} does not exist"),
+    )
+}
+
+fn message(cmd: GenlDevlinkCmd, nldev: Vec<GenlDevlinkAttrs>) -> GenlDevlink {
+    GenlDevlink { cmd, nldev }
+}
+
+fn stats(counters: &[(u16, u64)]) -> GenlDevlinkAttrs {
+    GenlDevlinkAttrs::Stats(
+        counters
+            .iter()
+            .map(|(kind, value)| {
+                RawNla::Value(DefaultNla::new(
+                    *kind,
+                    value.to_ne_bytes().to_vec(),
+                ))
+            })
+            .collect(),
+    )
+}
+
+// Bump the patch number of a firmware version, e.g. 1.0.0 to 1.0.1
+fn next_version(version: &str) -> String {
+    match version.rsplit_once('.') {
+        Some((prefix, patch)) => match patch.parse::<u32>() {
+            Ok(patch) => format!("{prefix}.{}", patch + 1),
+            Err(_) => format!("{version}.1"),
+        },
+        None => format!("{version}.1"),
+    }
+}
+
+// Build a fmsg object from pairs of names and values
+fn fmsg(pairs: &[(&str, FmsgValue)]) -> GenlDevlinkAttrs {
+    use GenlDevlinkAttrs::*;
+    let mut nlas = vec![FmsgObjNestStart(true)];
+    for (name, value) in pairs {
+        let (value_type, data) = match value {
+            FmsgValue::U32(v) => (NLA_U32, v.to_ne_bytes().to_vec()),
+            FmsgValue::U64(v) => (NLA_U64, v.to_ne_bytes().to_vec()),
+            FmsgValue::String(v) => {
+                let mut data = v.as_bytes().to_vec();
+                data.push(0);
+                (NLA_NUL_STRING, data)
+            }
+        };
+        nlas.push(FmsgPairNestStart(true));
+        nlas.push(FmsgObjName((*name).to_owned()));
+        nlas.push(FmsgObjValueType(value_type));
+        nlas.push(FmsgObjValueData(data));
+        nlas.push(FmsgNestEnd(true));
+    }
+    nlas.push(FmsgNestEnd(true));
+    Fmsg(nlas)
+}
+
+enum FmsgValue {
+    U32(u32),
+    U64(u64),
+    String(String),
+}
+
+#[derive(Clone, Debug)]
+struct Port {
+    index: u32,
+    number: u32,
+    lanes: u32,
+    // Split group, subport number and index of the port before the split
+    split: Option<(u32, u32, u32)>,
+    rate: Rate,
+    sf: Option<SubFunction>,
+}
+
+#[derive(Clone, Debug)]
+struct SubFunction {
+    number: u32,
+    hw_addr: Vec<u8>,
+    active: bool,
+    roce: bool,
+}
+
+impl SubFunction {
+    fn function(&self) -> Vec<PortFunctionAttrs> {
+        let mut caps = PortFunctionCaps::default();
+        caps.set(DEVLINK_PORT_FN_CAP_ROCE, self.roce);
+        let (state, opstate) = match self.active {
+            // The SF is probed by the host as soon as it is active
+            true => (PortFunctionState::Active, PortFunctionOpState::Attached),
+            false => {
+                (PortFunctionState::Inactive, PortFunctionOpState::Detached)
+            }
+        };
+        vec![
+            PortFunctionAttrs::HwAddr(HwAddr(self.hw_addr.clone())),
+            PortFunctionAttrs::Caps(caps),
+            PortFunctionAttrs::State(state),
+            PortFunctionAttrs::OpState(opstate),
+        ]
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+struct Rate {
+    tx_share: u64,
+    tx_max: u64,
+    parent: Option<String>,
+}
+
+#[derive(Clone, Debug)]
+struct Param {
+    name: &'static str,
+    generic: bool,
+    values: BTreeMap<u8, ParamValue>,
+}
+
+#[derive(Clone, Debug)]
+struct Region {
+    name: &'static str,
+    snapshots: BTreeMap<u32, Vec<u8>>,
+}
+
+#[derive(Clone, Debug)]
+struct Reporter {
+    name: &'static str,
+    // Whether the reporter supports recovery, diagnosis and dumps
+    ops: bool,
+    state: u8,
+    error_count: u64,
+    recover_count: u64,
+    graceful_period: u64,
+    auto_recover: bool,
+    auto_dump: bool,
+    last_error: String,
+    dump: Option<(u64, GenlDevlinkAttrs)>,
+}
+
+#[derive(Clone, Debug)]
+struct Trap {
+    name: &'static str,
+    generic: bool,
+    trap_type: u8,
+    action: u8,
+    group: &'static str,
+    rx_packets: u64,
+    rx_bytes: u64,
+}
+
+#[derive(Clone, Debug)]
+struct TrapGroup {
+    name: &'static str,
+    policer: u32,
+}
+
+#[derive(Clone, Debug)]
+struct Policer {
+    id: u32,
+    rate: u64,
+    burst: u64,
+}
+
+#[derive(Debug)]
+struct Device {
+    id: u32,
+    port_count: u32,
+    ports: BTreeMap<u32, Port>,
+    next_port_index: u32,
+    params: Vec<Param>,
+    regions: Vec<Region>,
+    next_snapshot_id: u32,
+    reporters: Vec<Reporter>,
+    traps: Vec<Trap>,
+    trap_groups: Vec<TrapGroup>,
+    policers: Vec<Policer>,
+    rate_nodes: BTreeMap<String, Rate>,
+    fw_running: String,
+    fw_stored: String,
+    reload_count: u32,
+}
+
+impl Device {
+    fn new(id: u32, port_count: u32) -> Self {
+        let mut dev = Self {
+            id,
+            port_count,
+            ports: BTreeMap::new(),
+            next_port_index: 0,
+            params: vec![
+                Param {
+                    name: "max_macs",
+                    generic: true,
+                    values: vec![(
+                        DEVLINK_PARAM_CMODE_DRIVERINIT,
+                        ParamValue::U32(32),
+                    )]
+                    .into_iter()
+                    .collect(),
+                },
+                Param {
+                    name: "test1",
+                    generic: false,
+                    values: vec![(
+                        DEVLINK_PARAM_CMODE_DRIVERINIT,
+                        ParamValue::Bool(true),
+                    )]
+                    .into_iter()
+                    .collect(),
+                },
+            ],
+            regions: vec![Region {
+                name: "dummy",
+                snapshots: BTreeMap::new(),
+            }],
+            next_snapshot_id: 1,
+            reporters: vec![
+                Reporter::new("empty", false),
+                Reporter::new("dummy", true),
+            ],
+            traps: TRAPS
+                .iter()
+                .map(|&(name, generic, exception, group)| Trap {
+                    name,
+                    generic,
+                    trap_type: match exception {
+                        true => DEVLINK_TRAP_TYPE_EXCEPTION,
+                        false => DEVLINK_TRAP_TYPE_DROP,
+                    },
+                    action: match exception {
+                        true => DEVLINK_TRAP_ACTION_TRAP,
+                        false => DEVLINK_TRAP_ACTION_DROP,
+                    },
+                    group,
+                    rx_packets: 0,
+                    rx_bytes: 0,
+                })
+                .collect(),
+            trap_groups: TRAP_GROUPS
+                .iter()
+                .map(|&(name, policer)| TrapGroup { name, policer })
+                .collect(),
+            policers: (1..=3)
+                .map(|id| Policer {
+                    id,
+                    rate: 1000,
+                    burst: 128,
+                })
+                .collect(),
+            rate_nodes: BTreeMap::new(),
+            fw_running: FW_VERSION.to_owned(),
+            fw_stored: FW_VERSION.to_owned(),
+            reload_count: 0,
+        };
+        dev.create_ports();
+        dev
+    }
+
+    fn create_ports(&mut self) {
+        self.ports.clear();
+        for number in 0..self.port_count {
+            self.ports.insert(
+                number,
+                Port {
+                    index: number,
+                    number,
+                    lanes: PORT_LANES,
+                    split: None,
+                    rate: Rate::default(),
+                    sf: None,
+                },
+            );
+        }
+        self.next_port_index = self.port_count;
+    }
+
+    fn handle(&self) -> Vec<GenlDevlinkAttrs> {
+        vec![
+            GenlDevlinkAttrs::BusName(BUS_NAME.to_owned()),
+            GenlDevlinkAttrs::Location(format!("{BUS_NAME}{}", self.id)),
+        ]
+    }
+
+    fn message(&self, cmd: GenlDevlinkCmd) -> GenlDevlink {
+        let mut nlas = self.handle();
+        nlas.push(GenlDevlinkAttrs::ReloadStatus(0));
+        message(cmd, nlas)
+    }
+
+    fn port(&self, nlas: &[GenlDevlinkAttrs]) -> Result<&Port, MockError> {
+        let index =
+            attr!(nlas, PortIndex).ok_or_else(|| MockError::new(EINVAL))?;
+        self.ports.get(index).ok_or_else(|| MockError::new(ENODEV))
+    }
+
+    fn port_message(&self, cmd: GenlDevlinkCmd, port: &Port) -> GenlDevlink {
+        use GenlDevlinkAttrs::*;
+        let mut nlas = self.handle();
+        let mut netdev_name = format!("eni{}np{}", self.id, port.number + 1);
+        nlas.push(PortIndex(port.index));
+        nlas.push(PortType(DEVLINK_PORT_TYPE_ETH));
+        if let Some(sf) = &port.sf {
+            nlas.push(NetdevName(format!("eni{}pf0sf{}", self.id, sf.number)));
+            nlas.push(PortFlavour(DEVLINK_PORT_FLAVOUR_PCI_SF));
+            nlas.push(PortControllerNo(0));
+            nlas.push(PortPciPfNumber(0));
+            nlas.push(PortPciSfNo(sf.number));
+            nlas.push(PortExternal(0));
+            nlas.push(PortFunction(sf.function()));
+            return message(cmd, nlas);
+        }
+        nlas.push(PortFlavour(DEVLINK_PORT_FLAVOUR_PHYSICAL));
+        nlas.push(PortNumber(port.number));
+        if let Some((group, subport, _)) = port.split {
+            netdev_name.push_str(&format!("s{subport}"));
+            nlas.push(PortSplitGroup(group));
+            nlas.push(PortSplitSubportNumber(subport));
+        }
+        nlas.push(NetdevName(netdev_name));
+        nlas.push(PortLanes(port.lanes));
+        nlas.push(PortSplittable(u8::from(
+            port.split.is_none() && port.lanes > 1,
+        )));
+        message(cmd, nlas)
+    }
+
+    fn new_port(
+        &mut self,
+        nlas: &[GenlDevlinkAttrs],
+        notifications: &mut Vec<GenlDevlink>,
+    ) -> Result<GenlDevlink, MockError> {
+        let (flavour, pf_number) =
+            match (attr!(nlas, PortFlavour), attr!(nlas, PortPciPfNumber)) {
+                (Some(flavour), Some(pf_number)) => (*flavour, *pf_number),
+                _ => {
+                    return Err(MockError::with_message(
+                        EINVAL,
+                        "Port flavour or PCI PF are not specified",
+                    ))
+                }
+            };
+        if flavour != DEVLINK_PORT_FLAVOUR_PCI_SF {
+            return Err(MockError::with_message(
+                EOPNOTSUPP,
+                "Driver supports only SF port addition",
+            ));
+        }
+        if attr!(nlas, PortIndex).is_some() {
+            return Err(MockError::with_message(
+                EOPNOTSUPP,
+                "Driver does not support user defined port index assignment",
+            ));
+        }
+        if attr!(nlas, PortControllerNo).is_some_and(|c| *c != 0) {
+            return Err(MockError::with_message(
+                EOPNOTSUPP,
+                "External controller is unsupported",
+            ));
+        }
+        if pf_number != 0 {
+            return Err(MockError::with_message(EINVAL, "Invalid PF number"));
+        }
+        let number = *attr!(nlas, PortPciSfNo).ok_or_else(|| {
+            MockError::with_message(
+                EOPNOTSUPP,
+                "User must provide unique sfnum. \
+                 Driver does not support auto assignment",
+            )
+        })?;
+        if self
+            .ports
+            .values()
+            .any(|port| port.sf.as_ref().is_some_and(|sf| sf.number == number))
+        {
+            return Err(MockError::with_message(EEXIST, "SF already exists"));
+        }
+
+        let index = self.next_port_index;
+        self.next_port_index += 1;
+        let port = Port {
+            index,
+            number: 0,
+            lanes: 0,
+            split: None,
+            rate: Rate::default(),
+            sf: Some(SubFunction {
+                number,
+                hw_addr: vec![0; 6],
+                active: false,
+                roce: true,
+            }),
+        };
+        let reply = self.port_message(GenlDevlinkCmd::NewPort, &port);
+        notifications.push(reply.clone());
+        self.ports.insert(index, port);
+        Ok(reply)
+    }
+
+    fn set_port(
+        &mut self,
+        nlas: &[GenlDevlinkAttrs],
+        notifications: &mut Vec<GenlDevlink>,
+    ) -> Result<(), MockError> {
+        let mut port = self.port(nlas)?.clone();
+        if attr!(nlas, PortType).is_some() {
+            return Err(MockError::with_message(
+                EOPNOTSUPP,
+                "Port type change is not supported",
+            ));
+        }
+        let function = match attr!(nlas, PortFunction) {
+            Some(function) => function,
+            None => return Ok(()),
+        };
+        let sf = port.sf.as_mut().ok_or_else(|| {
+            MockError::with_message(
+                EOPNOTSUPP,
+                "Port doesn't support function attributes",
+            )
+        })?;
+        // All the attributes are checked before the function is changed
+        for nla in function {
+            match nla {
+                PortFunctionAttrs::HwAddr(hw_addr) => {
+                    if hw_addr.mac().is_none() {
+                        return Err(MockError::with_message(
+                            EINVAL,
+                            "Invalid hardware address",
+                        ));
+                    }
+                    sf.hw_addr = hw_addr.0.clone();
+                }
+                PortFunctionAttrs::State(PortFunctionState::Active) => {
+                    sf.active = true
+                }
+                PortFunctionAttrs::State(PortFunctionState::Inactive) => {
+                    sf.active = false
+                }
+                PortFunctionAttrs::State(_) => {
+                    return Err(MockError::with_message(
+                        EINVAL,
+                        "Invalid port function state",
+                    ))
+                }
+                PortFunctionAttrs::Caps(caps) => {
+                    if caps.selector & !DEVLINK_PORT_FN_CAP_ROCE != 0 {
+                        return Err(MockError::with_message(
+                            EOPNOTSUPP,
+                            "Port doesn't support migratable function \
+                             attribute",
+                        ));
+                    }
+                    if let Some(roce) = caps.get(DEVLINK_PORT_FN_CAP_ROCE) {
+                        sf.roce = roce;
+                    }
+                }
+                _ => (),
+            }
+        }
+        notifications.push(self.port_message(GenlDevlinkCmd::NewPort, &port));
+        self.ports.insert(port.index, port);
+        Ok(())
+    }
+
+    fn del_port(
+        &mut self,
+        nlas: &[GenlDevlinkAttrs],
+        notifications: &mut Vec<GenlDevlink>,
+    ) -> Result<(), MockError> {
+        let port = self.port(nlas)?.clone();
+        if port.sf.is_none() {
+            return Err(MockError::with_message(
+                EOPNOTSUPP,
+                "Only the ports of subfunctions can be deleted",
+            ));
+        }
+        self.ports.remove(&port.index);
+        notifications
+            .push(self.port_message(GenlDevlinkCmd::DeletePort, &port));
+        Ok(())
+    }
+
+    fn split_port(
+        &mut self,
+        nlas: &[GenlDevlinkAttrs],
+        notifications: &mut Vec<GenlDevlink>,
+    ) -> Result<(), MockError> {
+        let port = self.port(nlas)?.clone();
+        let count = *attr!(nlas, PortSplitCount)
+            .ok_or_else(|| MockError::new(EINVAL))?;
+        if port.split.is_some() || port.lanes < 2 {
+            return Err(MockError::with_message(
+                EINVAL,
+                "Port cannot be split",
+            ));
+        }
+        if count < 2 || count > port.lanes || port.lanes % count != 0 {
+            return Err(MockError::with_message(EINVAL, "Invalid split count"));
+        }
+        self.ports.remove(&port.index);
+        notifications
+            .push(self.port_message(GenlDevlinkCmd::DeletePort, &port));
+        for subport in 0..count {
+            let index = self.next_port_index;
+            self.next_port_index += 1;
+            let new_port = Port {
+                index,
+                number: port.number,
+                lanes: port.lanes / count,
+                split: Some((port.number, subport, port.index)),
+                rate: Rate::default(),
+                sf: None,
+            };
+            notifications
+                .push(self.port_message(GenlDevlinkCmd::NewPort, &new_port));
+            self.ports.insert(index, new_port);
+        }
+        Ok(())
+    }
+
+    fn unsplit_port(
+        &mut self,
+        nlas: &[GenlDevlinkAttrs],
+        notifications: &mut Vec<GenlDevlink>,
+    ) -> Result<(), MockError> {
+        let port = self.port(nlas)?.clone();
+        let (group, _, index) = port.split.ok_or_else(|| {
+            MockError::with_message(EINVAL, "Port was not split")
+        })?;
+        let subports: Vec<Port> = self
+            .ports
+            .values()
+            .filter(|p| p.split.map(|(g, _, _)| g) == Some(group))
+            .cloned()
+            .collect();
+        for subport in subports.iter() {
+            self.ports.remove(&subport.index);
+            notifications
+                .push(self.port_message(GenlDevlinkCmd::DeletePort, subport));
+        }
+        let port = Port {
+            index,
+            number: group,
+            lanes: port.lanes * subports.len() as u32,
+            split: None,
+            rate: Rate::default(),
+            sf: None,
+        };
+        notifications.push(self.port_message(GenlDevlinkCmd::NewPort, &port));
+        self.ports.insert(index, port);
+        Ok(())
+    }
+
+    fn param(&self, nlas: &[GenlDevlinkAttrs]) -> Result<&Param, MockError> {
+        let name =
+            attr!(nlas, ParamName).ok_or_else(|| MockError::new(EINVAL))?;
+        self.params
+            .iter()
+            .find(|p| p.name == name)
+            .ok_or_else(|| MockError::new(EINVAL))
+    }
+
+    fn param_message(&self, cmd: GenlDevlinkCmd, param: &Param) -> GenlDevlink {
+        use GenlDevlinkAttrs::*;
+        let mut attrs = vec![ParamName(param.name.to_owned())];
+        if param.generic {
+            attrs.push(ParamGeneric(true));
+        }
+        if let Some(value) = param.values.values().next() {
+            attrs.push(ParamType(value.param_type()));
+        }
+        attrs.push(ParamValueList(
+            param
+                .values
+                .iter()
+                .map(|(cmode, value)| {
+                    let mut nlas = vec![ParamValueCmode(*cmode)];
+                    if let Some(data) = value.data() {
+                        nlas.push(ParamValueData(data));
+                    }
+                    ParamValue(nlas)
+                })
+                .collect(),
+        ));
+        let mut nlas = self.handle();
+        nlas.push(Param(attrs));
+        message(cmd, nlas)
+    }
+
+    fn set_param(
+        &mut self,
+        nlas: &[GenlDevlinkAttrs],
+        notifications: &mut Vec<GenlDevlink>,
+    ) -> Result<(), MockError> {
+        let name = self.param(nlas)?.name;
+        let param_type =
+            *attr!(nlas, ParamType).ok_or_else(|| MockError::new(EINVAL))?;
+        let cmode = *attr!(nlas, ParamValueCmode)
+            .ok_or_else(|| MockError::new(EINVAL))?;
+        let value = ParamValue::parse(
+            param_type,
+            attr!(nlas, ParamValueData).map(Vec::as_slice),
+        )?;
+        let param = self
+            .params
+            .iter_mut()
+            .find(|p| p.name == name)
+            .ok_or_else(|| MockError::new(EINVAL))?;
+        let current = param
+            .values
+            .get_mut(&cmode)
+            .ok_or_else(|| MockError::new(EOPNOTSUPP))?;
+        if current.param_type() != param_type {
+            return Err(MockError::new(EINVAL));
+        }
+        *current = value;
+        let param = param.clone();
+        notifications
+            .push(self.param_message(GenlDevlinkCmd::NewParam, &param));
+        Ok(())
+    }
+
+    fn region(&self, nlas: &[GenlDevlinkAttrs]) -> Result<usize, MockError> {
+        let name = attr!(nlas, RegionName).ok_or_else(|| {
+            MockError::with_message(EINVAL, "No region name provided")
+        })?;
+        self.regions
+            .iter()
+            .position(|r| r.name == name)
+            .ok_or_else(|| {
+                MockError::with_message(
+                    EINVAL,
+                    "The requested region does not exist",
+                )
+            })
+    }
+
+    fn region_message(&self, region: &Region) -> GenlDevlink {
+        use GenlDevlinkAttrs::*;
+        let mut nlas = self.handle();
+        nlas.push(RegionName(region.name.to_owned()));
+        nlas.push(RegionSize(REGION_SIZE));
+        nlas.push(RegionMaxSnapshots(REGION_MAX_SNAPSHOTS));
+        if !region.snapshots.is_empty() {
+            nlas.push(RegionSnapshots(
+                region
+                    .snapshots
+                    .keys()
+                    .map(|id| RegionSnapshot(vec![RegionSnapshotId(*id)]))
+                    .collect(),
+            ));
+        }
+        message(GenlDevlinkCmd::GetRegion, nlas)
+    }
+
+    fn snapshot_message(
+        &self,
+        cmd: GenlDevlinkCmd,
+        region: &Region,
+        id: u32,
+    ) -> GenlDevlink {
+        let mut nlas = self.handle();
+        nlas.push(GenlDevlinkAttrs::RegionName(region.name.to_owned()));
+        nlas.push(GenlDevlinkAttrs::RegionSnapshotId(id));
+        message(cmd, nlas)
+    }
+
+    fn new_snapshot(
+        &mut self,
+        nlas: &[GenlDevlinkAttrs],
+        notifications: &mut Vec<GenlDevlink>,
+    ) -> Result<GenlDevlink, MockError> {
+        let index = self.region(nlas)?;
+        if self.regions[index].snapshots.len() >= REGION_MAX_SNAPSHOTS as usize
+        {
+            return Err(MockError::with_message(
+                ENOSPC,
+                "The region has reached the maximum number of stored snapshots",
+            ));
+        }
+        let in_use = |dev: &Self, id| {
+            dev.regions.iter().any(|r| r.snapshots.contains_key(&id))
+        };
+        let id = match attr!(nlas, RegionSnapshotId) {
+            Some(&id) if in_use(self, id) => {
+                return Err(MockError::with_message(
+                    EEXIST,
+                    "The requested snapshot id is already in use",
+                ))
+            }
+            Some(&id) => id,
+            None => {
+                while in_use(self, self.next_snapshot_id) {
+                    self.next_snapshot_id += 1;
+                }
+                self.next_snapshot_id
+            }
+        };
+        // The snapshot content depends on its ID
+        let data = (0..REGION_SIZE)
+            .map(|offset| (offset as u32 ^ id) as u8)
+            .collect();
+        self.regions[index].snapshots.insert(id, data);
+        let region = &self.regions[index];
+        notifications.push(self.snapshot_message(
+            GenlDevlinkCmd::CreatRegion,
+            region,
+            id,
+        ));
+        Ok(self.snapshot_message(GenlDevlinkCmd::CreatRegion, region, id))
+    }
+
+    fn del_snapshot(
+        &mut self,
+        nlas: &[GenlDevlinkAttrs],
+        notifications: &mut Vec<GenlDevlink>,
+    ) -> Result<(), MockError> {
+        let index = self.region(nlas)?;
+        let id = *attr!(nlas, RegionSnapshotId)
+            .ok_or_else(|| MockError::new(EINVAL))?;
+        if self.regions[index].snapshots.remove(&id).is_none() {
+            return Err(MockError::with_message(
+                ENOENT,
+                "Requested snapshot does not exist",
+            ));
+        }
+        notifications.push(self.snapshot_message(
+            GenlDevlinkCmd::DeleteRegion,
+            &self.regions[index],
+            id,
+        ));
+        Ok(())
+    }
+
+    fn read_snapshot(
+        &self,
+        nlas: &[GenlDevlinkAttrs],
+    ) -> Result<GenlDevlink, MockError> {
+        use GenlDevlinkAttrs::*;
+        let region = &self.regions[self.region(nlas)?];
+        let id = attr!(nlas, RegionSnapshotId).ok_or_else(|| {
+            MockError::with_message(EINVAL, "No snapshot id provided")
+        })?;
+        let data = region.snapshots.get(id).ok_or_else(|| {
+            MockError::with_message(EINVAL, "Requested snapshot does not exist")
+        })?;
+        let start = attr!(nlas, RegionChunkOffset).copied().unwrap_or(0);
+        let len = attr!(nlas, RegionChunkSize).copied().unwrap_or(REGION_SIZE);
+        if start >= REGION_SIZE {
+            return Err(MockError::with_message(
+                EINVAL,
+                "Requested region address is out of range",
+            ));
+        }
+        let end = REGION_SIZE.min(start.saturating_add(len));
+        let chunks = data[start as usize..end as usize]
+            .chunks(REGION_CHUNK_SIZE)
+            .enumerate()
+            .map(|(i, chunk)| {
+                RegionChunk(vec![
+                    RegionChunkData(chunk.to_vec()),
+                    RegionChunkOffset(start + (i * REGION_CHUNK_SIZE) as u64),
+                ])
+            })
+            .collect();
+        let mut nlas = self.handle();
+        nlas.push(RegionName(region.name.to_owned()));
+        nlas.push(RegionChunks(chunks));
+        Ok(message(GenlDevlinkCmd::ReadRegion, nlas))
+    }
+
+    fn info_message(&self) -> GenlDevlink {
+        use GenlDevlinkAttrs::*;
+        let version = |name: &str, value: &str| {
+            vec![
+                InfoVersionName(name.to_owned()),
+                InfoVersionValue(value.to_owned()),
+            ]
+        };
+        let mut nlas = self.handle();
+        nlas.push(InfoDriverName(BUS_NAME.to_owned()));
+        nlas.push(InfoSerialNo(format!("{:08x}", self.id)));
+        nlas.push(InfoVersionFixed(version(
+            DEVLINK_INFO_VERSION_GENERIC_BOARD_ID,
+            BUS_NAME,
+        )));
+        nlas.push(InfoVersionRunning(version(
+            DEVLINK_INFO_VERSION_GENERIC_FW_MGMT,
+            &self.fw_running,
+        )));
+        nlas.push(InfoVersionStored(version(
+            DEVLINK_INFO_VERSION_GENERIC_FW_MGMT,
+            &self.fw_stored,
+        )));
+        message(GenlDevlinkCmd::GetDeviceInfo, nlas)
+    }
+
+    fn flash(
+        &mut self,
+        nlas: &[GenlDevlinkAttrs],
+        notifications: &mut Vec<GenlDevlink>,
+    ) -> Result<(), MockError> {
+        use GenlDevlinkAttrs::*;
+        if attr!(nlas, FlashUpdateFileName).is_none() {
+            return Err(MockError::with_message(
+                EINVAL,
+                "File name argument is missing",
+            ));
+        }
+        let component = attr!(nlas, FlashUpdateComponent);
+        if component.is_some_and(|c| c != "fw.mgmt") {
+            return Err(MockError::with_message(
+                EINVAL,
+                "Component not supported",
+            ));
+        }
+        let status = |msg: &str, done: u64, total: u64| {
+            let mut nlas = self.handle();
+            nlas.push(FlashUpdateStatusMsg(msg.to_owned()));
+            if let Some(component) = component {
+                nlas.push(FlashUpdateComponent(component.clone()));
+            }
+            nlas.push(FlashUpdateStatusDone(done));
+            nlas.push(FlashUpdateStatusTotal(total));
+            message(GenlDevlinkCmd::FlashUdpateStatus, nlas)
+        };
+        notifications.push(message(GenlDevlinkCmd::UpdateFlash, self.handle()));
+        notifications.push(status("Preparing to flash", 0, 0));
+        for chunk in 0..=FLASH_CHUNKS {
+            notifications.push(status(
+                "Flashing",
+                chunk * FLASH_SIZE / FLASH_CHUNKS,
+                FLASH_SIZE,
+            ));
+        }
+        notifications.push(status("Flashing done", 0, 0));
+        notifications
+            .push(message(GenlDevlinkCmd::FlashUdpateEnd, self.handle()));
+        self.fw_stored = next_version(&self.fw_running);
+        Ok(())
+    }
+
+    fn reload(
+        &mut self,
+        nlas: &[GenlDevlinkAttrs],
+        notifications: &mut Vec<GenlDevlink>,
+    ) -> Result<GenlDevlink, MockError> {
+        use GenlDevlinkAttrs::*;
+        let action = attr!(nlas, ReloadAction)
+            .copied()
+            .unwrap_or(DEVLINK_RELOAD_ACTION_DRIVER_REINIT);
+        if action != DEVLINK_RELOAD_ACTION_DRIVER_REINIT
+            && action != DEVLINK_RELOAD_ACTION_FW_ACTIVATE
+        {
+            return Err(MockError::with_message(
+                EOPNOTSUPP,
+                "Requested reload action is not supported by the driver",
+            ));
+        }
+        if attr!(nlas, ReloadLimits).map_or(false, |limits| {
+            limits & (1 << DEVLINK_RELOAD_LIMIT_NO_RESET) != 0
+        }) {
+            return Err(MockError::with_message(
+                EOPNOTSUPP,
+                "Requested limit is not supported by the driver",
+            ));
+        }
+        if nlas
+            .iter()
+            .any(|nla| matches!(nla, NetnsFd(_) | NetnsPid(_) | NetnsId(_)))
+        {
+            return Err(MockError::with_message(
+                EOPNOTSUPP,
+                "Namespace change is not supported",
+            ));
+        }
+
+        if self.ports.values().any(|port| port.sf.is_some()) {
+            return Err(MockError::with_message(
+                EOPNOTSUPP,
+                "reload is unsupported when SFs are allocated",
+            ));
+        }
+
+        for port in self.ports.values() {
+            notifications
+                .push(self.port_message(GenlDevlinkCmd::DeletePort, port));
+        }
+        self.create_ports();
+        self.rate_nodes.clear();
+        if action == DEVLINK_RELOAD_ACTION_FW_ACTIVATE {
+            self.fw_running = self.fw_stored.clone();
+        }
+        self.reload_count += 1;
+        for port in self.ports.values() {
+            notifications
+                .push(self.port_message(GenlDevlinkCmd::NewPort, port));
+        }
+        notifications.push(self.message(GenlDevlinkCmd::NewDevice));
+
+        let mut nlas = self.handle();
+        nlas.push(ReloadActionPerformed(1 << action));
+        Ok(message(GenlDevlinkCmd::Reload, nlas))
+    }
+
+    fn reporter(&self, nlas: &[GenlDevlinkAttrs]) -> Result<usize, MockError> {
+        let name = attr!(nlas, HealthReporterName)
+            .ok_or_else(|| MockError::new(EINVAL))?;
+        self.reporters
+            .iter()
+            .position(|r| r.name == name)
+            .ok_or_else(|| MockError::new(EINVAL))
+    }
+
+    fn reporter_message(
+        &self,
+        cmd: GenlDevlinkCmd,
+        reporter: &Reporter,
+    ) -> GenlDevlink {
+        use GenlDevlinkAttrs::*;
+        let mut attrs = vec![
+            HealthReporterName(reporter.name.to_owned()),
+            HealthReporterState(reporter.state),
+            HealthReporterErrCount(reporter.error_count),
+            HealthReporterRecoverCount(reporter.recover_count),
+        ];
+        if let Some((ts, _)) = reporter.dump {
+            attrs.push(HealthReporterDumpTsNs(ts));
+        }
+        if reporter.ops {
+            attrs.push(HealthReporterGracefulPeriod(reporter.graceful_period));
+            attrs.push(HealthReporterAucoRecover(u8::from(
+                reporter.auto_recover,
+            )));
+            attrs.push(HealthReporterAutoDump(u8::from(reporter.auto_dump)));
+        }
+        let mut nlas = self.handle();
+        nlas.push(HealthReporter(attrs));
+        message(cmd, nlas)
+    }
+
+    fn set_reporter(
+        &mut self,
+        nlas: &[GenlDevlinkAttrs],
+    ) -> Result<(), MockError> {
+        let index = self.reporter(nlas)?;
+        let reporter = &mut self.reporters[index];
+        let graceful_period = attr!(nlas, HealthReporterGracefulPeriod);
+        let auto_recover = attr!(nlas, HealthReporterAucoRecover);
+        let auto_dump = attr!(nlas, HealthReporterAutoDump);
+        if !reporter.ops
+            && (graceful_period.is_some()
+                || auto_recover.is_some()
+                || auto_dump.is_some())
+        {
+            return Err(MockError::new(EOPNOTSUPP));
+        }
+        if let Some(&v) = graceful_period {
+            reporter.graceful_period = v;
+        }
+        if let Some(&v) = auto_recover {
+            reporter.auto_recover = v != 0;
+        }
+        if let Some(&v) = auto_dump {
+            reporter.auto_dump = v != 0;
+        }
+        Ok(())
+    }
+
+    fn recover(
+        &mut self,
+        index: usize,
+        notifications: &mut Vec<GenlDevlink>,
+    ) -> Result<(), MockError> {
+        let reporter = &mut self.reporters[index];
+        if !reporter.ops {
+            return Err(MockError::new(EOPNOTSUPP));
+        }
+        reporter.recover_count += 1;
+        reporter.state = DEVLINK_HEALTH_REPORTER_STATE_HEALTHY;
+        let reporter = reporter.clone();
+        notifications.push(
+            self.reporter_message(
+                GenlDevlinkCmd::RecoverHealthReport,
+                &reporter,
+            ),
+        );
+        Ok(())
+    }
+
+    fn report_health_error(
+        &mut self,
+        name: &str,
+        msg: &str,
+        notifications: &mut Vec<GenlDevlink>,
+    ) -> Result<(), MockError> {
+        let index = self
+            .reporters
+            .iter()
+            .position(|r| r.name == name)
+            .ok_or_else(|| MockError::new(EINVAL))?;
+        let reporter = &mut self.reporters[index];
+        reporter.error_count += 1;
+        reporter.state = DEVLINK_HEALTH_REPORTER_STATE_ERROR;
+        reporter.last_error = msg.to_owned();
+        if reporter.ops && reporter.auto_dump && reporter.dump.is_none() {
+            reporter.take_dump();
+        }
+        let auto_recover = reporter.ops && reporter.auto_recover;
+        let reporter = reporter.clone();
+        notifications.push(
+            self.reporter_message(
+                GenlDevlinkCmd::RecoverHealthReport,
+                &reporter,
+            ),
+        );
+        if auto_recover {
+            self.recover(index, notifications)?;
+        }
+        Ok(())
+    }
+
+    fn trap_message(&self, cmd: GenlDevlinkCmd, trap: &Trap) -> GenlDevlink {
+        use GenlDevlinkAttrs::*;
+        let mut nlas = self.handle();
+        nlas.push(TrapGroupName(trap.group.to_owned()));
+        nlas.push(TrapName(trap.name.to_owned()));
+        if trap.generic {
+            nlas.push(TrapGeneric(true));
+        }
+        nlas.push(TrapType(trap.trap_type));
+        nlas.push(TrapAction(trap.action));
+        // As netdevsim, report the cookies of the flow actions dropping
+        // packets
+        let mut metadata = vec![DEVLINK_ATTR_TRAP_METADATA_TYPE_IN_PORT];
+        if trap.name == "ingress_flow_action_drop" {
+            metadata.push(DEVLINK_ATTR_TRAP_METADATA_TYPE_FA_COOKIE);
+        }
+        nlas.push(TrapMetadata(
+            metadata
+                .into_iter()
+                .map(|kind| RawNla::Value(DefaultNla::new(kind, vec![])))
+                .collect(),
+        ));
+        nlas.push(stats(&[
+            (DEVLINK_ATTR_STATS_RX_PACKETS, trap.rx_packets),
+            (DEVLINK_ATTR_STATS_RX_BYTES, trap.rx_bytes),
+        ]));
+        message(cmd, nlas)
+    }
+
+    fn trap_group_message(
+        &self,
+        cmd: GenlDevlinkCmd,
+        group: &TrapGroup,
+    ) -> GenlDevlink {
+        use GenlDevlinkAttrs::*;
+        let traps = self.traps.iter().filter(|t| t.group == group.name);
+        let (packets, bytes) =
+            traps.fold((0, 0), |(p, b), t| (p + t.rx_packets, b + t.rx_bytes));
+        let mut nlas = self.handle();
+        nlas.push(TrapGroupName(group.name.to_owned()));
+        nlas.push(TrapGeneric(true));
+        if group.policer != 0 {
+            nlas.push(TrapPolicerId(group.policer));
+        }
+        nlas.push(stats(&[
+            (DEVLINK_ATTR_STATS_RX_PACKETS, packets),
+            (DEVLINK_ATTR_STATS_RX_BYTES, bytes),
+        ]));
+        message(cmd, nlas)
+    }
+
+    fn policer_message(
+        &self,
+        cmd: GenlDevlinkCmd,
+        policer: &Policer,
+    ) -> GenlDevlink {
+        use GenlDevlinkAttrs::*;
+        let mut nlas = self.handle();
+        nlas.push(TrapPolicerId(policer.id));
+        nlas.push(TrapPolicerRate(policer.rate));
+        nlas.push(TrapPolicerBurst(policer.burst));
+        nlas.push(stats(&[(DEVLINK_ATTR_STATS_RX_DROPPED, 0)]));
+        message(cmd, nlas)
+    }
+
+    fn set_trap(
+        &mut self,
+        nlas: &[GenlDevlinkAttrs],
+        notifications: &mut Vec<GenlDevlink>,
+    ) -> Result<(), MockError> {
+        let name =
+            attr!(nlas, TrapName).ok_or_else(|| MockError::new(EINVAL))?;
+        let index = self.traps.iter().position(|t| t.name == name).ok_or_else(
+            || {
+                MockError::with_message(
+                    ENOENT,
+                    "Device did not register this trap",
+                )
+            },
+        )?;
+        if let Some(&action) = attr!(nlas, TrapAction) {
+            let action = trap_action(action)?;
+            // As the kernel, silently skip the traps which are not drop ones
+            let trap = &mut self.traps[index];
+            if trap.trap_type == DEVLINK_TRAP_TYPE_DROP {
+                trap.action = action;
+            }
+        }
+        notifications.push(
+            self.trap_message(GenlDevlinkCmd::NewTrap, &self.traps[index]),
+        );
+        Ok(())
+    }
+
+    fn set_trap_group(
+        &mut self,
+        nlas: &[GenlDevlinkAttrs],
+        notifications: &mut Vec<GenlDevlink>,
+    ) -> Result<(), MockError> {
+        let name =
+            attr!(nlas, TrapGroupName).ok_or_else(|| MockError::new(EINVAL))?;
+        let index = self
+            .trap_groups
+            .iter()
+            .position(|g| g.name == name)
+            .ok_or_else(|| {
+                MockError::with_message(
+                    ENOENT,
+                    "Device did not register this trap group",
+                )
+            })?;
+        let action = attr!(nlas, TrapAction)
+            .map(|&a| trap_action(a))
+            .transpose()?;
+        if let Some(&policer) = attr!(nlas, TrapPolicerId) {
+            if policer != 0 && !self.policers.iter().any(|p| p.id == policer) {
+                return Err(MockError::with_message(
+                    ENOENT,
+                    "Device did not register this trap policer",
+                ));
+            }
+            self.trap_groups[index].policer = policer;
+        }
+        if let Some(action) = action {
+            for trap in self.traps.iter_mut() {
+                if trap.group == name
+                    && trap.trap_type == DEVLINK_TRAP_TYPE_DROP
+                {
+                    trap.action = action;
+                }
+            }
+        }
+        notifications.push(self.trap_group_message(
+            GenlDevlinkCmd::NewTrapGroup,
+            &self.trap_groups[index],
+        ));
+        Ok(())
+    }
+
+    fn set_policer(
+        &mut self,
+        nlas: &[GenlDevlinkAttrs],
+        notifications: &mut Vec<GenlDevlink>,
+    ) -> Result<(), MockError> {
+        let id =
+            attr!(nlas, TrapPolicerId).ok_or_else(|| MockError::new(EINVAL))?;
+        let index = self.policers.iter().position(|p| p.id == *id).ok_or_else(
+            || {
+                MockError::with_message(
+                    ENOENT,
+                    "Device did not register this trap policer",
+                )
+            },
+        )?;
+        let rate = attr!(nlas, TrapPolicerRate).copied();
+        let burst = attr!(nlas, TrapPolicerBurst).copied();
+        check_limit("rate", rate, POLICER_RATE)?;
+        check_limit("burst size", burst, POLICER_BURST)?;
+        let policer = &mut self.policers[index];
+        policer.rate = rate.unwrap_or(policer.rate);
+        policer.burst = burst.unwrap_or(policer.burst);
+        notifications.push(self.policer_message(
+            GenlDevlinkCmd::NewTrapPolicer,
+            &self.policers[index],
+        ));
+        Ok(())
+    }
+
+    fn rate_messages(&self) -> Vec<GenlDevlink> {
+        let leaves = self.ports.values().map(|port| {
+            let mut nlas = vec![GenlDevlinkAttrs::PortIndex(port.index)];
+            nlas.push(GenlDevlinkAttrs::RateType(DEVLINK_RATE_TYPE_LEAF));
+            self.rate_message(GenlDevlinkCmd::NewRate, nlas, &port.rate)
+        });
+        let nodes = self.rate_nodes.iter().map(|(name, rate)| {
+            self.node_message(GenlDevlinkCmd::NewRate, name, rate)
+        });
+        leaves.chain(nodes).collect()
+    }
+
+    fn node_message(
+        &self,
+        cmd: GenlDevlinkCmd,
+        name: &str,
+        rate: &Rate,
+    ) -> GenlDevlink {
+        let nlas = vec![
+            GenlDevlinkAttrs::RateType(DEVLINK_RATE_TYPE_NODE),
+            GenlDevlinkAttrs::RateNodeName(name.to_owned()),
+        ];
+        self.rate_message(cmd, nlas, rate)
+    }
+
+    fn rate_message(
+        &self,
+        cmd: GenlDevlinkCmd,
+        attrs: Vec<GenlDevlinkAttrs>,
+        rate: &Rate,
+    ) -> GenlDevlink {
+        use GenlDevlinkAttrs::*;
+        let mut nlas = self.handle();
+        nlas.extend(attrs);
+        nlas.push(RateTxShare(rate.tx_share));
+        nlas.push(RateTxMax(rate.tx_max));
+        if let Some(parent) = &rate.parent {
+            nlas.push(RateParentNodeName(parent.clone()));
+        }
+        message(cmd, nlas)
+    }
+
+    fn get_rate(
+        &self,
+        nlas: &[GenlDevlinkAttrs],
+    ) -> Result<GenlDevlink, MockError> {
+        if let Some(name) = attr!(nlas, RateNodeName) {
+            let rate = self.rate_nodes.get(name).ok_or_else(|| {
+                MockError::with_message(ENODEV, "Rate node not found")
+            })?;
+            return Ok(self.node_message(GenlDevlinkCmd::NewRate, name, rate));
+        }
+        let port = self.port(nlas)?;
+        Ok(self.rate_message(
+            GenlDevlinkCmd::NewRate,
+            vec![
+                GenlDevlinkAttrs::PortIndex(port.index),
+                GenlDevlinkAttrs::RateType(DEVLINK_RATE_TYPE_LEAF),
+            ],
+            &port.rate,
+        ))
+    }
+
+    // Apply the rate attributes of a request to a rate object
+    fn update_rate(
+        &self,
+        nlas: &[GenlDevlinkAttrs],
+        node: Option<&str>,
+        rate: &mut Rate,
+    ) -> Result<(), MockError> {
+        if let Some(&v) = attr!(nlas, RateTxShare) {
+            rate.tx_share = v;
+        }
+        if let Some(&v) = attr!(nlas, RateTxMax) {
+            rate.tx_max = v;
+        }
+        if let Some(parent) = attr!(nlas, RateParentNodeName) {
+            if parent.is_empty() {
+                rate.parent = None;
+                return Ok(());
+            }
+            if !self.rate_nodes.contains_key(parent) {
+                return Err(MockError::with_message(
+                    EINVAL,
+                    "Requested parent node does not exist",
+                ));
+            }
+            // Walk up from the parent, the node must not be found
+            let mut ancestor = Some(parent.as_str());
+            while let Some(name) = ancestor {
+                if Some(name) == node {
+                    return Err(MockError::with_message(
+                        EEXIST,
+                        "Node is already a parent of parent node",
+                    ));
+                }
+                ancestor = self.rate_nodes[name].parent.as_deref();
+            }
+            rate.parent = Some(parent.clone());
+        }
+        Ok(())
+    }
+
+    fn set_rate(
+        &mut self,
+        nlas: &[GenlDevlinkAttrs],
+        notifications: &mut Vec<GenlDevlink>,
+    ) -> Result<(), MockError> {
+        if let Some(name) = attr!(nlas, RateNodeName) {
+            let mut rate =
+                self.rate_nodes.get(name).cloned().ok_or_else(|| {
+                    MockError::with_message(ENODEV, "Rate node not found")
+                })?;
+            self.update_rate(nlas, Some(name), &mut rate)?;
+            notifications.push(self.node_message(
+                GenlDevlinkCmd::NewRate,
+                name,
+                &rate,
+            ));
+            self.rate_nodes.insert(name.clone(), rate);
+        } else {
+            let port = self.port(nlas)?;
+            let index = port.index;
+            let mut rate = port.rate.clone();
+            self.update_rate(nlas, None, &mut rate)?;
+            if let Some(port) = self.ports.get_mut(&index) {
+                port.rate = rate;
+            }
+            let mut leaf = self.get_rate(nlas)?;
+            leaf.cmd = GenlDevlinkCmd::NewRate;
+            notifications.push(leaf);
+        }
+        Ok(())
+    }
+
+    fn new_rate(
+        &mut self,
+        nlas: &[GenlDevlinkAttrs],
+        notifications: &mut Vec<GenlDevlink>,
+    ) -> Result<(), MockError> {
+        let name = attr!(nlas, RateNodeName).ok_or_else(|| {
+            MockError::with_message(EINVAL, "Rate node name is missing")
+        })?;
+        if self.rate_nodes.contains_key(name) {
+            return Err(MockError::with_message(
+                EEXIST,
+                "Rate node already exists",
+            ));
+        }
+        let mut rate = Rate::default();
+        self.update_rate(nlas, Some(name), &mut rate)?;
+        notifications.push(self.node_message(
+            GenlDevlinkCmd::NewRate,
+            name,
+            &rate,
+        ));
+        self.rate_nodes.insert(name.clone(), rate);
+        Ok(())
+    }
+
+    fn del_rate(
+        &mut self,
+        nlas: &[GenlDevlinkAttrs],
+        notifications: &mut Vec<GenlDevlink>,
+    ) -> Result<(), MockError> {
+        let name = attr!(nlas, RateNodeName).ok_or_else(|| {
+            MockError::with_message(EINVAL, "Rate node name is missing")
+        })?;
+        let in_use = self
+            .rate_nodes
+            .values()
+            .chain(self.ports.values().map(|port| &port.rate))
+            .any(|rate| rate.parent.as_ref() == Some(name));
+        if in_use {
+            return Err(MockError::with_message(
+                EBUSY,
+                "Node has children. Cannot delete node.",
+            ));
+        }
+        let rate = self.rate_nodes.remove(name).ok_or_else(|| {
+            MockError::with_message(ENODEV, "Rate node not found")
+        })?;
+        notifications.push(self.node_message(
+            GenlDevlinkCmd::DeleteRate,
+            name,
+            &rate,
+        ));
+        Ok(())
+    }
+}
+
+// Traps: name, generic, exception or drop trap, and group
+const TRAPS: &[(&str, bool, bool, &str)] = &[
+    ("source_mac_is_multicast", true, false, "l2_drops"),
+    ("vlan_tag_mismatch", true, false, "l2_drops"),
+    ("ingress_vlan_filter", true, false, "l2_drops"),
+    ("fid_miss", false, true, "l2_drops"),
+    ("blackhole_route", true, false, "l3_drops"),
+    ("ttl_value_is_too_small", true, true, "l3_exceptions"),
+    ("tail_drop", true, false, "buffer_drops"),
+    ("ingress_flow_action_drop", true, false, "acl_drops"),
+];
+
+// Trap groups: name and policer
+const TRAP_GROUPS: &[(&str, u32)] = &[
+    ("l2_drops", 1),
+    ("l3_drops", 1),
+    ("l3_exceptions", 1),
+    ("buffer_drops", 2),
+    ("acl_drops", 3),
+];
+
+fn trap_action(action: u8) -> Result<u8, MockError> {
+    match action {
+        DEVLINK_TRAP_ACTION_DROP
+        | DEVLINK_TRAP_ACTION_TRAP
+        | DEVLINK_TRAP_ACTION_MIRROR => Ok(action),
+        _ => Err(MockError::with_message(EINVAL, "Unknown trap action")),
+    }
+}
+
+fn check_limit(
+    what: &str,
+    value: Option<u64>,
+    (min, max): (u64, u64),
+) -> Result<(), MockError> {
+    match value {
+        Some(v) if v < min => Err(MockError::with_message(
+            EINVAL,
+            &format!("Policer {what} lower than limit"),
+        )),
+        Some(v) if v > max => Err(MockError::with_message(
+            EINVAL,
+            &format!("Policer {what} higher than limit"),
+        )),
+        _ => Ok(()),
+    }
+}
+
+impl Reporter {
+    fn new(name: &'static str, ops: bool) -> Self {
+        Self {
+            name,
+            ops,
+            state: DEVLINK_HEALTH_REPORTER_STATE_HEALTHY,
+            error_count: 0,
+            recover_count: 0,
+            graceful_period: 0,
+            auto_recover: ops,
+            auto_dump: ops,
+            last_error: String::new(),
+            dump: None,
+        }
+    }
+
+    fn diagnose(&self) -> GenlDevlinkAttrs {
+        let state = match self.state {
+            DEVLINK_HEALTH_REPORTER_STATE_HEALTHY => "healthy",
+            _ => "error",
+        };
+        fmsg(&[
+            ("state", FmsgValue::String(state.to_owned())),
+            ("error_count", FmsgValue::U64(self.error_count)),
+            ("recover_count", FmsgValue::U64(self.recover_count)),
+        ])
+    }
+
+    fn take_dump(&mut self) {
+        let ts = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_nanos() as u64);
+        let dump = fmsg(&[
+            ("last_error", FmsgValue::String(self.last_error.clone())),
+            ("error_count", FmsgValue::U64(self.error_count)),
+            ("test_u32", FmsgValue::U32(3)),
+        ]);
+        self.dump = Some((ts, dump));
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    devices: BTreeMap<u32, Device>,
+}
+
+impl State {
+    // Look up the device addressed by the bus name and the location
+    // attributes, if any
+    fn device_id(
+        &self,
+        nlas: &[GenlDevlinkAttrs],
+    ) -> Result<Option<u32>, MockError> {
+        let id = match (attr!(nlas, BusName), attr!(nlas, Location)) {
+            (None, None) => return Ok(None),
+            (Some(bus), Some(location)) if bus == BUS_NAME => location
+                .strip_prefix(BUS_NAME)
+                .and_then(|id| id.parse().ok()),
+            (Some(_), Some(_)) => None,
+            _ => return Err(MockError::new(EINVAL)),
+        };
+        match id {
+            Some(id) if self.devices.contains_key(&id) => Ok(Some(id)),
+            _ => Err(MockError::new(ENODEV)),
+        }
+    }
+
+    fn device(
+        &mut self,
+        nlas: &[GenlDevlinkAttrs],
+    ) -> Result<&mut Device, MockError> {
+        let id = self
+            .device_id(nlas)?
+            .ok_or_else(|| MockError::new(EINVAL))?;
+        Ok(self.devices.get_mut(&id).unwrap())
+    }
+
+    // Run `f` on the addressed device, or on every device for dump requests
+    // not addressing one
+    fn query<F>(
+        &mut self,
+        dump: bool,
+        nlas: &[GenlDevlinkAttrs],
+        f: F,
+    ) -> Result<Vec<GenlDevlink>, MockError>
+    where
+        F: Fn(&Device) -> Result<Vec<GenlDevlink>, MockError>,
+    {
+        match self.device_id(nlas)? {
+            Some(id) => f(&self.devices[&id]),
+            None if dump => {
+                let mut replies = Vec::new();
+                for dev in self.devices.values() {
+                    replies.extend(f(dev)?);
+                }
+                Ok(replies)
+            }
+            None => Err(MockError::new(EINVAL)),
+        }
+    }
+
+    fn handle(
+        &mut self,
+        request: &mut MockRequest<GenlDevlink>,
+    ) -> Result<Vec<GenlDevlink>, MockError> {
+        use GenlDevlinkCmd::*;
+        let dump = request.is_dump();
+        let nlas = request.message.payload.nldev.clone();
+        let nlas = &nlas[..];
+        let mut notifications = Vec::new();
+        let replies = match request.message.payload.cmd {
+            GetDevices => {
+                self.query(dump, nlas, |dev| Ok(vec![dev.message(NewDevice)]))?
+            }
+            GetPort if dump => self.query(dump, nlas, |dev| {
+                Ok(dev
+                    .ports
+                    .values()
+                    .map(|port| dev.port_message(NewPort, port))
+                    .collect())
+            })?,
+            GetPort => {
+                let dev = self.device(nlas)?;
+                vec![dev.port_message(NewPort, dev.port(nlas)?)]
+            }
+            NewPort => {
+                vec![self.device(nlas)?.new_port(nlas, &mut notifications)?]
+            }
+            SetPort => {
+                self.device(nlas)?.set_port(nlas, &mut notifications)?;
+                vec![]
+            }
+            DeletePort => {
+                self.device(nlas)?.del_port(nlas, &mut notifications)?;
+                vec![]
+            }
+            SplitPort => {
+                self.device(nlas)?.split_port(nlas, &mut notifications)?;
+                vec![]
+            }
+            UnsplitPort => {
+                self.device(nlas)?.unsplit_port(nlas, &mut notifications)?;
+                vec![]
+            }
+            GetParams if dump => self.query(dump, nlas, |dev| {
+                Ok(dev
+                    .params
+                    .iter()
+                    .map(|param| dev.param_message(GetParams, param))
+                    .collect())
+            })?,
+            GetParams => {
+                let dev = self.device(nlas)?;
+                vec![dev.param_message(GetParams, dev.param(nlas)?)]
+            }
+            SetParams => {
+                self.device(nlas)?.set_param(nlas, &mut notifications)?;
+                vec![]
+            }
+            GetRegion if dump => self.query(dump, nlas, |dev| {
+                Ok(dev.regions.iter().map(|r| dev.region_message(r)).collect())
+            })?,
+            GetRegion => {
+                let dev = self.device(nlas)?;
+                vec![dev.region_message(&dev.regions[dev.region(nlas)?])]
+            }
+            CreatRegion => {
+                vec![self
+                    .device(nlas)?
+                    .new_snapshot(nlas, &mut notifications)?]
+            }
+            DeleteRegion => {
+                self.device(nlas)?.del_snapshot(nlas, &mut notifications)?;
+                vec![]
+            }
+            ReadRegion if dump => {
+                vec![self.device(nlas)?.read_snapshot(nlas)?]
+            }
+            GetDeviceInfo => {
+                self.query(dump, nlas, |dev| Ok(vec![dev.info_message()]))?
+            }
+            UpdateFlash => {
+                self.device(nlas)?.flash(nlas, &mut notifications)?;
+                vec![]
+            }
+            Reload => {
+                vec![self.device(nlas)?.reload(nlas, &mut notifications)?]
+            }
+            GetHealthReport if dump => self.query(dump, nlas, |dev| {
+                Ok(dev
+                    .reporters
+                    .iter()
+                    .map(|r| dev.reporter_message(GetHealthReport, r))
+                    .collect())
+            })?,
+            GetHealthReport => {
+                let dev = self.device(nlas)?;
+                let reporter = &dev.reporters[dev.reporter(nlas)?];
+                vec![dev.reporter_message(GetHealthReport, reporter)]
+            }
+            SetHealthReport => {
+                self.device(nlas)?.set_reporter(nlas)?;
+                vec![]
+            }
+            RecoverHealthReport => {
+                let dev = self.device(nlas)?;
+                let index = dev.reporter(nlas)?;
+                dev.recover(index, &mut notifications)?;
+                vec![]
+            }
+            DaignoseHealthReport => {
+                let dev = self.device(nlas)?;
+                let reporter = &dev.reporters[dev.reporter(nlas)?];
+                if !reporter.ops {
+                    return Err(MockError::new(EOPNOTSUPP));
+                }
+                let mut nlas = dev.handle();
+                nlas.push(reporter.diagnose());
+                vec![message(DaignoseHealthReport, nlas)]
+            }
+            GetHealthReportDump if dump => {
+                let dev = self.device(nlas)?;
+                let index = dev.reporter(nlas)?;
+                let reporter = &mut dev.reporters[index];
+                if !reporter.ops {
+                    return Err(MockError::new(EOPNOTSUPP));
+                }
+                if reporter.dump.is_none() {
+                    reporter.take_dump();
+                }
+                let dump = reporter.dump.clone().map(|(_, dump)| dump);
+                let mut nlas = dev.handle();
+                nlas.extend(dump);
+                vec![message(GetHealthReportDump, nlas)]
+            }
+            ClearHealthReportDump => {
+                let dev = self.device(nlas)?;
+                let index = dev.reporter(nlas)?;
+                let reporter = &mut dev.reporters[index];
+                if !reporter.ops {
+                    return Err(MockError::new(EOPNOTSUPP));
+                }
+                reporter.dump = None;
+                vec![]
+            }
+            TestHealthReport => {
+                let dev = self.device(nlas)?;
+                let name = dev.reporters[dev.reporter(nlas)?].name;
+                if !dev.reporters.iter().any(|r| r.name == name && r.ops) {
+                    return Err(MockError::new(EOPNOTSUPP));
+                }
+                dev.report_health_error(
+                    name,
+                    "Test message",
+                    &mut notifications,
+                )?;
+                vec![]
+            }
+            GetTrap => {
+                let name = attr!(nlas, TrapName);
+                if !dump && name.is_none() {
+                    return Err(MockError::new(EINVAL));
+                }
+                self.query(dump, nlas, |dev| {
+                    let traps: Vec<_> = dev
+                        .traps
+                        .iter()
+                        .filter(|t| name.map_or(true, |name| t.name == name))
+                        .map(|t| dev.trap_message(NewTrap, t))
+                        .collect();
+                    if traps.is_empty() {
+                        return Err(MockError::new(ENOENT));
+                    }
+                    Ok(traps)
+                })?
+            }
+            SetTrap => {
+                self.device(nlas)?.set_trap(nlas, &mut notifications)?;
+                vec![]
+            }
+            GetTrapGroup => {
+                let name = attr!(nlas, TrapGroupName);
+                if !dump && name.is_none() {
+                    return Err(MockError::new(EINVAL));
+                }
+                self.query(dump, nlas, |dev| {
+                    let groups: Vec<_> = dev
+                        .trap_groups
+                        .iter()
+                        .filter(|g| name.map_or(true, |name| g.name == name))
+                        .map(|g| dev.trap_group_message(NewTrapGroup, g))
+                        .collect();
+                    if groups.is_empty() {
+                        return Err(MockError::new(ENOENT));
+                    }
+                    Ok(groups)
+                })?
+            }
+            SetTrapGroup => {
+                self.device(nlas)?
+                    .set_trap_group(nlas, &mut notifications)?;
+                vec![]
+            }
+            GetTrapPolicer => {
+                let id = attr!(nlas, TrapPolicerId);
+                if !dump && id.is_none() {
+                    return Err(MockError::new(EINVAL));
+                }
+                self.query(dump, nlas, |dev| {
+                    let policers: Vec<_> = dev
+                        .policers
+                        .iter()
+                        .filter(|p| id.map_or(true, |id| p.id == *id))
+                        .map(|p| dev.policer_message(NewTrapPolicer, p))
+                        .collect();
+                    if policers.is_empty() {
+                        return Err(MockError::new(ENOENT));
+                    }
+                    Ok(policers)
+                })?
+            }
+            SetTrapPolicer => {
+                self.device(nlas)?.set_policer(nlas, &mut notifications)?;
+                vec![]
+            }
+            GetRate if dump => {
+                self.query(dump, nlas, |dev| Ok(dev.rate_messages()))?
+            }
+            GetRate => vec![self.device(nlas)?.get_rate(nlas)?],
+            SetRate => {
+                self.device(nlas)?.set_rate(nlas, &mut notifications)?;
+                vec![]
+            }
+            NewRate => {
+                self.device(nlas)?.new_rate(nlas, &mut notifications)?;
+                vec![]
+            }
+            DeleteRate => {
+                self.device(nlas)?.del_rate(nlas, &mut notifications)?;
+                vec![]
+            }
+            _ => return Err(MockError::new(EOPNOTSUPP)),
+        };
+        for notification in notifications {
+            request.notify("config", notification);
+        }
+        Ok(replies)
+    }
+}
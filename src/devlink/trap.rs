@@ -0,0 +1,719 @@
+// SPDX-License-Identifier: MIT
+
+//! Packet traps of devlink devices
+//!
+//! A [`Trap`] tells the device what to do with the packets it would drop or
+//! which need the attention of the CPU: drop them, send them to the CPU, or
+//! both. Traps are gathered in a [`TrapGroup`], which may be bound to a
+//! [`TrapPolicer`] limiting the rate of the packets sent to the CPU.
+//!
+//! The counters of each object are nested in `DEVLINK_ATTR_STATS`, and the
+//! metadata reported along with the trapped packets is listed in
+//! `DEVLINK_ATTR_TRAP_METADATA`. Both are decoded into [`TrapStats`] and
+//! [`TrapMetadata`].
+//!
+//! The trapped packets themselves are not sent by devlink but by the drop
+//! monitor family, `NET_DM`, on its `events` multicast group: see
+//! [`TrapReport`].
+
+use super::{
+    nldev::GenlDevlinkAttrs, DevlinkHandle, GenlDevlink, GenlDevlinkCmd,
+};
+use crate::{
+    constants::*,
+    raw::{GenlRaw, RawNla},
+};
+use netlink_packet_utils::{
+    nla::{DefaultNla, NlasIterator},
+    parsers::{parse_string, parse_u16, parse_u32, parse_u64},
+    DecodeError,
+};
+use std::convert::{TryFrom, TryInto};
+
+/// What the device does with the packets hitting a trap
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TrapAction {
+    /// Drop the packets
+    Drop,
+    /// Send the packets to the CPU instead of forwarding them
+    Trap,
+    /// Forward the packets and send a copy to the CPU
+    Mirror,
+}
+
+impl From<TrapAction> for u8 {
+    fn from(action: TrapAction) -> Self {
+        match action {
+            TrapAction::Drop => DEVLINK_TRAP_ACTION_DROP,
+            TrapAction::Trap => DEVLINK_TRAP_ACTION_TRAP,
+            TrapAction::Mirror => DEVLINK_TRAP_ACTION_MIRROR,
+        }
+    }
+}
+
+impl TryFrom<u8> for TrapAction {
+    type Error = DecodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            DEVLINK_TRAP_ACTION_DROP => Ok(Self::Drop),
+            DEVLINK_TRAP_ACTION_TRAP => Ok(Self::Trap),
+            DEVLINK_TRAP_ACTION_MIRROR => Ok(Self::Mirror),
+            v => Err(format!("Unknown trap action: {v}").into()),
+        }
+    }
+}
+
+/// Kind of packets a trap applies to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum TrapType {
+    /// Packets dropped by the device, only the action of such traps can be
+    /// changed
+    Drop,
+    /// Packets the device cannot forward and sends to the CPU
+    Exception,
+    /// Control packets sent to the CPU, e.g. ARP or LACP
+    Control,
+}
+
+impl From<TrapType> for u8 {
+    fn from(trap_type: TrapType) -> Self {
+        match trap_type {
+            TrapType::Drop => DEVLINK_TRAP_TYPE_DROP,
+            TrapType::Exception => DEVLINK_TRAP_TYPE_EXCEPTION,
+            TrapType::Control => DEVLINK_TRAP_TYPE_CONTROL,
+        }
+    }
+}
+
+impl TryFrom<u8> for TrapType {
+    type Error = DecodeError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            DEVLINK_TRAP_TYPE_DROP => Ok(Self::Drop),
+            DEVLINK_TRAP_TYPE_EXCEPTION => Ok(Self::Exception),
+            DEVLINK_TRAP_TYPE_CONTROL => Ok(Self::Control),
+            v => Err(format!("Unknown trap type: {v}").into()),
+        }
+    }
+}
+
+/// Metadata reported along with the packets of a trap
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TrapMetadata {
+    /// The port the packets were received on
+    pub in_port: bool,
+    /// The cookie of the flow action which dropped the packets
+    pub fa_cookie: bool,
+}
+
+impl TrapMetadata {
+    /// Read the flags nested in `DEVLINK_ATTR_TRAP_METADATA`
+    ///
+    /// Unknown metadata types are ignored.
+    pub fn from_raw(nlas: &[RawNla]) -> Self {
+        let has = |kind| nlas.iter().any(|nla| nla.attr_type() == kind);
+        Self {
+            in_port: has(DEVLINK_ATTR_TRAP_METADATA_TYPE_IN_PORT),
+            fa_cookie: has(DEVLINK_ATTR_TRAP_METADATA_TYPE_FA_COOKIE),
+        }
+    }
+}
+
+/// Counters of a trap or trap group
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct TrapStats {
+    /// Packets received by the CPU
+    pub rx_packets: u64,
+    /// Bytes received by the CPU
+    pub rx_bytes: u64,
+    /// Packets dropped by the device, for the drop traps of the drivers
+    /// reporting it
+    pub rx_dropped: Option<u64>,
+}
+
+impl TrapStats {
+    /// Read the counters nested in `DEVLINK_ATTR_STATS`
+    pub fn from_raw(nlas: &[RawNla]) -> Result<Self, DecodeError> {
+        Ok(Self {
+            rx_packets: counter(nlas, DEVLINK_ATTR_STATS_RX_PACKETS)?
+                .ok_or("missing DEVLINK_ATTR_STATS_RX_PACKETS attribute")?,
+            rx_bytes: counter(nlas, DEVLINK_ATTR_STATS_RX_BYTES)?
+                .ok_or("missing DEVLINK_ATTR_STATS_RX_BYTES attribute")?,
+            rx_dropped: counter(nlas, DEVLINK_ATTR_STATS_RX_DROPPED)?,
+        })
+    }
+
+    // A message without counters is read as zeros
+    fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Result<Self, DecodeError> {
+        attr!(nlas, Stats)
+            .map(|stats| Self::from_raw(stats))
+            .unwrap_or_else(|| Ok(Self::default()))
+    }
+}
+
+/// Trap of a device, from a `DEVLINK_CMD_TRAP_GET` reply
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Trap {
+    pub handle: DevlinkHandle,
+    pub name: String,
+    /// Name of the group of the trap
+    pub group: String,
+    /// Whether the trap is defined by devlink rather than by the driver
+    pub generic: bool,
+    pub trap_type: TrapType,
+    pub action: TrapAction,
+    pub metadata: TrapMetadata,
+    pub stats: TrapStats,
+}
+
+impl Trap {
+    /// Read the trap from the attributes of a reply
+    pub fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Result<Self, DecodeError> {
+        Ok(Self {
+            handle: DevlinkHandle::from_nlas(nlas)?,
+            name: required!(nlas, TrapName)?.clone(),
+            group: required!(nlas, TrapGroupName)?.clone(),
+            generic: attr!(nlas, TrapGeneric).is_some(),
+            trap_type: (*required!(nlas, TrapType)?).try_into()?,
+            action: (*required!(nlas, TrapAction)?).try_into()?,
+            metadata: attr!(nlas, TrapMetadata)
+                .map(|nlas| TrapMetadata::from_raw(nlas))
+                .unwrap_or_default(),
+            stats: TrapStats::from_nlas(nlas)?,
+        })
+    }
+
+    /// Build the request reading the trap `name` of `handle`
+    pub fn request(handle: &DevlinkHandle, name: &str) -> GenlDevlink {
+        let mut request = handle.request(GenlDevlinkCmd::GetTrap);
+        request
+            .nldev
+            .push(GenlDevlinkAttrs::TrapName(name.to_owned()));
+        request
+    }
+}
+
+impl TryFrom<&GenlDevlink> for Trap {
+    type Error = DecodeError;
+
+    fn try_from(msg: &GenlDevlink) -> Result<Self, Self::Error> {
+        Self::from_nlas(&msg.nldev)
+    }
+}
+
+/// Trap group of a device, from a `DEVLINK_CMD_TRAP_GROUP_GET` reply
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrapGroup {
+    pub handle: DevlinkHandle,
+    pub name: String,
+    /// Whether the group is defined by devlink rather than by the driver
+    pub generic: bool,
+    /// Policer the group is bound to, if any
+    pub policer_id: Option<u32>,
+    /// Counters of the traps of the group
+    pub stats: TrapStats,
+}
+
+impl TrapGroup {
+    /// Read the group from the attributes of a reply
+    pub fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Result<Self, DecodeError> {
+        Ok(Self {
+            handle: DevlinkHandle::from_nlas(nlas)?,
+            name: required!(nlas, TrapGroupName)?.clone(),
+            generic: attr!(nlas, TrapGeneric).is_some(),
+            policer_id: attr!(nlas, TrapPolicerId).copied(),
+            stats: TrapStats::from_nlas(nlas)?,
+        })
+    }
+
+    /// Build the request reading the trap group `name` of `handle`
+    pub fn request(handle: &DevlinkHandle, name: &str) -> GenlDevlink {
+        let mut request = handle.request(GenlDevlinkCmd::GetTrapGroup);
+        request
+            .nldev
+            .push(GenlDevlinkAttrs::TrapGroupName(name.to_owned()));
+        request
+    }
+}
+
+impl TryFrom<&GenlDevlink> for TrapGroup {
+    type Error = DecodeError;
+
+    fn try_from(msg: &GenlDevlink) -> Result<Self, Self::Error> {
+        Self::from_nlas(&msg.nldev)
+    }
+}
+
+/// Trap policer of a device, from a `DEVLINK_CMD_TRAP_POLICER_GET` reply
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrapPolicer {
+    pub handle: DevlinkHandle,
+    pub id: u32,
+    /// Rate of the packets let through, in packets per second
+    pub rate: u64,
+    /// Size of the bursts let through, in packets
+    pub burst: u64,
+    /// Packets dropped by the policer, if the driver reports it
+    pub dropped: Option<u64>,
+}
+
+impl TrapPolicer {
+    /// Read the policer from the attributes of a reply
+    pub fn from_nlas(nlas: &[GenlDevlinkAttrs]) -> Result<Self, DecodeError> {
+        Ok(Self {
+            handle: DevlinkHandle::from_nlas(nlas)?,
+            id: *required!(nlas, TrapPolicerId)?,
+            rate: *required!(nlas, TrapPolicerRate)?,
+            burst: *required!(nlas, TrapPolicerBurst)?,
+            dropped: match attr!(nlas, Stats) {
+                Some(stats) => counter(stats, DEVLINK_ATTR_STATS_RX_DROPPED)?,
+                None => None,
+            },
+        })
+    }
+
+    /// Build the request reading the policer `id` of `handle`
+    pub fn request(handle: &DevlinkHandle, id: u32) -> GenlDevlink {
+        let mut request = handle.request(GenlDevlinkCmd::GetTrapPolicer);
+        request.nldev.push(GenlDevlinkAttrs::TrapPolicerId(id));
+        request
+    }
+}
+
+impl TryFrom<&GenlDevlink> for TrapPolicer {
+    type Error = DecodeError;
+
+    fn try_from(msg: &GenlDevlink) -> Result<Self, Self::Error> {
+        Self::from_nlas(&msg.nldev)
+    }
+}
+
+/// Builder of a `DEVLINK_CMD_TRAP_SET` request
+///
+/// The kernel only changes the action of the drop traps, the request is
+/// silently ignored for the other ones.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrapSet {
+    handle: DevlinkHandle,
+    name: String,
+    action: TrapAction,
+}
+
+impl TrapSet {
+    /// Set the action of the trap `name` of `handle`
+    pub fn new(handle: &DevlinkHandle, name: &str, action: TrapAction) -> Self {
+        Self {
+            handle: handle.clone(),
+            name: name.to_owned(),
+            action,
+        }
+    }
+
+    /// Build the request
+    pub fn build(&self) -> GenlDevlink {
+        let mut request = self.handle.request(GenlDevlinkCmd::SetTrap);
+        request.nldev.extend([
+            GenlDevlinkAttrs::TrapName(self.name.clone()),
+            GenlDevlinkAttrs::TrapAction(self.action.into()),
+        ]);
+        request
+    }
+}
+
+/// Builder of a `DEVLINK_CMD_TRAP_GROUP_SET` request
+///
+/// Only the attributes which are set are changed.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrapGroupSet {
+    handle: DevlinkHandle,
+    name: String,
+    action: Option<TrapAction>,
+    policer_id: Option<u32>,
+}
+
+impl TrapGroupSet {
+    /// Change the trap group `name` of `handle`
+    pub fn new(handle: &DevlinkHandle, name: &str) -> Self {
+        Self {
+            handle: handle.clone(),
+            name: name.to_owned(),
+            action: None,
+            policer_id: None,
+        }
+    }
+
+    /// Set the action of the drop traps of the group
+    pub fn action(mut self, action: TrapAction) -> Self {
+        self.action = Some(action);
+        self
+    }
+
+    /// Bind the group to the policer `policer_id`, or unbind it from its
+    /// policer if `None`
+    pub fn policer(mut self, policer_id: Option<u32>) -> Self {
+        // The kernel unbinds the group when the policer ID is 0
+        self.policer_id = Some(policer_id.unwrap_or(0));
+        self
+    }
+
+    /// Build the request
+    pub fn build(&self) -> GenlDevlink {
+        let mut request = self.handle.request(GenlDevlinkCmd::SetTrapGroup);
+        request
+            .nldev
+            .push(GenlDevlinkAttrs::TrapGroupName(self.name.clone()));
+        if let Some(action) = self.action {
+            request
+                .nldev
+                .push(GenlDevlinkAttrs::TrapAction(action.into()));
+        }
+        if let Some(id) = self.policer_id {
+            request.nldev.push(GenlDevlinkAttrs::TrapPolicerId(id));
+        }
+        request
+    }
+}
+
+/// Builder of a `DEVLINK_CMD_TRAP_POLICER_SET` request
+///
+/// Only the parameters which are set are changed. The driver rejects the
+/// values out of the limits of the device.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrapPolicerSet {
+    handle: DevlinkHandle,
+    id: u32,
+    rate: Option<u64>,
+    burst: Option<u64>,
+}
+
+impl TrapPolicerSet {
+    /// Change the policer `id` of `handle`
+    pub fn new(handle: &DevlinkHandle, id: u32) -> Self {
+        Self {
+            handle: handle.clone(),
+            id,
+            rate: None,
+            burst: None,
+        }
+    }
+
+    /// Set the rate, in packets per second
+    pub fn rate(mut self, rate: u64) -> Self {
+        self.rate = Some(rate);
+        self
+    }
+
+    /// Set the burst size, in packets
+    pub fn burst(mut self, burst: u64) -> Self {
+        self.burst = Some(burst);
+        self
+    }
+
+    /// Build the request
+    pub fn build(&self) -> GenlDevlink {
+        let mut request = self.handle.request(GenlDevlinkCmd::SetTrapPolicer);
+        request.nldev.push(GenlDevlinkAttrs::TrapPolicerId(self.id));
+        if let Some(rate) = self.rate {
+            request.nldev.push(GenlDevlinkAttrs::TrapPolicerRate(rate));
+        }
+        if let Some(burst) = self.burst {
+            request
+                .nldev
+                .push(GenlDevlinkAttrs::TrapPolicerBurst(burst));
+        }
+        request
+    }
+}
+
+/// Packet trapped by a device, from a drop monitor
+/// `NET_DM_CMD_PACKET_ALERT` notification
+///
+/// Devlink has no notification carrying the trapped packets: the kernel
+/// passes them to the drop monitor family, `NET_DM`, which sends them on its
+/// `events` multicast group once it is configured in packet mode and
+/// started for hardware drops, see
+/// [`monitor_requests()`](Self::monitor_requests). Since that family is not
+/// modelled by this crate, the notifications are decoded from [`GenlRaw`]
+/// messages.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TrapReport {
+    /// Name of the group of the trap
+    pub group: String,
+    /// Name of the trap
+    pub name: String,
+    /// Port the packet was received on, if known
+    pub in_port: Option<TrapReportPort>,
+    /// Time the packet was trapped, in nanoseconds since the epoch
+    pub timestamp: Option<u64>,
+    /// Ethertype of the packet
+    pub protocol: Option<u16>,
+    /// Length of the packet, which may be larger than the payload if the
+    /// drop monitor truncates it
+    pub orig_len: u32,
+    /// Cookie of the flow action which dropped the packet, for the traps
+    /// reporting it
+    pub flow_action_cookie: Option<Vec<u8>>,
+    /// Bytes of the packet, starting at its Ethernet header
+    pub payload: Vec<u8>,
+}
+
+/// Port a trapped packet was received on
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct TrapReportPort {
+    pub ifindex: u32,
+    pub name: Option<String>,
+}
+
+impl TrapReport {
+    /// Name of the drop monitor family
+    pub const FAMILY_NAME: &'static str = "NET_DM";
+    /// Multicast group the reports are sent on
+    pub const MULTICAST_GROUP: &'static str = "events";
+
+    /// Read the report from a drop monitor notification
+    ///
+    /// Fails if the message is not a packet alert, or reports a packet
+    /// dropped by the kernel rather than trapped by a device.
+    pub fn from_raw(msg: &GenlRaw) -> Result<Self, DecodeError> {
+        if msg.cmd != NET_DM_CMD_PACKET_ALERT {
+            return Err(format!(
+                "Unexpected drop monitor command: {}",
+                msg.cmd
+            )
+            .into());
+        }
+        let nlas = &msg.nlas[..];
+        match raw_value(nlas, NET_DM_ATTR_ORIGIN) {
+            Some(origin) if parse_u16(&origin)? == NET_DM_ORIGIN_HW => (),
+            _ => return Err("packet alert does not come from a trap".into()),
+        }
+        Ok(Self {
+            group: raw_required(nlas, NET_DM_ATTR_HW_TRAP_GROUP_NAME)
+                .and_then(|v| parse_string(&v))?,
+            name: raw_required(nlas, NET_DM_ATTR_HW_TRAP_NAME)
+                .and_then(|v| parse_string(&v))?,
+            in_port: raw_value(nlas, NET_DM_ATTR_IN_PORT)
+                .map(|v| TrapReportPort::parse(&v))
+                .transpose()?,
+            timestamp: raw_value(nlas, NET_DM_ATTR_TIMESTAMP)
+                .map(|v| parse_u64(&v))
+                .transpose()?,
+            protocol: raw_value(nlas, NET_DM_ATTR_PROTO)
+                .map(|v| parse_u16(&v))
+                .transpose()?,
+            orig_len: raw_required(nlas, NET_DM_ATTR_ORIG_LEN)
+                .and_then(|v| parse_u32(&v))?,
+            flow_action_cookie: raw_value(nlas, NET_DM_ATTR_FLOW_ACTION_COOKIE),
+            payload: raw_value(nlas, NET_DM_ATTR_PAYLOAD).unwrap_or_default(),
+        })
+    }
+
+    /// Build the requests switching the drop monitor to packet mode and
+    /// starting the monitoring of the hardware drops
+    ///
+    /// `family_id` is the ID of the drop monitor family, resolved from
+    /// [`FAMILY_NAME`](Self::FAMILY_NAME). The requests need the
+    /// `CAP_NET_ADMIN` capability.
+    pub fn monitor_requests(family_id: u16) -> Vec<GenlRaw> {
+        let raw = |cmd, kind, value: &[u8]| GenlRaw {
+            family_id,
+            cmd,
+            version: 2,
+            nlas: vec![RawNla::Value(DefaultNla::new(kind, value.to_vec()))],
+        };
+        vec![
+            raw(
+                NET_DM_CMD_CONFIG,
+                NET_DM_ATTR_ALERT_MODE,
+                &[NET_DM_ALERT_MODE_PACKET],
+            ),
+            raw(NET_DM_CMD_START, NET_DM_ATTR_HW_DROPS, &[]),
+        ]
+    }
+}
+
+impl TryFrom<&GenlRaw> for TrapReport {
+    type Error = DecodeError;
+
+    fn try_from(msg: &GenlRaw) -> Result<Self, Self::Error> {
+        Self::from_raw(msg)
+    }
+}
+
+impl TrapReportPort {
+    // Parse the attributes nested in `NET_DM_ATTR_IN_PORT`
+    fn parse(buf: &[u8]) -> Result<Self, DecodeError> {
+        let mut ifindex = None;
+        let mut name = None;
+        for nla in NlasIterator::new(buf) {
+            let nla = nla?;
+            match nla.kind() {
+                NET_DM_ATTR_PORT_NETDEV_IFINDEX => {
+                    ifindex = Some(parse_u32(nla.value())?)
+                }
+                NET_DM_ATTR_PORT_NETDEV_NAME => {
+                    name = Some(parse_string(nla.value())?)
+                }
+                _ => (),
+            }
+        }
+        Ok(Self {
+            ifindex: ifindex
+                .ok_or("missing NET_DM_ATTR_PORT_NETDEV_IFINDEX attribute")?,
+            name,
+        })
+    }
+}
+
+// Return the bytes of the attribute `kind`, which may have been taken for
+// nested attributes
+fn raw_value(nlas: &[RawNla], kind: u16) -> Option<Vec<u8>> {
+    nlas.iter()
+        .find(|nla| nla.attr_type() == kind)
+        .map(|nla| nla.value())
+}
+
+fn raw_required(nlas: &[RawNla], kind: u16) -> Result<Vec<u8>, DecodeError> {
+    raw_value(nlas, kind)
+        .ok_or_else(|| format!("missing drop monitor attribute {kind}").into())
+}
+
+// Read the counter `kind` of a `DEVLINK_ATTR_STATS` nest
+fn counter(nlas: &[RawNla], kind: u16) -> Result<Option<u64>, DecodeError> {
+    raw_value(nlas, kind).map(|v| parse_u64(&v)).transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::devlink::handle::{handle, reply};
+    use netlink_packet_utils::Emitable;
+
+    fn raw(kind: u16, value: &[u8]) -> RawNla {
+        RawNla::Value(DefaultNla::new(kind, value.to_vec()))
+    }
+
+    #[test]
+    fn parse_trap() {
+        let reply = reply(
+            GenlDevlinkCmd::NewTrap,
+            vec![
+                GenlDevlinkAttrs::TrapGroupName("acl_drops".to_owned()),
+                GenlDevlinkAttrs::TrapName(
+                    "ingress_flow_action_drop".to_owned(),
+                ),
+                GenlDevlinkAttrs::TrapGeneric(true),
+                GenlDevlinkAttrs::TrapType(DEVLINK_TRAP_TYPE_DROP),
+                GenlDevlinkAttrs::TrapAction(DEVLINK_TRAP_ACTION_TRAP),
+                GenlDevlinkAttrs::TrapMetadata(vec![
+                    raw(DEVLINK_ATTR_TRAP_METADATA_TYPE_IN_PORT, &[]),
+                    raw(DEVLINK_ATTR_TRAP_METADATA_TYPE_FA_COOKIE, &[]),
+                ]),
+                GenlDevlinkAttrs::Stats(vec![
+                    raw(DEVLINK_ATTR_STATS_RX_PACKETS, &3u64.to_ne_bytes()),
+                    raw(DEVLINK_ATTR_STATS_RX_BYTES, &300u64.to_ne_bytes()),
+                    raw(DEVLINK_ATTR_STATS_RX_DROPPED, &7u64.to_ne_bytes()),
+                ]),
+            ],
+        );
+        let trap = Trap::try_from(&reply).unwrap();
+        assert_eq!("acl_drops", trap.group);
+        assert!(trap.generic);
+        assert_eq!(TrapType::Drop, trap.trap_type);
+        assert_eq!(TrapAction::Trap, trap.action);
+        assert_eq!(
+            TrapMetadata {
+                in_port: true,
+                fa_cookie: true,
+            },
+            trap.metadata
+        );
+        assert_eq!(
+            TrapStats {
+                rx_packets: 3,
+                rx_bytes: 300,
+                rx_dropped: Some(7),
+            },
+            trap.stats
+        );
+    }
+
+    #[test]
+    fn invalid_counter() {
+        let stats = [raw(DEVLINK_ATTR_STATS_RX_PACKETS, &[1, 0])];
+        assert!(TrapStats::from_raw(&stats).is_err());
+        assert!(TrapStats::from_raw(&[]).is_err());
+    }
+
+    #[test]
+    fn build_requests() {
+        let group = TrapGroupSet::new(&handle(), "l3_drops")
+            .action(TrapAction::Drop)
+            .policer(None)
+            .build();
+        assert_eq!(GenlDevlinkCmd::SetTrapGroup, group.cmd);
+        assert_eq!(
+            &[
+                GenlDevlinkAttrs::TrapGroupName("l3_drops".to_owned()),
+                GenlDevlinkAttrs::TrapAction(DEVLINK_TRAP_ACTION_DROP),
+                GenlDevlinkAttrs::TrapPolicerId(0),
+            ][..],
+            &group.nldev[2..]
+        );
+
+        let policer = TrapPolicerSet::new(&handle(), 1).burst(256).build();
+        assert_eq!(
+            &[
+                GenlDevlinkAttrs::TrapPolicerId(1),
+                GenlDevlinkAttrs::TrapPolicerBurst(256)
+            ][..],
+            &policer.nldev[2..]
+        );
+    }
+
+    #[test]
+    fn parse_report() {
+        let mut in_port = raw(NET_DM_ATTR_PORT_NETDEV_IFINDEX, &[7, 0, 0, 0]);
+        let mut port = vec![0; 8];
+        in_port.emit(&mut port);
+        in_port = raw(NET_DM_ATTR_IN_PORT, &port);
+        let string = |s: &str| [s.as_bytes(), &[0]].concat();
+        let mut msg = GenlRaw {
+            family_id: 0,
+            cmd: NET_DM_CMD_PACKET_ALERT,
+            version: 2,
+            nlas: vec![
+                raw(NET_DM_ATTR_ORIGIN, &NET_DM_ORIGIN_HW.to_ne_bytes()),
+                raw(NET_DM_ATTR_HW_TRAP_GROUP_NAME, &string("acl_drops")),
+                raw(
+                    NET_DM_ATTR_HW_TRAP_NAME,
+                    &string("ingress_flow_action_drop"),
+                ),
+                in_port,
+                raw(NET_DM_ATTR_TIMESTAMP, &42u64.to_ne_bytes()),
+                raw(NET_DM_ATTR_ORIG_LEN, &60u32.to_ne_bytes()),
+                raw(NET_DM_ATTR_PROTO, &0x0800u16.to_ne_bytes()),
+                raw(NET_DM_ATTR_FLOW_ACTION_COOKIE, &[0xca, 0xfe]),
+                raw(NET_DM_ATTR_PAYLOAD, &[0xff; 14]),
+            ],
+        };
+        let report = TrapReport::try_from(&msg).unwrap();
+        assert_eq!("ingress_flow_action_drop", report.name);
+        assert_eq!(
+            Some(TrapReportPort {
+                ifindex: 7,
+                name: None,
+            }),
+            report.in_port
+        );
+        assert_eq!(Some(42), report.timestamp);
+        assert_eq!(Some(0x0800), report.protocol);
+        assert_eq!(Some(vec![0xca, 0xfe]), report.flow_action_cookie);
+        assert_eq!(vec![0xff; 14], report.payload);
+
+        msg.nlas[0] = raw(NET_DM_ATTR_ORIGIN, &NET_DM_ORIGIN_SW.to_ne_bytes());
+        assert!(TrapReport::from_raw(&msg).is_err());
+    }
+}
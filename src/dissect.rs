@@ -0,0 +1,411 @@
+// SPDX-License-Identifier: MIT
+
+//! Human-readable dump of captured generic netlink messages
+//!
+//! [`Dissector`] turns raw netlink messages, e.g. captured with `nlmon`,
+//! into an indented tree showing the netlink header, the family and command
+//! names and the decoded attributes:
+//!
+//! ```text
+//! nlmsghdr: len 32, type 16 (nlctrl), flags 0x0005, seq 1, pid 0
+//!   genlmsghdr: cmd 3 (GetFamily), version 2
+//!   FamilyName: devlink
+//! ```
+//!
+//! The families registered in the [`GenlRegistry`] are decoded with their
+//! payload type. The other ones are shown as raw attributes, with their
+//! values in hexadecimal unless a [`FamilyPolicy`] is provided.
+
+use crate::{
+    ctrl::{
+        nlas::{
+            GenlCtrlAttrs, McastGrpAttrs, NlPolicyTypeAttrs, OpAttrs,
+            OppolicyIndexAttr,
+        },
+        GenlCtrl,
+    },
+    devlink::{nldev::GenlDevlinkAttrs, GenlDevlink},
+    raw::{FamilyPolicy, PolicyNla, PolicyValue, RawNla},
+    registry::{AnyGenlMessage, GenlRegistry},
+    GenlMessage, GenlRaw,
+};
+use netlink_packet_core::{NetlinkBuffer, NetlinkHeader, NetlinkPayload};
+use netlink_packet_utils::DecodeError;
+use std::{collections::HashMap, fmt::Write};
+
+const INDENT: usize = 2;
+
+/// Render generic netlink messages as an indented tree
+#[derive(Clone, Debug, Default)]
+pub struct Dissector {
+    registry: GenlRegistry,
+    policies: HashMap<u16, FamilyPolicy>,
+}
+
+impl Dissector {
+    /// Create a dissector decoding the families of the given registry
+    pub fn new(registry: GenlRegistry) -> Self {
+        Self {
+            registry,
+            policies: HashMap::new(),
+        }
+    }
+
+    /// Decode the messages of an unregistered family with its policy
+    ///
+    /// The policy is looked up by its [`FamilyPolicy::family_id()`].
+    pub fn add_policy(&mut self, policy: FamilyPolicy) {
+        self.policies.insert(policy.family_id(), policy);
+    }
+
+    /// Dissect every netlink message of the buffer, e.g. a datagram
+    pub fn dissect(&self, buf: &[u8]) -> Result<String, DecodeError> {
+        let mut out = String::new();
+        let mut offset = 0;
+        while offset < buf.len() {
+            let len =
+                NetlinkBuffer::new_checked(&buf[offset..])?.length() as usize;
+            self.dissect_message(&buf[offset..offset + len], &mut out)?;
+            offset += (len + 3) & !3;
+        }
+        Ok(out)
+    }
+
+    fn dissect_message(
+        &self,
+        buf: &[u8],
+        out: &mut String,
+    ) -> Result<(), DecodeError> {
+        let msg = self.registry.deserialize(buf)?;
+        self.write_header(&msg.header, out);
+        match msg.payload {
+            NetlinkPayload::InnerMessage(msg) => self.write_genl(&msg, out),
+            NetlinkPayload::Error(e) => match e.code {
+                Some(code) => line(out, 1, format!("error: {code}")),
+                None => line(out, 1, "ack"),
+            },
+            NetlinkPayload::Done(_) => line(out, 1, "done"),
+            NetlinkPayload::Noop => line(out, 1, "noop"),
+            NetlinkPayload::Overrun(bytes) => {
+                line(out, 1, format!("overrun: {}", hex(&bytes)))
+            }
+            _ => line(out, 1, "unknown payload"),
+        }
+        Ok(())
+    }
+
+    fn write_header(&self, header: &NetlinkHeader, out: &mut String) {
+        let name = self
+            .registry
+            .family_name(header.message_type)
+            .map(|name| format!(" ({name})"))
+            .unwrap_or_default();
+        line(
+            out,
+            0,
+            format!(
+                "nlmsghdr: len {}, type {}{}, flags {:#06x}, seq {}, pid {}",
+                header.length,
+                header.message_type,
+                name,
+                header.flags,
+                header.sequence_number,
+                header.port_number,
+            ),
+        );
+    }
+
+    fn write_genl(&self, msg: &AnyGenlMessage, out: &mut String) {
+        match msg {
+            AnyGenlMessage::Ctrl(msg) => write_ctrl(msg, out),
+            AnyGenlMessage::Devlink(msg) => write_devlink(msg, out),
+            AnyGenlMessage::Raw(msg) => self.write_raw(msg, out),
+        }
+    }
+
+    fn write_raw(&self, msg: &GenlMessage<GenlRaw>, out: &mut String) {
+        line(
+            out,
+            1,
+            format!(
+                "genlmsghdr: cmd {}, version {}",
+                msg.header.cmd, msg.header.version
+            ),
+        );
+        let decoded = self
+            .policies
+            .get(&msg.resolved_family_id())
+            .and_then(|policy| policy.decode(&msg.payload).ok());
+        match decoded {
+            Some(nlas) => write_policy_nlas(&nlas, 1, out),
+            None => write_raw_nlas(&msg.payload.nlas, 1, out),
+        }
+    }
+}
+
+fn write_ctrl(msg: &GenlMessage<GenlCtrl>, out: &mut String) {
+    line(
+        out,
+        1,
+        format!(
+            "genlmsghdr: cmd {} ({:?}), version {}",
+            msg.header.cmd, msg.payload.cmd, msg.header.version
+        ),
+    );
+    for nla in msg.payload.nlas.iter() {
+        use GenlCtrlAttrs::*;
+        match nla {
+            FamilyId(v) => line(out, 1, format!("FamilyId: {v}")),
+            FamilyName(s) => line(out, 1, format!("FamilyName: {s}")),
+            Version(v) => line(out, 1, format!("Version: {v}")),
+            HdrSize(v) => line(out, 1, format!("HdrSize: {v}")),
+            MaxAttr(v) => line(out, 1, format!("MaxAttr: {v}")),
+            Op(v) => line(out, 1, format!("Op: {v}")),
+            Ops(ops) => {
+                line(out, 1, "Ops:");
+                for (index, op) in ops.iter().enumerate() {
+                    line(out, 2, format!("#{}:", index + 1));
+                    for attr in op {
+                        match attr {
+                            OpAttrs::Id(v) => line(out, 3, format!("Id: {v}")),
+                            OpAttrs::Flags(v) => {
+                                line(out, 3, format!("Flags: {v:#x}"))
+                            }
+                        }
+                    }
+                }
+            }
+            McastGroups(groups) => {
+                line(out, 1, "McastGroups:");
+                for (index, group) in groups.iter().enumerate() {
+                    line(out, 2, format!("#{}:", index + 1));
+                    for attr in group {
+                        match attr {
+                            McastGrpAttrs::Name(s) => {
+                                line(out, 3, format!("Name: {s}"))
+                            }
+                            McastGrpAttrs::Id(v) => {
+                                line(out, 3, format!("Id: {v}"))
+                            }
+                        }
+                    }
+                }
+            }
+            Policy(policy) => {
+                line(out, 1, format!("Policy: #{}", policy.index));
+                line(
+                    out,
+                    2,
+                    format!("Attribute: {}", policy.attr_policy.index),
+                );
+                for attr in policy.attr_policy.policies.iter() {
+                    let text = match attr {
+                        NlPolicyTypeAttrs::Pad(_) => continue,
+                        NlPolicyTypeAttrs::Type(t) => format!("Type: {t:?}"),
+                        attr => debug_as_attribute(attr),
+                    };
+                    line(out, 3, text);
+                }
+            }
+            OpPolicy(policy) => {
+                line(out, 1, format!("OpPolicy: cmd {}", policy.cmd));
+                for idx in policy.policy_idx.iter() {
+                    match idx {
+                        OppolicyIndexAttr::Do(v) => {
+                            line(out, 2, format!("Do: #{v}"))
+                        }
+                        OppolicyIndexAttr::Dump(v) => {
+                            line(out, 2, format!("Dump: #{v}"))
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+fn write_devlink(msg: &GenlMessage<GenlDevlink>, out: &mut String) {
+    line(
+        out,
+        1,
+        format!(
+            "genlmsghdr: cmd {} ({:?}), version {}",
+            msg.header.cmd, msg.payload.cmd, msg.header.version
+        ),
+    );
+    write_devlink_nlas(&msg.payload.nldev, 1, out);
+}
+
+fn write_devlink_nlas(
+    nlas: &[GenlDevlinkAttrs],
+    depth: usize,
+    out: &mut String,
+) {
+    for nla in nlas {
+        match (nla, nla.get_nested_value()) {
+            (_, Some(nested)) => {
+                line(out, depth, format!("{}:", nla.get_attribute_name()));
+                write_devlink_nlas(&nested, depth + 1, out);
+            }
+            (GenlDevlinkAttrs::RegionChunkData(data), None) => line(
+                out,
+                depth,
+                format!("{}: {}", nla.get_attribute_name(), hex(data)),
+            ),
+            (nla, None) => line(out, depth, nla.to_string()),
+        }
+    }
+}
+
+fn write_raw_nlas(nlas: &[RawNla], depth: usize, out: &mut String) {
+    for nla in nlas {
+        match nla.nested() {
+            Some(nested) => {
+                line(out, depth, format!("attr {}:", nla.attr_type()));
+                write_raw_nlas(nested, depth + 1, out);
+            }
+            None => line(
+                out,
+                depth,
+                format!("attr {}: {}", nla.attr_type(), hex(&nla.value())),
+            ),
+        }
+    }
+}
+
+fn write_policy_nlas(nlas: &[PolicyNla], depth: usize, out: &mut String) {
+    for nla in nlas {
+        let text = match &nla.value {
+            PolicyValue::Nested(nested) | PolicyValue::NestedArray(nested) => {
+                line(out, depth, format!("attr {}:", nla.kind));
+                write_policy_nlas(nested, depth + 1, out);
+                continue;
+            }
+            PolicyValue::Flag => "true".to_owned(),
+            PolicyValue::U8(v) => v.to_string(),
+            PolicyValue::U16(v) => v.to_string(),
+            PolicyValue::U32(v) => v.to_string(),
+            PolicyValue::U64(v) | PolicyValue::UInt(v) => v.to_string(),
+            PolicyValue::S8(v) => v.to_string(),
+            PolicyValue::S16(v) => v.to_string(),
+            PolicyValue::S32(v) => v.to_string(),
+            PolicyValue::S64(v) | PolicyValue::SInt(v) => v.to_string(),
+            PolicyValue::String(s) => format!("{s:?}"),
+            PolicyValue::Bitfield32 { value, selector } => {
+                format!("{value:#x}/{selector:#x}")
+            }
+            PolicyValue::Binary(v) | PolicyValue::Unknown(v) => hex(v),
+        };
+        line(out, depth, format!("attr {}: {}", nla.kind, text));
+    }
+}
+
+// Turn the `Variant(value)` debug output into `Variant: value`
+fn debug_as_attribute<T: std::fmt::Debug>(attr: &T) -> String {
+    let debug = format!("{attr:?}");
+    match debug.split_once('(') {
+        Some((name, value)) => {
+            format!("{}: {}", name, value.trim_end_matches(')'))
+        }
+        None => debug,
+    }
+}
+
+fn line<S: AsRef<str>>(out: &mut String, depth: usize, text: S) {
+    let _ = writeln!(
+        out,
+        "{:indent$}{}",
+        "",
+        text.as_ref(),
+        indent = depth * INDENT
+    );
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{constants::GENL_ID_DEVLINK, ctrl::GenlCtrlCmd, GenlFamily};
+    use netlink_packet_core::{NetlinkMessage, NLM_F_ACK, NLM_F_REQUEST};
+    use netlink_packet_utils::{nla::DefaultNla, Emitable};
+
+    fn serialize<F>(payload: F, family_id: u16) -> Vec<u8>
+    where
+        F: GenlFamily + Emitable + std::fmt::Debug,
+    {
+        let mut genlmsg = GenlMessage::from_payload(payload);
+        genlmsg.set_resolved_family_id(family_id);
+        let mut nlmsg = NetlinkMessage::from(genlmsg);
+        nlmsg.header.flags = NLM_F_REQUEST | NLM_F_ACK;
+        nlmsg.header.sequence_number = 1;
+        nlmsg.finalize();
+        let mut buf = vec![0; nlmsg.buffer_len()];
+        nlmsg.serialize(&mut buf);
+        buf
+    }
+
+    #[test]
+    fn dissect_ctrl() {
+        let buf = serialize(
+            GenlCtrl {
+                cmd: GenlCtrlCmd::GetFamily,
+                nlas: vec![GenlCtrlAttrs::FamilyName("devlink".to_owned())],
+            },
+            0x10,
+        );
+        assert_eq!(
+            "nlmsghdr: len 32, type 16 (nlctrl), flags 0x0005, seq 1, pid 0\n\
+             \x20 genlmsghdr: cmd 3 (GetFamily), version 2\n\
+             \x20 FamilyName: devlink\n",
+            Dissector::default().dissect(&buf).unwrap()
+        );
+    }
+
+    #[test]
+    fn dissect_devlink_and_raw() {
+        let devlink = GenlDevlink {
+            cmd: crate::devlink::GenlDevlinkCmd::GetDeviceInfo,
+            nldev: vec![
+                GenlDevlinkAttrs::BusName("pci".to_owned()),
+                GenlDevlinkAttrs::Location("0000:01:00.0".to_owned()),
+            ],
+        };
+        let raw = GenlRaw {
+            family_id: 0x20,
+            cmd: 1,
+            version: 1,
+            nlas: vec![
+                RawNla::Value(DefaultNla::new(1, vec![0xde, 0xad])),
+                RawNla::Nested(
+                    2,
+                    vec![RawNla::Value(DefaultNla::new(3, vec![0; 4]))],
+                ),
+            ],
+        };
+        let mut buf = serialize(devlink, GENL_ID_DEVLINK);
+        buf.extend(serialize(raw, 0x20));
+
+        let mut registry = GenlRegistry::new();
+        registry.register::<GenlDevlink>(GENL_ID_DEVLINK);
+        let text = Dissector::new(registry).dissect(&buf).unwrap();
+        let expected = [
+            "nlmsghdr: len 48, type 23 (devlink), flags 0x0005, seq 1, pid 0",
+            "  genlmsghdr: cmd 51 (GetDeviceInfo), version 1",
+            "  BusName: pci",
+            "  Location: 0000:01:00.0",
+            "nlmsghdr: len 40, type 32, flags 0x0005, seq 1, pid 0",
+            "  genlmsghdr: cmd 1, version 1",
+            "  attr 1: de ad",
+            "  attr 2:",
+            "    attr 3: 00 00 00 00",
+        ];
+        assert_eq!(expected.join("\n") + "\n", text);
+    }
+}
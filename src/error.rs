@@ -0,0 +1,295 @@
+// SPDX-License-Identifier: MIT
+
+//! Structured decoding errors
+
+use netlink_packet_utils::{
+    nla::{
+        NlaBuffer, NlasIterator, NLA_ALIGNTO, NLA_HEADER_SIZE, NLA_TYPE_MASK,
+    },
+    DecodeError,
+};
+use std::fmt;
+
+/// Error raised when a generic netlink message fails to decode
+///
+/// Unlike the plain [`DecodeError`] returned by the
+/// [`ParseableParametrized`](netlink_packet_utils::ParseableParametrized)
+/// implementations, it tells which family, command and attribute the
+/// failure comes from, so that failures can be grouped and the offending
+/// bytes found in the message.
+#[derive(Debug)]
+pub struct GenlDecodeError {
+    family: &'static str,
+    command: u8,
+    path: Vec<u16>,
+    offset: Option<usize>,
+    reason: DecodeError,
+}
+
+impl GenlDecodeError {
+    pub(crate) fn new(
+        family: &'static str,
+        command: u8,
+        reason: DecodeError,
+    ) -> Self {
+        Self {
+            family,
+            command,
+            path: Vec::new(),
+            offset: None,
+            reason,
+        }
+    }
+
+    /// Return the name of the family of the message
+    pub fn family(&self) -> &'static str {
+        self.family
+    }
+
+    /// Return the command of the message
+    pub fn command(&self) -> u8 {
+        self.command
+    }
+
+    /// Return the kind of the attribute which failed to parse
+    ///
+    /// This is the innermost attribute of [`path()`](Self::path), or `None`
+    /// if the failure does not come from an attribute, e.g. for an unknown
+    /// command.
+    pub fn attribute(&self) -> Option<u16> {
+        self.path.last().copied()
+    }
+
+    /// Return the kinds of the attributes leading to the failure
+    ///
+    /// The path starts at the top-level attribute and goes down the nested
+    /// attributes, without the `NLA_F_NESTED` and `NLA_F_NET_BYTEORDER`
+    /// flags. The kind of an attribute whose header is truncated is unknown,
+    /// so the path may stop at its parent.
+    pub fn path(&self) -> &[u16] {
+        &self.path
+    }
+
+    /// Return the offset of the header of the offending attribute
+    ///
+    /// The offset is counted from the start of the attributes, i.e. right
+    /// after the generic netlink header.
+    pub fn offset(&self) -> Option<usize> {
+        self.offset
+    }
+
+    /// Return the error raised by the parser of the attribute
+    pub fn reason(&self) -> &DecodeError {
+        &self.reason
+    }
+}
+
+impl fmt::Display for GenlDecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "failed to decode {} command {}",
+            self.family, self.command
+        )?;
+        if let Some(offset) = self.offset {
+            write!(f, ": attribute ")?;
+            for (i, kind) in self.path.iter().enumerate() {
+                if i > 0 {
+                    write!(f, "/")?;
+                }
+                write!(f, "{}", kind)?;
+            }
+            if self.path.is_empty() {
+                write!(f, "?")?;
+            }
+            write!(f, " at offset {}", offset)?;
+        }
+        write!(f, ": {}", self.reason)
+    }
+}
+
+impl std::error::Error for GenlDecodeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        Some(&self.reason)
+    }
+}
+
+/// Convert into the error of the [`ParseableParametrized`] implementations
+///
+/// [`DecodeError`] gives no access to the error it wraps, so only the message
+/// of the [`GenlDecodeError`] is kept: the family, command, path and offset
+/// cannot be recovered from it.
+///
+/// [`ParseableParametrized`]: netlink_packet_utils::ParseableParametrized
+impl From<GenlDecodeError> for DecodeError {
+    fn from(err: GenlDecodeError) -> Self {
+        DecodeError::from(format!("{}", err))
+    }
+}
+
+/// Parse the attributes of a message with `parse`
+///
+/// When an attribute fails to parse and `nested` tells that its kind holds
+/// attributes parsed by `parse` as well, they are parsed one by one to find
+/// the innermost attribute which fails.
+pub(crate) fn parse_nlas<T, P, N>(
+    family: &'static str,
+    command: u8,
+    buf: &[u8],
+    parse: P,
+    nested: N,
+) -> Result<Vec<T>, GenlDecodeError>
+where
+    P: Fn(&NlaBuffer<&[u8]>) -> Result<T, DecodeError>,
+    N: Fn(u16) -> bool,
+{
+    let mut path = Vec::new();
+    let located = parse_located(buf, 0, &parse, &nested, &mut path);
+    located.map_err(|(offset, reason)| GenlDecodeError {
+        family,
+        command,
+        path,
+        offset: Some(offset),
+        reason,
+    })
+}
+
+fn parse_located<T, P, N>(
+    buf: &[u8],
+    base: usize,
+    parse: &P,
+    nested: &N,
+    path: &mut Vec<u16>,
+) -> Result<Vec<T>, (usize, DecodeError)>
+where
+    P: Fn(&NlaBuffer<&[u8]>) -> Result<T, DecodeError>,
+    N: Fn(u16) -> bool,
+{
+    let mut nlas = Vec::new();
+    let mut offset = 0;
+    for nla in NlasIterator::new(buf) {
+        let nla = nla.map_err(|e| {
+            // The kind is known when the header is complete
+            if let Some(kind) = buf.get(offset + 2..offset + NLA_HEADER_SIZE) {
+                let kind = u16::from_ne_bytes([kind[0], kind[1]]);
+                path.push(kind & NLA_TYPE_MASK);
+            }
+            (base + offset, e)
+        })?;
+        let kind = nla.kind() & NLA_TYPE_MASK;
+        match parse(&nla) {
+            Ok(value) => nlas.push(value),
+            Err(e) => {
+                path.push(kind);
+                if nested(kind) {
+                    parse_located(
+                        nla.value(),
+                        base + offset + NLA_HEADER_SIZE,
+                        parse,
+                        nested,
+                        path,
+                    )?;
+                }
+                return Err((base + offset, e));
+            }
+        }
+        offset += nla_align!(nla.length() as usize);
+    }
+    Ok(nlas)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        constants::*, ctrl::GenlCtrl, devlink::GenlDevlink, GenlHeader,
+    };
+    use netlink_packet_utils::ParseableParametrized;
+
+    fn nla(kind: u16, value: &[u8]) -> Vec<u8> {
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&(value.len() as u16 + 4).to_ne_bytes());
+        buf.extend_from_slice(&kind.to_ne_bytes());
+        buf.extend_from_slice(value);
+        buf.resize(nla_align!(buf.len()), 0);
+        buf
+    }
+
+    #[test]
+    fn unknown_command() {
+        let header = GenlHeader {
+            cmd: 0xff,
+            version: 2,
+        };
+        let err = GenlCtrl::decode(&[], header).unwrap_err();
+        assert_eq!("nlctrl", err.family());
+        assert_eq!(0xff, err.command());
+        assert_eq!(None, err.attribute());
+        assert_eq!(None, err.offset());
+    }
+
+    #[test]
+    fn invalid_attribute() {
+        let mut buf = nla(CTRL_ATTR_FAMILY_NAME, b"devlink\0");
+        buf.extend(nla(CTRL_ATTR_FAMILY_ID, &[0x15]));
+        let header = GenlHeader {
+            cmd: CTRL_CMD_NEWFAMILY,
+            version: 2,
+        };
+        let err = GenlCtrl::decode(&buf, header).unwrap_err();
+        assert_eq!(CTRL_CMD_NEWFAMILY, err.command());
+        assert_eq!(Some(CTRL_ATTR_FAMILY_ID), err.attribute());
+        assert_eq!(&[CTRL_ATTR_FAMILY_ID][..], err.path());
+        assert_eq!(Some(12), err.offset());
+        assert!(err.to_string().starts_with(
+            "failed to decode nlctrl command 1: attribute 1 at offset 12: "
+        ));
+    }
+
+    #[test]
+    fn parseable_keeps_message() {
+        let buf = nla(CTRL_ATTR_FAMILY_ID, &[0x15]);
+        let header = GenlHeader {
+            cmd: CTRL_CMD_NEWFAMILY,
+            version: 2,
+        };
+        let err = GenlCtrl::parse_with_param(&buf, header).unwrap_err();
+        assert!(err.to_string().contains("attribute 1 at offset 0"));
+    }
+
+    #[test]
+    fn truncated_attribute() {
+        let mut buf = nla(CTRL_ATTR_FAMILY_NAME, b"devlink\0");
+        buf.extend_from_slice(&[8, 0]);
+        let header = GenlHeader {
+            cmd: CTRL_CMD_NEWFAMILY,
+            version: 2,
+        };
+        let err = GenlCtrl::decode(&buf, header).unwrap_err();
+        assert!(err.path().is_empty());
+        assert_eq!(Some(12), err.offset());
+    }
+
+    #[test]
+    fn nested_attribute() {
+        let mut param = nla(DEVLINK_ATTR_PARAM_NAME, b"max_macs\0");
+        param.extend(nla(DEVLINK_ATTR_PARAM_TYPE, &[3, 0]));
+        let mut buf = nla(DEVLINK_ATTR_BUS_NAME, b"pci\0");
+        buf.extend(nla(DEVLINK_ATTR_PARAM, &param));
+        let header = GenlHeader {
+            cmd: DEVLINK_CMD_PARAM_GET,
+            version: 1,
+        };
+        let err = GenlDevlink::decode(&buf, header).unwrap_err();
+        assert_eq!("devlink", err.family());
+        assert_eq!(
+            &[DEVLINK_ATTR_PARAM, DEVLINK_ATTR_PARAM_TYPE][..],
+            err.path()
+        );
+        assert_eq!(Some(28), err.offset());
+        assert!(err
+            .reason()
+            .to_string()
+            .contains("invalid DEVLINK_ATTR_PARAM_TYPE value"));
+    }
+}
@@ -6,6 +6,8 @@ use netlink_packet_utils::{DecodeError, Emitable, Parseable};
 
 /// Generic Netlink header
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct GenlHeader {
     pub cmd: u8,
     pub version: u8,
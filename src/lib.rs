@@ -66,6 +66,65 @@
 //!
 //! If you are implementing such a generic family, note that you should define
 //! the header data structure in your payload type and handle the serialization.
+//!
+//! # Decoding errors
+//! The controller and devlink payloads can also be decoded with
+//! [`ctrl::GenlCtrl::decode()`] and [`devlink::GenlDevlink::decode()`],
+//! which return a [`GenlDecodeError`] telling the family, the command, the
+//! path of the offending attribute and its offset in the payload. The
+//! [`ParseableParametrized`](netlink_packet_utils::ParseableParametrized)
+//! implementations, used when parsing a whole [`GenlMessage`], only keep the
+//! message of this error, as [`DecodeError`](netlink_packet_utils::DecodeError)
+//! cannot hold it.
+//!
+//! # Unknown families
+//! Messages of a family which is not modeled can be handled with the
+//! [`GenlRaw`] payload, which keeps the attributes as a tree of raw netlink
+//! attributes. They can be decoded into typed values with the policy dumped
+//! from the kernel, see [`raw::FamilyPolicy`].
+//!
+//! To receive messages of several families on one socket, register the
+//! resolved family IDs in a [`registry::GenlRegistry`], which dispatches the
+//! messages by ID into [`registry::AnyGenlMessage`].
+//!
+//! # Dissector
+//! [`dissect::Dissector`] renders raw netlink messages, e.g. captured with
+//! `nlmon`, as a human-readable tree. The `genl_dissect` example does the
+//! same from the command line.
+//!
+//! # Captures
+//! [`pcap::PcapReader`] reads `nlmon` captures in the pcap and pcapng
+//! formats and yields the decoded generic netlink messages with their
+//! timestamp and direction. [`pcap::PcapWriter`] records messages into a
+//! pcapng capture, and [`sync::GenlSocket::start_capture()`] records the
+//! traffic of a socket, without requiring an `nlmon` interface.
+//!
+//! # Serde
+//! With the `serde` feature enabled, the messages and attributes implement
+//! `serde::Serialize` and `serde::Deserialize`.
+//!
+//! # Arbitrary
+//! With the `arbitrary` feature enabled, the header and the attributes of
+//! the controller and devlink implement `arbitrary::Arbitrary`. The values
+//! generated survive an emit and parse round trip, which makes them suitable
+//! for property tests and structured fuzzing.
+//!
+//! # Blocking socket
+//! With the `sync` feature enabled, the [`sync::GenlSocket`] type provides a
+//! blocking client on top of `netlink-sys`, which resolves the dynamic family
+//! IDs and handles the ACK and dump replies for you. Multicast groups
+//! declared by [`GenlFamily::multicast_groups()`] can be joined by name with
+//! [`sync::GenlSocket::subscribe()`].
+//!
+//! # Mock kernel
+//! With the `mock` feature enabled, [`mock::MockKernel`] provides an
+//! in-process generic netlink endpoint, which answers the controller
+//! requests for the families registered in it and dispatches the other
+//! requests to user-supplied handlers. Flows can be tested without hardware
+//! or privileges, and with the `sync` feature,
+//! [`sync::GenlSocket::from_mock()`] runs the blocking client against it.
+//! [`devlink::sim::DevlinkSim`] emulates `netdevsim`-like devlink devices
+//! on top of it.
 
 #[macro_use]
 extern crate netlink_packet_utils;
@@ -79,11 +138,29 @@ pub mod ctrl;
 
 pub mod devlink;
 
+pub mod dissect;
+
+pub mod error;
+pub use self::error::GenlDecodeError;
+
 pub mod header;
 pub use self::header::GenlHeader;
 
 pub mod message;
 pub use self::message::GenlMessage;
 
+#[cfg(feature = "mock")]
+pub mod mock;
+
+pub mod pcap;
+
+pub mod raw;
+pub use self::raw::GenlRaw;
+
+pub mod registry;
+
+#[cfg(feature = "sync")]
+pub mod sync;
+
 pub mod traits;
 pub use self::traits::GenlFamily;
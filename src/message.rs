@@ -19,6 +19,7 @@ use netlink_packet_core::NetlinkMessage;
 /// [`GenlFamily`], [`Emitable`], and [`ParseableParametrized<[u8],
 /// GenlHeader>`](ParseableParametrized).
 #[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct GenlMessage<F> {
     pub header: GenlHeader,
     pub payload: F,
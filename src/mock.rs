@@ -0,0 +1,1148 @@
+// SPDX-License-Identifier: MIT
+
+//! In-process generic netlink endpoint for tests
+//!
+//! [`MockKernel`] plays the kernel side of generic netlink. It keeps a set of
+//! registered families, answers the controller requests
+//! (`CTRL_CMD_GETFAMILY` and `CTRL_CMD_GETPOLICY`) for them, dispatches the
+//! other requests to the handler of their family, and delivers the
+//! multicast notifications to the subscribed sockets.
+//!
+//! The messages are exchanged as serialized netlink datagrams, framed as the
+//! kernel does: dump replies are flagged `NLM_F_MULTI` and end with
+//! `NLMSG_DONE`, requests flagged `NLM_F_ACK` are acknowledged, and errors
+//! carry the request and the extended ACK message, if any.
+//!
+//! ```
+//! use netlink_packet_core::{NetlinkMessage, NetlinkPayload, NLM_F_REQUEST};
+//! use netlink_packet_generic::{
+//!     constants::GENL_ID_DEVLINK,
+//!     devlink::{nldev::GenlDevlinkAttrs, GenlDevlink, GenlDevlinkCmd},
+//!     mock::{MockFamily, MockKernel, MockRequest},
+//!     GenlMessage,
+//! };
+//!
+//! let kernel = MockKernel::new();
+//! let mut family = MockFamily::of::<GenlDevlink>();
+//! // GenlDevlink uses a static family ID
+//! family.set_id(GENL_ID_DEVLINK);
+//! family.set_handler(|request: &mut MockRequest<GenlDevlink>| {
+//!     Ok(vec![GenlDevlink {
+//!         cmd: GenlDevlinkCmd::NewDevice,
+//!         nldev: vec![
+//!             GenlDevlinkAttrs::BusName("netdevsim".to_owned()),
+//!             GenlDevlinkAttrs::Location("netdevsim1".to_owned()),
+//!         ],
+//!     }])
+//! });
+//! kernel.register(family).unwrap();
+//!
+//! let socket = kernel.connect();
+//! let mut nlmsg = NetlinkMessage::from(GenlMessage::from_payload(GenlDevlink {
+//!     cmd: GenlDevlinkCmd::GetDevices,
+//!     nldev: vec![],
+//! }));
+//! nlmsg.header.flags = NLM_F_REQUEST;
+//! nlmsg.finalize();
+//! let mut buf = vec![0; nlmsg.buffer_len()];
+//! nlmsg.serialize(&mut buf);
+//! socket.send(&buf).unwrap();
+//!
+//! let reply = socket.recv(None).unwrap();
+//! let reply =
+//!     NetlinkMessage::<GenlMessage<GenlDevlink>>::deserialize(&reply).unwrap();
+//! match reply.payload {
+//!     NetlinkPayload::InnerMessage(genlmsg) => {
+//!         assert_eq!(GenlDevlinkCmd::NewDevice, genlmsg.payload.cmd)
+//!     }
+//!     payload => panic!("Unexpected payload: {:?}", payload),
+//! }
+//! ```
+//!
+//! With the `sync` feature,
+//! [`GenlSocket::from_mock()`](crate::sync::GenlSocket::from_mock) runs the
+//! blocking client against the mock.
+//!
+//! This module is only available with the `mock` feature.
+
+use crate::{
+    constants::*,
+    ctrl::{
+        nlas::{
+            AttributePolicyAttr, GenlCtrlAttrs, McastGrpAttrs,
+            NlPolicyTypeAttrs, OppolicyAttr, OppolicyIndexAttr, PolicyAttr,
+        },
+        GenlCtrl, GenlCtrlCmd,
+    },
+    header::GenlHeader,
+    message::GenlMessage,
+    traits::GenlFamily,
+};
+use netlink_packet_core::{
+    NetlinkBuffer, NetlinkHeader, NLMSG_DONE, NLMSG_ERROR, NLM_F_ACK,
+    NLM_F_ACK_TLVS, NLM_F_CAPPED, NLM_F_DUMP, NLM_F_MULTIPART, NLM_F_REQUEST,
+};
+use netlink_packet_utils::{
+    nla::DefaultNla, DecodeError, Emitable, Parseable, ParseableParametrized,
+};
+use std::{
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
+    fmt::{self, Debug},
+    io,
+    sync::{Arc, Condvar, Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+const ENOENT: i32 = 2;
+const EINVAL: i32 = 22;
+const ENODATA: i32 = 61;
+const EOPNOTSUPP: i32 = 95;
+
+// Message types below this value are reserved for the netlink control
+// messages
+const NLMSG_MIN_TYPE: u16 = 0x10;
+// First ID allocated to the families without a static ID
+const GENL_START_ALLOC: u16 = 0x13;
+const NLMSGERR_ATTR_MSG: u16 = 1;
+const NLMSGERR_ATTR_OFFS: u16 = 2;
+const CTRL_VERSION: u32 = 2;
+
+/// Error returned by a request handler
+///
+/// It is sent back as an `NLMSG_ERROR` message carrying the negated
+/// `errno`, and the message and the offset of the extended ACK when set.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MockError {
+    /// Positive `errno` value, e.g. `EINVAL`
+    pub errno: i32,
+    /// Extended ACK message (`NLMSGERR_ATTR_MSG`)
+    pub message: Option<String>,
+    /// Offset in the request of the faulty attribute
+    /// (`NLMSGERR_ATTR_OFFS`)
+    pub offset: Option<u32>,
+}
+
+impl MockError {
+    pub fn new(errno: i32) -> Self {
+        Self {
+            errno,
+            message: None,
+            offset: None,
+        }
+    }
+
+    /// Build an error carrying an extended ACK message
+    pub fn with_message(errno: i32, message: &str) -> Self {
+        Self {
+            errno,
+            message: Some(message.to_owned()),
+            offset: None,
+        }
+    }
+}
+
+impl From<DecodeError> for MockError {
+    fn from(e: DecodeError) -> Self {
+        Self::with_message(EINVAL, &e.to_string())
+    }
+}
+
+/// Request received by a family handler
+#[derive(Debug)]
+pub struct MockRequest<F> {
+    /// Netlink header of the request
+    pub header: NetlinkHeader,
+    /// Parsed request
+    pub message: GenlMessage<F>,
+    notifications: Vec<(String, F)>,
+}
+
+impl<F> MockRequest<F> {
+    /// Return whether the request is a dump request
+    pub fn is_dump(&self) -> bool {
+        self.header.flags & NLM_F_DUMP == NLM_F_DUMP
+    }
+
+    /// Send `payload` to the multicast group `group` of the family, after
+    /// the replies of the request
+    ///
+    /// Groups not registered by the family are ignored.
+    pub fn notify(&mut self, group: &str, payload: F) {
+        self.notifications.push((group.to_owned(), payload));
+    }
+}
+
+// Serialized generic netlink messages (generic header and attributes)
+struct HandlerOutput {
+    replies: Vec<Vec<u8>>,
+    notifications: Vec<(String, Vec<u8>)>,
+}
+
+type Handler = Box<
+    dyn FnMut(&NetlinkHeader, &[u8]) -> Result<HandlerOutput, MockError> + Send,
+>;
+
+/// Generic family registered in a [`MockKernel`]
+pub struct MockFamily {
+    name: String,
+    id: u16,
+    version: u8,
+    hdr_size: u32,
+    max_attr: u32,
+    multicast_groups: Vec<String>,
+    policies: BTreeMap<u32, BTreeMap<u16, Vec<NlPolicyTypeAttrs>>>,
+    op_policies: BTreeMap<u8, (Option<u32>, Option<u32>)>,
+    handler: Option<Handler>,
+}
+
+impl MockFamily {
+    /// Describe a family with a dynamic ID and no multicast group
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_owned(),
+            id: 0,
+            version: 1,
+            hdr_size: 0,
+            max_attr: 0,
+            multicast_groups: Vec::new(),
+            policies: BTreeMap::new(),
+            op_policies: BTreeMap::new(),
+            handler: None,
+        }
+    }
+
+    /// Describe the family `F` with its name and multicast groups
+    ///
+    /// The ID is left dynamic, since it is only known from a message of `F`.
+    pub fn of<F: GenlFamily>() -> Self {
+        let mut family = Self::new(F::family_name());
+        family.multicast_groups = F::multicast_groups()
+            .iter()
+            .map(|&g| g.to_owned())
+            .collect();
+        family
+    }
+
+    /// Set a static family ID, instead of allocating one on registration
+    pub fn set_id(&mut self, id: u16) {
+        self.id = id;
+    }
+
+    /// Set the version reported by the controller
+    pub fn set_version(&mut self, version: u8) {
+        self.version = version;
+    }
+
+    /// Set the family header size reported by the controller
+    pub fn set_hdr_size(&mut self, hdr_size: u32) {
+        self.hdr_size = hdr_size;
+    }
+
+    /// Set the maximum attribute type reported by the controller
+    pub fn set_max_attr(&mut self, max_attr: u32) {
+        self.max_attr = max_attr;
+    }
+
+    /// Declare a multicast group
+    pub fn add_multicast_group(&mut self, name: &str) {
+        self.multicast_groups.push(name.to_owned());
+    }
+
+    /// Set the policy of the attribute `attr_type` in the policy
+    /// `policy_idx`, as reported by `CTRL_CMD_GETPOLICY`
+    pub fn add_policy(
+        &mut self,
+        policy_idx: u32,
+        attr_type: u16,
+        policy: Vec<NlPolicyTypeAttrs>,
+    ) {
+        self.policies
+            .entry(policy_idx)
+            .or_default()
+            .insert(attr_type, policy);
+    }
+
+    /// Set the policies applied to the do and dump requests of `cmd`
+    pub fn add_op_policy(
+        &mut self,
+        cmd: u8,
+        do_policy: Option<u32>,
+        dump_policy: Option<u32>,
+    ) {
+        self.op_policies.insert(cmd, (do_policy, dump_policy));
+    }
+
+    /// Set the handler of the requests sent to the family
+    ///
+    /// The handler returns the replies of the request. They are sent as a
+    /// multi-part reply to dump requests. Without handler, the requests are
+    /// answered with `EOPNOTSUPP`.
+    ///
+    /// The handler runs with the kernel locked, it must not call the
+    /// [`MockKernel`].
+    pub fn set_handler<F, H>(&mut self, mut handler: H)
+    where
+        F: GenlFamily
+            + Emitable
+            + ParseableParametrized<[u8], GenlHeader>
+            + Debug,
+        H: FnMut(&mut MockRequest<F>) -> Result<Vec<F>, MockError>
+            + Send
+            + 'static,
+    {
+        self.handler = Some(Box::new(move |header, payload| {
+            let message =
+                GenlMessage::parse_with_param(payload, header.message_type)?;
+            let mut request = MockRequest {
+                header: *header,
+                message,
+                notifications: Vec::new(),
+            };
+            let replies = handler(&mut request)?;
+            Ok(HandlerOutput {
+                replies: replies.into_iter().map(emit_genl).collect(),
+                notifications: request
+                    .notifications
+                    .into_iter()
+                    .map(|(group, payload)| (group, emit_genl(payload)))
+                    .collect(),
+            })
+        }));
+    }
+}
+
+impl Debug for MockFamily {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockFamily")
+            .field("name", &self.name)
+            .field("id", &self.id)
+            .field("version", &self.version)
+            .field("multicast_groups", &self.multicast_groups)
+            .field("handler", &self.handler.is_some())
+            .finish()
+    }
+}
+
+fn emit_genl<F>(payload: F) -> Vec<u8>
+where
+    F: GenlFamily + Emitable + Debug,
+{
+    let genlmsg = GenlMessage::from_payload(payload);
+    let mut buf = vec![0; genlmsg.buffer_len()];
+    genlmsg.emit(&mut buf);
+    buf
+}
+
+// Registered family, with the IDs of its multicast groups
+struct Family {
+    desc: MockFamily,
+    groups: Vec<(String, u32)>,
+}
+
+impl Family {
+    fn group_id(&self, name: &str) -> Option<u32> {
+        self.groups
+            .iter()
+            .find(|(group, _)| group == name)
+            .map(|(_, id)| *id)
+    }
+
+    fn ctrl_nlas(&self) -> Vec<GenlCtrlAttrs> {
+        let mut nlas = vec![
+            GenlCtrlAttrs::FamilyName(self.desc.name.clone()),
+            GenlCtrlAttrs::FamilyId(self.desc.id),
+            GenlCtrlAttrs::Version(u32::from(self.desc.version)),
+            GenlCtrlAttrs::HdrSize(self.desc.hdr_size),
+            GenlCtrlAttrs::MaxAttr(self.desc.max_attr),
+        ];
+        if !self.groups.is_empty() {
+            nlas.push(GenlCtrlAttrs::McastGroups(
+                self.groups
+                    .iter()
+                    .map(|(name, id)| {
+                        vec![
+                            McastGrpAttrs::Name(name.clone()),
+                            McastGrpAttrs::Id(*id),
+                        ]
+                    })
+                    .collect(),
+            ));
+        }
+        nlas
+    }
+}
+
+#[derive(Default)]
+struct Connection {
+    rx_queue: VecDeque<Vec<u8>>,
+    groups: HashSet<u32>,
+}
+
+struct Inner {
+    families: Vec<Family>,
+    next_group_id: u32,
+    connections: HashMap<u32, Connection>,
+    next_port: u32,
+}
+
+struct Shared {
+    inner: Mutex<Inner>,
+    readable: Condvar,
+}
+
+/// In-process generic netlink kernel
+///
+/// The kernel is shared by its clones and by the sockets connected to it.
+/// The controller is registered with its static ID and its `notify` group,
+/// which announces the families registered afterwards.
+#[derive(Clone)]
+pub struct MockKernel {
+    shared: Arc<Shared>,
+}
+
+impl Default for MockKernel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MockKernel {
+    pub fn new() -> Self {
+        let mut ctrl = MockFamily::of::<GenlCtrl>();
+        ctrl.set_id(GENL_ID_CTRL);
+        ctrl.set_version(CTRL_VERSION as u8);
+        ctrl.set_max_attr(u32::from(CTRL_ATTR_OP));
+        let inner = Inner {
+            families: vec![Family {
+                desc: ctrl,
+                // The notify group of the controller has the ID of the family
+                groups: vec![("notify".to_owned(), u32::from(GENL_ID_CTRL))],
+            }],
+            next_group_id: 1,
+            connections: HashMap::new(),
+            next_port: 1,
+        };
+        Self {
+            shared: Arc::new(Shared {
+                inner: Mutex::new(inner),
+                readable: Condvar::new(),
+            }),
+        }
+    }
+
+    /// Register a family and return its ID
+    ///
+    /// A `CTRL_CMD_NEWFAMILY` notification is sent to the `notify` group of
+    /// the controller. Registering a name or an ID twice fails.
+    pub fn register(&self, mut family: MockFamily) -> io::Result<u16> {
+        let mut inner = self.lock();
+        if inner.families.iter().any(|f| {
+            f.desc.name == family.name
+                || (family.id != 0 && f.desc.id == family.id)
+        }) {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("family {} is already registered", family.name),
+            ));
+        }
+        if family.id == 0 {
+            family.id = (GENL_START_ALLOC..=u16::MAX)
+                .find(|&id| inner.families.iter().all(|f| f.desc.id != id))
+                .ok_or_else(|| io::Error::other("no family ID left"))?;
+        }
+        let mut groups = Vec::new();
+        for name in family.multicast_groups.iter() {
+            if inner.next_group_id == u32::from(GENL_ID_CTRL) {
+                inner.next_group_id += 1;
+            }
+            groups.push((name.clone(), inner.next_group_id));
+            inner.next_group_id += 1;
+        }
+        let family = Family {
+            desc: family,
+            groups,
+        };
+        let id = family.desc.id;
+        let notification = emit_genl(GenlCtrl {
+            cmd: GenlCtrlCmd::NewFamily,
+            nlas: family.ctrl_nlas(),
+        });
+        inner.families.push(family);
+        inner.multicast(GENL_ID_CTRL, u32::from(GENL_ID_CTRL), &notification);
+        drop(inner);
+        self.shared.readable.notify_all();
+        Ok(id)
+    }
+
+    /// Return the ID of a registered family
+    pub fn family_id(&self, name: &str) -> Option<u16> {
+        self.lock().family_by_name(name).map(|f| f.desc.id)
+    }
+
+    /// Return the ID of the multicast group `group` of the family `family`
+    pub fn multicast_group_id(&self, family: &str, group: &str) -> Option<u32> {
+        self.lock().family_by_name(family)?.group_id(group)
+    }
+
+    /// Open a socket on the kernel
+    pub fn connect(&self) -> MockSocket {
+        let mut inner = self.lock();
+        let port = inner.next_port;
+        inner.next_port += 1;
+        inner.connections.insert(port, Connection::default());
+        MockSocket {
+            kernel: self.clone(),
+            port,
+        }
+    }
+
+    /// Send `payload` to the multicast group `group` of the family `F`
+    pub fn notify<F>(&self, group: &str, payload: F) -> io::Result<()>
+    where
+        F: GenlFamily + Emitable + Debug,
+    {
+        let mut inner = self.lock();
+        let family =
+            inner.family_by_name(F::family_name()).ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("family {} is not registered", F::family_name()),
+                )
+            })?;
+        let family_id = family.desc.id;
+        let group_id = family.group_id(group).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("unknown multicast group {group}"),
+            )
+        })?;
+        inner.multicast(family_id, group_id, &emit_genl(payload));
+        drop(inner);
+        self.shared.readable.notify_all();
+        Ok(())
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Inner> {
+        // A panicking handler leaves the state consistent
+        self.shared
+            .inner
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+impl Debug for MockKernel {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let inner = self.lock();
+        f.debug_struct("MockKernel")
+            .field(
+                "families",
+                &inner.families.iter().map(|f| &f.desc).collect::<Vec<_>>(),
+            )
+            .field("connections", &inner.connections.len())
+            .finish()
+    }
+}
+
+impl Inner {
+    fn family_by_name(&self, name: &str) -> Option<&Family> {
+        self.families.iter().find(|f| f.desc.name == name)
+    }
+
+    fn family_index(&self, id: u16) -> Option<usize> {
+        self.families.iter().position(|f| f.desc.id == id)
+    }
+
+    fn queue(&mut self, port: u32, datagram: Vec<u8>) {
+        if let Some(connection) = self.connections.get_mut(&port) {
+            connection.rx_queue.push_back(datagram);
+        }
+    }
+
+    fn multicast(&mut self, family_id: u16, group_id: u32, genlmsg: &[u8]) {
+        let datagram = message(family_id, 0, 0, 0, genlmsg);
+        for connection in self.connections.values_mut() {
+            if connection.groups.contains(&group_id) {
+                connection.rx_queue.push_back(datagram.clone());
+            }
+        }
+    }
+
+    // Process every request of a datagram sent by `port`
+    fn receive(&mut self, port: u32, datagram: &[u8]) {
+        let mut offset = 0;
+        while offset < datagram.len() {
+            let buf = match NetlinkBuffer::new_checked(&datagram[offset..]) {
+                Ok(buf) => buf,
+                Err(_) => break,
+            };
+            let len = buf.length() as usize;
+            let request = &datagram[offset..offset + len];
+            offset += (len + 3) & !3;
+            if let Ok(header) = NetlinkHeader::parse(&buf) {
+                if header.flags & NLM_F_REQUEST != 0
+                    && header.message_type >= NLMSG_MIN_TYPE
+                {
+                    self.handle(port, &header, request);
+                }
+            }
+        }
+    }
+
+    fn handle(&mut self, port: u32, header: &NetlinkHeader, request: &[u8]) {
+        let payload = &request[NetlinkHeader::default().buffer_len()..];
+        let output = match self.family_index(header.message_type) {
+            None => Err(MockError::new(ENOENT)),
+            Some(_) if header.message_type == GENL_ID_CTRL => {
+                self.handle_ctrl(header, payload)
+            }
+            Some(index) => match &mut self.families[index].desc.handler {
+                Some(handler) => handler(header, payload),
+                None => Err(MockError::new(EOPNOTSUPP)),
+            },
+        };
+        let output = match output {
+            Ok(output) => output,
+            Err(e) => {
+                self.queue(port, error_message(header, port, request, &e));
+                return;
+            }
+        };
+
+        let dump = header.flags & NLM_F_DUMP == NLM_F_DUMP;
+        let flags = if dump { NLM_F_MULTIPART } else { 0 };
+        for reply in output.replies {
+            let datagram = message(
+                header.message_type,
+                flags,
+                header.sequence_number,
+                port,
+                &reply,
+            );
+            self.queue(port, datagram);
+        }
+        if dump {
+            let datagram = message(
+                NLMSG_DONE,
+                NLM_F_MULTIPART,
+                header.sequence_number,
+                port,
+                &0i32.to_ne_bytes(),
+            );
+            self.queue(port, datagram);
+        } else if header.flags & NLM_F_ACK != 0 {
+            self.queue(port, ack_message(header, port));
+        }
+
+        if let Some(index) = self.family_index(header.message_type) {
+            for (group, genlmsg) in output.notifications {
+                if let Some(group_id) = self.families[index].group_id(&group) {
+                    self.multicast(header.message_type, group_id, &genlmsg);
+                }
+            }
+        }
+    }
+
+    fn handle_ctrl(
+        &self,
+        header: &NetlinkHeader,
+        payload: &[u8],
+    ) -> Result<HandlerOutput, MockError> {
+        let request =
+            GenlMessage::<GenlCtrl>::parse_with_param(payload, GENL_ID_CTRL)?
+                .payload;
+        let dump = header.flags & NLM_F_DUMP == NLM_F_DUMP;
+        let replies = match request.cmd {
+            GenlCtrlCmd::GetFamily if dump => self
+                .families
+                .iter()
+                .map(|family| GenlCtrl {
+                    cmd: GenlCtrlCmd::NewFamily,
+                    nlas: family.ctrl_nlas(),
+                })
+                .collect(),
+            GenlCtrlCmd::GetFamily => {
+                let family = self.requested_family(&request)?;
+                vec![GenlCtrl {
+                    cmd: GenlCtrlCmd::NewFamily,
+                    nlas: family.ctrl_nlas(),
+                }]
+            }
+            GenlCtrlCmd::GetPolicy if dump => {
+                let family = self.requested_family(&request)?;
+                let op = request.nlas.iter().find_map(|nla| match nla {
+                    GenlCtrlAttrs::Op(op) => Some(*op),
+                    _ => None,
+                });
+                policy_replies(family, op)?
+            }
+            _ => return Err(MockError::new(EOPNOTSUPP)),
+        };
+        Ok(HandlerOutput {
+            replies: replies.into_iter().map(emit_genl).collect(),
+            notifications: Vec::new(),
+        })
+    }
+
+    // Look up the family named or identified by a controller request
+    fn requested_family(
+        &self,
+        request: &GenlCtrl,
+    ) -> Result<&Family, MockError> {
+        for nla in request.nlas.iter() {
+            match nla {
+                GenlCtrlAttrs::FamilyId(id) => {
+                    return self
+                        .family_index(*id)
+                        .map(|index| &self.families[index])
+                        .ok_or_else(|| MockError::new(ENOENT))
+                }
+                GenlCtrlAttrs::FamilyName(name) => {
+                    return self
+                        .family_by_name(name)
+                        .ok_or_else(|| MockError::new(ENOENT))
+                }
+                _ => {}
+            }
+        }
+        Err(MockError::new(EINVAL))
+    }
+}
+
+// Build the replies of a CTRL_CMD_GETPOLICY dump, optionally restricted to
+// one command: the policies of the commands, then the attribute policies
+fn policy_replies(
+    family: &Family,
+    op: Option<u32>,
+) -> Result<Vec<GenlCtrl>, MockError> {
+    let desc = &family.desc;
+    if desc.policies.is_empty() {
+        return Err(MockError::new(ENODATA));
+    }
+    let mut replies = Vec::new();
+    let mut used = HashSet::new();
+    for (&cmd, &(do_policy, dump_policy)) in desc.op_policies.iter() {
+        if op.is_some_and(|op| op != u32::from(cmd)) {
+            continue;
+        }
+        let mut policy_idx = Vec::new();
+        if let Some(idx) = do_policy {
+            policy_idx.push(OppolicyIndexAttr::Do(idx));
+            used.insert(idx);
+        }
+        if let Some(idx) = dump_policy {
+            policy_idx.push(OppolicyIndexAttr::Dump(idx));
+            used.insert(idx);
+        }
+        replies.push(GenlCtrl {
+            cmd: GenlCtrlCmd::GetPolicy,
+            nlas: vec![
+                GenlCtrlAttrs::FamilyId(desc.id),
+                GenlCtrlAttrs::OpPolicy(OppolicyAttr { cmd, policy_idx }),
+            ],
+        });
+    }
+    if op.is_some() && replies.is_empty() {
+        return Err(MockError::new(ENOENT));
+    }
+    for (&index, attrs) in desc.policies.iter() {
+        // The nested policies are reachable from the policies of the
+        // commands, which are the only ones dumped for a single command
+        if op.is_some() && !used.contains(&index) && !is_nested(desc, index) {
+            continue;
+        }
+        for (&attr, policies) in attrs.iter() {
+            replies.push(GenlCtrl {
+                cmd: GenlCtrlCmd::GetPolicy,
+                nlas: vec![
+                    GenlCtrlAttrs::FamilyId(desc.id),
+                    GenlCtrlAttrs::Policy(PolicyAttr {
+                        index: index as u16,
+                        attr_policy: AttributePolicyAttr {
+                            index: attr,
+                            policies: policies.clone(),
+                        },
+                    }),
+                ],
+            });
+        }
+    }
+    Ok(replies)
+}
+
+// Whether the policy `index` is referenced by an attribute policy
+fn is_nested(family: &MockFamily, index: u32) -> bool {
+    family
+        .policies
+        .values()
+        .flat_map(|attrs| attrs.values())
+        .any(|nla| nla.contains(&NlPolicyTypeAttrs::PolicyIdx(index)))
+}
+
+// Serialize a netlink message
+fn message(
+    message_type: u16,
+    flags: u16,
+    sequence_number: u32,
+    port_number: u32,
+    payload: &[u8],
+) -> Vec<u8> {
+    let mut header = NetlinkHeader::default();
+    let header_len = header.buffer_len();
+    header.length = (header_len + payload.len()) as u32;
+    header.message_type = message_type;
+    header.flags = flags;
+    header.sequence_number = sequence_number;
+    header.port_number = port_number;
+    let mut buf = vec![0; (header.length as usize + 3) & !3];
+    header.emit(&mut buf);
+    buf[header_len..header_len + payload.len()].copy_from_slice(payload);
+    buf
+}
+
+// The ACK carries the header of the request only
+fn ack_message(header: &NetlinkHeader, port: u32) -> Vec<u8> {
+    let mut payload = vec![0; 4 + header.buffer_len()];
+    header.emit(&mut payload[4..]);
+    message(
+        NLMSG_ERROR,
+        NLM_F_CAPPED,
+        header.sequence_number,
+        port,
+        &payload,
+    )
+}
+
+// The error carries the whole request, followed by the extended ACK
+// attributes
+fn error_message(
+    header: &NetlinkHeader,
+    port: u32,
+    request: &[u8],
+    error: &MockError,
+) -> Vec<u8> {
+    let mut payload = (-error.errno).to_ne_bytes().to_vec();
+    payload.extend_from_slice(request);
+    let mut tlvs = Vec::new();
+    if let Some(message) = &error.message {
+        let mut value = message.as_bytes().to_vec();
+        value.push(0);
+        tlvs.push(DefaultNla::new(NLMSGERR_ATTR_MSG, value));
+    }
+    if let Some(offset) = error.offset {
+        tlvs.push(DefaultNla::new(
+            NLMSGERR_ATTR_OFFS,
+            offset.to_ne_bytes().to_vec(),
+        ));
+    }
+    let mut flags = 0;
+    if !tlvs.is_empty() {
+        flags |= NLM_F_ACK_TLVS;
+        let start = payload.len();
+        payload.resize(start + tlvs.as_slice().buffer_len(), 0);
+        tlvs.as_slice().emit(&mut payload[start..]);
+    }
+    message(NLMSG_ERROR, flags, header.sequence_number, port, &payload)
+}
+
+/// Socket connected to a [`MockKernel`]
+///
+/// The socket is closed when dropped.
+pub struct MockSocket {
+    kernel: MockKernel,
+    port: u32,
+}
+
+impl MockSocket {
+    /// Return the port ID assigned to the socket
+    pub fn port_number(&self) -> u32 {
+        self.port
+    }
+
+    /// Return the kernel the socket is connected to
+    pub fn kernel(&self) -> &MockKernel {
+        &self.kernel
+    }
+
+    /// Send a datagram of netlink messages to the kernel
+    ///
+    /// The requests are processed before returning, their replies are
+    /// queued on the socket.
+    pub fn send(&self, datagram: &[u8]) -> io::Result<usize> {
+        let mut inner = self.kernel.lock();
+        inner.receive(self.port, datagram);
+        drop(inner);
+        self.kernel.shared.readable.notify_all();
+        Ok(datagram.len())
+    }
+
+    /// Receive the next datagram, waiting at most `timeout` for it
+    ///
+    /// `None` blocks until a datagram is queued. A
+    /// [`io::ErrorKind::WouldBlock`] error is returned on timeout.
+    pub fn recv(&self, timeout: Option<Duration>) -> io::Result<Vec<u8>> {
+        let deadline = timeout.map(|timeout| Instant::now() + timeout);
+        let mut inner = self.kernel.lock();
+        loop {
+            if let Some(datagram) = inner
+                .connections
+                .get_mut(&self.port)
+                .and_then(|connection| connection.rx_queue.pop_front())
+            {
+                return Ok(datagram);
+            }
+            inner = match deadline {
+                None => self
+                    .kernel
+                    .shared
+                    .readable
+                    .wait(inner)
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()),
+                Some(deadline) => {
+                    let remaining =
+                        deadline.saturating_duration_since(Instant::now());
+                    if remaining.is_zero() {
+                        return Err(io::ErrorKind::WouldBlock.into());
+                    }
+                    self.kernel
+                        .shared
+                        .readable
+                        .wait_timeout(inner, remaining)
+                        .unwrap_or_else(|poisoned| poisoned.into_inner())
+                        .0
+                }
+            };
+        }
+    }
+
+    /// Join the multicast group with the ID `group`
+    pub fn add_membership(&self, group: u32) -> io::Result<()> {
+        self.membership(group, true)
+    }
+
+    /// Leave the multicast group with the ID `group`
+    pub fn drop_membership(&self, group: u32) -> io::Result<()> {
+        self.membership(group, false)
+    }
+
+    fn membership(&self, group: u32, join: bool) -> io::Result<()> {
+        let mut inner = self.kernel.lock();
+        let known = inner
+            .families
+            .iter()
+            .any(|f| f.groups.iter().any(|(_, id)| *id == group));
+        if !known {
+            return Err(io::Error::from_raw_os_error(EINVAL));
+        }
+        if let Some(connection) = inner.connections.get_mut(&self.port) {
+            if join {
+                connection.groups.insert(group);
+            } else {
+                connection.groups.remove(&group);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Debug for MockSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MockSocket")
+            .field("port", &self.port)
+            .finish()
+    }
+}
+
+impl Drop for MockSocket {
+    fn drop(&mut self) {
+        self.kernel.lock().connections.remove(&self.port);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::raw::{FamilyPolicy, GenlRaw};
+    use netlink_packet_core::{NetlinkMessage, NetlinkPayload};
+
+    fn request<F>(socket: &MockSocket, payload: F, flags: u16, seq: u32)
+    where
+        F: GenlFamily + Emitable + Debug,
+    {
+        let mut nlmsg =
+            NetlinkMessage::from(GenlMessage::from_payload(payload));
+        nlmsg.header.flags = flags;
+        nlmsg.header.sequence_number = seq;
+        nlmsg.finalize();
+        let mut buf = vec![0; nlmsg.buffer_len()];
+        nlmsg.serialize(&mut buf);
+        socket.send(&buf).unwrap();
+    }
+
+    fn replies(
+        socket: &MockSocket,
+    ) -> Vec<NetlinkMessage<GenlMessage<GenlCtrl>>> {
+        let mut replies = Vec::new();
+        while let Ok(buf) = socket.recv(Some(Duration::ZERO)) {
+            replies.push(NetlinkMessage::deserialize(&buf).unwrap());
+        }
+        replies
+    }
+
+    #[test]
+    fn get_family() {
+        let kernel = MockKernel::new();
+        let mut family = MockFamily::new("test");
+        family.add_multicast_group("events");
+        let id = kernel.register(family).unwrap();
+        assert_eq!(GENL_START_ALLOC, id);
+        assert!(kernel.register(MockFamily::new("test")).is_err());
+
+        let socket = kernel.connect();
+        request(
+            &socket,
+            GenlCtrl {
+                cmd: GenlCtrlCmd::GetFamily,
+                nlas: vec![GenlCtrlAttrs::FamilyName("test".to_owned())],
+            },
+            NLM_F_REQUEST | NLM_F_ACK,
+            7,
+        );
+        let replies = replies(&socket);
+        assert_eq!(2, replies.len());
+        assert_eq!(7, replies[0].header.sequence_number);
+        assert_eq!(socket.port_number(), replies[0].header.port_number);
+        match &replies[0].payload {
+            NetlinkPayload::InnerMessage(genlmsg) => {
+                assert_eq!(GenlCtrlCmd::NewFamily, genlmsg.payload.cmd);
+                assert!(genlmsg
+                    .payload
+                    .nlas
+                    .contains(&GenlCtrlAttrs::FamilyId(id)));
+                assert!(genlmsg.payload.nlas.contains(
+                    &GenlCtrlAttrs::McastGroups(vec![vec![
+                        McastGrpAttrs::Name("events".to_owned()),
+                        McastGrpAttrs::Id(1),
+                    ]])
+                ));
+            }
+            payload => panic!("Unexpected payload: {:?}", payload),
+        }
+        assert_eq!(socket.port_number(), replies[1].header.port_number);
+        match &replies[1].payload {
+            NetlinkPayload::Error(e) => assert_eq!(None, e.code),
+            payload => panic!("Unexpected payload: {:?}", payload),
+        }
+    }
+
+    #[test]
+    fn unknown_family() {
+        let kernel = MockKernel::new();
+        let socket = kernel.connect();
+        request(
+            &socket,
+            GenlCtrl {
+                cmd: GenlCtrlCmd::GetFamily,
+                nlas: vec![GenlCtrlAttrs::FamilyName("nosuch".to_owned())],
+            },
+            NLM_F_REQUEST,
+            1,
+        );
+        let replies = replies(&socket);
+        assert_eq!(socket.port_number(), replies[0].header.port_number);
+        match &replies[0].payload {
+            NetlinkPayload::Error(e) => assert_eq!(-ENOENT, e.raw_code()),
+            payload => panic!("Unexpected payload: {:?}", payload),
+        }
+    }
+
+    #[test]
+    fn get_policy() {
+        let kernel = MockKernel::new();
+        let mut family = MockFamily::new("test");
+        family.add_policy(
+            0,
+            1,
+            vec![NlPolicyTypeAttrs::Type(crate::ctrl::nlas::NlaType::U32)],
+        );
+        family.add_op_policy(3, Some(0), None);
+        let id = kernel.register(family).unwrap();
+
+        let socket = kernel.connect();
+        request(
+            &socket,
+            GenlCtrl {
+                cmd: GenlCtrlCmd::GetPolicy,
+                nlas: vec![GenlCtrlAttrs::FamilyId(id)],
+            },
+            NLM_F_REQUEST | NLM_F_DUMP,
+            1,
+        );
+        let replies = replies(&socket);
+        assert!(matches!(
+            replies.last().unwrap().payload,
+            NetlinkPayload::Done(_)
+        ));
+        let policy =
+            FamilyPolicy::from_replies(replies.iter().filter_map(|msg| {
+                match &msg.payload {
+                    NetlinkPayload::InnerMessage(genlmsg) => {
+                        Some(&genlmsg.payload)
+                    }
+                    _ => None,
+                }
+            }));
+        assert_eq!(id, policy.family_id());
+        assert_eq!(Some(0), policy.do_policy(3));
+        assert_eq!(
+            Some(
+                &[NlPolicyTypeAttrs::Type(crate::ctrl::nlas::NlaType::U32)][..]
+            ),
+            policy.attribute(0, 1)
+        );
+    }
+
+    #[test]
+    fn handler_error_and_notification() {
+        let kernel = MockKernel::new();
+        let mut family = MockFamily::new("test");
+        family.add_multicast_group("events");
+        family.set_handler(|request: &mut MockRequest<GenlRaw>| {
+            if request.message.payload.cmd == 2 {
+                return Err(MockError::with_message(EINVAL, "bad command"));
+            }
+            let reply = request.message.payload.clone();
+            request.notify("events", reply.clone());
+            Ok(vec![reply])
+        });
+        let id = kernel.register(family).unwrap();
+
+        let socket = kernel.connect();
+        let listener = kernel.connect();
+        listener
+            .add_membership(
+                kernel.multicast_group_id("test", "events").unwrap(),
+            )
+            .unwrap();
+        let raw = |cmd| GenlRaw {
+            family_id: id,
+            cmd,
+            version: 1,
+            nlas: vec![],
+        };
+
+        request(&socket, raw(1), NLM_F_REQUEST, 1);
+        let reply = socket.recv(Some(Duration::ZERO)).unwrap();
+        assert_eq!(id, NetlinkBuffer::new(&reply).message_type());
+        let notification = listener.recv(Some(Duration::ZERO)).unwrap();
+        assert_eq!(0, NetlinkBuffer::new(&notification).sequence_number());
+
+        request(&socket, raw(2), NLM_F_REQUEST, 2);
+        let error = socket.recv(Some(Duration::ZERO)).unwrap();
+        let buf = NetlinkBuffer::new(&error);
+        assert_eq!(NLMSG_ERROR, buf.message_type());
+        assert_eq!(NLM_F_ACK_TLVS, buf.flags());
+        assert!(error.windows(12).any(|w| w == b"bad command\0"));
+        assert!(listener.recv(Some(Duration::ZERO)).is_err());
+    }
+}
@@ -0,0 +1,132 @@
+// SPDX-License-Identifier: MIT
+
+//! Read and write `nlmon` captures
+//!
+//! Netlink traffic can be captured with the `nlmon` virtual interface:
+//!
+//! ```sh
+//! ip link add nlmon0 type nlmon
+//! ip link set nlmon0 up
+//! tcpdump -i nlmon0 -w genl.pcap
+//! ```
+//!
+//! The capture uses the `LINKTYPE_NETLINK` (253) link type: each packet
+//! starts with a 16-byte pseudo-header, holding the direction of the
+//! message and the netlink protocol, followed by the netlink messages.
+//!
+//! [`PcapReader`] reads such captures in the pcap and pcapng formats and
+//! yields the generic netlink messages they contain, decoded with a
+//! [`GenlRegistry`](crate::registry::GenlRegistry). [`PcapWriter`] records
+//! messages into a pcapng capture with the same format, without requiring
+//! an `nlmon` interface.
+
+use crate::registry::AnyGenlMessage;
+use netlink_packet_core::NetlinkMessage;
+use netlink_packet_utils::DecodeError;
+use std::{fmt, io, time::Duration};
+
+mod reader;
+pub use self::reader::PcapReader;
+mod writer;
+pub use self::writer::PcapWriter;
+
+/// Link type of the netlink captures
+pub const LINKTYPE_NETLINK: u16 = 253;
+
+// Netlink protocol of the generic netlink messages
+const NETLINK_GENERIC: u16 = 16;
+
+// Length of the LINKTYPE_NETLINK pseudo-header
+const COOKED_HEADER_LEN: usize = 16;
+
+// Packet types set by nlmon, see `netlink_deliver_tap()` in the kernel
+const PACKET_HOST: u16 = 0;
+const PACKET_OUTGOING: u16 = 4;
+const PACKET_USER: u16 = 6;
+const PACKET_KERNEL: u16 = 7;
+
+/// Direction of a captured message
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Direction {
+    /// Message sent to a user space socket, e.g. a kernel reply
+    ToUser,
+    /// Message sent to the kernel, e.g. a request
+    ToKernel,
+    /// Other packet type
+    Other(u16),
+}
+
+impl From<u16> for Direction {
+    fn from(pkt_type: u16) -> Self {
+        match pkt_type {
+            // Kernels older than 3.19 use the regular packet types
+            PACKET_USER | PACKET_HOST => Self::ToUser,
+            PACKET_KERNEL | PACKET_OUTGOING => Self::ToKernel,
+            pkt_type => Self::Other(pkt_type),
+        }
+    }
+}
+
+impl From<Direction> for u16 {
+    fn from(direction: Direction) -> u16 {
+        match direction {
+            Direction::ToUser => PACKET_USER,
+            Direction::ToKernel => PACKET_KERNEL,
+            Direction::Other(pkt_type) => pkt_type,
+        }
+    }
+}
+
+/// Generic netlink message read from a capture
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CapturedMessage {
+    /// Capture time, since the UNIX epoch
+    ///
+    /// pcapng simple packet blocks have no timestamp, it is zero then.
+    pub timestamp: Duration,
+    pub direction: Direction,
+    pub message: NetlinkMessage<AnyGenlMessage>,
+}
+
+/// Error returned when reading a capture
+#[derive(Debug)]
+pub enum PcapError {
+    /// Reading the capture failed
+    Io(io::Error),
+    /// The capture file is malformed or uses an unsupported link type
+    Format(String),
+    /// A captured netlink message could not be decoded
+    Decode(DecodeError),
+}
+
+impl fmt::Display for PcapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "failed to read the capture: {e}"),
+            Self::Format(e) => write!(f, "invalid capture: {e}"),
+            Self::Decode(e) => write!(f, "failed to decode message: {e}"),
+        }
+    }
+}
+
+impl std::error::Error for PcapError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Decode(e) => Some(e),
+            Self::Format(_) => None,
+        }
+    }
+}
+
+impl From<io::Error> for PcapError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<DecodeError> for PcapError {
+    fn from(e: DecodeError) -> Self {
+        Self::Decode(e)
+    }
+}
@@ -0,0 +1,566 @@
+// SPDX-License-Identifier: MIT
+
+use super::{
+    CapturedMessage, Direction, PcapError, COOKED_HEADER_LEN, LINKTYPE_NETLINK,
+    NETLINK_GENERIC,
+};
+use crate::registry::GenlRegistry;
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use netlink_packet_core::NetlinkBuffer;
+use std::{collections::VecDeque, io::Read, time::Duration};
+
+const PCAP_MAGIC_MICROS: u32 = 0xa1b2_c3d4;
+const PCAP_MAGIC_NANOS: u32 = 0xa1b2_3c4d;
+const PCAPNG_SHB: u32 = 0x0a0d_0d0a;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+const PCAPNG_IDB: u32 = 1;
+const PCAPNG_SPB: u32 = 3;
+const PCAPNG_EPB: u32 = 6;
+const PCAPNG_OPT_END: u16 = 0;
+const PCAPNG_OPT_IF_TSRESOL: u16 = 9;
+
+// Upper bound of a block or record, to not allocate an arbitrary amount of
+// memory on corrupted files
+const MAX_BLOCK_LEN: usize = 16 * 1024 * 1024;
+
+#[derive(Clone, Copy, Debug)]
+enum Endian {
+    Little,
+    Big,
+}
+
+impl Endian {
+    fn u16(self, buf: &[u8]) -> u16 {
+        match self {
+            Self::Little => LittleEndian::read_u16(buf),
+            Self::Big => BigEndian::read_u16(buf),
+        }
+    }
+
+    fn u32(self, buf: &[u8]) -> u32 {
+        match self {
+            Self::Little => LittleEndian::read_u32(buf),
+            Self::Big => BigEndian::read_u32(buf),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+struct Interface {
+    link_type: u16,
+    // Timestamp units per second
+    ts_units: u64,
+}
+
+#[derive(Debug)]
+enum Format {
+    Pcap {
+        endian: Endian,
+        ts_units: u64,
+    },
+    PcapNg {
+        endian: Endian,
+        interfaces: Vec<Interface>,
+    },
+}
+
+/// Iterator over the generic netlink messages of a pcap or pcapng capture
+///
+/// The format is detected from the file header. Each captured packet may
+/// carry several netlink messages, they are yielded one by one. Packets of
+/// other netlink protocols than `NETLINK_GENERIC` are skipped.
+///
+/// ```no_run
+/// use netlink_packet_generic::{pcap::PcapReader, registry::GenlRegistry};
+/// use std::{fs::File, io::BufReader};
+///
+/// let file = BufReader::new(File::open("genl.pcap").unwrap());
+/// for msg in PcapReader::new(file, GenlRegistry::new()).unwrap() {
+///     let msg = msg.unwrap();
+///     println!("{:?} {:?}", msg.direction, msg.message);
+/// }
+/// ```
+#[derive(Debug)]
+pub struct PcapReader<R> {
+    reader: R,
+    format: Format,
+    registry: GenlRegistry,
+    pending: VecDeque<CapturedMessage>,
+}
+
+impl<R: Read> PcapReader<R> {
+    /// Read the file header and create the reader
+    ///
+    /// The messages are decoded with the families of the `registry`.
+    pub fn new(
+        mut reader: R,
+        registry: GenlRegistry,
+    ) -> Result<Self, PcapError> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+
+        let format = if BigEndian::read_u32(&magic) == PCAPNG_SHB {
+            let mut format = Format::PcapNg {
+                endian: Endian::Little,
+                interfaces: Vec::new(),
+            };
+            read_section_header(&mut reader, &mut format)?;
+            format
+        } else {
+            let (endian, ts_units) = match (
+                LittleEndian::read_u32(&magic),
+                BigEndian::read_u32(&magic),
+            ) {
+                (PCAP_MAGIC_MICROS, _) => (Endian::Little, 1_000_000),
+                (PCAP_MAGIC_NANOS, _) => (Endian::Little, 1_000_000_000),
+                (_, PCAP_MAGIC_MICROS) => (Endian::Big, 1_000_000),
+                (_, PCAP_MAGIC_NANOS) => (Endian::Big, 1_000_000_000),
+                _ => {
+                    return Err(PcapError::Format(format!(
+                        "unknown file magic {magic:02x?}"
+                    )))
+                }
+            };
+            // version, thiszone, sigfigs, snaplen, network
+            let mut header = [0u8; 20];
+            reader.read_exact(&mut header)?;
+            let link_type = endian.u32(&header[16..20]);
+            if link_type != LINKTYPE_NETLINK as u32 {
+                return Err(PcapError::Format(format!(
+                    "unsupported link type {link_type}"
+                )));
+            }
+            Format::Pcap { endian, ts_units }
+        };
+
+        Ok(Self {
+            reader,
+            format,
+            registry,
+            pending: VecDeque::new(),
+        })
+    }
+
+    /// Return the registry used to decode the messages
+    pub fn registry_mut(&mut self) -> &mut GenlRegistry {
+        &mut self.registry
+    }
+
+    // Read the next netlink packet: timestamp and data
+    fn next_packet(
+        &mut self,
+    ) -> Result<Option<(Duration, Vec<u8>)>, PcapError> {
+        match self.format {
+            Format::Pcap { endian, ts_units } => {
+                let mut header = [0u8; 16];
+                if !read_or_eof(&mut self.reader, &mut header)? {
+                    return Ok(None);
+                }
+                let timestamp = timestamp(
+                    endian.u32(&header[0..4]) as u64 * ts_units
+                        + endian.u32(&header[4..8]) as u64,
+                    ts_units,
+                );
+                let len = endian.u32(&header[8..12]) as usize;
+                Ok(Some((timestamp, read_vec(&mut self.reader, len)?)))
+            }
+            Format::PcapNg { .. } => self.next_pcapng_packet(),
+        }
+    }
+
+    fn next_pcapng_packet(
+        &mut self,
+    ) -> Result<Option<(Duration, Vec<u8>)>, PcapError> {
+        loop {
+            let mut block_type = [0u8; 4];
+            if !read_or_eof(&mut self.reader, &mut block_type)? {
+                return Ok(None);
+            }
+            if BigEndian::read_u32(&block_type) == PCAPNG_SHB {
+                read_section_header(&mut self.reader, &mut self.format)?;
+                continue;
+            }
+
+            let (endian, interfaces) = match &mut self.format {
+                Format::PcapNg { endian, interfaces } => (*endian, interfaces),
+                Format::Pcap { .. } => unreachable!(),
+            };
+            let mut len = [0u8; 4];
+            self.reader.read_exact(&mut len)?;
+            let len = endian.u32(&len) as usize;
+            if len < 12 || len & 3 != 0 {
+                return Err(PcapError::Format(format!(
+                    "invalid block length {len}"
+                )));
+            }
+            // Body and trailing block length
+            let block = read_vec(&mut self.reader, len - 8)?;
+            let body = &block[..block.len() - 4];
+
+            match endian.u32(&block_type) {
+                PCAPNG_IDB => interfaces.push(parse_interface(endian, body)?),
+                PCAPNG_EPB => {
+                    if body.len() < 20 {
+                        return Err(truncated("enhanced packet block"));
+                    }
+                    let interface = interfaces
+                        .get(endian.u32(&body[0..4]) as usize)
+                        .ok_or_else(|| {
+                            PcapError::Format("unknown interface".to_owned())
+                        })?;
+                    let ts = (endian.u32(&body[4..8]) as u64) << 32
+                        | endian.u32(&body[8..12]) as u64;
+                    let cap_len = endian.u32(&body[12..16]) as usize;
+                    let data = body
+                        .get(20..20 + cap_len)
+                        .ok_or_else(|| truncated("enhanced packet block"))?;
+                    if interface.link_type == LINKTYPE_NETLINK {
+                        return Ok(Some((
+                            timestamp(ts, interface.ts_units),
+                            data.to_vec(),
+                        )));
+                    }
+                }
+                PCAPNG_SPB => {
+                    if body.len() < 4 {
+                        return Err(truncated("simple packet block"));
+                    }
+                    let orig_len = endian.u32(&body[0..4]) as usize;
+                    let data = &body[4..];
+                    let data = &data[..orig_len.min(data.len())];
+                    if interfaces.first().map(|i| i.link_type)
+                        == Some(LINKTYPE_NETLINK)
+                    {
+                        return Ok(Some((Duration::ZERO, data.to_vec())));
+                    }
+                }
+                // Statistics, name resolution, custom blocks, etc.
+                _ => {}
+            }
+        }
+    }
+
+    fn decode_packet(
+        &mut self,
+        timestamp: Duration,
+        data: &[u8],
+    ) -> Result<(), PcapError> {
+        if data.len() < COOKED_HEADER_LEN {
+            return Err(truncated("netlink packet"));
+        }
+        let direction = Direction::from(BigEndian::read_u16(&data[0..2]));
+        if BigEndian::read_u16(&data[14..16]) != NETLINK_GENERIC {
+            return Ok(());
+        }
+
+        let data = &data[COOKED_HEADER_LEN..];
+        let mut offset = 0;
+        while offset < data.len() {
+            let len =
+                NetlinkBuffer::new_checked(&data[offset..])?.length() as usize;
+            let message =
+                self.registry.deserialize(&data[offset..offset + len])?;
+            self.pending.push_back(CapturedMessage {
+                timestamp,
+                direction,
+                message,
+            });
+            offset += (len + 3) & !3;
+        }
+        Ok(())
+    }
+}
+
+impl<R: Read> Iterator for PcapReader<R> {
+    type Item = Result<CapturedMessage, PcapError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            let result = match self.next_packet() {
+                Ok(Some((timestamp, data))) => {
+                    self.decode_packet(timestamp, &data)
+                }
+                Ok(None) => return None,
+                Err(e) => Err(e),
+            };
+            if let Err(e) = result {
+                return Some(Err(e));
+            }
+        }
+        self.pending.pop_front().map(Ok)
+    }
+}
+
+// Parse the section header block, after its block type
+fn read_section_header<R: Read>(
+    reader: &mut R,
+    format: &mut Format,
+) -> Result<(), PcapError> {
+    let mut header = [0u8; 8];
+    reader.read_exact(&mut header)?;
+    let endian = match LittleEndian::read_u32(&header[4..8]) {
+        PCAPNG_BYTE_ORDER_MAGIC => Endian::Little,
+        _ if BigEndian::read_u32(&header[4..8]) == PCAPNG_BYTE_ORDER_MAGIC => {
+            Endian::Big
+        }
+        _ => {
+            return Err(PcapError::Format(
+                "invalid byte order magic".to_owned(),
+            ))
+        }
+    };
+    let len = endian.u32(&header[0..4]) as usize;
+    if len < 28 || len & 3 != 0 {
+        return Err(PcapError::Format(format!(
+            "invalid section header length {len}"
+        )));
+    }
+    // Skip the version, section length, options and trailing length
+    read_vec(reader, len - 12)?;
+    *format = Format::PcapNg {
+        endian,
+        interfaces: Vec::new(),
+    };
+    Ok(())
+}
+
+fn parse_interface(
+    endian: Endian,
+    body: &[u8],
+) -> Result<Interface, PcapError> {
+    if body.len() < 8 {
+        return Err(truncated("interface description block"));
+    }
+    let mut interface = Interface {
+        link_type: endian.u16(&body[0..2]),
+        ts_units: 1_000_000,
+    };
+    let mut options = &body[8..];
+    while options.len() >= 4 {
+        let code = endian.u16(&options[0..2]);
+        let len = endian.u16(&options[2..4]) as usize;
+        let value = options
+            .get(4..4 + len)
+            .ok_or_else(|| truncated("interface option"))?;
+        match code {
+            PCAPNG_OPT_END => break,
+            PCAPNG_OPT_IF_TSRESOL if len == 1 => {
+                let exponent = u32::from(value[0] & 0x7f);
+                interface.ts_units = if value[0] & 0x80 == 0 {
+                    10u64.checked_pow(exponent)
+                } else {
+                    2u64.checked_pow(exponent)
+                }
+                .ok_or_else(|| {
+                    PcapError::Format("invalid timestamp resolution".to_owned())
+                })?;
+            }
+            _ => {}
+        }
+        options = options.get(4 + ((len + 3) & !3)..).unwrap_or_default();
+    }
+    Ok(interface)
+}
+
+fn timestamp(ts: u64, ts_units: u64) -> Duration {
+    let nanos =
+        u128::from(ts % ts_units) * 1_000_000_000 / u128::from(ts_units);
+    Duration::new(ts / ts_units, nanos as u32)
+}
+
+fn truncated(what: &str) -> PcapError {
+    PcapError::Format(format!("truncated {what}"))
+}
+
+// Fill the buffer, return false if the reader is at the end of the file
+fn read_or_eof<R: Read>(
+    reader: &mut R,
+    buf: &mut [u8],
+) -> Result<bool, PcapError> {
+    let mut read = 0;
+    while read < buf.len() {
+        match reader.read(&mut buf[read..]) {
+            Ok(0) if read == 0 => return Ok(false),
+            Ok(0) => return Err(truncated("file")),
+            Ok(len) => read += len,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(true)
+}
+
+fn read_vec<R: Read>(reader: &mut R, len: usize) -> Result<Vec<u8>, PcapError> {
+    if len > MAX_BLOCK_LEN {
+        return Err(PcapError::Format(format!("block too large: {len}")));
+    }
+    let mut buf = vec![0; len];
+    reader.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pcap::{PACKET_KERNEL, PACKET_USER};
+    use crate::{
+        constants::GENL_ID_CTRL,
+        ctrl::{nlas::GenlCtrlAttrs, GenlCtrl, GenlCtrlCmd},
+        registry::AnyGenlMessage,
+        GenlMessage,
+    };
+    use netlink_packet_core::{NetlinkMessage, NetlinkPayload, NLM_F_REQUEST};
+
+    fn get_family(name: &str, seq: u32) -> Vec<u8> {
+        let mut genlmsg = GenlMessage::from_payload(GenlCtrl {
+            cmd: GenlCtrlCmd::GetFamily,
+            nlas: vec![GenlCtrlAttrs::FamilyName(name.to_owned())],
+        });
+        genlmsg.set_resolved_family_id(GENL_ID_CTRL);
+        let mut nlmsg = NetlinkMessage::from(genlmsg);
+        nlmsg.header.flags = NLM_F_REQUEST;
+        nlmsg.header.sequence_number = seq;
+        nlmsg.finalize();
+        let mut buf = vec![0; nlmsg.buffer_len()];
+        nlmsg.serialize(&mut buf);
+        buf
+    }
+
+    fn packet(pkt_type: u16, protocol: u16, messages: &[Vec<u8>]) -> Vec<u8> {
+        let mut buf = vec![0; COOKED_HEADER_LEN];
+        BigEndian::write_u16(&mut buf[0..2], pkt_type);
+        BigEndian::write_u16(&mut buf[2..4], 824);
+        BigEndian::write_u16(&mut buf[14..16], protocol);
+        for msg in messages {
+            buf.extend_from_slice(msg);
+        }
+        buf
+    }
+
+    fn family_name(msg: &CapturedMessage) -> String {
+        match &msg.message.payload {
+            NetlinkPayload::InnerMessage(AnyGenlMessage::Ctrl(genlmsg)) => {
+                match &genlmsg.payload.nlas[..] {
+                    [GenlCtrlAttrs::FamilyName(name)] => name.clone(),
+                    nlas => panic!("Unexpected attributes: {:?}", nlas),
+                }
+            }
+            payload => panic!("Unexpected payload: {:?}", payload),
+        }
+    }
+
+    #[test]
+    fn read_pcap() {
+        let mut file = Vec::new();
+        file.extend_from_slice(&PCAP_MAGIC_MICROS.to_le_bytes());
+        file.extend_from_slice(&[2, 0, 4, 0]);
+        file.extend_from_slice(&[0; 8]);
+        file.extend_from_slice(&65535u32.to_le_bytes());
+        file.extend_from_slice(&u32::from(LINKTYPE_NETLINK).to_le_bytes());
+        let packets = [
+            (
+                1,
+                packet(
+                    PACKET_KERNEL,
+                    NETLINK_GENERIC,
+                    &[get_family("nlctrl", 1), get_family("devlink", 2)],
+                ),
+            ),
+            // rtnetlink, skipped
+            (2, packet(PACKET_USER, 0, &[get_family("ignored", 3)])),
+            (
+                3,
+                packet(PACKET_USER, NETLINK_GENERIC, &[get_family("foo", 4)]),
+            ),
+        ];
+        for (secs, data) in &packets {
+            file.extend_from_slice(&(*secs as u32).to_le_bytes());
+            file.extend_from_slice(&500u32.to_le_bytes());
+            file.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            file.extend_from_slice(&(data.len() as u32).to_le_bytes());
+            file.extend_from_slice(data);
+        }
+
+        let messages = PcapReader::new(&file[..], GenlRegistry::new())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(3, messages.len());
+        assert_eq!(Duration::new(1, 500_000), messages[0].timestamp);
+        assert_eq!(Direction::ToKernel, messages[0].direction);
+        assert_eq!("nlctrl", family_name(&messages[0]));
+        assert_eq!(Direction::ToKernel, messages[1].direction);
+        assert_eq!("devlink", family_name(&messages[1]));
+        assert_eq!(2, messages[1].message.header.sequence_number);
+        assert_eq!(Duration::new(3, 500_000), messages[2].timestamp);
+        assert_eq!(Direction::ToUser, messages[2].direction);
+        assert_eq!("foo", family_name(&messages[2]));
+    }
+
+    fn block(block_type: u32, body: &[u8]) -> Vec<u8> {
+        let len = (12 + body.len() as u32 + 3) & !3;
+        let mut buf = Vec::new();
+        buf.extend_from_slice(&block_type.to_be_bytes());
+        buf.extend_from_slice(&len.to_be_bytes());
+        buf.extend_from_slice(body);
+        buf.resize(len as usize - 4, 0);
+        buf.extend_from_slice(&len.to_be_bytes());
+        buf
+    }
+
+    #[test]
+    fn read_pcapng() {
+        let data = packet(PACKET_USER, NETLINK_GENERIC, &[get_family("x", 7)]);
+        let ts: u64 = 1_600_000_000_123_456_789;
+
+        let mut file = Vec::new();
+        let mut shb = Vec::new();
+        shb.extend_from_slice(&PCAPNG_BYTE_ORDER_MAGIC.to_be_bytes());
+        shb.extend_from_slice(&[0, 1, 0, 0]);
+        shb.extend_from_slice(&u64::MAX.to_be_bytes());
+        file.extend(block(PCAPNG_SHB, &shb));
+        // Interface 0 is not a netlink one
+        file.extend(block(PCAPNG_IDB, &[0, 1, 0, 0, 0, 0, 0, 0]));
+        let mut idb = vec![0, 253, 0, 0, 0, 0, 0, 0];
+        idb.extend_from_slice(&[0, 9, 0, 1, 9, 0, 0, 0, 0, 0, 0, 0]);
+        file.extend(block(PCAPNG_IDB, &idb));
+        for interface in 0..2u32 {
+            let mut epb = Vec::new();
+            epb.extend_from_slice(&interface.to_be_bytes());
+            epb.extend_from_slice(&((ts >> 32) as u32).to_be_bytes());
+            epb.extend_from_slice(&(ts as u32).to_be_bytes());
+            epb.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            epb.extend_from_slice(&(data.len() as u32).to_be_bytes());
+            epb.extend_from_slice(&data);
+            file.extend(block(PCAPNG_EPB, &epb));
+        }
+        // Interface statistics block, skipped
+        file.extend(block(5, &[0; 12]));
+
+        let messages = PcapReader::new(&file[..], GenlRegistry::new())
+            .unwrap()
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(1, messages.len());
+        assert_eq!(
+            Duration::new(1_600_000_000, 123_456_789),
+            messages[0].timestamp
+        );
+        assert_eq!(Direction::ToUser, messages[0].direction);
+        assert_eq!("x", family_name(&messages[0]));
+        assert_eq!(7, messages[0].message.header.sequence_number);
+    }
+
+    #[test]
+    fn unsupported_link_type() {
+        let mut file = Vec::new();
+        file.extend_from_slice(&PCAP_MAGIC_NANOS.to_be_bytes());
+        file.extend_from_slice(&[0, 2, 0, 4]);
+        file.extend_from_slice(&[0; 12]);
+        file.extend_from_slice(&1u32.to_be_bytes());
+        assert!(matches!(
+            PcapReader::new(&file[..], GenlRegistry::new()),
+            Err(PcapError::Format(_))
+        ));
+    }
+}
@@ -0,0 +1,239 @@
+// SPDX-License-Identifier: MIT
+
+use super::{Direction, COOKED_HEADER_LEN, LINKTYPE_NETLINK, NETLINK_GENERIC};
+use netlink_packet_core::{NetlinkMessage, NetlinkSerializable};
+use std::{
+    convert::TryFrom,
+    fmt::Debug,
+    io::{self, Write},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+const PCAPNG_SHB: u32 = 0x0a0d_0d0a;
+const PCAPNG_BYTE_ORDER_MAGIC: u32 = 0x1a2b_3c4d;
+const PCAPNG_IDB: u32 = 1;
+const PCAPNG_EPB: u32 = 6;
+const PCAPNG_OPT_IF_TSRESOL: u16 = 9;
+
+// ARPHRD_NETLINK
+const NETLINK_HATYPE: u16 = 824;
+
+/// Writer of `LINKTYPE_NETLINK` pcapng captures
+///
+/// The messages are recorded with the same pseudo-header as the `nlmon`
+/// interface, so the captures can be opened in Wireshark or read back with
+/// [`PcapReader`](super::PcapReader). Timestamps have a nanosecond
+/// resolution.
+///
+/// ```
+/// use netlink_packet_core::{NetlinkMessage, NLM_F_REQUEST};
+/// use netlink_packet_generic::{
+///     ctrl::{nlas::GenlCtrlAttrs, GenlCtrl, GenlCtrlCmd},
+///     pcap::{Direction, PcapWriter},
+///     GenlMessage,
+/// };
+///
+/// let mut writer = PcapWriter::new(Vec::new()).unwrap();
+/// let mut nlmsg = NetlinkMessage::from(GenlMessage::from_payload(GenlCtrl {
+///     cmd: GenlCtrlCmd::GetFamily,
+///     nlas: vec![GenlCtrlAttrs::FamilyName("nlctrl".to_owned())],
+/// }));
+/// nlmsg.header.flags = NLM_F_REQUEST;
+/// nlmsg.finalize();
+/// writer.write_message(Direction::ToKernel, &nlmsg).unwrap();
+/// let capture = writer.into_inner();
+/// ```
+#[derive(Debug)]
+pub struct PcapWriter<W: Write> {
+    writer: W,
+}
+
+impl<W: Write> PcapWriter<W> {
+    /// Write the section header and the netlink interface description
+    pub fn new(mut writer: W) -> io::Result<Self> {
+        let mut shb = Vec::with_capacity(16);
+        shb.extend_from_slice(&PCAPNG_BYTE_ORDER_MAGIC.to_ne_bytes());
+        // Version 1.0
+        shb.extend_from_slice(&1u16.to_ne_bytes());
+        shb.extend_from_slice(&0u16.to_ne_bytes());
+        // Unspecified section length
+        shb.extend_from_slice(&u64::MAX.to_ne_bytes());
+        write_block(&mut writer, PCAPNG_SHB, &shb)?;
+
+        let mut idb = Vec::with_capacity(20);
+        idb.extend_from_slice(&LINKTYPE_NETLINK.to_ne_bytes());
+        idb.extend_from_slice(&0u16.to_ne_bytes());
+        // No snapshot length limit
+        idb.extend_from_slice(&0u32.to_ne_bytes());
+        idb.extend_from_slice(&PCAPNG_OPT_IF_TSRESOL.to_ne_bytes());
+        idb.extend_from_slice(&1u16.to_ne_bytes());
+        idb.extend_from_slice(&[9, 0, 0, 0]);
+        // End of options
+        idb.extend_from_slice(&[0; 4]);
+        write_block(&mut writer, PCAPNG_IDB, &idb)?;
+
+        Ok(Self { writer })
+    }
+
+    /// Record the serialized netlink messages of one datagram
+    ///
+    /// `timestamp` is the capture time since the UNIX epoch.
+    pub fn write_packet(
+        &mut self,
+        timestamp: Duration,
+        direction: Direction,
+        data: &[u8],
+    ) -> io::Result<()> {
+        let len = COOKED_HEADER_LEN + data.len();
+        let len32 = u32::try_from(len).map_err(|_| {
+            io::Error::new(io::ErrorKind::InvalidInput, "packet too large")
+        })?;
+        let ts = u64::try_from(timestamp.as_nanos()).unwrap_or(u64::MAX);
+
+        let mut epb = Vec::with_capacity(20 + len);
+        // Interface 0
+        epb.extend_from_slice(&0u32.to_ne_bytes());
+        epb.extend_from_slice(&((ts >> 32) as u32).to_ne_bytes());
+        epb.extend_from_slice(&(ts as u32).to_ne_bytes());
+        epb.extend_from_slice(&len32.to_ne_bytes());
+        epb.extend_from_slice(&len32.to_ne_bytes());
+        // The pseudo-header is in network byte order
+        epb.extend_from_slice(&u16::from(direction).to_be_bytes());
+        epb.extend_from_slice(&NETLINK_HATYPE.to_be_bytes());
+        epb.extend_from_slice(&[0; 10]);
+        epb.extend_from_slice(&NETLINK_GENERIC.to_be_bytes());
+        epb.extend_from_slice(data);
+        write_block(&mut self.writer, PCAPNG_EPB, &epb)
+    }
+
+    /// Serialize and record a netlink message, timestamped with the current
+    /// time
+    ///
+    /// Any payload implementing [`NetlinkSerializable`] can be recorded, e.g.
+    /// a `GenlMessage<F>` or an
+    /// [`AnyGenlMessage`](crate::registry::AnyGenlMessage). The message
+    /// should be finalized.
+    pub fn write_message<T>(
+        &mut self,
+        direction: Direction,
+        message: &NetlinkMessage<T>,
+    ) -> io::Result<()>
+    where
+        T: NetlinkSerializable + Debug,
+    {
+        let mut buf = vec![0; message.buffer_len()];
+        message.serialize(&mut buf);
+        self.write_datagram(direction, &buf)
+    }
+
+    /// Record already serialized netlink messages, timestamped with the
+    /// current time
+    pub fn write_datagram(
+        &mut self,
+        direction: Direction,
+        data: &[u8],
+    ) -> io::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        self.write_packet(timestamp, direction, data)
+    }
+
+    /// Flush the underlying writer
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.writer.flush()
+    }
+
+    /// Return the underlying writer
+    pub fn get_ref(&self) -> &W {
+        &self.writer
+    }
+
+    /// Unwrap the underlying writer
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+}
+
+// Write a block in host byte order, padding the body to 32 bits
+fn write_block<W: Write>(
+    writer: &mut W,
+    block_type: u32,
+    body: &[u8],
+) -> io::Result<()> {
+    let padding = (4 - body.len() % 4) % 4;
+    let len = u32::try_from(12 + body.len() + padding).map_err(|_| {
+        io::Error::new(io::ErrorKind::InvalidInput, "block too large")
+    })?;
+    writer.write_all(&block_type.to_ne_bytes())?;
+    writer.write_all(&len.to_ne_bytes())?;
+    writer.write_all(body)?;
+    writer.write_all(&[0; 3][..padding])?;
+    writer.write_all(&len.to_ne_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        constants::GENL_ID_CTRL,
+        ctrl::{nlas::GenlCtrlAttrs, GenlCtrl, GenlCtrlCmd},
+        pcap::PcapReader,
+        registry::{AnyGenlMessage, GenlRegistry},
+        GenlMessage,
+    };
+    use netlink_packet_core::{NetlinkPayload, NLM_F_REQUEST};
+
+    #[test]
+    fn write_and_read_back() {
+        let mut genlmsg = GenlMessage::from_payload(GenlCtrl {
+            cmd: GenlCtrlCmd::GetFamily,
+            nlas: vec![GenlCtrlAttrs::FamilyName("devlink".to_owned())],
+        });
+        genlmsg.set_resolved_family_id(GENL_ID_CTRL);
+        let mut nlmsg = NetlinkMessage::from(genlmsg);
+        nlmsg.header.flags = NLM_F_REQUEST;
+        nlmsg.header.sequence_number = 42;
+        nlmsg.finalize();
+
+        let mut writer = PcapWriter::new(Vec::new()).unwrap();
+        writer.write_message(Direction::ToKernel, &nlmsg).unwrap();
+        let mut buf = vec![0; nlmsg.buffer_len()];
+        nlmsg.serialize(&mut buf);
+        writer
+            .write_packet(
+                Duration::new(1_600_000_000, 123_456_789),
+                Direction::ToUser,
+                &buf,
+            )
+            .unwrap();
+        // pcapng blocks are 32-bit aligned
+        assert_eq!(0, writer.get_ref().len() % 4);
+
+        let messages =
+            PcapReader::new(&writer.into_inner()[..], GenlRegistry::new())
+                .unwrap()
+                .collect::<Result<Vec<_>, _>>()
+                .unwrap();
+        assert_eq!(2, messages.len());
+        assert_eq!(Direction::ToKernel, messages[0].direction);
+        assert!(messages[0].timestamp > Duration::ZERO);
+        assert_eq!(Direction::ToUser, messages[1].direction);
+        assert_eq!(
+            Duration::new(1_600_000_000, 123_456_789),
+            messages[1].timestamp
+        );
+        for msg in messages {
+            assert_eq!(nlmsg.header, msg.message.header);
+            match msg.message.payload {
+                NetlinkPayload::InnerMessage(AnyGenlMessage::Ctrl(genlmsg)) => {
+                    assert_eq!(
+                        vec![GenlCtrlAttrs::FamilyName("devlink".to_owned())],
+                        genlmsg.payload.nlas
+                    );
+                }
+                payload => panic!("Unexpected payload: {:?}", payload),
+            }
+        }
+    }
+}
@@ -0,0 +1,686 @@
+// SPDX-License-Identifier: MIT
+
+//! Catch-all payload for the generic families this crate does not model
+//!
+//! [`GenlRaw`] keeps the command, the version and the attributes of any
+//! generic netlink message as a tree of [`RawNla`]. Since the attributes
+//! carry no type information on the wire, the tree is built with a
+//! heuristic, see [`RawNla`].
+//!
+//! When the policy of the family is known, which can be dumped from the
+//! kernel with [`GenlCtrlCmd::GetPolicy`](crate::ctrl::GenlCtrlCmd::GetPolicy),
+//! [`FamilyPolicy`] decodes the attributes into typed values.
+
+use crate::{
+    ctrl::{
+        nlas::{GenlCtrlAttrs, NlPolicyTypeAttrs, NlaType, OppolicyIndexAttr},
+        GenlCtrl,
+    },
+    traits::GenlFamily,
+    GenlHeader,
+};
+use anyhow::Context;
+#[cfg(feature = "arbitrary")]
+use arbitrary::Arbitrary;
+use byteorder::{ByteOrder, NativeEndian};
+use netlink_packet_utils::{
+    nla::{
+        DefaultNla, Nla, NlaBuffer, NlasIterator, NLA_F_NESTED,
+        NLA_F_NET_BYTEORDER, NLA_TYPE_MASK,
+    },
+    parsers::*,
+    traits::*,
+    DecodeError,
+};
+use std::collections::HashMap;
+#[cfg(feature = "arbitrary")]
+use std::convert::TryFrom;
+
+// Nested attributes deeper than this are kept as opaque values
+const MAX_NESTING_DEPTH: usize = 32;
+
+/// Message of an arbitrary generic netlink family
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GenlRaw {
+    /// Family ID used to serialize the message
+    ///
+    /// This is left to 0 when the message is parsed, the ID found in the
+    /// netlink header is kept by
+    /// [`GenlMessage::resolved_family_id()`](crate::GenlMessage::resolved_family_id).
+    pub family_id: u16,
+    pub cmd: u8,
+    pub version: u8,
+    pub nlas: Vec<RawNla>,
+}
+
+impl GenlFamily for GenlRaw {
+    /// The family is not known at compile time, so the name is empty and
+    /// cannot be resolved. Set [`GenlRaw::family_id`] instead.
+    fn family_name() -> &'static str {
+        ""
+    }
+
+    fn family_id(&self) -> u16 {
+        self.family_id
+    }
+
+    fn command(&self) -> u8 {
+        self.cmd
+    }
+
+    fn version(&self) -> u8 {
+        self.version
+    }
+}
+
+impl Emitable for GenlRaw {
+    fn emit(&self, buffer: &mut [u8]) {
+        self.nlas.as_slice().emit(buffer)
+    }
+
+    fn buffer_len(&self) -> usize {
+        self.nlas.as_slice().buffer_len()
+    }
+}
+
+impl ParseableParametrized<[u8], GenlHeader> for GenlRaw {
+    fn parse_with_param(
+        buf: &[u8],
+        header: GenlHeader,
+    ) -> Result<Self, DecodeError> {
+        Ok(Self {
+            family_id: 0,
+            cmd: header.cmd,
+            version: header.version,
+            nlas: parse_rawnlas(buf, 0)
+                .context("failed to parse raw message attributes")?,
+        })
+    }
+}
+
+/// Attribute of a [`GenlRaw`] message
+///
+/// The value of an attribute is considered to be a list of nested
+/// attributes when the `NLA_F_NESTED` flag is set, or when it can be parsed
+/// as such a list which re-emits to the very same bytes and does not contain
+/// any attribute of type 0 (`*_UNSPEC`). Otherwise the attribute is kept
+/// as a [`DefaultNla`].
+///
+/// Both variants keep the `NLA_F_NESTED` and `NLA_F_NET_BYTEORDER` flags in
+/// their kind, so the message is emitted exactly as it was received. The
+/// only exception is an attribute carrying both flags: `netlink-packet-utils`
+/// clears `NLA_F_NET_BYTEORDER` when setting `NLA_F_NESTED`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(from = "RawNlaRepr", into = "RawNlaRepr")
+)]
+pub enum RawNla {
+    /// Attribute holding a list of attributes: kind and children
+    Nested(u16, Vec<RawNla>),
+    /// Attribute holding an opaque value
+    Value(DefaultNla),
+}
+
+impl RawNla {
+    /// Return the attribute type, without the flags
+    pub fn attr_type(&self) -> u16 {
+        self.kind() & NLA_TYPE_MASK
+    }
+
+    /// Return the nested attributes, if the value was recognized as such
+    pub fn nested(&self) -> Option<&[RawNla]> {
+        match self {
+            Self::Nested(_, nlas) => Some(nlas),
+            Self::Value(_) => None,
+        }
+    }
+
+    /// Return the bytes of the attribute value
+    pub fn value(&self) -> Vec<u8> {
+        let mut value = vec![0; self.value_len()];
+        self.emit_value(&mut value);
+        value
+    }
+}
+
+impl Nla for RawNla {
+    fn value_len(&self) -> usize {
+        match self {
+            Self::Nested(_, nlas) => nlas.as_slice().buffer_len(),
+            Self::Value(nla) => nla.value_len(),
+        }
+    }
+
+    fn kind(&self) -> u16 {
+        match self {
+            Self::Nested(kind, _) => *kind,
+            Self::Value(nla) => nla.kind(),
+        }
+    }
+
+    fn emit_value(&self, buffer: &mut [u8]) {
+        match self {
+            Self::Nested(_, nlas) => nlas.as_slice().emit(buffer),
+            Self::Value(nla) => nla.emit_value(buffer),
+        }
+    }
+    fn is_nested(&self) -> bool {
+        self.kind() & NLA_F_NESTED != 0
+    }
+
+    fn is_network_byteorder(&self) -> bool {
+        self.kind() & NLA_F_NET_BYTEORDER != 0
+    }
+}
+
+// DefaultNla does not implement serde, so its kind and value are serialized
+// as separate fields
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+enum RawNlaRepr {
+    Nested(u16, Vec<RawNla>),
+    Value { kind: u16, value: Vec<u8> },
+}
+
+#[cfg(feature = "serde")]
+impl From<RawNlaRepr> for RawNla {
+    fn from(repr: RawNlaRepr) -> Self {
+        match repr {
+            RawNlaRepr::Nested(kind, nlas) => Self::Nested(kind, nlas),
+            RawNlaRepr::Value { kind, value } => {
+                Self::Value(DefaultNla::new(kind, value))
+            }
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl From<RawNla> for RawNlaRepr {
+    fn from(nla: RawNla) -> Self {
+        match nla {
+            RawNla::Nested(kind, nlas) => Self::Nested(kind, nlas),
+            RawNla::Value(_) => Self::Value {
+                kind: nla.kind(),
+                value: nla.value(),
+            },
+        }
+    }
+}
+
+// Generated attributes are in the form the parser produces, so that they
+// survive an emit and parse round trip
+#[cfg(feature = "arbitrary")]
+impl<'a> arbitrary::Arbitrary<'a> for RawNla {
+    fn arbitrary(
+        u: &mut arbitrary::Unstructured<'a>,
+    ) -> arbitrary::Result<Self> {
+        let kind = u16::arbitrary(u)?;
+        let value = if u.ratio(1, 4)? {
+            let nlas = Vec::<RawNla>::arbitrary(u)?;
+            let mut value = vec![0; nlas.as_slice().buffer_len()];
+            nlas.as_slice().emit(&mut value);
+            value
+        } else {
+            Vec::<u8>::arbitrary(u)?
+        };
+        raw_nla_from_parts(kind, &value)
+    }
+}
+
+// Parse the attribute of the given kind, flags included, and value
+#[cfg(feature = "arbitrary")]
+pub(crate) fn raw_nla_from_parts(
+    mut kind: u16,
+    value: &[u8],
+) -> arbitrary::Result<RawNla> {
+    // netlink-packet-utils cannot emit both flags
+    if kind & NLA_F_NESTED != 0 {
+        kind &= !NLA_F_NET_BYTEORDER;
+    }
+    let len = u16::try_from(4 + value.len())
+        .map_err(|_| arbitrary::Error::IncorrectFormat)?;
+    let mut buf = vec![0; (usize::from(len) + 3) & !3];
+    NativeEndian::write_u16(&mut buf[0..2], len);
+    NativeEndian::write_u16(&mut buf[2..4], kind);
+    buf[4..usize::from(len)].copy_from_slice(value);
+    NlaBuffer::new_checked(&buf[..])
+        .and_then(|nla| RawNla::parse(&nla))
+        .map_err(|_| arbitrary::Error::IncorrectFormat)
+}
+
+/// Generate an attribute type, without the flags
+#[cfg(feature = "arbitrary")]
+pub(crate) fn arbitrary_nla_type(
+    u: &mut arbitrary::Unstructured,
+) -> arbitrary::Result<u16> {
+    Ok(u16::arbitrary(u)? & NLA_TYPE_MASK)
+}
+
+impl<'a, T: AsRef<[u8]> + ?Sized> Parseable<NlaBuffer<&'a T>> for RawNla {
+    fn parse(buf: &NlaBuffer<&'a T>) -> Result<Self, DecodeError> {
+        parse_rawnla(buf, 0)
+    }
+}
+
+fn parse_rawnla<T: AsRef<[u8]> + ?Sized>(
+    buf: &NlaBuffer<&T>,
+    depth: usize,
+) -> Result<RawNla, DecodeError> {
+    let nla = DefaultNla::parse(buf)?;
+    let payload = buf.value();
+    if depth < MAX_NESTING_DEPTH && payload.len() >= 4 {
+        if let Ok(nlas) = parse_rawnlas(payload, depth + 1) {
+            let mut emitted = vec![0; nlas.as_slice().buffer_len()];
+            nlas.as_slice().emit(&mut emitted);
+            if emitted == payload
+                && (buf.nested_flag()
+                    || nlas.iter().all(|nla| nla.attr_type() != 0))
+            {
+                return Ok(RawNla::Nested(nla.kind(), nlas));
+            }
+        }
+    }
+    Ok(RawNla::Value(nla))
+}
+
+fn parse_rawnlas(buf: &[u8], depth: usize) -> Result<Vec<RawNla>, DecodeError> {
+    NlasIterator::new(buf)
+        .map(|nla| nla.and_then(|nla| parse_rawnla(&nla, depth)))
+        .collect()
+}
+
+/// Attribute policy of a generic family, as dumped by the controller
+///
+/// The policy is built from the replies of a
+/// [`GenlCtrlCmd::GetPolicy`](crate::ctrl::GenlCtrlCmd::GetPolicy) dump.
+/// Each policy is identified by its index; index 0 is the top-level policy
+/// of the family, the other ones describe nested attributes.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct FamilyPolicy {
+    family_id: u16,
+    do_policies: HashMap<u8, u32>,
+    dump_policies: HashMap<u8, u32>,
+    policies: HashMap<u32, HashMap<u16, Vec<NlPolicyTypeAttrs>>>,
+}
+
+impl FamilyPolicy {
+    /// Build the policy from the replies of a `CTRL_CMD_GETPOLICY` dump
+    pub fn from_replies<'a, I>(replies: I) -> Self
+    where
+        I: IntoIterator<Item = &'a GenlCtrl>,
+    {
+        let mut policy = Self::default();
+        for nla in replies.into_iter().flat_map(|reply| reply.nlas.iter()) {
+            match nla {
+                GenlCtrlAttrs::FamilyId(id) => policy.family_id = *id,
+                GenlCtrlAttrs::Policy(nla) => {
+                    policy
+                        .policies
+                        .entry(nla.index as u32)
+                        .or_default()
+                        .insert(
+                            nla.attr_policy.index,
+                            nla.attr_policy.policies.clone(),
+                        );
+                }
+                GenlCtrlAttrs::OpPolicy(nla) => {
+                    for idx in nla.policy_idx.iter() {
+                        match idx {
+                            OppolicyIndexAttr::Do(idx) => {
+                                policy.do_policies.insert(nla.cmd, *idx)
+                            }
+                            OppolicyIndexAttr::Dump(idx) => {
+                                policy.dump_policies.insert(nla.cmd, *idx)
+                            }
+                        };
+                    }
+                }
+                _ => {}
+            }
+        }
+        policy
+    }
+
+    /// Return the ID of the family
+    pub fn family_id(&self) -> u16 {
+        self.family_id
+    }
+
+    /// Return the index of the policy applied to the `cmd` requests
+    pub fn do_policy(&self, cmd: u8) -> Option<u32> {
+        self.do_policies.get(&cmd).copied()
+    }
+
+    /// Return the index of the policy applied to the `cmd` dump requests
+    pub fn dump_policy(&self, cmd: u8) -> Option<u32> {
+        self.dump_policies.get(&cmd).copied()
+    }
+
+    /// Return the policy of the attribute `attr_type` in the policy
+    /// `policy_idx`
+    pub fn attribute(
+        &self,
+        policy_idx: u32,
+        attr_type: u16,
+    ) -> Option<&[NlPolicyTypeAttrs]> {
+        self.policies
+            .get(&policy_idx)?
+            .get(&attr_type)
+            .map(Vec::as_slice)
+    }
+
+    /// Decode the attributes of a message with the policy of its command
+    ///
+    /// The do policy of the command is used if any, then its dump policy,
+    /// then the top-level policy of the family.
+    pub fn decode(&self, msg: &GenlRaw) -> Result<Vec<PolicyNla>, DecodeError> {
+        let policy_idx = self
+            .do_policy(msg.cmd)
+            .or_else(|| self.dump_policy(msg.cmd))
+            .unwrap_or(0);
+        self.decode_nlas(policy_idx, &msg.nlas)
+    }
+
+    /// Decode attributes with the policy `policy_idx`
+    ///
+    /// Attributes which are not described by the policy are decoded as
+    /// [`PolicyValue::Unknown`].
+    pub fn decode_nlas(
+        &self,
+        policy_idx: u32,
+        nlas: &[RawNla],
+    ) -> Result<Vec<PolicyNla>, DecodeError> {
+        nlas.iter()
+            .map(|nla| {
+                let kind = nla.attr_type();
+                let value = nla.value();
+                let value = match self.attribute(policy_idx, kind) {
+                    Some(policy) => self.decode_value(policy, &value)?,
+                    None => PolicyValue::Unknown(value),
+                };
+                Ok(PolicyNla { kind, value })
+            })
+            .collect()
+    }
+
+    fn decode_value(
+        &self,
+        policy: &[NlPolicyTypeAttrs],
+        payload: &[u8],
+    ) -> Result<PolicyValue, DecodeError> {
+        let nla_type = policy.iter().find_map(|attr| match attr {
+            NlPolicyTypeAttrs::Type(t) => Some(*t),
+            _ => None,
+        });
+        let nested_idx = policy.iter().find_map(|attr| match attr {
+            NlPolicyTypeAttrs::PolicyIdx(idx) => Some(*idx),
+            _ => None,
+        });
+        let nla_type = match nla_type {
+            Some(nla_type) => nla_type,
+            None => return Ok(PolicyValue::Unknown(payload.to_vec())),
+        };
+
+        Ok(match nla_type {
+            NlaType::Flag => PolicyValue::Flag,
+            NlaType::U8 => {
+                PolicyValue::U8(parse_u8(payload).context("invalid u8 value")?)
+            }
+            NlaType::U16 => PolicyValue::U16(
+                parse_u16(payload).context("invalid u16 value")?,
+            ),
+            NlaType::U32 => PolicyValue::U32(
+                parse_u32(payload).context("invalid u32 value")?,
+            ),
+            NlaType::U64 => PolicyValue::U64(
+                parse_u64(payload).context("invalid u64 value")?,
+            ),
+            NlaType::S8 => PolicyValue::S8(
+                parse_u8(payload).context("invalid s8 value")? as i8,
+            ),
+            NlaType::S16 => PolicyValue::S16(
+                parse_u16(payload).context("invalid s16 value")? as i16,
+            ),
+            NlaType::S32 => PolicyValue::S32(
+                parse_i32(payload).context("invalid s32 value")?,
+            ),
+            NlaType::S64 => PolicyValue::S64(
+                parse_u64(payload).context("invalid s64 value")? as i64,
+            ),
+            NlaType::UInt => PolicyValue::UInt(
+                parse_uint(payload).context("invalid uint value")?,
+            ),
+            NlaType::SInt => PolicyValue::SInt(
+                parse_sint(payload).context("invalid sint value")?,
+            ),
+            NlaType::Binary => PolicyValue::Binary(payload.to_vec()),
+            NlaType::String | NlaType::NulString => PolicyValue::String(
+                parse_string(payload).context("invalid string value")?,
+            ),
+            NlaType::Bitfield32 => {
+                if payload.len() != 8 {
+                    return Err(format!(
+                        "invalid bitfield32 value: {payload:?}"
+                    )
+                    .into());
+                }
+                PolicyValue::Bitfield32 {
+                    value: NativeEndian::read_u32(&payload[..4]),
+                    selector: NativeEndian::read_u32(&payload[4..]),
+                }
+            }
+            NlaType::Nested => match nested_idx {
+                Some(idx) => PolicyValue::Nested(
+                    self.decode_nlas(idx, &parse_rawnlas(payload, 0)?)?,
+                ),
+                None => PolicyValue::Unknown(payload.to_vec()),
+            },
+            NlaType::NestedArray => match nested_idx {
+                Some(idx) => PolicyValue::NestedArray(
+                    NlasIterator::new(payload)
+                        .map(|nla| {
+                            let nla = nla?;
+                            Ok(PolicyNla {
+                                kind: nla.kind(),
+                                value: PolicyValue::Nested(self.decode_nlas(
+                                    idx,
+                                    &parse_rawnlas(nla.value(), 0)?,
+                                )?),
+                            })
+                        })
+                        .collect::<Result<Vec<_>, DecodeError>>()
+                        .context("invalid nested array value")?,
+                ),
+                None => PolicyValue::Unknown(payload.to_vec()),
+            },
+        })
+    }
+}
+
+fn parse_uint(payload: &[u8]) -> Result<u64, DecodeError> {
+    match payload.len() {
+        4 => Ok(parse_u32(payload)? as u64),
+        _ => parse_u64(payload),
+    }
+}
+
+fn parse_sint(payload: &[u8]) -> Result<i64, DecodeError> {
+    match payload.len() {
+        4 => Ok(parse_i32(payload)? as i64),
+        _ => Ok(parse_u64(payload)? as i64),
+    }
+}
+
+/// Attribute decoded by a [`FamilyPolicy`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PolicyNla {
+    /// Attribute type, without the flags
+    pub kind: u16,
+    pub value: PolicyValue,
+}
+
+/// Typed value of a [`PolicyNla`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PolicyValue {
+    Flag,
+    U8(u8),
+    U16(u16),
+    U32(u32),
+    U64(u64),
+    S8(i8),
+    S16(i16),
+    S32(i32),
+    S64(i64),
+    UInt(u64),
+    SInt(i64),
+    Binary(Vec<u8>),
+    String(String),
+    Nested(Vec<PolicyNla>),
+    /// Nested attributes indexed by the kind of each item
+    NestedArray(Vec<PolicyNla>),
+    Bitfield32 {
+        value: u32,
+        selector: u32,
+    },
+    /// Value of an attribute the policy does not describe
+    Unknown(Vec<u8>),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ctrl::{
+            nlas::{AttributePolicyAttr, OppolicyAttr, PolicyAttr},
+            GenlCtrlCmd,
+        },
+        GenlBuffer, GenlMessage,
+    };
+
+    // CTRL_CMD_NEWMCAST_GRP from nlctrl: family name and one multicast group
+    #[rustfmt::skip]
+    static NEWMCAST: [u8; 48] = [
+        0x07, 0x02, 0x00, 0x00, // cmd, version
+        0x0c, 0x00, 0x02, 0x00, // CTRL_ATTR_FAMILY_NAME
+        0x74, 0x65, 0x73, 0x74, 0x66, 0x61, 0x6d, 0x00, // "testfam"
+        0x18, 0x00, 0x07, 0x00, // CTRL_ATTR_MCAST_GROUPS
+        0x14, 0x00, 0x01, 0x00, // group #1
+        0x08, 0x00, 0x02, 0x00, 0x05, 0x00, 0x00, 0x00, // ID 5
+        0x08, 0x00, 0x01, 0x00, 0x65, 0x76, 0x74, 0x00, // NAME "evt"
+        0x08, 0x00, 0x01, 0x00, 0x04, 0x00, 0x00, 0x00, // FAMILY_ID 4
+    ];
+
+    fn parse_newmcast() -> GenlMessage<GenlRaw> {
+        let buffer = GenlBuffer::new_checked(&NEWMCAST[..]).unwrap();
+        GenlMessage::parse_with_param(&buffer, 0x10).unwrap()
+    }
+
+    #[test]
+    fn parse_raw() {
+        let msg = parse_newmcast();
+        assert_eq!(u8::from(GenlCtrlCmd::NewMcastGrp), msg.payload.cmd);
+        assert_eq!(0x10, msg.resolved_family_id());
+
+        let nlas = &msg.payload.nlas;
+        assert_eq!(3, nlas.len());
+        assert_eq!(None, nlas[0].nested());
+        assert_eq!(b"testfam\0".to_vec(), nlas[0].value());
+        // The value of the multicast groups attribute looks like a list of
+        // attributes, so it is parsed as such
+        let groups = nlas[1].nested().unwrap();
+        assert_eq!(1, groups[0].attr_type());
+        assert_eq!(2, groups[0].nested().unwrap().len());
+        // 0x00000004 would also parse as an attribute of type 0
+        assert_eq!(None, nlas[2].nested());
+    }
+
+    #[test]
+    fn emit_raw() {
+        let msg = parse_newmcast();
+        let mut buf = vec![0; msg.buffer_len()];
+        msg.emit(&mut buf);
+        assert_eq!(&NEWMCAST[..], &buf[..]);
+    }
+
+    #[test]
+    fn decode_with_policy() {
+        let nla_policy = |attr: u16, policies| GenlCtrl {
+            cmd: GenlCtrlCmd::GetPolicy,
+            nlas: vec![
+                GenlCtrlAttrs::FamilyId(0x10),
+                GenlCtrlAttrs::Policy(PolicyAttr {
+                    index: if attr > 7 { 1 } else { 0 },
+                    attr_policy: AttributePolicyAttr {
+                        index: attr & 7,
+                        policies,
+                    },
+                }),
+            ],
+        };
+        let replies = vec![
+            GenlCtrl {
+                cmd: GenlCtrlCmd::GetPolicy,
+                nlas: vec![GenlCtrlAttrs::OpPolicy(OppolicyAttr {
+                    cmd: 7,
+                    policy_idx: vec![OppolicyIndexAttr::Do(0)],
+                })],
+            },
+            nla_policy(
+                2,
+                vec![
+                    NlPolicyTypeAttrs::Type(NlaType::NulString),
+                    NlPolicyTypeAttrs::MaxLength(16),
+                ],
+            ),
+            nla_policy(
+                7,
+                vec![
+                    NlPolicyTypeAttrs::Type(NlaType::NestedArray),
+                    NlPolicyTypeAttrs::PolicyIdx(1),
+                ],
+            ),
+            nla_policy(8 | 1, vec![NlPolicyTypeAttrs::Type(NlaType::String)]),
+            nla_policy(8 | 2, vec![NlPolicyTypeAttrs::Type(NlaType::U32)]),
+        ];
+        let policy = FamilyPolicy::from_replies(&replies);
+        assert_eq!(0x10, policy.family_id());
+        assert_eq!(Some(0), policy.do_policy(7));
+        assert_eq!(None, policy.dump_policy(7));
+
+        let decoded = policy.decode(&parse_newmcast().payload).unwrap();
+        assert_eq!(
+            vec![
+                PolicyNla {
+                    kind: 2,
+                    value: PolicyValue::String("testfam".to_owned()),
+                },
+                PolicyNla {
+                    kind: 7,
+                    value: PolicyValue::NestedArray(vec![PolicyNla {
+                        kind: 1,
+                        value: PolicyValue::Nested(vec![
+                            PolicyNla {
+                                kind: 2,
+                                value: PolicyValue::U32(5),
+                            },
+                            PolicyNla {
+                                kind: 1,
+                                value: PolicyValue::String("evt".to_owned()),
+                            },
+                        ]),
+                    }]),
+                },
+                PolicyNla {
+                    kind: 1,
+                    value: PolicyValue::Unknown(vec![4, 0, 0, 0]),
+                },
+            ],
+            decoded
+        );
+    }
+}
@@ -0,0 +1,315 @@
+// SPDX-License-Identifier: MIT
+
+//! Decode messages of several generic families received on one socket
+//!
+//! The family of a generic netlink message is only known from the
+//! `message_type` field of its netlink header, which holds the family ID
+//! dynamically assigned by the kernel. [`GenlRegistry`] maps the resolved
+//! family IDs to their payload types, so the messages can be decoded into
+//! [`AnyGenlMessage`] without knowing their family beforehand.
+//!
+//! ```
+//! use netlink_packet_generic::{
+//!     devlink::GenlDevlink,
+//!     registry::GenlRegistry,
+//! };
+//!
+//! let mut registry = GenlRegistry::new();
+//! // The ID is usually resolved with `CTRL_CMD_GETFAMILY`
+//! registry.register::<GenlDevlink>(0x15);
+//! assert_eq!(Some(0x15), registry.family_id("devlink"));
+//! ```
+
+use crate::{
+    buffer::GenlBuffer, constants::GENL_ID_CTRL, ctrl::GenlCtrl,
+    devlink::GenlDevlink, header::GenlHeader, message::GenlMessage,
+    raw::GenlRaw, traits::GenlFamily,
+};
+use netlink_packet_core::{
+    NetlinkBuffer, NetlinkDeserializable, NetlinkHeader, NetlinkMessage,
+    NetlinkPayload, NetlinkSerializable,
+};
+use netlink_packet_utils::{DecodeError, Parseable, ParseableParametrized};
+use std::{collections::HashMap, fmt::Debug};
+
+// Message types below this value are reserved for the netlink control
+// messages
+const NLMSG_MIN_TYPE: u16 = 0x10;
+
+/// Message of any generic family
+///
+/// The families which are not registered are decoded as [`GenlRaw`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum AnyGenlMessage {
+    Ctrl(GenlMessage<GenlCtrl>),
+    Devlink(GenlMessage<GenlDevlink>),
+    Raw(GenlMessage<GenlRaw>),
+}
+
+impl AnyGenlMessage {
+    /// Return the family ID of the message
+    pub fn family_id(&self) -> u16 {
+        match self {
+            Self::Ctrl(msg) => msg.family_id(),
+            Self::Devlink(msg) => msg.family_id(),
+            Self::Raw(msg) => msg.family_id(),
+        }
+    }
+
+    /// Return the generic netlink header of the message
+    pub fn header(&self) -> &GenlHeader {
+        match self {
+            Self::Ctrl(msg) => &msg.header,
+            Self::Devlink(msg) => &msg.header,
+            Self::Raw(msg) => &msg.header,
+        }
+    }
+}
+
+impl From<GenlMessage<GenlCtrl>> for AnyGenlMessage {
+    fn from(msg: GenlMessage<GenlCtrl>) -> Self {
+        Self::Ctrl(msg)
+    }
+}
+
+impl From<GenlMessage<GenlDevlink>> for AnyGenlMessage {
+    fn from(msg: GenlMessage<GenlDevlink>) -> Self {
+        Self::Devlink(msg)
+    }
+}
+
+impl From<GenlMessage<GenlRaw>> for AnyGenlMessage {
+    fn from(msg: GenlMessage<GenlRaw>) -> Self {
+        Self::Raw(msg)
+    }
+}
+
+impl NetlinkSerializable for AnyGenlMessage {
+    fn message_type(&self) -> u16 {
+        self.family_id()
+    }
+
+    fn buffer_len(&self) -> usize {
+        match self {
+            Self::Ctrl(msg) => NetlinkSerializable::buffer_len(msg),
+            Self::Devlink(msg) => NetlinkSerializable::buffer_len(msg),
+            Self::Raw(msg) => NetlinkSerializable::buffer_len(msg),
+        }
+    }
+
+    fn serialize(&self, buffer: &mut [u8]) {
+        match self {
+            Self::Ctrl(msg) => msg.serialize(buffer),
+            Self::Devlink(msg) => msg.serialize(buffer),
+            Self::Raw(msg) => msg.serialize(buffer),
+        }
+    }
+}
+
+/// Decode with the statically known families only, i.e. the controller
+///
+/// Use [`GenlRegistry::deserialize()`] to decode the families with a
+/// dynamic ID.
+impl NetlinkDeserializable for AnyGenlMessage {
+    type Error = DecodeError;
+    fn deserialize(
+        header: &NetlinkHeader,
+        payload: &[u8],
+    ) -> Result<Self, Self::Error> {
+        GenlRegistry::new().decode(header, payload)
+    }
+}
+
+impl From<AnyGenlMessage> for NetlinkPayload<AnyGenlMessage> {
+    fn from(message: AnyGenlMessage) -> Self {
+        NetlinkPayload::InnerMessage(message)
+    }
+}
+
+type Decoder = fn(&[u8], u16) -> Result<AnyGenlMessage, DecodeError>;
+
+fn decode_as<F>(
+    buf: &[u8],
+    family_id: u16,
+) -> Result<AnyGenlMessage, DecodeError>
+where
+    F: ParseableParametrized<[u8], GenlHeader> + Debug,
+    AnyGenlMessage: From<GenlMessage<F>>,
+{
+    let buffer = GenlBuffer::new_checked(buf)?;
+    GenlMessage::<F>::parse_with_param(&buffer, family_id).map(Into::into)
+}
+
+/// Map the resolved family IDs to their payload types
+#[derive(Clone, Debug)]
+pub struct GenlRegistry {
+    families: HashMap<u16, (&'static str, Decoder)>,
+}
+
+impl GenlRegistry {
+    /// Create a registry where only the controller (`nlctrl`) is registered
+    pub fn new() -> Self {
+        let mut registry = Self {
+            families: HashMap::new(),
+        };
+        registry.register::<GenlCtrl>(GENL_ID_CTRL);
+        registry
+    }
+
+    /// Register the family `F` with its resolved ID
+    ///
+    /// A family previously registered with the same ID is replaced.
+    pub fn register<F>(&mut self, family_id: u16)
+    where
+        F: GenlFamily + ParseableParametrized<[u8], GenlHeader> + Debug,
+        AnyGenlMessage: From<GenlMessage<F>>,
+    {
+        self.families
+            .insert(family_id, (F::family_name(), decode_as::<F>));
+    }
+
+    /// Remove the family registered with the given ID, e.g. when the
+    /// controller notifies it was unregistered from the kernel
+    pub fn unregister(&mut self, family_id: u16) {
+        self.families.remove(&family_id);
+    }
+
+    /// Return the ID the family `family_name` is registered with
+    pub fn family_id(&self, family_name: &str) -> Option<u16> {
+        self.families
+            .iter()
+            .find(|(_, (name, _))| *name == family_name)
+            .map(|(id, _)| *id)
+    }
+
+    /// Return the name of the family registered with the given ID
+    pub fn family_name(&self, family_id: u16) -> Option<&'static str> {
+        self.families.get(&family_id).map(|(name, _)| *name)
+    }
+
+    /// Decode the payload of a netlink message according to its
+    /// `message_type`
+    ///
+    /// The messages of unregistered families are decoded as [`GenlRaw`].
+    pub fn decode(
+        &self,
+        header: &NetlinkHeader,
+        payload: &[u8],
+    ) -> Result<AnyGenlMessage, DecodeError> {
+        let decode = self
+            .families
+            .get(&header.message_type)
+            .map_or(decode_as::<GenlRaw> as Decoder, |(_, decode)| *decode);
+        decode(payload, header.message_type)
+    }
+
+    /// Deserialize a netlink message, decoding its payload with the
+    /// registered families
+    pub fn deserialize(
+        &self,
+        buffer: &[u8],
+    ) -> Result<NetlinkMessage<AnyGenlMessage>, DecodeError> {
+        let buf = NetlinkBuffer::new_checked(buffer)?;
+        let header = NetlinkHeader::parse(&buf)?;
+        if header.message_type < NLMSG_MIN_TYPE {
+            // NLMSG_ERROR, NLMSG_DONE, etc.
+            return NetlinkMessage::deserialize(buffer);
+        }
+        let payload = self.decode(&header, buf.payload())?;
+        Ok(NetlinkMessage::new(header, payload.into()))
+    }
+}
+
+impl Default for GenlRegistry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        constants::GENL_ID_DEVLINK,
+        ctrl::{nlas::GenlCtrlAttrs, GenlCtrlCmd},
+        devlink::{nldev::GenlDevlinkAttrs, GenlDevlinkCmd},
+    };
+    use netlink_packet_core::NLM_F_REQUEST;
+
+    fn serialize<F>(payload: F, family_id: u16) -> Vec<u8>
+    where
+        F: GenlFamily + netlink_packet_utils::Emitable + Debug,
+    {
+        let mut genlmsg = GenlMessage::from_payload(payload);
+        genlmsg.set_resolved_family_id(family_id);
+        let mut nlmsg = NetlinkMessage::from(genlmsg);
+        nlmsg.header.flags = NLM_F_REQUEST;
+        nlmsg.finalize();
+        let mut buf = vec![0; nlmsg.buffer_len()];
+        nlmsg.serialize(&mut buf);
+        buf
+    }
+
+    fn devlink_get() -> GenlDevlink {
+        GenlDevlink {
+            cmd: GenlDevlinkCmd::GetDevices,
+            nldev: vec![GenlDevlinkAttrs::BusName("pci".to_owned())],
+        }
+    }
+
+    #[test]
+    fn dispatch_by_family_id() {
+        let mut registry = GenlRegistry::new();
+        assert_eq!(Some("nlctrl"), registry.family_name(GENL_ID_CTRL));
+        registry.register::<GenlDevlink>(GENL_ID_DEVLINK);
+        assert_eq!(Some(GENL_ID_DEVLINK), registry.family_id("devlink"));
+
+        let buf = serialize(
+            GenlCtrl {
+                cmd: GenlCtrlCmd::GetFamily,
+                nlas: vec![GenlCtrlAttrs::FamilyName("devlink".to_owned())],
+            },
+            GENL_ID_CTRL,
+        );
+        let msg = registry.deserialize(&buf).unwrap();
+        assert!(matches!(
+            msg.payload,
+            NetlinkPayload::InnerMessage(AnyGenlMessage::Ctrl(_))
+        ));
+
+        let buf = serialize(devlink_get(), GENL_ID_DEVLINK);
+        match registry.deserialize(&buf).unwrap().payload {
+            NetlinkPayload::InnerMessage(AnyGenlMessage::Devlink(msg)) => {
+                assert_eq!(GENL_ID_DEVLINK, msg.family_id());
+                assert_eq!(devlink_get(), msg.payload);
+            }
+            payload => panic!("Unexpected payload: {:?}", payload),
+        }
+
+        // Re-serializing gives the same bytes
+        let mut msg = registry.deserialize(&buf).unwrap();
+        msg.finalize();
+        let mut emitted = vec![0; msg.buffer_len()];
+        msg.serialize(&mut emitted);
+        assert_eq!(buf, emitted);
+    }
+
+    #[test]
+    fn unknown_family_as_raw() {
+        let mut registry = GenlRegistry::new();
+        registry.register::<GenlDevlink>(GENL_ID_DEVLINK);
+        registry.unregister(GENL_ID_DEVLINK);
+        assert_eq!(None, registry.family_name(GENL_ID_DEVLINK));
+
+        let buf = serialize(devlink_get(), GENL_ID_DEVLINK);
+        match registry.deserialize(&buf).unwrap().payload {
+            NetlinkPayload::InnerMessage(AnyGenlMessage::Raw(msg)) => {
+                assert_eq!(GENL_ID_DEVLINK, msg.family_id());
+                assert_eq!(1, msg.payload.cmd);
+                assert_eq!(1, msg.payload.nlas.len());
+            }
+            payload => panic!("Unexpected payload: {:?}", payload),
+        }
+    }
+}
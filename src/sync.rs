@@ -0,0 +1,713 @@
+// SPDX-License-Identifier: MIT
+
+//! Blocking generic netlink socket
+//!
+//! This module provides [`GenlSocket`], a small synchronous client built on
+//! top of [`netlink_sys::Socket`]. It takes care of the netlink framing,
+//! sequence numbers, ACK handling and dynamic family ID resolution, so a
+//! consumer which does not use an async runtime can talk to a generic
+//! netlink family with a few lines of code.
+//!
+//! ```no_run
+//! use netlink_packet_generic::{
+//!     ctrl::{GenlCtrl, GenlCtrlCmd},
+//!     sync::GenlSocket,
+//! };
+//!
+//! let mut socket = GenlSocket::new().unwrap();
+//! let families = socket
+//!     .dump(GenlCtrl {
+//!         cmd: GenlCtrlCmd::GetFamily,
+//!         nlas: vec![],
+//!     })
+//!     .unwrap();
+//! println!("{} families registered", families.len());
+//! ```
+//!
+//! This module is only available with the `sync` feature.
+
+#[cfg(feature = "mock")]
+use crate::mock::MockSocket;
+use crate::{
+    ctrl::{nlas::GenlCtrlAttrs, GenlCtrl, GenlCtrlCmd, McastGroupResolver},
+    header::GenlHeader,
+    message::GenlMessage,
+    pcap::{Direction, PcapWriter},
+    raw::FamilyPolicy,
+    registry::{AnyGenlMessage, GenlRegistry},
+    traits::GenlFamily,
+};
+use netlink_packet_core::{
+    ErrorMessage, NetlinkBuffer, NetlinkMessage, NetlinkPayload, NLMSG_DONE,
+    NLMSG_ERROR, NLM_F_ACK, NLM_F_DUMP, NLM_F_REQUEST,
+};
+use netlink_packet_utils::{DecodeError, Emitable, ParseableParametrized};
+use netlink_sys::{protocols::NETLINK_GENERIC, Socket, SocketAddr};
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fmt::{self, Debug},
+    io::{self, Write},
+    marker::PhantomData,
+    os::unix::io::AsRawFd,
+    time::{Duration, Instant},
+};
+
+/// Error returned by [`GenlSocket`] operations
+#[derive(Debug)]
+pub enum GenlSocketError {
+    /// The underlying socket operation failed
+    Io(io::Error),
+    /// A reply from the kernel could not be deserialized
+    Decode(DecodeError),
+    /// The kernel answered the request with a netlink error
+    Netlink(ErrorMessage),
+    /// No reply was received within the configured timeout
+    Timeout,
+    /// The controller reply did not carry the expected attribute
+    MissingAttribute(&'static str),
+    /// The multicast group is not declared by the family or not registered
+    /// by the kernel
+    UnknownMulticastGroup(String),
+    /// The family has no name to resolve its ID with, e.g. a
+    /// [`GenlRaw`](crate::GenlRaw) whose `family_id` is left to 0
+    UnnamedFamily,
+}
+
+impl fmt::Display for GenlSocketError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io(e) => write!(f, "socket error: {e}"),
+            Self::Decode(e) => write!(f, "failed to decode reply: {e}"),
+            Self::Netlink(e) => write!(f, "netlink error: {e}"),
+            Self::Timeout => write!(f, "timed out waiting for reply"),
+            Self::MissingAttribute(name) => {
+                write!(f, "reply is missing the {name} attribute")
+            }
+            Self::UnknownMulticastGroup(name) => {
+                write!(f, "unknown multicast group {name}")
+            }
+            Self::UnnamedFamily => {
+                write!(f, "family ID is unset and family has no name")
+            }
+        }
+    }
+}
+
+impl std::error::Error for GenlSocketError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            Self::Decode(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for GenlSocketError {
+    fn from(e: io::Error) -> Self {
+        Self::Io(e)
+    }
+}
+
+impl From<DecodeError> for GenlSocketError {
+    fn from(e: DecodeError) -> Self {
+        Self::Decode(e)
+    }
+}
+
+/// Blocking generic netlink socket
+///
+/// Every request is tagged with its own sequence number. Replies carrying
+/// another sequence number (e.g. multicast notifications) are kept aside
+/// and never returned as part of a request. The replies of a request whose
+/// [`GenlReplies`] is dropped before its end are discarded, even when they
+/// arrive later.
+///
+/// The IDs of the families resolved through
+/// [`resolve_family_id()`](Self::resolve_family_id) are cached for the
+/// lifetime of the socket.
+///
+/// The traffic of the socket can be recorded into a pcapng capture with
+/// [`start_capture()`](Self::start_capture).
+pub struct GenlSocket {
+    transport: Transport,
+    sequence_number: u32,
+    timeout: Option<Duration>,
+    families: HashMap<String, McastGroupResolver>,
+    rx_queue: VecDeque<Vec<u8>>,
+    // Requests whose GenlReplies was dropped before their last reply
+    abandoned: HashSet<u32>,
+    capture: Option<PcapWriter<Box<dyn Write + Send>>>,
+}
+
+impl GenlSocket {
+    /// Open a `NETLINK_GENERIC` socket connected to the kernel
+    pub fn new() -> io::Result<Self> {
+        let mut socket = Socket::new(NETLINK_GENERIC)?;
+        socket.bind_auto()?;
+        socket.connect(&SocketAddr::new(0, 0))?;
+        Ok(Self::from_socket(socket))
+    }
+
+    /// Wrap an already bound and connected `NETLINK_GENERIC` socket
+    pub fn from_socket(socket: Socket) -> Self {
+        Self::from_transport(Transport::Kernel(socket))
+    }
+
+    /// Wrap a socket connected to a [`MockKernel`](crate::mock::MockKernel)
+    #[cfg(feature = "mock")]
+    pub fn from_mock(socket: MockSocket) -> Self {
+        Self::from_transport(Transport::Mock(socket))
+    }
+
+    fn from_transport(transport: Transport) -> Self {
+        Self {
+            transport,
+            sequence_number: 0,
+            timeout: None,
+            families: HashMap::new(),
+            rx_queue: VecDeque::new(),
+            abandoned: HashSet::new(),
+            capture: None,
+        }
+    }
+
+    /// Return the underlying socket, if connected to the kernel
+    pub fn socket(&self) -> Option<&Socket> {
+        match &self.transport {
+            Transport::Kernel(socket) => Some(socket),
+            #[cfg(feature = "mock")]
+            Transport::Mock(_) => None,
+        }
+    }
+
+    /// Return the timeout applied while waiting for each reply
+    pub fn timeout(&self) -> Option<Duration> {
+        self.timeout
+    }
+
+    /// Set the timeout applied while waiting for each reply
+    ///
+    /// `None` (the default) blocks until the kernel answers.
+    pub fn set_timeout(&mut self, timeout: Option<Duration>) {
+        self.timeout = timeout;
+    }
+
+    /// Record every message sent and received by the socket into a pcapng
+    /// capture written to `writer`
+    ///
+    /// A capture already in progress is replaced.
+    pub fn start_capture<W>(&mut self, writer: W) -> io::Result<()>
+    where
+        W: Write + Send + 'static,
+    {
+        let writer: Box<dyn Write + Send> = Box::new(writer);
+        self.capture = Some(PcapWriter::new(writer)?);
+        Ok(())
+    }
+
+    /// Stop recording the messages and return the capture writer, if any
+    pub fn stop_capture(
+        &mut self,
+    ) -> Option<PcapWriter<Box<dyn Write + Send>>> {
+        self.capture.take()
+    }
+
+    /// Resolve the dynamic family ID of `F` through the controller
+    ///
+    /// The result is cached, further calls do not hit the kernel. A family
+    /// without name, e.g. [`GenlRaw`](crate::GenlRaw), cannot be resolved.
+    pub fn resolve_family_id<F>(&mut self) -> Result<u16, GenlSocketError>
+    where
+        F: GenlFamily,
+    {
+        if F::family_name().is_empty() {
+            return Err(GenlSocketError::UnnamedFamily);
+        }
+        self.resolve_family_id_by_name(F::family_name())
+    }
+
+    /// Resolve the dynamic family ID of the given family name
+    pub fn resolve_family_id_by_name(
+        &mut self,
+        family_name: &str,
+    ) -> Result<u16, GenlSocketError> {
+        Ok(self.resolve_family(family_name)?.family_id())
+    }
+
+    /// Look up the family ID and the multicast groups of the given family
+    ///
+    /// The result is cached, further calls do not hit the kernel.
+    pub fn resolve_family(
+        &mut self,
+        family_name: &str,
+    ) -> Result<&McastGroupResolver, GenlSocketError> {
+        if !self.families.contains_key(family_name) {
+            let replies = self.request(GenlCtrl {
+                cmd: GenlCtrlCmd::GetFamily,
+                nlas: vec![GenlCtrlAttrs::FamilyName(family_name.to_owned())],
+            })?;
+            let resolver = replies
+                .iter()
+                .map(|msg| McastGroupResolver::from(&msg.payload))
+                .find(|resolver| resolver.family_id() != 0)
+                .ok_or(GenlSocketError::MissingAttribute(
+                    "CTRL_ATTR_FAMILY_ID",
+                ))?;
+            self.families.insert(family_name.to_owned(), resolver);
+        }
+        Ok(&self.families[family_name])
+    }
+
+    /// Dump the attribute policy of the given family
+    pub fn family_policy(
+        &mut self,
+        family_name: &str,
+    ) -> Result<FamilyPolicy, GenlSocketError> {
+        let replies = self.dump(GenlCtrl {
+            cmd: GenlCtrlCmd::GetPolicy,
+            nlas: vec![GenlCtrlAttrs::FamilyName(family_name.to_owned())],
+        })?;
+        Ok(FamilyPolicy::from_replies(
+            replies.iter().map(|msg| &msg.payload),
+        ))
+    }
+
+    /// Join the multicast group `group` of the family `F`
+    ///
+    /// The group must be declared by [`GenlFamily::multicast_groups()`].
+    /// Returns the numeric ID of the group.
+    pub fn subscribe<F>(&mut self, group: &str) -> Result<u32, GenlSocketError>
+    where
+        F: GenlFamily,
+    {
+        let id = self.multicast_group_id::<F>(group)?;
+        self.transport.add_membership(id)?;
+        Ok(id)
+    }
+
+    /// Leave the multicast group `group` of the family `F`
+    pub fn unsubscribe<F>(
+        &mut self,
+        group: &str,
+    ) -> Result<u32, GenlSocketError>
+    where
+        F: GenlFamily,
+    {
+        let id = self.multicast_group_id::<F>(group)?;
+        self.transport.drop_membership(id)?;
+        Ok(id)
+    }
+
+    /// Look up the numeric ID of the multicast group `group` of the family
+    /// `F`
+    pub fn multicast_group_id<F>(
+        &mut self,
+        group: &str,
+    ) -> Result<u32, GenlSocketError>
+    where
+        F: GenlFamily,
+    {
+        self.resolve_family(F::family_name())?
+            .subscribe::<F>(group)
+            .ok_or(GenlSocketError::UnknownMulticastGroup(group.to_owned()))
+    }
+
+    /// Send a request and wait for the kernel to acknowledge it
+    ///
+    /// The request is sent with `NLM_F_REQUEST | NLM_F_ACK`. All the replies
+    /// received before the ACK are returned.
+    pub fn request<F>(
+        &mut self,
+        payload: F,
+    ) -> Result<Vec<GenlMessage<F>>, GenlSocketError>
+    where
+        F: GenlFamily
+            + Emitable
+            + ParseableParametrized<[u8], GenlHeader>
+            + Debug,
+    {
+        self.request_with_flags(payload, NLM_F_REQUEST | NLM_F_ACK)?
+            .collect()
+    }
+
+    /// Send a dump request and collect every reply until `NLMSG_DONE`
+    pub fn dump<F>(
+        &mut self,
+        payload: F,
+    ) -> Result<Vec<GenlMessage<F>>, GenlSocketError>
+    where
+        F: GenlFamily
+            + Emitable
+            + ParseableParametrized<[u8], GenlHeader>
+            + Debug,
+    {
+        self.request_with_flags(payload, NLM_F_REQUEST | NLM_F_DUMP)?
+            .collect()
+    }
+
+    /// Send a request with the given netlink flags and return an iterator
+    /// over its replies
+    ///
+    /// The iterator ends on `NLMSG_DONE`, on the ACK of the request, or after
+    /// yielding the first error.
+    /// Dropping it before its end discards the remaining replies of the
+    /// request.
+    pub fn request_with_flags<F>(
+        &mut self,
+        payload: F,
+        flags: u16,
+    ) -> Result<GenlReplies<'_, F>, GenlSocketError>
+    where
+        F: GenlFamily
+            + Emitable
+            + ParseableParametrized<[u8], GenlHeader>
+            + Debug,
+    {
+        let mut genlmsg = GenlMessage::from_payload(payload);
+        if genlmsg.payload.family_id() == 0 {
+            let id = self.resolve_family_id::<F>()?;
+            genlmsg.set_resolved_family_id(id);
+        }
+        let sequence_number = self.send(genlmsg, flags)?;
+        Ok(GenlReplies {
+            socket: self,
+            sequence_number,
+            finished: false,
+            last_reply_received: false,
+            _family: PhantomData,
+        })
+    }
+
+    /// Serialize and send a message, returning its sequence number
+    pub fn send<F>(
+        &mut self,
+        mut message: GenlMessage<F>,
+        flags: u16,
+    ) -> Result<u32, GenlSocketError>
+    where
+        F: GenlFamily + Emitable + Debug,
+    {
+        message.finalize();
+        self.sequence_number = self.sequence_number.wrapping_add(1);
+
+        let mut nlmsg = NetlinkMessage::from(message);
+        nlmsg.header.flags = flags;
+        nlmsg.header.sequence_number = self.sequence_number;
+        nlmsg.finalize();
+
+        let mut txbuf = vec![0u8; nlmsg.buffer_len()];
+        nlmsg.serialize(&mut txbuf);
+        if let Some(capture) = &mut self.capture {
+            capture.write_datagram(Direction::ToKernel, &txbuf)?;
+        }
+        self.transport.send(&txbuf)?;
+
+        Ok(self.sequence_number)
+    }
+
+    /// Receive the next raw netlink message which is not the reply of a
+    /// request sent with [`request_with_flags()`](Self::request_with_flags),
+    /// e.g. a multicast notification or the reply of a message sent with
+    /// [`send()`](Self::send)
+    ///
+    /// The socket timeout applies, [`GenlSocketError::Timeout`] is returned
+    /// when no message arrives in time.
+    pub fn recv_raw(&mut self) -> Result<Vec<u8>, GenlSocketError> {
+        let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        while self.rx_queue.is_empty() {
+            self.recv_datagram(deadline)?;
+        }
+        Ok(self.rx_queue.pop_front().unwrap_or_default())
+    }
+
+    /// Receive the next notification sent by the family `F`
+    ///
+    /// Messages of other families received in the meantime are dropped.
+    pub fn recv_notification<F>(
+        &mut self,
+    ) -> Result<GenlMessage<F>, GenlSocketError>
+    where
+        F: GenlFamily + ParseableParametrized<[u8], GenlHeader> + Debug,
+    {
+        let family_id = self.resolve_family_id::<F>()?;
+        loop {
+            let buf = self.recv_raw()?;
+            if NetlinkBuffer::new_checked(&buf[..])?.message_type() != family_id
+            {
+                continue;
+            }
+            let msg = NetlinkMessage::<GenlMessage<F>>::deserialize(&buf)?;
+            if let NetlinkPayload::InnerMessage(mut genlmsg) = msg.payload {
+                genlmsg.set_resolved_family_id(family_id);
+                return Ok(genlmsg);
+            }
+        }
+    }
+
+    /// Receive the next message which is not part of a pending request and
+    /// decode it with the families of the registry
+    pub fn recv_any(
+        &mut self,
+        registry: &GenlRegistry,
+    ) -> Result<NetlinkMessage<AnyGenlMessage>, GenlSocketError> {
+        let buf = self.recv_raw()?;
+        Ok(registry.deserialize(&buf)?)
+    }
+
+    fn next_reply(
+        &mut self,
+        sequence_number: u32,
+    ) -> Result<Vec<u8>, GenlSocketError> {
+        let deadline = self.timeout.map(|timeout| Instant::now() + timeout);
+        loop {
+            if let Some(pos) = self
+                .rx_queue
+                .iter()
+                .position(|buf| message_sequence(buf) == sequence_number)
+            {
+                return Ok(self.rx_queue.remove(pos).unwrap_or_default());
+            }
+            self.recv_datagram(deadline)?;
+        }
+    }
+
+    // Receive one datagram and split it into netlink messages
+    fn recv_datagram(
+        &mut self,
+        deadline: Option<Instant>,
+    ) -> Result<(), GenlSocketError> {
+        let rxbuf = self.transport.recv(deadline)?;
+        if let Some(capture) = &mut self.capture {
+            capture.write_datagram(Direction::ToUser, &rxbuf)?;
+        }
+        let mut offset = 0;
+        while offset < rxbuf.len() {
+            let buf = NetlinkBuffer::new_checked(&rxbuf[offset..])?;
+            let len = buf.length() as usize;
+            if len == 0 {
+                break;
+            }
+            let seq = buf.sequence_number();
+            if self.abandoned.contains(&seq) {
+                if is_last_reply(buf.message_type()) {
+                    self.abandoned.remove(&seq);
+                }
+            } else {
+                self.rx_queue
+                    .push_back(rxbuf[offset..offset + len].to_vec());
+            }
+            offset += (len + 3) & !3;
+        }
+        Ok(())
+    }
+}
+
+// Endpoint of a GenlSocket
+enum Transport {
+    Kernel(Socket),
+    #[cfg(feature = "mock")]
+    Mock(MockSocket),
+}
+
+impl Transport {
+    fn send(&self, buf: &[u8]) -> io::Result<()> {
+        match self {
+            Self::Kernel(socket) => socket.send(buf, 0)?,
+            #[cfg(feature = "mock")]
+            Self::Mock(socket) => socket.send(buf)?,
+        };
+        Ok(())
+    }
+
+    // Receive one datagram, waiting until the deadline at most
+    fn recv(
+        &self,
+        deadline: Option<Instant>,
+    ) -> Result<Vec<u8>, GenlSocketError> {
+        match self {
+            Self::Kernel(socket) => {
+                Self::wait_readable(socket, deadline)?;
+                Ok(socket.recv_from_full()?.0)
+            }
+            #[cfg(feature = "mock")]
+            Self::Mock(socket) => {
+                let timeout = deadline.map(|deadline| {
+                    deadline.saturating_duration_since(Instant::now())
+                });
+                socket.recv(timeout).map_err(|e| match e.kind() {
+                    io::ErrorKind::WouldBlock => GenlSocketError::Timeout,
+                    _ => e.into(),
+                })
+            }
+        }
+    }
+
+    fn add_membership(&self, group: u32) -> io::Result<()> {
+        match self {
+            Self::Kernel(socket) => socket.add_membership(group),
+            #[cfg(feature = "mock")]
+            Self::Mock(socket) => socket.add_membership(group),
+        }
+    }
+
+    fn drop_membership(&self, group: u32) -> io::Result<()> {
+        match self {
+            Self::Kernel(socket) => socket.drop_membership(group),
+            #[cfg(feature = "mock")]
+            Self::Mock(socket) => socket.drop_membership(group),
+        }
+    }
+
+    fn wait_readable(
+        socket: &Socket,
+        deadline: Option<Instant>,
+    ) -> Result<(), GenlSocketError> {
+        let deadline = match deadline {
+            Some(deadline) => deadline,
+            None => return Ok(()),
+        };
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Err(GenlSocketError::Timeout);
+            }
+            let mut pollfd = libc::pollfd {
+                fd: socket.as_raw_fd(),
+                events: libc::POLLIN,
+                revents: 0,
+            };
+            let timeout_ms =
+                remaining.as_millis().clamp(1, libc::c_int::MAX as u128);
+            let res = unsafe { libc::poll(&mut pollfd, 1, timeout_ms as _) };
+            match res {
+                0 => return Err(GenlSocketError::Timeout),
+                n if n > 0 => return Ok(()),
+                _ => {
+                    let err = io::Error::last_os_error();
+                    if err.kind() != io::ErrorKind::Interrupted {
+                        return Err(err.into());
+                    }
+                }
+            }
+        }
+    }
+}
+
+impl Debug for Transport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Kernel(socket) => write!(f, "fd {}", socket.as_raw_fd()),
+            #[cfg(feature = "mock")]
+            Self::Mock(socket) => {
+                write!(f, "mock port {}", socket.port_number())
+            }
+        }
+    }
+}
+
+fn message_sequence(buf: &[u8]) -> u32 {
+    NetlinkBuffer::new(buf).sequence_number()
+}
+
+// The kernel ends the replies of a request with NLMSG_DONE or NLMSG_ERROR,
+// which carries the ACK
+fn is_last_reply(message_type: u16) -> bool {
+    message_type == NLMSG_DONE || message_type == NLMSG_ERROR
+}
+
+/// Iterator over the replies of a request sent by [`GenlSocket`]
+pub struct GenlReplies<'a, F> {
+    socket: &'a mut GenlSocket,
+    sequence_number: u32,
+    finished: bool,
+    last_reply_received: bool,
+    _family: PhantomData<F>,
+}
+
+impl<'a, F> GenlReplies<'a, F> {
+    /// Return the sequence number of the request
+    pub fn sequence_number(&self) -> u32 {
+        self.sequence_number
+    }
+}
+
+impl<'a, F> Iterator for GenlReplies<'a, F>
+where
+    F: ParseableParametrized<[u8], GenlHeader> + Debug,
+{
+    type Item = Result<GenlMessage<F>, GenlSocketError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.finished {
+            let buf = match self.socket.next_reply(self.sequence_number) {
+                Ok(buf) => buf,
+                Err(e) => {
+                    self.finished = true;
+                    return Some(Err(e));
+                }
+            };
+            let msg = match NetlinkMessage::<GenlMessage<F>>::deserialize(&buf)
+            {
+                Ok(msg) => msg,
+                Err(e) => {
+                    self.finished = true;
+                    return Some(Err(e.into()));
+                }
+            };
+            match msg.payload {
+                NetlinkPayload::InnerMessage(genlmsg) => {
+                    return Some(Ok(genlmsg))
+                }
+                NetlinkPayload::Done(_) => {
+                    self.finished = true;
+                    self.last_reply_received = true;
+                }
+                NetlinkPayload::Error(err) => {
+                    self.finished = true;
+                    self.last_reply_received = true;
+                    if err.code.is_some() {
+                        return Some(Err(GenlSocketError::Netlink(err)));
+                    }
+                }
+                _ => {}
+            }
+        }
+        None
+    }
+}
+
+impl<'a, F> Drop for GenlReplies<'a, F> {
+    // Drain the remaining replies so they are not left in the queue, and
+    // discard those which are still to come
+    fn drop(&mut self) {
+        if self.last_reply_received {
+            return;
+        }
+        let seq = self.sequence_number;
+        let mut last_reply_received = false;
+        self.socket.rx_queue.retain(|buf| {
+            let buf = NetlinkBuffer::new(&buf[..]);
+            if buf.sequence_number() != seq {
+                return true;
+            }
+            last_reply_received |= is_last_reply(buf.message_type());
+            false
+        });
+        if !last_reply_received {
+            self.socket.abandoned.insert(seq);
+        }
+    }
+}
+
+impl Debug for GenlSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("GenlSocket")
+            .field("transport", &self.transport)
+            .field("sequence_number", &self.sequence_number)
+            .field("timeout", &self.timeout)
+            .field("families", &self.families)
+            .field("capture", &self.capture.is_some())
+            .finish()
+    }
+}
@@ -34,4 +34,12 @@ pub trait GenlFamily {
 
     /// Indicate the protocol version
     fn version(&self) -> u8;
+
+    /// Return the names of the multicast groups registered by the family
+    ///
+    /// The numeric IDs of the groups are assigned by the kernel, they can be
+    /// looked up with [`McastGroupResolver`](crate::ctrl::McastGroupResolver).
+    fn multicast_groups() -> &'static [&'static str] {
+        &[]
+    }
 }
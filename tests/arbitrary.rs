@@ -0,0 +1,102 @@
+// SPDX-License-Identifier: MIT
+
+//! Property tests of the emit and parse identity
+//!
+//! The values are generated by the `Arbitrary` implementations, from the
+//! random bytes proptest provides and shrinks.
+
+#![cfg(feature = "arbitrary")]
+
+use arbitrary::{Arbitrary, Unstructured};
+use netlink_packet_generic::{
+    ctrl::nlas::{
+        GenlCtrlAttrs, McastGrpAttrs, NlPolicyTypeAttrs, OpAttrs, OppolicyAttr,
+    },
+    devlink::nldev::{GenlDevlinkAttrs, PortFunctionAttrs},
+    raw::RawNla,
+    GenlBuffer, GenlHeader,
+};
+use netlink_packet_utils::{
+    nla::{Nla, NlaBuffer},
+    Emitable, Parseable,
+};
+use proptest::prelude::*;
+use std::fmt::Debug;
+
+fn nla_round_trip<T>(data: &[u8]) -> Result<(), TestCaseError>
+where
+    T: for<'a> Arbitrary<'a>
+        + Nla
+        + Debug
+        + PartialEq
+        + for<'a> Parseable<NlaBuffer<&'a [u8]>>,
+{
+    let nla = match T::arbitrary(&mut Unstructured::new(data)) {
+        Ok(nla) => nla,
+        Err(_) => return Ok(()),
+    };
+    let mut buf = vec![0; nla.buffer_len()];
+    nla.emit(&mut buf);
+    let parsed = NlaBuffer::new_checked(&buf[..])
+        .and_then(|buf| T::parse(&buf))
+        .map_err(|e| TestCaseError::fail(format!("{:?}: {:?}", nla, e)))?;
+    prop_assert_eq!(nla, parsed, "emitted as {:?}", buf);
+    Ok(())
+}
+
+fn bytes() -> impl Strategy<Value = Vec<u8>> {
+    proptest::collection::vec(any::<u8>(), 0..1024)
+}
+
+proptest! {
+    #[test]
+    fn genl_header(data in bytes()) {
+        if let Ok(header) = GenlHeader::arbitrary(&mut Unstructured::new(&data)) {
+            let mut buf = vec![0; header.buffer_len()];
+            header.emit(&mut buf);
+            let parsed =
+                GenlHeader::parse(&GenlBuffer::new_checked(&buf[..]).unwrap());
+            prop_assert_eq!(header, parsed.unwrap());
+        }
+    }
+
+    #[test]
+    fn ctrl_attrs(data in bytes()) {
+        nla_round_trip::<GenlCtrlAttrs>(&data)?;
+    }
+
+    #[test]
+    fn policy_type_attrs(data in bytes()) {
+        nla_round_trip::<NlPolicyTypeAttrs>(&data)?;
+    }
+
+    #[test]
+    fn oppolicy_attr(data in bytes()) {
+        nla_round_trip::<OppolicyAttr>(&data)?;
+    }
+
+    #[test]
+    fn mcast_group_attrs(data in bytes()) {
+        nla_round_trip::<McastGrpAttrs>(&data)?;
+    }
+
+    #[test]
+    fn op_attrs(data in bytes()) {
+        nla_round_trip::<OpAttrs>(&data)?;
+    }
+
+    #[test]
+    fn devlink_attrs(data in bytes()) {
+        nla_round_trip::<GenlDevlinkAttrs>(&data)?;
+    }
+
+    #[test]
+    fn port_function_attrs(data in bytes()) {
+        nla_round_trip::<PortFunctionAttrs>(&data)?;
+    }
+
+    #[test]
+    fn raw_nla(data in bytes()) {
+        nla_round_trip::<RawNla>(&data)?;
+    }
+}
@@ -0,0 +1,64 @@
+// SPDX-License-Identifier: MIT
+
+//! Round trip of the kernel replies stored in `tests/fixtures`
+//!
+//! Every fixture holds the netlink messages of one reply or dump, as
+//! received from the kernel. Each message must parse, re-emit to the very
+//! same bytes, and parse again to the same value.
+
+use netlink_packet_core::{
+    NetlinkBuffer, NetlinkDeserializable, NetlinkMessage, NetlinkSerializable,
+};
+use netlink_packet_generic::{ctrl::GenlCtrl, GenlMessage};
+use std::{fmt::Debug, fs, path::Path};
+
+fn split_messages(buf: &[u8]) -> Vec<&[u8]> {
+    let mut messages = Vec::new();
+    let mut offset = 0;
+    while offset < buf.len() {
+        let len = NetlinkBuffer::new_checked(&buf[offset..]).unwrap().length()
+            as usize;
+        messages.push(&buf[offset..offset + len]);
+        offset += (len + 3) & !3;
+    }
+    messages
+}
+
+// Replay all the fixtures of the family
+fn round_trip<T>(family: &str)
+where
+    T: NetlinkSerializable + NetlinkDeserializable + Debug + PartialEq,
+{
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/fixtures")
+        .join(family);
+    let mut paths = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension() == Some("bin".as_ref()))
+        .collect::<Vec<_>>();
+    paths.sort();
+    assert!(!paths.is_empty(), "no fixture in {}", dir.display());
+
+    for path in paths {
+        let buf = fs::read(&path).unwrap();
+        for (i, bytes) in split_messages(&buf).into_iter().enumerate() {
+            let name = format!("{} message {}", path.display(), i);
+            let parsed = NetlinkMessage::<T>::deserialize(bytes)
+                .unwrap_or_else(|e| panic!("{}: {:?}", name, e));
+
+            let mut emitted = vec![0; parsed.buffer_len()];
+            parsed.serialize(&mut emitted);
+            assert_eq!(bytes, &emitted[..], "{}: {:#?}", name, parsed);
+
+            let reparsed = NetlinkMessage::<T>::deserialize(&emitted)
+                .unwrap_or_else(|e| panic!("{}: {:?}", name, e));
+            assert_eq!(parsed, reparsed, "{}", name);
+        }
+    }
+}
+
+#[test]
+fn ctrl_corpus() {
+    round_trip::<GenlMessage<GenlCtrl>>("ctrl");
+}
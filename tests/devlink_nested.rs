@@ -0,0 +1,23 @@
+// SPDX-License-Identifier: MIT
+
+use netlink_packet_generic::devlink::nldev::GenlDevlinkAttrs;
+use netlink_packet_utils::{nla::NlaBuffer, Emitable, Parseable};
+
+fn emit(nla: &GenlDevlinkAttrs) -> Vec<u8> {
+    let mut buf = vec![0; nla.buffer_len()];
+    nla.emit(&mut buf);
+    buf
+}
+
+#[test]
+fn emit_nested_attributes() {
+    let name = GenlDevlinkAttrs::InfoVersionName("fw.mgmt".to_owned());
+    let value = GenlDevlinkAttrs::InfoVersionValue("22.31.1014".to_owned());
+    let nla =
+        GenlDevlinkAttrs::InfoVersionRunning(vec![name.clone(), value.clone()]);
+
+    // Each nested attribute is emitted with its header, one after another
+    let buf = emit(&nla);
+    assert_eq!(&buf[4..], [emit(&name), emit(&value)].concat());
+    assert_eq!(nla, GenlDevlinkAttrs::parse(&NlaBuffer::new(&buf)).unwrap());
+}
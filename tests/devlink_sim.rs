@@ -0,0 +1,1032 @@
+// SPDX-License-Identifier: MIT
+
+#![cfg(all(feature = "sync", feature = "mock"))]
+
+use netlink_packet_core::ErrorMessage;
+use netlink_packet_generic::{
+    constants::*,
+    devlink::{
+        info::{DeviceInfo, PendingUpdate},
+        nldev::{GenlDevlinkAttrs, PortFunctionOpState, PortFunctionState},
+        port::{
+            Port, PortFlavour, PortFunctionSet, SfPortSpec, SplitError,
+            SplitPlanner,
+        },
+        rate::{RateError, RateId, RateSet, RateTree},
+        sim::{self, DevlinkSim},
+        trap::{
+            Trap, TrapAction, TrapGroup, TrapGroupSet, TrapPolicer,
+            TrapPolicerSet, TrapSet, TrapType,
+        },
+        DevlinkHandle, GenlDevlink, GenlDevlinkCmd,
+    },
+    mock::MockKernel,
+    sync::{GenlSocket, GenlSocketError},
+    GenlMessage,
+};
+use netlink_packet_utils::nla::NlasIterator;
+use std::{
+    convert::{TryFrom, TryInto},
+    time::Duration,
+};
+
+const ENOENT: i32 = 2;
+const EBUSY: i32 = 16;
+const EEXIST: i32 = 17;
+const EINVAL: i32 = 22;
+const EOPNOTSUPP: i32 = 95;
+
+fn setup() -> (MockKernel, DevlinkSim, GenlSocket) {
+    let kernel = MockKernel::new();
+    let sim = DevlinkSim::register(&kernel).unwrap();
+    sim.add_device(1, 2).unwrap();
+    let socket = connect(&kernel);
+    (kernel, sim, socket)
+}
+
+fn connect(kernel: &MockKernel) -> GenlSocket {
+    let mut socket = GenlSocket::from_mock(kernel.connect());
+    socket.set_timeout(Some(Duration::from_secs(1)));
+    socket
+}
+
+fn dev(attrs: Vec<GenlDevlinkAttrs>) -> Vec<GenlDevlinkAttrs> {
+    let mut nldev = vec![
+        GenlDevlinkAttrs::BusName("netdevsim".to_owned()),
+        GenlDevlinkAttrs::Location("netdevsim1".to_owned()),
+    ];
+    nldev.extend(attrs);
+    nldev
+}
+
+fn request(
+    socket: &mut GenlSocket,
+    cmd: GenlDevlinkCmd,
+    attrs: Vec<GenlDevlinkAttrs>,
+) -> Result<Vec<GenlMessage<GenlDevlink>>, GenlSocketError> {
+    socket.request(GenlDevlink {
+        cmd,
+        nldev: dev(attrs),
+    })
+}
+
+fn dump(
+    socket: &mut GenlSocket,
+    cmd: GenlDevlinkCmd,
+    attrs: Vec<GenlDevlinkAttrs>,
+) -> Vec<GenlDevlink> {
+    socket
+        .dump(GenlDevlink { cmd, nldev: attrs })
+        .unwrap()
+        .into_iter()
+        .map(|msg| msg.payload)
+        .collect()
+}
+
+// Return the reply to a request expecting a single one
+fn reply(socket: &mut GenlSocket, request: GenlDevlink) -> GenlDevlink {
+    socket.request(request).unwrap().remove(0).payload
+}
+
+// Return the errno and the extended ACK message of an error
+fn netlink_error(err: GenlSocketError) -> (i32, Option<String>) {
+    let err: ErrorMessage = match err {
+        GenlSocketError::Netlink(e) => e,
+        e => panic!("Unexpected error: {}", e),
+    };
+    let request_len =
+        u32::from_ne_bytes(err.header[..4].try_into().unwrap()) as usize;
+    let message =
+        NlasIterator::new(&err.header[request_len.min(err.header.len())..])
+            .filter_map(Result::ok)
+            .find(|nla| nla.kind() == 1)
+            .map(|nla| {
+                String::from_utf8_lossy(nla.value())
+                    .trim_end_matches('\0')
+                    .to_owned()
+            });
+    (-err.raw_code(), message)
+}
+
+fn port_indexes(socket: &mut GenlSocket) -> Vec<u32> {
+    dump(socket, GenlDevlinkCmd::GetPort, vec![])
+        .iter()
+        .filter_map(|msg| {
+            msg.nldev.iter().find_map(|nla| match nla {
+                GenlDevlinkAttrs::PortIndex(index) => Some(*index),
+                _ => None,
+            })
+        })
+        .collect()
+}
+
+fn nested(msg: &GenlDevlink) -> &[GenlDevlinkAttrs] {
+    msg.nldev
+        .iter()
+        .find_map(|nla| match nla {
+            GenlDevlinkAttrs::Param(nlas)
+            | GenlDevlinkAttrs::HealthReporter(nlas)
+            | GenlDevlinkAttrs::RegionChunks(nlas)
+            | GenlDevlinkAttrs::Fmsg(nlas) => Some(nlas.as_slice()),
+            _ => None,
+        })
+        .unwrap()
+}
+
+#[test]
+fn devices() {
+    let (kernel, sim, mut socket) = setup();
+    let mut listener = connect(&kernel);
+    listener.subscribe::<GenlDevlink>("config").unwrap();
+
+    sim.add_device(2, 1).unwrap();
+    assert!(sim.add_device(2, 1).is_err());
+    let new_device = listener.recv_notification::<GenlDevlink>().unwrap();
+    assert_eq!(GenlDevlinkCmd::NewDevice, new_device.payload.cmd);
+    let new_port = listener.recv_notification::<GenlDevlink>().unwrap();
+    assert_eq!(GenlDevlinkCmd::NewPort, new_port.payload.cmd);
+
+    assert_eq!(
+        2,
+        dump(&mut socket, GenlDevlinkCmd::GetDevices, vec![]).len()
+    );
+    assert_eq!(3, port_indexes(&mut socket).len());
+    // Dumps can be restricted to a device
+    let ports = dump(&mut socket, GenlDevlinkCmd::GetPort, dev(vec![]));
+    assert_eq!(2, ports.len());
+
+    sim.remove_device(2).unwrap();
+    assert_eq!(
+        1,
+        dump(&mut socket, GenlDevlinkCmd::GetDevices, vec![]).len()
+    );
+    let err = socket
+        .request(GenlDevlink {
+            cmd: GenlDevlinkCmd::GetDevices,
+            nldev: vec![
+                GenlDevlinkAttrs::BusName("netdevsim".to_owned()),
+                GenlDevlinkAttrs::Location("netdevsim2".to_owned()),
+            ],
+        })
+        .unwrap_err();
+    assert_eq!(19, netlink_error(err).0);
+}
+
+#[test]
+fn split_ports() {
+    use GenlDevlinkAttrs::*;
+    let (kernel, _sim, mut socket) = setup();
+    let mut listener = connect(&kernel);
+    listener.subscribe::<GenlDevlink>("config").unwrap();
+
+    let err = request(
+        &mut socket,
+        GenlDevlinkCmd::SplitPort,
+        vec![PortIndex(0), PortSplitCount(3)],
+    )
+    .unwrap_err();
+    assert_eq!(
+        (EINVAL, Some("Invalid split count".to_owned())),
+        netlink_error(err)
+    );
+
+    request(
+        &mut socket,
+        GenlDevlinkCmd::SplitPort,
+        vec![PortIndex(0), PortSplitCount(2)],
+    )
+    .unwrap();
+    assert_eq!(vec![1, 2, 3], port_indexes(&mut socket));
+    let cmds: Vec<_> = (0..3)
+        .map(|_| listener.recv_notification::<GenlDevlink>().unwrap().payload)
+        .collect();
+    assert_eq!(GenlDevlinkCmd::DeletePort, cmds[0].cmd);
+    assert_eq!(GenlDevlinkCmd::NewPort, cmds[1].cmd);
+    assert!(cmds[1].nldev.contains(&PortSplitGroup(0)));
+    assert!(cmds[2].nldev.contains(&PortSplitSubportNumber(1)));
+    assert!(cmds[2].nldev.contains(&PortLanes(2)));
+    assert!(cmds[2].nldev.contains(&PortSplittable(0)));
+
+    // Subports cannot be split again
+    let err = request(
+        &mut socket,
+        GenlDevlinkCmd::SplitPort,
+        vec![PortIndex(2), PortSplitCount(2)],
+    )
+    .unwrap_err();
+    assert_eq!(EINVAL, netlink_error(err).0);
+
+    request(&mut socket, GenlDevlinkCmd::UnsplitPort, vec![PortIndex(3)])
+        .unwrap();
+    assert_eq!(vec![0, 1], port_indexes(&mut socket));
+    let port =
+        request(&mut socket, GenlDevlinkCmd::GetPort, vec![PortIndex(0)])
+            .unwrap()
+            .remove(0)
+            .payload;
+    assert!(port.nldev.contains(&PortLanes(4)));
+    assert!(port.nldev.contains(&NetdevName("eni1np1".to_owned())));
+}
+
+#[test]
+fn params() {
+    use GenlDevlinkAttrs::*;
+    let (_kernel, sim, mut socket) = setup();
+
+    let param = request(
+        &mut socket,
+        GenlDevlinkCmd::GetParams,
+        vec![ParamName("max_macs".to_owned())],
+    )
+    .unwrap()
+    .remove(0)
+    .payload;
+    let attrs = nested(&param);
+    assert!(attrs.contains(&ParamGeneric(true)));
+    assert!(attrs.contains(&ParamType(DEVLINK_PARAM_TYPE_U32)));
+    assert!(attrs.contains(&ParamValueList(vec![ParamValue(vec![
+        ParamValueCmode(DEVLINK_PARAM_CMODE_DRIVERINIT),
+        ParamValueData(32u32.to_ne_bytes().to_vec()),
+    ])])));
+    assert_eq!(
+        2,
+        dump(&mut socket, GenlDevlinkCmd::GetParams, vec![]).len()
+    );
+
+    let set = |cmode, param_type, data: Option<Vec<u8>>| {
+        let mut attrs = vec![
+            ParamName("max_macs".to_owned()),
+            ParamType(param_type),
+            ParamValueCmode(cmode),
+        ];
+        attrs.extend(data.map(ParamValueData));
+        attrs
+    };
+    request(
+        &mut socket,
+        GenlDevlinkCmd::SetParams,
+        set(
+            DEVLINK_PARAM_CMODE_DRIVERINIT,
+            DEVLINK_PARAM_TYPE_U32,
+            Some(64u32.to_ne_bytes().to_vec()),
+        ),
+    )
+    .unwrap();
+    assert_eq!(
+        Some(sim::ParamValue::U32(64)),
+        sim.param(1, "max_macs", DEVLINK_PARAM_CMODE_DRIVERINIT)
+    );
+
+    let err = request(
+        &mut socket,
+        GenlDevlinkCmd::SetParams,
+        set(
+            DEVLINK_PARAM_CMODE_RUNTIME,
+            DEVLINK_PARAM_TYPE_U32,
+            Some(64u32.to_ne_bytes().to_vec()),
+        ),
+    )
+    .unwrap_err();
+    assert_eq!(EOPNOTSUPP, netlink_error(err).0);
+    let err = request(
+        &mut socket,
+        GenlDevlinkCmd::SetParams,
+        set(
+            DEVLINK_PARAM_CMODE_DRIVERINIT,
+            DEVLINK_PARAM_TYPE_BOOL,
+            None,
+        ),
+    )
+    .unwrap_err();
+    assert_eq!(EINVAL, netlink_error(err).0);
+
+    // A false boolean is sent without data
+    request(
+        &mut socket,
+        GenlDevlinkCmd::SetParams,
+        vec![
+            ParamName("test1".to_owned()),
+            ParamType(DEVLINK_PARAM_TYPE_BOOL),
+            ParamValueCmode(DEVLINK_PARAM_CMODE_DRIVERINIT),
+        ],
+    )
+    .unwrap();
+    assert_eq!(
+        Some(sim::ParamValue::Bool(false)),
+        sim.param(1, "test1", DEVLINK_PARAM_CMODE_DRIVERINIT)
+    );
+}
+
+#[test]
+fn region_snapshots() {
+    use GenlDevlinkAttrs::*;
+    let (_kernel, _sim, mut socket) = setup();
+    let region = || RegionName("dummy".to_owned());
+
+    let reply =
+        request(&mut socket, GenlDevlinkCmd::CreatRegion, vec![region()])
+            .unwrap()
+            .remove(0)
+            .payload;
+    assert!(reply.nldev.contains(&RegionSnapshotId(1)));
+    request(
+        &mut socket,
+        GenlDevlinkCmd::CreatRegion,
+        vec![region(), RegionSnapshotId(7)],
+    )
+    .unwrap();
+    let err = request(
+        &mut socket,
+        GenlDevlinkCmd::CreatRegion,
+        vec![region(), RegionSnapshotId(7)],
+    )
+    .unwrap_err();
+    assert_eq!(EEXIST, netlink_error(err).0);
+
+    let regions = dump(&mut socket, GenlDevlinkCmd::GetRegion, vec![]);
+    assert!(regions[0].nldev.contains(&RegionSnapshots(vec![
+        RegionSnapshot(vec![RegionSnapshotId(1)]),
+        RegionSnapshot(vec![RegionSnapshotId(7)]),
+    ])));
+
+    let read = dump(
+        &mut socket,
+        GenlDevlinkCmd::ReadRegion,
+        dev(vec![
+            region(),
+            RegionSnapshotId(7),
+            RegionChunkOffset(128),
+            RegionChunkSize(300),
+        ]),
+    );
+    let chunks = nested(&read[0]);
+    assert_eq!(2, chunks.len());
+    match &chunks[1] {
+        RegionChunk(nlas) => {
+            assert!(nlas.contains(&RegionChunkOffset(384)));
+            assert!(nlas.contains(&RegionChunkData(
+                (384u32..428).map(|i| (i ^ 7) as u8).collect()
+            )));
+        }
+        nla => panic!("Unexpected attribute: {:?}", nla),
+    }
+
+    request(
+        &mut socket,
+        GenlDevlinkCmd::DeleteRegion,
+        vec![region(), RegionSnapshotId(7)],
+    )
+    .unwrap();
+    let err = request(
+        &mut socket,
+        GenlDevlinkCmd::DeleteRegion,
+        vec![region(), RegionSnapshotId(7)],
+    )
+    .unwrap_err();
+    assert_eq!(ENOENT, netlink_error(err).0);
+}
+
+#[test]
+fn health_reporters() {
+    use GenlDevlinkAttrs::*;
+    let (kernel, sim, mut socket) = setup();
+    let mut listener = connect(&kernel);
+    listener.subscribe::<GenlDevlink>("config").unwrap();
+    let dummy = || HealthReporterName("dummy".to_owned());
+
+    assert_eq!(
+        2,
+        dump(&mut socket, GenlDevlinkCmd::GetHealthReport, vec![]).len()
+    );
+    let err = request(
+        &mut socket,
+        GenlDevlinkCmd::TestHealthReport,
+        vec![HealthReporterName("empty".to_owned())],
+    )
+    .unwrap_err();
+    assert_eq!(EOPNOTSUPP, netlink_error(err).0);
+
+    // The error is dumped and recovered automatically
+    request(&mut socket, GenlDevlinkCmd::TestHealthReport, vec![dummy()])
+        .unwrap();
+    let error = listener.recv_notification::<GenlDevlink>().unwrap().payload;
+    assert_eq!(GenlDevlinkCmd::RecoverHealthReport, error.cmd);
+    assert!(nested(&error)
+        .contains(&HealthReporterState(DEVLINK_HEALTH_REPORTER_STATE_ERROR)));
+    let recovered =
+        listener.recv_notification::<GenlDevlink>().unwrap().payload;
+    assert!(nested(&recovered)
+        .contains(&HealthReporterState(DEVLINK_HEALTH_REPORTER_STATE_HEALTHY)));
+
+    // Without auto recovery, the reporter stays in error
+    request(
+        &mut socket,
+        GenlDevlinkCmd::SetHealthReport,
+        vec![dummy(), HealthReporterAucoRecover(0)],
+    )
+    .unwrap();
+    sim.report_health_error(1, "dummy", "link down").unwrap();
+    let reporter =
+        request(&mut socket, GenlDevlinkCmd::GetHealthReport, vec![dummy()])
+            .unwrap()
+            .remove(0)
+            .payload;
+    let attrs = nested(&reporter);
+    assert!(attrs.contains(&HealthReporterErrCount(2)));
+    assert!(attrs.contains(&HealthReporterRecoverCount(1)));
+    assert!(attrs
+        .contains(&HealthReporterState(DEVLINK_HEALTH_REPORTER_STATE_ERROR)));
+    assert!(attrs
+        .iter()
+        .any(|nla| matches!(nla, HealthReporterDumpTsNs(_))));
+
+    let diagnose = request(
+        &mut socket,
+        GenlDevlinkCmd::DaignoseHealthReport,
+        vec![dummy()],
+    )
+    .unwrap()
+    .remove(0)
+    .payload;
+    let fmsg = nested(&diagnose);
+    assert_eq!(FmsgObjNestStart(true), fmsg[0]);
+    assert!(fmsg.contains(&FmsgObjValueData(b"error\0".to_vec())));
+
+    // The dump was taken on the first error
+    let dump = dump(
+        &mut socket,
+        GenlDevlinkCmd::GetHealthReportDump,
+        dev(vec![dummy()]),
+    );
+    assert!(nested(&dump[0])
+        .contains(&FmsgObjValueData(b"Test message\0".to_vec())));
+
+    request(
+        &mut socket,
+        GenlDevlinkCmd::RecoverHealthReport,
+        vec![dummy()],
+    )
+    .unwrap();
+    let reporter =
+        request(&mut socket, GenlDevlinkCmd::GetHealthReport, vec![dummy()])
+            .unwrap()
+            .remove(0)
+            .payload;
+    assert!(nested(&reporter).contains(&HealthReporterRecoverCount(2)));
+}
+
+#[test]
+fn traps() {
+    use GenlDevlinkAttrs::*;
+    let (_kernel, sim, mut socket) = setup();
+    let blackhole = || TrapName("blackhole_route".to_owned());
+
+    assert_eq!(8, dump(&mut socket, GenlDevlinkCmd::GetTrap, vec![]).len());
+    assert_eq!(
+        5,
+        dump(&mut socket, GenlDevlinkCmd::GetTrapGroup, vec![]).len()
+    );
+    assert_eq!(
+        3,
+        dump(&mut socket, GenlDevlinkCmd::GetTrapPolicer, vec![]).len()
+    );
+
+    // Dropped packets are not accounted
+    sim.record_trap(1, "blackhole_route", 100).unwrap();
+    request(
+        &mut socket,
+        GenlDevlinkCmd::SetTrap,
+        vec![blackhole(), TrapAction(DEVLINK_TRAP_ACTION_TRAP)],
+    )
+    .unwrap();
+    sim.record_trap(1, "blackhole_route", 100).unwrap();
+    let trap = request(&mut socket, GenlDevlinkCmd::GetTrap, vec![blackhole()])
+        .unwrap()
+        .remove(0)
+        .payload;
+    assert!(trap.nldev.contains(&TrapAction(DEVLINK_TRAP_ACTION_TRAP)));
+    let stats = trap
+        .nldev
+        .iter()
+        .find_map(|nla| match nla {
+            Stats(stats) => Some(stats),
+            _ => None,
+        })
+        .unwrap();
+    assert_eq!(1u64.to_ne_bytes().to_vec(), stats[0].value());
+    assert_eq!(100u64.to_ne_bytes().to_vec(), stats[1].value());
+
+    let err = request(
+        &mut socket,
+        GenlDevlinkCmd::SetTrapGroup,
+        vec![TrapGroupName("l3_drops".to_owned()), TrapPolicerId(9)],
+    )
+    .unwrap_err();
+    assert_eq!(ENOENT, netlink_error(err).0);
+    request(
+        &mut socket,
+        GenlDevlinkCmd::SetTrapGroup,
+        vec![TrapGroupName("l3_drops".to_owned()), TrapPolicerId(2)],
+    )
+    .unwrap();
+    let group = request(
+        &mut socket,
+        GenlDevlinkCmd::GetTrapGroup,
+        vec![TrapGroupName("l3_drops".to_owned())],
+    )
+    .unwrap()
+    .remove(0)
+    .payload;
+    assert!(group.nldev.contains(&TrapPolicerId(2)));
+
+    let err = request(
+        &mut socket,
+        GenlDevlinkCmd::SetTrapPolicer,
+        vec![TrapPolicerId(1), TrapPolicerRate(10000)],
+    )
+    .unwrap_err();
+    assert_eq!(
+        (EINVAL, Some("Policer rate higher than limit".to_owned())),
+        netlink_error(err)
+    );
+    request(
+        &mut socket,
+        GenlDevlinkCmd::SetTrapPolicer,
+        vec![
+            TrapPolicerId(1),
+            TrapPolicerRate(2000),
+            TrapPolicerBurst(256),
+        ],
+    )
+    .unwrap();
+    let policer = request(
+        &mut socket,
+        GenlDevlinkCmd::GetTrapPolicer,
+        vec![TrapPolicerId(1)],
+    )
+    .unwrap()
+    .remove(0)
+    .payload;
+    assert!(policer.nldev.contains(&TrapPolicerRate(2000)));
+    assert!(policer.nldev.contains(&TrapPolicerBurst(256)));
+}
+
+#[test]
+fn rates() {
+    use GenlDevlinkAttrs::*;
+    let (_kernel, _sim, mut socket) = setup();
+    let node = |name: &str| RateNodeName(name.to_owned());
+    let parent = |name: &str| RateParentNodeName(name.to_owned());
+
+    request(&mut socket, GenlDevlinkCmd::NewRate, vec![node("root")]).unwrap();
+    request(
+        &mut socket,
+        GenlDevlinkCmd::NewRate,
+        vec![node("group"), parent("root"), RateTxMax(1000)],
+    )
+    .unwrap();
+    let err = request(&mut socket, GenlDevlinkCmd::NewRate, vec![node("root")])
+        .unwrap_err();
+    assert_eq!(EEXIST, netlink_error(err).0);
+
+    // Cycles are rejected
+    let err = request(
+        &mut socket,
+        GenlDevlinkCmd::SetRate,
+        vec![node("root"), parent("group")],
+    )
+    .unwrap_err();
+    assert_eq!(EEXIST, netlink_error(err).0);
+
+    request(
+        &mut socket,
+        GenlDevlinkCmd::SetRate,
+        vec![PortIndex(1), parent("group"), RateTxShare(100)],
+    )
+    .unwrap();
+    let leaf =
+        request(&mut socket, GenlDevlinkCmd::GetRate, vec![PortIndex(1)])
+            .unwrap()
+            .remove(0)
+            .payload;
+    assert!(leaf.nldev.contains(&RateType(DEVLINK_RATE_TYPE_LEAF)));
+    assert!(leaf.nldev.contains(&RateTxShare(100)));
+    assert!(leaf.nldev.contains(&parent("group")));
+    // 2 leaves and 2 nodes
+    assert_eq!(4, dump(&mut socket, GenlDevlinkCmd::GetRate, vec![]).len());
+
+    let err =
+        request(&mut socket, GenlDevlinkCmd::DeleteRate, vec![node("group")])
+            .unwrap_err();
+    assert_eq!(EBUSY, netlink_error(err).0);
+    request(
+        &mut socket,
+        GenlDevlinkCmd::SetRate,
+        vec![PortIndex(1), parent("")],
+    )
+    .unwrap();
+    request(&mut socket, GenlDevlinkCmd::DeleteRate, vec![node("group")])
+        .unwrap();
+    assert_eq!(3, dump(&mut socket, GenlDevlinkCmd::GetRate, vec![]).len());
+}
+
+#[test]
+fn flash_and_reload() {
+    use GenlDevlinkAttrs::*;
+    let (kernel, sim, mut socket) = setup();
+    let mut listener = connect(&kernel);
+    listener.subscribe::<GenlDevlink>("config").unwrap();
+
+    request(
+        &mut socket,
+        GenlDevlinkCmd::UpdateFlash,
+        vec![FlashUpdateFileName("fw-2.0.0.bin".to_owned())],
+    )
+    .unwrap();
+    let mut notifications = Vec::new();
+    loop {
+        let msg = listener.recv_notification::<GenlDevlink>().unwrap();
+        notifications.push(msg.payload);
+        if notifications.last().unwrap().cmd == GenlDevlinkCmd::FlashUdpateEnd {
+            break;
+        }
+    }
+    assert_eq!(GenlDevlinkCmd::UpdateFlash, notifications[0].cmd);
+    assert!(notifications.iter().any(|msg| {
+        msg.nldev.contains(&FlashUpdateStatusDone(500000))
+            && msg.nldev.contains(&FlashUpdateStatusTotal(500000))
+    }));
+
+    let version = |info: &GenlDevlink, running: bool| {
+        info.nldev
+            .iter()
+            .find_map(|nla| match nla {
+                InfoVersionRunning(v) if running => Some(v.clone()),
+                InfoVersionStored(v) if !running => Some(v.clone()),
+                _ => None,
+            })
+            .unwrap()
+    };
+    let info = request(&mut socket, GenlDevlinkCmd::GetDeviceInfo, vec![])
+        .unwrap()
+        .remove(0)
+        .payload;
+    assert!(
+        version(&info, true).contains(&InfoVersionValue("1.0.0".to_owned()))
+    );
+    assert!(
+        version(&info, false).contains(&InfoVersionValue("1.0.1".to_owned()))
+    );
+
+    // Reloading recreates the ports
+    request(
+        &mut socket,
+        GenlDevlinkCmd::SplitPort,
+        vec![PortIndex(0), PortSplitCount(4)],
+    )
+    .unwrap();
+    let reply = request(
+        &mut socket,
+        GenlDevlinkCmd::Reload,
+        vec![ReloadAction(DEVLINK_RELOAD_ACTION_FW_ACTIVATE)],
+    )
+    .unwrap()
+    .remove(0)
+    .payload;
+    assert!(reply.nldev.contains(&ReloadActionPerformed(
+        1 << DEVLINK_RELOAD_ACTION_FW_ACTIVATE
+    )));
+    assert_eq!(Some(1), sim.reload_count(1));
+    assert_eq!(vec![0, 1], port_indexes(&mut socket));
+    let info = request(&mut socket, GenlDevlinkCmd::GetDeviceInfo, vec![])
+        .unwrap()
+        .remove(0)
+        .payload;
+    assert!(
+        version(&info, true).contains(&InfoVersionValue("1.0.1".to_owned()))
+    );
+    assert!(
+        version(&info, false).contains(&InfoVersionValue("1.0.1".to_owned()))
+    );
+
+    let err = request(
+        &mut socket,
+        GenlDevlinkCmd::Reload,
+        vec![ReloadLimits(1 << DEVLINK_RELOAD_LIMIT_NO_RESET)],
+    )
+    .unwrap_err();
+    assert_eq!(EOPNOTSUPP, netlink_error(err).0);
+}
+
+#[test]
+fn typed_traps() {
+    let (_kernel, sim, mut socket) = setup();
+    let handle = DevlinkHandle::new("netdevsim", "netdevsim1");
+
+    let trap = Trap::try_from(&reply(
+        &mut socket,
+        Trap::request(&handle, "ingress_flow_action_drop"),
+    ))
+    .unwrap();
+    assert_eq!("acl_drops", trap.group);
+    assert_eq!(TrapType::Drop, trap.trap_type);
+    assert_eq!(TrapAction::Drop, trap.action);
+    assert!(trap.metadata.in_port && trap.metadata.fa_cookie);
+
+    socket
+        .request(
+            TrapSet::new(&handle, "blackhole_route", TrapAction::Trap).build(),
+        )
+        .unwrap();
+    sim.record_trap(1, "blackhole_route", 100).unwrap();
+    sim.record_trap(1, "blackhole_route", 60).unwrap();
+    let trap = Trap::try_from(&reply(
+        &mut socket,
+        Trap::request(&handle, "blackhole_route"),
+    ))
+    .unwrap();
+    assert_eq!(TrapAction::Trap, trap.action);
+    assert!(!trap.metadata.fa_cookie);
+    assert_eq!((2, 160), (trap.stats.rx_packets, trap.stats.rx_bytes));
+    let group = TrapGroup::try_from(&reply(
+        &mut socket,
+        TrapGroup::request(&handle, "l3_drops"),
+    ))
+    .unwrap();
+    assert_eq!(Some(1), group.policer_id);
+    assert_eq!(2, group.stats.rx_packets);
+
+    socket
+        .request(
+            TrapGroupSet::new(&handle, "l3_drops")
+                .action(TrapAction::Drop)
+                .policer(None)
+                .build(),
+        )
+        .unwrap();
+    let group = TrapGroup::try_from(&reply(
+        &mut socket,
+        TrapGroup::request(&handle, "l3_drops"),
+    ))
+    .unwrap();
+    assert_eq!(None, group.policer_id);
+    let trap = Trap::try_from(&reply(
+        &mut socket,
+        Trap::request(&handle, "blackhole_route"),
+    ))
+    .unwrap();
+    assert_eq!(TrapAction::Drop, trap.action);
+
+    socket
+        .request(TrapPolicerSet::new(&handle, 3).rate(500).build())
+        .unwrap();
+    let policer = TrapPolicer::try_from(&reply(
+        &mut socket,
+        TrapPolicer::request(&handle, 3),
+    ))
+    .unwrap();
+    assert_eq!((500, 128), (policer.rate, policer.burst));
+    assert_eq!(Some(0), policer.dropped);
+}
+
+#[test]
+fn typed_rates() {
+    let (_kernel, _sim, mut socket) = setup();
+    let handle = DevlinkHandle::new("netdevsim", "netdevsim1");
+    let tree = |socket: &mut GenlSocket| {
+        let replies = dump(socket, GenlDevlinkCmd::GetRate, handle.nlas());
+        RateTree::from_replies(&handle, &replies).unwrap()
+    };
+
+    let changes = [
+        RateSet::new_node(&handle, "root").tx_max(1_250_000),
+        RateSet::new_node(&handle, "group").parent("root"),
+        RateSet::leaf(&handle, 1).parent("group").tx_share(125_000),
+    ];
+    for change in &changes {
+        tree(&mut socket).validate(change).unwrap();
+        socket.request(change.build()).unwrap();
+    }
+
+    let rates = tree(&mut socket);
+    assert_eq!(4, rates.iter().count());
+    assert_eq!(
+        vec!["group", "root"],
+        rates.ancestors(&RateId::Leaf(1)).collect::<Vec<_>>()
+    );
+    assert_eq!(125_000, rates.get(&RateId::Leaf(1)).unwrap().tx_share);
+    assert_eq!(
+        Err(RateError::Cycle {
+            node: "root".to_owned(),
+            parent: "group".to_owned(),
+        }),
+        rates.validate(&RateSet::node(&handle, "root").parent("group"))
+    );
+    assert_eq!(
+        Err(RateError::NodeHasChildren("group".to_owned())),
+        rates.delete_node("group")
+    );
+
+    socket
+        .request(RateSet::leaf(&handle, 1).no_parent().build())
+        .unwrap();
+    let delete = tree(&mut socket).delete_node("group").unwrap();
+    socket.request(delete).unwrap();
+    assert_eq!(3, tree(&mut socket).iter().count());
+}
+
+#[test]
+fn sf_ports() {
+    let (kernel, _sim, mut socket) = setup();
+    let mut listener = connect(&kernel);
+    listener.subscribe::<GenlDevlink>("config").unwrap();
+    let handle = DevlinkHandle::new("netdevsim", "netdevsim1");
+
+    let err = socket
+        .request(SfPortSpec::new(&handle, 0).build())
+        .unwrap_err();
+    assert_eq!(EOPNOTSUPP, netlink_error(err).0);
+    let err = socket
+        .request(SfPortSpec::new(&handle, 1).sf_number(88).build())
+        .unwrap_err();
+    assert_eq!(
+        (EINVAL, Some("Invalid PF number".to_owned())),
+        netlink_error(err)
+    );
+
+    let spec = SfPortSpec::new(&handle, 0).sf_number(88).controller(0);
+    let port = Port::try_from(&reply(&mut socket, spec.build())).unwrap();
+    assert_eq!(PortFlavour::PciSf, port.flavour);
+    assert_eq!(
+        (Some(0), Some(88), Some(0), false),
+        (
+            port.pci_pf_number,
+            port.pci_sf_number,
+            port.controller,
+            port.external
+        )
+    );
+    assert!(!port.is_active());
+    let function = port.function.as_ref().unwrap();
+    assert_eq!(Some(PortFunctionOpState::Detached), function.opstate);
+    assert_eq!(Some(true), function.roce());
+    let notification = listener.recv_notification::<GenlDevlink>().unwrap();
+    assert_eq!(port, Port::try_from(&notification.payload).unwrap());
+
+    let err = socket.request(spec.build()).unwrap_err();
+    assert_eq!(EEXIST, netlink_error(err).0);
+
+    socket
+        .request(
+            PortFunctionSet::new(&handle, port.index)
+                .hw_addr([0x00, 0x00, 0x00, 0x00, 0x88, 0x88])
+                .roce(false)
+                .build(),
+        )
+        .unwrap();
+    let err = socket
+        .request(
+            PortFunctionSet::new(&handle, port.index)
+                .migratable(true)
+                .build(),
+        )
+        .unwrap_err();
+    assert_eq!(EOPNOTSUPP, netlink_error(err).0);
+    socket.request(port.activate()).unwrap();
+
+    let port =
+        Port::try_from(&reply(&mut socket, Port::request(&handle, port.index)))
+            .unwrap();
+    assert!(port.is_active());
+    let function = port.function.as_ref().unwrap();
+    assert_eq!(Some(PortFunctionState::Active), function.state);
+    assert_eq!(Some(PortFunctionOpState::Attached), function.opstate);
+    assert_eq!(Some(false), function.roce());
+    assert_eq!(
+        "00:00:00:00:88:88",
+        function.hw_addr.as_ref().unwrap().to_string()
+    );
+
+    // Physical ports have no function and cannot be deleted
+    let err = socket
+        .request(
+            PortFunctionSet::new(&handle, 0)
+                .state(PortFunctionState::Active)
+                .build(),
+        )
+        .unwrap_err();
+    assert_eq!(EOPNOTSUPP, netlink_error(err).0);
+    let physical =
+        Port::try_from(&reply(&mut socket, Port::request(&handle, 0))).unwrap();
+    assert_eq!(PortFlavour::Physical, physical.flavour);
+    assert_eq!(None, physical.function);
+    let err = socket.request(physical.delete()).unwrap_err();
+    assert_eq!(EOPNOTSUPP, netlink_error(err).0);
+
+    let err = request(&mut socket, GenlDevlinkCmd::Reload, vec![]).unwrap_err();
+    assert_eq!(EOPNOTSUPP, netlink_error(err).0);
+    socket.request(port.deactivate()).unwrap();
+    socket.request(port.delete()).unwrap();
+    assert_eq!(vec![0, 1], port_indexes(&mut socket));
+}
+
+#[test]
+fn split_planner() {
+    let (_kernel, _sim, mut socket) = setup();
+    let handle = DevlinkHandle::new("netdevsim", "netdevsim1");
+    let planner = |socket: &mut GenlSocket| {
+        let replies = dump(socket, GenlDevlinkCmd::GetPort, handle.nlas());
+        SplitPlanner::from_replies(&handle, &replies).unwrap()
+    };
+
+    let before = planner(&mut socket);
+    assert_eq!(
+        vec![(0, vec![2, 4]), (1, vec![2, 4])],
+        before
+            .splittable()
+            .map(|(port, counts)| (port.index, counts))
+            .collect::<Vec<_>>()
+    );
+    let split = before.split(1, 4).unwrap();
+    socket.request(split.build()).unwrap();
+
+    let after = planner(&mut socket);
+    let subports = split.subports(&after).unwrap();
+    assert_eq!(
+        vec![(Some(0), Some(1)), (Some(1), Some(1)), (Some(2), Some(1))],
+        subports
+            .iter()
+            .take(3)
+            .map(|port| (port.split_subport, port.lanes))
+            .collect::<Vec<_>>()
+    );
+    assert_eq!(
+        Err(SplitError::NotSplittable(subports[0].index)),
+        after.split(subports[0].index, 2)
+    );
+
+    socket
+        .request(after.unsplit(subports[3].index).unwrap())
+        .unwrap();
+    let unsplit = planner(&mut socket);
+    assert!(unsplit.subports(split.group).is_empty());
+    assert_eq!(Some(4), unsplit.get(1).unwrap().lanes);
+}
+
+#[test]
+fn device_info() {
+    let (_kernel, _sim, mut socket) = setup();
+    let handle = DevlinkHandle::new("netdevsim", "netdevsim1");
+    let info = |socket: &mut GenlSocket| {
+        DeviceInfo::try_from(&reply(socket, DeviceInfo::request(&handle)))
+            .unwrap()
+    };
+
+    let before = info(&mut socket);
+    assert_eq!("netdevsim", before.driver_name);
+    assert_eq!(Some("00000001"), before.serial_number.as_deref());
+    assert_eq!(
+        Some("netdevsim"),
+        before.version(DEVLINK_INFO_VERSION_GENERIC_BOARD_ID)
+    );
+    assert!(before.pending_updates().is_empty());
+
+    request(
+        &mut socket,
+        GenlDevlinkCmd::UpdateFlash,
+        vec![GenlDevlinkAttrs::FlashUpdateFileName(
+            "fw-2.0.0.bin".to_owned(),
+        )],
+    )
+    .unwrap();
+    assert_eq!(
+        vec![PendingUpdate {
+            component: DEVLINK_INFO_VERSION_GENERIC_FW_MGMT.to_owned(),
+            running: Some("1.0.0".to_owned()),
+            stored: "1.0.1".to_owned(),
+        }],
+        info(&mut socket).pending_updates()
+    );
+
+    request(
+        &mut socket,
+        GenlDevlinkCmd::Reload,
+        vec![GenlDevlinkAttrs::ReloadAction(
+            DEVLINK_RELOAD_ACTION_FW_ACTIVATE,
+        )],
+    )
+    .unwrap();
+    let after = info(&mut socket);
+    assert!(after.pending_updates().is_empty());
+    assert_eq!(
+        Some("1.0.1"),
+        after.version(DEVLINK_INFO_VERSION_GENERIC_FW_MGMT)
+    );
+    assert_eq!(
+        Some("1.0.1"),
+        after
+            .stored
+            .get(DEVLINK_INFO_VERSION_GENERIC_FW_MGMT)
+            .map(String::as_str)
+    );
+}
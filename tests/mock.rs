@@ -0,0 +1,326 @@
+// SPDX-License-Identifier: MIT
+
+#![cfg(all(feature = "sync", feature = "mock"))]
+
+use netlink_packet_core::{
+    NetlinkMessage, NetlinkPayload, NLM_F_ACK, NLM_F_REQUEST,
+};
+use netlink_packet_generic::{
+    constants::*,
+    ctrl::{
+        nlas::{NlPolicyTypeAttrs, NlaType},
+        GenlCtrl, GenlCtrlCmd,
+    },
+    devlink::{nldev::GenlDevlinkAttrs, GenlDevlink, GenlDevlinkCmd},
+    mock::{MockError, MockFamily, MockKernel, MockRequest},
+    raw::GenlRaw,
+    sync::{GenlSocket, GenlSocketError},
+    GenlMessage,
+};
+use netlink_packet_utils::nla::NlasIterator;
+use std::{convert::TryInto, time::Duration};
+
+const EINVAL: i32 = 22;
+const EOPNOTSUPP: i32 = 95;
+const NLMSGERR_ATTR_MSG: u16 = 1;
+
+fn device(bus: &str, location: &str) -> Vec<GenlDevlinkAttrs> {
+    vec![
+        GenlDevlinkAttrs::BusName(bus.to_owned()),
+        GenlDevlinkAttrs::Location(location.to_owned()),
+    ]
+}
+
+// Register devlink with one netdevsim device, which can be reloaded
+fn devlink_kernel() -> MockKernel {
+    let kernel = MockKernel::new();
+    let mut family = MockFamily::of::<GenlDevlink>();
+    family.set_id(GENL_ID_DEVLINK);
+    family.add_policy(
+        0,
+        DEVLINK_ATTR_BUS_NAME,
+        vec![NlPolicyTypeAttrs::Type(NlaType::NulString)],
+    );
+    family.add_op_policy(DEVLINK_CMD_GET, Some(0), Some(0));
+    family.set_handler(|request: &mut MockRequest<GenlDevlink>| {
+        let cmd = request.message.payload.cmd;
+        match cmd {
+            GenlDevlinkCmd::GetDevices => Ok(vec![GenlDevlink {
+                cmd: GenlDevlinkCmd::NewDevice,
+                nldev: device("netdevsim", "netdevsim1"),
+            }]),
+            GenlDevlinkCmd::Reload => {
+                if !request.message.payload.nldev.contains(
+                    &GenlDevlinkAttrs::BusName("netdevsim".to_owned()),
+                ) {
+                    return Err(MockError::with_message(
+                        EINVAL,
+                        "unknown device",
+                    ));
+                }
+                request.notify(
+                    "config",
+                    GenlDevlink {
+                        cmd: GenlDevlinkCmd::NewDevice,
+                        nldev: device("netdevsim", "netdevsim1"),
+                    },
+                );
+                Ok(vec![])
+            }
+            _ => Err(MockError::new(EOPNOTSUPP)),
+        }
+    });
+    kernel.register(family).unwrap();
+    kernel
+}
+
+fn socket(kernel: &MockKernel) -> GenlSocket {
+    let mut socket = GenlSocket::from_mock(kernel.connect());
+    socket.set_timeout(Some(Duration::from_secs(1)));
+    socket
+}
+
+#[test]
+fn resolve_family() {
+    let kernel = devlink_kernel();
+    let mut socket = socket(&kernel);
+
+    assert!(socket.socket().is_none());
+    assert_eq!(
+        GENL_ID_CTRL,
+        socket.resolve_family_id::<GenlCtrl>().unwrap()
+    );
+    assert_eq!(
+        GENL_ID_DEVLINK,
+        socket.resolve_family_id::<GenlDevlink>().unwrap()
+    );
+    assert_eq!(
+        kernel.multicast_group_id("devlink", "config"),
+        socket.multicast_group_id::<GenlDevlink>("config").ok()
+    );
+    assert!(matches!(
+        socket.resolve_family_id_by_name("nosuchfamily"),
+        Err(GenlSocketError::Netlink(e)) if e.raw_code() == -2
+    ));
+
+    // The family of a raw message is only known by its ID
+    let raw = GenlRaw {
+        family_id: 0,
+        cmd: 1,
+        version: 1,
+        nlas: vec![],
+    };
+    assert!(matches!(
+        socket.request(raw),
+        Err(GenlSocketError::UnnamedFamily)
+    ));
+
+    let families = socket
+        .dump(GenlCtrl {
+            cmd: GenlCtrlCmd::GetFamily,
+            nlas: vec![],
+        })
+        .unwrap();
+    assert_eq!(2, families.len());
+}
+
+#[test]
+fn request_and_dump() {
+    let kernel = devlink_kernel();
+    let mut socket = socket(&kernel);
+
+    let devices = socket
+        .dump(GenlDevlink {
+            cmd: GenlDevlinkCmd::GetDevices,
+            nldev: vec![],
+        })
+        .unwrap();
+    assert_eq!(1, devices.len());
+    assert_eq!(device("netdevsim", "netdevsim1"), devices[0].payload.nldev);
+
+    let replies = socket
+        .request(GenlDevlink {
+            cmd: GenlDevlinkCmd::Reload,
+            nldev: device("netdevsim", "netdevsim1"),
+        })
+        .unwrap();
+    assert!(replies.is_empty());
+}
+
+#[test]
+fn request_error_with_extack() {
+    let kernel = devlink_kernel();
+    let mut socket = socket(&kernel);
+
+    let err = socket
+        .request(GenlDevlink {
+            cmd: GenlDevlinkCmd::Reload,
+            nldev: device("pci", "0000:01:00.0"),
+        })
+        .unwrap_err();
+    let err = match err {
+        GenlSocketError::Netlink(e) => e,
+        e => panic!("Unexpected error: {}", e),
+    };
+    assert_eq!(-EINVAL, err.raw_code());
+
+    // The request is echoed in front of the extended ACK attributes
+    let request_len = u32::from_ne_bytes(err.header[..4].try_into().unwrap());
+    let tlvs = &err.header[request_len as usize..];
+    let message = NlasIterator::new(tlvs)
+        .map(|nla| nla.unwrap())
+        .find(|nla| nla.kind() == NLMSGERR_ATTR_MSG)
+        .unwrap();
+    assert_eq!(b"unknown device\0", message.value());
+
+    let err = socket
+        .request(GenlDevlink {
+            cmd: GenlDevlinkCmd::GetRegion,
+            nldev: vec![],
+        })
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        GenlSocketError::Netlink(e) if e.raw_code() == -EOPNOTSUPP
+    ));
+}
+
+#[test]
+fn family_policy() {
+    let kernel = devlink_kernel();
+    let mut socket = socket(&kernel);
+
+    let policy = socket.family_policy("devlink").unwrap();
+    assert_eq!(GENL_ID_DEVLINK, policy.family_id());
+    assert_eq!(Some(0), policy.do_policy(DEVLINK_CMD_GET));
+    assert_eq!(Some(0), policy.dump_policy(DEVLINK_CMD_GET));
+    assert_eq!(
+        Some(&[NlPolicyTypeAttrs::Type(NlaType::NulString)][..]),
+        policy.attribute(0, DEVLINK_ATTR_BUS_NAME)
+    );
+
+    // No policy registered for nlctrl
+    assert!(matches!(
+        socket.family_policy("nlctrl"),
+        Err(GenlSocketError::Netlink(e)) if e.raw_code() == -61
+    ));
+}
+
+#[test]
+fn notifications() {
+    let kernel = devlink_kernel();
+    let mut socket = socket(&kernel);
+    let mut listener = self::socket(&kernel);
+
+    listener.subscribe::<GenlDevlink>("config").unwrap();
+    listener.subscribe::<GenlCtrl>("notify").unwrap();
+
+    socket
+        .request(GenlDevlink {
+            cmd: GenlDevlinkCmd::Reload,
+            nldev: device("netdevsim", "netdevsim1"),
+        })
+        .unwrap();
+    let notification = listener.recv_notification::<GenlDevlink>().unwrap();
+    assert_eq!(GenlDevlinkCmd::NewDevice, notification.payload.cmd);
+
+    kernel
+        .notify(
+            "config",
+            GenlDevlink {
+                cmd: GenlDevlinkCmd::DeleteDevice,
+                nldev: device("netdevsim", "netdevsim1"),
+            },
+        )
+        .unwrap();
+    let notification = listener.recv_notification::<GenlDevlink>().unwrap();
+    assert_eq!(GenlDevlinkCmd::DeleteDevice, notification.payload.cmd);
+
+    // Families registered later are announced by the controller
+    let id = kernel.register(MockFamily::new("test")).unwrap();
+    let notification = listener.recv_notification::<GenlCtrl>().unwrap();
+    assert_eq!(GenlCtrlCmd::NewFamily, notification.payload.cmd);
+    assert_eq!(id, socket.resolve_family_id_by_name("test").unwrap());
+
+    // Without handler, the requests of the family are not supported
+    let err = socket
+        .request(GenlRaw {
+            family_id: id,
+            cmd: 1,
+            version: 1,
+            nlas: vec![],
+        })
+        .unwrap_err();
+    assert!(matches!(
+        err,
+        GenlSocketError::Netlink(e) if e.raw_code() == -EOPNOTSUPP
+    ));
+
+    listener.unsubscribe::<GenlDevlink>("config").unwrap();
+    kernel
+        .notify(
+            "config",
+            GenlDevlink {
+                cmd: GenlDevlinkCmd::DeleteDevice,
+                nldev: vec![],
+            },
+        )
+        .unwrap();
+    listener.set_timeout(Some(Duration::from_millis(10)));
+    assert!(matches!(
+        listener.recv_notification::<GenlDevlink>(),
+        Err(GenlSocketError::Timeout)
+    ));
+}
+
+#[test]
+fn abandoned_request() {
+    let kernel = devlink_kernel();
+    let mut socket = socket(&kernel);
+    socket.subscribe::<GenlDevlink>("config").unwrap();
+
+    // The reply and the ACK arrive after the iterator is dropped, and are
+    // not mistaken for a notification
+    drop(
+        socket
+            .request_with_flags(
+                GenlDevlink {
+                    cmd: GenlDevlinkCmd::GetDevices,
+                    nldev: vec![],
+                },
+                NLM_F_REQUEST | NLM_F_ACK,
+            )
+            .unwrap(),
+    );
+    kernel
+        .notify(
+            "config",
+            GenlDevlink {
+                cmd: GenlDevlinkCmd::DeleteDevice,
+                nldev: device("netdevsim", "netdevsim1"),
+            },
+        )
+        .unwrap();
+    let buf = socket.recv_raw().unwrap();
+    let msg =
+        NetlinkMessage::<GenlMessage<GenlDevlink>>::deserialize(&buf).unwrap();
+    assert_eq!(0, msg.header.sequence_number);
+    assert!(matches!(
+        msg.payload,
+        NetlinkPayload::InnerMessage(genlmsg)
+            if genlmsg.payload.cmd == GenlDevlinkCmd::DeleteDevice
+    ));
+
+    socket.set_timeout(Some(Duration::from_millis(10)));
+    assert!(matches!(socket.recv_raw(), Err(GenlSocketError::Timeout)));
+
+    // The next requests get their own replies
+    socket.set_timeout(Some(Duration::from_secs(1)));
+    let devices = socket
+        .dump(GenlDevlink {
+            cmd: GenlDevlinkCmd::GetDevices,
+            nldev: vec![],
+        })
+        .unwrap();
+    assert_eq!(1, devices.len());
+}
@@ -0,0 +1,90 @@
+// SPDX-License-Identifier: MIT
+
+#![cfg(feature = "serde")]
+
+use netlink_packet_generic::{
+    ctrl::{
+        nlas::{GenlCtrlAttrs, McastGrpAttrs},
+        GenlCtrl, GenlCtrlCmd,
+    },
+    devlink::{nldev::GenlDevlinkAttrs, GenlDevlink, GenlDevlinkCmd},
+    raw::RawNla,
+    GenlMessage, GenlRaw,
+};
+use netlink_packet_utils::nla::DefaultNla;
+use serde_json::json;
+
+#[test]
+fn devlink_info_to_json() {
+    let info = GenlDevlink {
+        cmd: GenlDevlinkCmd::GetDeviceInfo,
+        nldev: vec![
+            GenlDevlinkAttrs::BusName("pci".to_owned()),
+            GenlDevlinkAttrs::InfoDriverName("mlx5_core".to_owned()),
+            GenlDevlinkAttrs::InfoVersionRunning(vec![
+                GenlDevlinkAttrs::InfoVersionName("fw".to_owned()),
+                GenlDevlinkAttrs::InfoVersionValue("16.35.2000".to_owned()),
+            ]),
+        ],
+    };
+
+    let value = serde_json::to_value(&info).unwrap();
+    assert_eq!(
+        json!({
+            "cmd": "GetDeviceInfo",
+            "nldev": [
+                {"BusName": "pci"},
+                {"InfoDriverName": "mlx5_core"},
+                {"InfoVersionRunning": [
+                    {"InfoVersionName": "fw"},
+                    {"InfoVersionValue": "16.35.2000"},
+                ]},
+            ],
+        }),
+        value
+    );
+    assert_eq!(info, serde_json::from_value(value).unwrap());
+}
+
+#[test]
+fn ctrl_message_round_trip() {
+    let mut msg = GenlMessage::from_payload(GenlCtrl {
+        cmd: GenlCtrlCmd::NewFamily,
+        nlas: vec![
+            GenlCtrlAttrs::FamilyName("devlink".to_owned()),
+            GenlCtrlAttrs::McastGroups(vec![vec![
+                McastGrpAttrs::Name("config".to_owned()),
+                McastGrpAttrs::Id(4),
+            ]]),
+        ],
+    });
+    msg.set_resolved_family_id(0x10);
+
+    let json = serde_json::to_string(&msg).unwrap();
+    let parsed: GenlMessage<GenlCtrl> = serde_json::from_str(&json).unwrap();
+    assert_eq!(msg, parsed);
+    assert_eq!(0x10, parsed.resolved_family_id());
+}
+
+#[test]
+fn raw_round_trip() {
+    let raw = GenlRaw {
+        family_id: 0x20,
+        cmd: 1,
+        version: 1,
+        nlas: vec![
+            RawNla::Value(DefaultNla::new(1, vec![1, 2, 3, 4])),
+            RawNla::Nested(
+                2,
+                vec![RawNla::Value(DefaultNla::new(1, b"eth0\0".to_vec()))],
+            ),
+        ],
+    };
+
+    let value = serde_json::to_value(&raw).unwrap();
+    assert_eq!(
+        json!({"kind": 1, "value": [1, 2, 3, 4]}),
+        value["nlas"][0]["Value"]
+    );
+    assert_eq!(raw, serde_json::from_value(value).unwrap());
+}
@@ -0,0 +1,175 @@
+// SPDX-License-Identifier: MIT
+
+#![cfg(feature = "sync")]
+
+use netlink_packet_core::NetlinkPayload;
+use netlink_packet_generic::{
+    constants::*,
+    ctrl::{nlas::GenlCtrlAttrs, GenlCtrl, GenlCtrlCmd},
+    pcap::{Direction, PcapReader},
+    raw::{GenlRaw, PolicyNla, PolicyValue, RawNla},
+    registry::{AnyGenlMessage, GenlRegistry},
+    sync::{GenlSocket, GenlSocketError},
+};
+use netlink_packet_utils::nla::DefaultNla;
+use std::{
+    io::{self, Write},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+#[test]
+fn resolve_family_id() {
+    let mut socket = GenlSocket::new().unwrap();
+    socket.set_timeout(Some(Duration::from_secs(5)));
+
+    // nlctrl's family must be 0x10
+    assert_eq!(0x10, socket.resolve_family_id::<GenlCtrl>().unwrap());
+    // Served from the cache
+    assert_eq!(0x10, socket.resolve_family_id::<GenlCtrl>().unwrap());
+}
+
+#[test]
+fn dump_families() {
+    let mut socket = GenlSocket::new().unwrap();
+    socket.set_timeout(Some(Duration::from_secs(5)));
+
+    let families = socket
+        .dump(GenlCtrl {
+            cmd: GenlCtrlCmd::GetFamily,
+            nlas: vec![],
+        })
+        .unwrap();
+
+    assert!(families.iter().any(|msg| {
+        msg.payload.cmd == GenlCtrlCmd::NewFamily
+            && msg
+                .payload
+                .nlas
+                .contains(&GenlCtrlAttrs::FamilyName("nlctrl".to_owned()))
+    }));
+}
+
+#[test]
+fn request_error() {
+    let mut socket = GenlSocket::new().unwrap();
+    socket.set_timeout(Some(Duration::from_secs(5)));
+
+    let err = socket
+        .resolve_family_id_by_name("nosuchfamily")
+        .unwrap_err();
+    match err {
+        GenlSocketError::Netlink(e) => assert_eq!(-2, e.raw_code()),
+        e => panic!("Unexpected error: {}", e),
+    }
+}
+
+#[test]
+fn subscribe_notify() {
+    let mut socket = GenlSocket::new().unwrap();
+    socket.set_timeout(Some(Duration::from_millis(100)));
+
+    // nlctrl's "notify" group is statically assigned to 0x10
+    assert_eq!(0x10, socket.subscribe::<GenlCtrl>("notify").unwrap());
+    match socket.subscribe::<GenlCtrl>("config").unwrap_err() {
+        GenlSocketError::UnknownMulticastGroup(name) => {
+            assert_eq!("config", name)
+        }
+        e => panic!("Unexpected error: {}", e),
+    }
+    assert_eq!(0x10, socket.unsubscribe::<GenlCtrl>("notify").unwrap());
+
+    // Nothing was sent to the group
+    match socket.recv_notification::<GenlCtrl>().unwrap_err() {
+        GenlSocketError::Timeout => {}
+        e => panic!("Unexpected error: {}", e),
+    }
+}
+
+#[test]
+fn raw_family_policy() {
+    let mut socket = GenlSocket::new().unwrap();
+    socket.set_timeout(Some(Duration::from_secs(5)));
+
+    let policy = socket.family_policy("nlctrl").unwrap();
+    assert_eq!(0x10, policy.family_id());
+
+    // Query nlctrl as if it were an unknown family
+    let replies = socket
+        .request(GenlRaw {
+            family_id: 0x10,
+            cmd: CTRL_CMD_GETFAMILY,
+            version: 2,
+            nlas: vec![RawNla::Value(DefaultNla::new(
+                CTRL_ATTR_FAMILY_NAME,
+                b"nlctrl\0".to_vec(),
+            ))],
+        })
+        .unwrap();
+    assert_eq!(1, replies.len());
+    assert_eq!(CTRL_CMD_NEWFAMILY, replies[0].payload.cmd);
+
+    let nlas = policy.decode(&replies[0].payload).unwrap();
+    assert!(nlas.contains(&PolicyNla {
+        kind: CTRL_ATTR_FAMILY_ID,
+        value: PolicyValue::U16(0x10),
+    }));
+    assert!(nlas.contains(&PolicyNla {
+        kind: CTRL_ATTR_FAMILY_NAME,
+        value: PolicyValue::String("nlctrl".to_owned()),
+    }));
+}
+
+#[derive(Clone, Default)]
+struct SharedBuffer(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedBuffer {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+fn capture_traffic() {
+    let mut socket = GenlSocket::new().unwrap();
+    socket.set_timeout(Some(Duration::from_secs(5)));
+    let capture = SharedBuffer::default();
+    socket.start_capture(capture.clone()).unwrap();
+
+    socket.resolve_family_id::<GenlCtrl>().unwrap();
+    assert!(socket.stop_capture().is_some());
+    // Not recorded anymore
+    socket
+        .resolve_family_id_by_name("nosuchfamily")
+        .unwrap_err();
+
+    let buf = capture.0.lock().unwrap().clone();
+    let messages = PcapReader::new(&buf[..], GenlRegistry::new())
+        .unwrap()
+        .collect::<Result<Vec<_>, _>>()
+        .unwrap();
+    // Request, reply and ACK
+    assert_eq!(3, messages.len());
+
+    assert_eq!(Direction::ToKernel, messages[0].direction);
+    assert_eq!(Direction::ToUser, messages[1].direction);
+    assert_eq!(
+        messages[0].message.header.sequence_number,
+        messages[1].message.header.sequence_number
+    );
+    match &messages[1].message.payload {
+        NetlinkPayload::InnerMessage(AnyGenlMessage::Ctrl(msg)) => {
+            assert_eq!(GenlCtrlCmd::NewFamily, msg.payload.cmd);
+            assert!(msg.payload.nlas.contains(&GenlCtrlAttrs::FamilyId(0x10)));
+        }
+        payload => panic!("Unexpected payload: {:?}", payload),
+    }
+    assert!(matches!(
+        messages[2].message.payload,
+        NetlinkPayload::Error(_)
+    ));
+}